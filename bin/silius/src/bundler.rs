@@ -12,24 +12,24 @@ use silius_bundler::{ConditionalClient, EthereumClient, FastlaneClient, Flashbot
 use silius_contracts::EntryPoint;
 use silius_grpc::{
     bundler_client::BundlerClient, bundler_service_run, uo_pool_client::UoPoolClient,
-    uopool_service_run,
+    uopool_service_run, UoPoolServiceConfig,
 };
 use silius_mempool::{
     init_env,
-    validate::validator::{new_canonical, new_canonical_unsafe},
+    validate::{
+        sanity::denylist::Denylist,
+        validator::{new_canonical, new_canonical_unsafe, CanonicalConfig},
+        UserOperationValidatorMode,
+    },
     CodeHashes, DatabaseTable, EntitiesReputation, Mempool, Reputation, UserOperations,
-    UserOperationsByEntity, UserOperationsBySender, WriteMap,
+    UserOperationsByEntity, UserOperationsBySender, UserOperationsMetadata, WriteMap,
 };
 use silius_metrics::{launch_metrics_exporter, mempool::MetricsHandler};
 use silius_primitives::{
     bundler::SendStrategy,
     constants::{
         entry_point, fastlane_relay_endpoints, flashbots_relay_endpoints,
-        storage::DATABASE_FOLDER_NAME,
-        supported_chains::CHAINS,
-        validation::reputation::{
-            BAN_SLACK, MIN_INCLUSION_RATE_DENOMINATOR, MIN_UNSTAKE_DELAY, THROTTLING_SLACK,
-        },
+        storage::DATABASE_FOLDER_NAME, supported_chains::CHAINS,
     },
     provider::BlockStream,
     reputation::ReputationEntry,
@@ -48,6 +48,7 @@ use std::{
     net::SocketAddr,
     str::FromStr,
     sync::Arc,
+    time::Duration,
 };
 use tracing::{info, warn};
 
@@ -296,13 +297,13 @@ where
         Address::from_str(entry_point::ADDRESS).expect("address should be valid"),
     );
 
-    let (mempool, reputation) = match args.storage_type {
+    let (mut mempool, reputation) = match args.storage_type {
         StorageType::Database => {
             let env = Arc::new(
                 init_env::<WriteMap>(datadir.join(DATABASE_FOLDER_NAME)).expect("Init mdbx failed"),
             );
             env.create_tables().expect("Create mdbx database tables failed");
-            let mempool = Mempool::new(
+            let mut mempool = Mempool::new(
                 Box::new(MetricsHandler::new(DatabaseTable::<WriteMap, UserOperations>::new(
                     env.clone(),
                 ))),
@@ -310,12 +311,18 @@ where
                 Box::new(DatabaseTable::<WriteMap, UserOperationsByEntity>::new(env.clone())),
                 Box::new(DatabaseTable::<WriteMap, CodeHashes>::new(env.clone())),
             );
+            mempool
+                .set_user_operations_metadata(Box::new(DatabaseTable::<
+                    WriteMap,
+                    UserOperationsMetadata,
+                >::new(env.clone())))
+                .expect("Restore mempool metadata failed");
             let mut reputation = Reputation::new(
-                MIN_INCLUSION_RATE_DENOMINATOR,
-                THROTTLING_SLACK,
-                BAN_SLACK,
+                args.min_inclusion_denominator,
+                args.throttling_slack,
+                args.ban_slack,
                 args.min_stake,
-                MIN_UNSTAKE_DELAY.into(),
+                args.min_unstake_delay,
                 Arc::new(RwLock::new(HashSet::<Address>::default())),
                 Arc::new(RwLock::new(HashSet::<Address>::default())),
                 Box::new(MetricsHandler::new(DatabaseTable::<WriteMap, EntitiesReputation>::new(
@@ -344,11 +351,11 @@ where
                 ))),
             );
             let reputation = Reputation::new(
-                MIN_INCLUSION_RATE_DENOMINATOR,
-                THROTTLING_SLACK,
-                BAN_SLACK,
+                args.min_inclusion_denominator,
+                args.throttling_slack,
+                args.ban_slack,
                 args.min_stake,
-                MIN_UNSTAKE_DELAY.into(),
+                args.min_unstake_delay,
                 Arc::new(RwLock::new(HashSet::<Address>::default())),
                 Arc::new(RwLock::new(HashSet::<Address>::default())),
                 Box::new(Arc::new(RwLock::new(MetricsHandler::new(HashMap::<
@@ -359,52 +366,76 @@ where
             (mempool, reputation)
         }
     };
+    mempool.set_max_size(args.mempool_max_size);
+    mempool.set_max_size_unstaked(args.mempool_max_size_unstaked);
+
+    let validator_modes = args
+        .skip_trace_entry_points
+        .iter()
+        .map(|ep| {
+            (*ep, UserOperationValidatorMode::Sanity | UserOperationValidatorMode::Simulation)
+        })
+        .collect::<HashMap<_, _>>();
+
+    // the lists are shared by the validator and the `UpdateDenylist` endpoint
+    let denylist = Denylist::default();
+
+    let config = CanonicalConfig {
+        max_verification_gas: args.max_verification_gas,
+        max_bundle_gas: args.max_bundle_gas,
+        min_priority_fee_per_gas: args.min_priority_fee_per_gas,
+        gas_increase_perc: args.gas_increase_perc,
+        denylist: denylist.clone(),
+        aggregator_allowlist: (!args.aggregators.is_empty())
+            .then(|| args.aggregators.iter().cloned().collect()),
+        max_valid_until_window: args.max_valid_until_window,
+        flag_time_dependent: args.flag_time_dependent,
+        restrict_deployment_storage: args.restrict_deployment_storage,
+    };
+    let service_config = UoPoolServiceConfig {
+        addr: SocketAddr::new(args.uopool_addr, args.uopool_port),
+        mode: args.uopool_mode,
+        chain,
+        max_verification_gas: args.max_verification_gas,
+        p2p_config,
+        enable_metrics: metrics_args.enable_metrics,
+        validator_modes,
+        max_concurrent_validations: args.max_concurrent_validations,
+        max_log_range: args.max_log_range,
+        post_op_gas: args.post_op_gas,
+        revalidation: args
+            .revalidation_interval
+            .map(|interval| (Duration::from_secs(interval), args.revalidation_batch_size)),
+        denylist: Some(denylist),
+    };
+
     match args.uopool_mode {
         silius_primitives::UoPoolMode::Standard => {
-            let validator = new_canonical(
-                entrypoint_api,
-                chain,
-                args.max_verification_gas,
-                args.min_priority_fee_per_gas,
-            );
-
+            let validator = new_canonical(entrypoint_api, chain, config)
+                .with_deposit_cache_max_entries(args.deposit_cache_max_entries);
             uopool_service_run(
-                SocketAddr::new(args.uopool_addr, args.uopool_port),
-                args.uopool_mode,
+                service_config,
                 entry_points,
                 eth_client,
                 block_streams,
-                chain,
-                args.max_verification_gas,
                 mempool,
                 reputation,
                 validator,
-                p2p_config,
-                metrics_args.enable_metrics,
             )
             .await?;
             info!("Started uopool gRPC service at {:?}:{:?}", args.uopool_addr, args.uopool_port);
         }
         silius_primitives::UoPoolMode::Unsafe => {
-            let validator = new_canonical_unsafe(
-                entrypoint_api,
-                chain,
-                args.max_verification_gas,
-                args.min_priority_fee_per_gas,
-            );
+            let validator = new_canonical_unsafe(entrypoint_api, chain, config)
+                .with_deposit_cache_max_entries(args.deposit_cache_max_entries);
             uopool_service_run(
-                SocketAddr::new(args.uopool_addr, args.uopool_port),
-                args.uopool_mode,
+                service_config,
                 entry_points,
                 eth_client,
                 block_streams,
-                chain,
-                args.max_verification_gas,
                 mempool,
                 reputation,
                 validator,
-                p2p_config,
-                metrics_args.enable_metrics,
             )
             .await?;
             info!("Started uopool gRPC service at {:?}:{:?}", args.uopool_addr, args.uopool_port);
@@ -7,6 +7,7 @@ use clap::{ArgGroup, Parser, ValueEnum};
 use discv5::Enr;
 use ethers::types::{Address, U256};
 use expanded_pathbuf::ExpandedPathBuf;
+use silius_mempool::POST_OP_GAS;
 use silius_metrics::label::LabelValue;
 use silius_p2p::{
     config::{gossipsub_config, Config, ConfigBuilder},
@@ -16,10 +17,17 @@ use silius_primitives::{
     bundler::SendStrategy,
     chain::ChainSpec,
     constants::{
-        bundler::BUNDLE_INTERVAL,
-        grpc::{BUNDLER_PORT, MEMPOOL_PORT},
+        bundler::{BUNDLE_INTERVAL, MAX_BUNDLE_GAS},
+        grpc::{BUNDLER_PORT, MAX_CONCURRENT_VALIDATIONS, MEMPOOL_PORT},
+        mempool::{GAS_INCREASE_PERC, MAX_LOG_RANGE, REVALIDATION_BATCH_SIZE},
         p2p::{NODE_ENR_FILE_NAME, NODE_KEY_FILE_NAME},
         rpc::{HTTP_PORT, WS_PORT},
+        validation::{
+            reputation::{
+                BAN_SLACK, MIN_INCLUSION_RATE_DENOMINATOR, MIN_UNSTAKE_DELAY, THROTTLING_SLACK,
+            },
+            DEPOSIT_INFO_CACHE_MAX_ENTRIES, MAX_VERIFICATION_GAS,
+        },
     },
     UoPoolMode,
 };
@@ -107,17 +115,56 @@ pub struct UoPoolArgs {
     pub storage_type: StorageType,
 
     /// Max allowed verification gas.
-    #[clap(long, default_value="5000000", value_parser=parse_u256)]
+    #[clap(long, value_parser=parse_u256, default_value_t = U256::from(MAX_VERIFICATION_GAS))]
     pub max_verification_gas: U256,
 
+    /// Max allowed aggregate gas of the user operations in one bundle.
+    #[clap(long, value_parser=parse_u256, default_value_t = U256::from(MAX_BUNDLE_GAS))]
+    pub max_bundle_gas: U256,
+
     /// Minimum stake required for entities.
     #[clap(long, value_parser=parse_u256, default_value = "1")]
     pub min_stake: U256,
 
+    /// Minimum denominator of the expected inclusion rate of the entities' user operations.
+    #[clap(
+        long,
+        default_value_t = MIN_INCLUSION_RATE_DENOMINATOR,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub min_inclusion_denominator: u64,
+
+    /// Number of the expected but not included user operations after which an entity is
+    /// throttled.
+    #[clap(long, default_value_t = THROTTLING_SLACK)]
+    pub throttling_slack: u64,
+
+    /// Number of the expected but not included user operations after which an entity is banned.
+    #[clap(long, default_value_t = BAN_SLACK)]
+    pub ban_slack: u64,
+
+    /// Minimum unstake delay (in seconds) required for staked entities. Entities with a shorter
+    /// delay are treated as unstaked.
+    #[clap(long, value_parser=parse_u256, default_value_t = U256::from(MIN_UNSTAKE_DELAY))]
+    pub min_unstake_delay: U256,
+
     /// Minimum priority fee per gas.
     #[clap(long, value_parser=parse_u256, default_value = "0")]
     pub min_priority_fee_per_gas: U256,
 
+    /// Gas increase percentage required to replace a user operation in the mempool.
+    #[clap(long, value_parser=parse_u256, default_value_t = U256::from(GAS_INCREASE_PERC))]
+    pub gas_increase_perc: U256,
+
+    /// Maximum number of user operations in the mempool (unlimited if not set).
+    #[clap(long)]
+    pub mempool_max_size: Option<usize>,
+
+    /// Maximum number of user operations without a staked entity in the mempool (only the
+    /// maximum size of the mempool applies if not set).
+    #[clap(long)]
+    pub mempool_max_size_unstaked: Option<usize>,
+
     /// Addresses of whitelisted entities.
     #[clap(long, value_delimiter=',', value_parser = parse_address)]
     pub whitelist: Vec<Address>,
@@ -126,6 +173,60 @@ pub struct UoPoolArgs {
     #[clap(long, default_value = "standard", value_parser=parse_uopool_mode)]
     pub uopool_mode: UoPoolMode,
 
+    /// Entry points whose user operations are validated without the simulation trace (only
+    /// sanity checks and simulation).
+    #[clap(long, value_delimiter=',', value_parser = parse_address)]
+    pub skip_trace_entry_points: Vec<Address>,
+
+    /// Signature aggregators whose user operations are accepted (user operations using any other
+    /// aggregator are rejected). All aggregators are accepted if none is set.
+    #[clap(long, value_delimiter=',', value_parser = parse_address)]
+    pub aggregators: Vec<Address>,
+
+    /// Max number of seconds a user operation may be valid for (its `validUntil` from now). Any
+    /// `validUntil` is accepted if not set.
+    #[clap(long, value_parser=parse_u256)]
+    pub max_valid_until_window: Option<U256>,
+
+    /// Flags the user operations whose validation reads TIMESTAMP or NUMBER (even by staked
+    /// entities) in the validation outcome instead of rejecting them.
+    #[clap(long)]
+    pub flag_time_dependent: bool,
+
+    /// Restricts the storage writes of the factory while deploying the account to the account's
+    /// storage (and the factory's own and the account's associated storage if the factory is
+    /// staked).
+    #[clap(long)]
+    pub restrict_deployment_storage: bool,
+
+    /// Maximum number of user operation validations (simulations) run concurrently.
+    #[clap(long, default_value_t = MAX_CONCURRENT_VALIDATIONS)]
+    pub max_concurrent_validations: usize,
+
+    /// Maximum number of blocks queried in a single `eth_getLogs` call. Larger block ranges are
+    /// split into chunks (and halved if the provider still rejects the range).
+    #[clap(long, default_value_t = MAX_LOG_RANGE)]
+    pub max_log_range: u64,
+
+    /// Gas reserved for the paymaster postOp call of sponsored user operations in the gas
+    /// estimation.
+    #[clap(long, value_parser=parse_u256, default_value_t = U256::from(POST_OP_GAS))]
+    pub post_op_gas: U256,
+
+    /// Interval (in seconds) of the re-validation of the pending user operations (disabled if not
+    /// set).
+    #[clap(long)]
+    pub revalidation_interval: Option<u64>,
+
+    /// Maximum number of pending user operations re-validated at once.
+    #[clap(long, default_value_t = REVALIDATION_BATCH_SIZE)]
+    pub revalidation_batch_size: usize,
+
+    /// Maximum number of entities whose deposit info is cached (the oldest-fetched entry is
+    /// evicted beyond it).
+    #[clap(long, default_value_t = DEPOSIT_INFO_CACHE_MAX_ENTRIES)]
+    pub deposit_cache_max_entries: usize,
+
     /// P2P configuration
     #[clap(flatten)]
     pub p2p_opts: P2PArgs,
@@ -510,6 +611,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn uopool_args_min_inclusion_denominator() {
+        let args = vec!["uopoolargs", "--min-inclusion-denominator", "5"];
+        assert_eq!(UoPoolArgs::try_parse_from(args).unwrap().min_inclusion_denominator, 5);
+
+        let args = vec!["uopoolargs", "--min-inclusion-denominator", "0"];
+        assert!(UoPoolArgs::try_parse_from(args).is_err());
+    }
+
     #[test]
     fn rpc_args_when_http_and_ws_flag() {
         let args = vec![
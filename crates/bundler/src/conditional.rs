@@ -16,6 +16,37 @@ use silius_primitives::{simulation::StorageMap, Wallet};
 use std::{collections::HashMap, sync::Arc, time::Duration};
 use tracing::trace;
 
+/// Builds the `eth_sendRawTransactionConditional` options from the storage maps of the user
+/// operations in the bundle. The bundle is only included if the storage accessed during the
+/// validation is unchanged. An account with a known storage root is pinned by the root (the root
+/// covers all of its slots), otherwise by the values of the accessed slots of all user operations.
+///
+/// # Arguments
+/// * `storage_maps` - The [StorageMaps](StorageMap) of the user operations in the bundle
+///
+/// # Returns
+/// * `ConditionalOptions` - The [ConditionalOptions](ConditionalOptions) with the known accounts
+pub fn conditional_options(storage_maps: &[StorageMap]) -> ConditionalOptions {
+    let mut root_hashes: HashMap<Address, H256> = HashMap::default();
+    let mut slots: HashMap<Address, HashMap<String, String>> = HashMap::default();
+
+    for storage_map in storage_maps {
+        root_hashes.extend(storage_map.root_hashes.iter().map(|(k, v)| (*k, *v)));
+        for (k, v) in storage_map.slots.iter() {
+            slots.entry(*k).or_default().extend(v.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+    }
+
+    let mut known_accounts: HashMap<Address, AccountStorage> = slots
+        .into_iter()
+        .filter(|(k, _)| !root_hashes.contains_key(k))
+        .map(|(k, v)| (k, AccountStorage::SlotValues(v)))
+        .collect();
+    known_accounts.extend(root_hashes.into_iter().map(|(k, v)| (k, AccountStorage::RootHash(v))));
+
+    ConditionalOptions { known_accounts, ..Default::default() }
+}
+
 /// A type alias for the Ethereum Conditional Signer client
 #[derive(Clone)]
 pub struct ConditionalClient<M>(pub SignerMiddleware<Arc<M>, LocalWallet>);
@@ -41,16 +72,6 @@ where
     ) -> eyre::Result<H256> {
         trace!("Sending transaction to the conditional endpoint: {bundle:?}");
 
-        let mut known_accounts: HashMap<Address, AccountStorage> = HashMap::default();
-
-        for (k, v) in storage_map.root_hashes {
-            known_accounts.insert(k, AccountStorage::RootHash(v));
-        }
-
-        for (k, v) in storage_map.slots {
-            known_accounts.insert(k, AccountStorage::SlotValues(v));
-        }
-
         let signed_tx = self.0.sign_transaction(bundle).await?;
 
         let prefix: Option<String> =
@@ -65,7 +86,7 @@ where
             .send_raw_transaction_conditional(
                 signed_tx,
                 prefix,
-                ConditionalOptions { known_accounts, ..Default::default() },
+                conditional_options(&[storage_map]),
             )
             .await?
             .interval(Duration::from_millis(75));
@@ -96,3 +117,40 @@ where
         Self(signer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditional_options_serialization() {
+        let (account, token, paymaster) = (Address::random(), Address::random(), Address::random());
+        let root_hash = H256::random();
+        let storage_maps = [
+            StorageMap {
+                root_hashes: HashMap::from([(account, root_hash)]),
+                slots: HashMap::from([
+                    (token, HashMap::from([("0x01".to_string(), "0x0a".to_string())])),
+                    (account, HashMap::from([("0x00".to_string(), "0x01".to_string())])),
+                ]),
+            },
+            StorageMap {
+                root_hashes: HashMap::new(),
+                slots: HashMap::from([
+                    (token, HashMap::from([("0x02".to_string(), "0x0b".to_string())])),
+                    (paymaster, HashMap::from([("0x03".to_string(), "0x0c".to_string())])),
+                ]),
+            },
+        ];
+
+        let options = serde_json::to_value(conditional_options(&storage_maps)).unwrap();
+        let known_accounts = &options["knownAccounts"];
+        assert_eq!(known_accounts.as_object().unwrap().len(), 3);
+        assert_eq!(known_accounts[format!("{account:?}")], serde_json::json!(root_hash));
+        assert_eq!(
+            known_accounts[format!("{token:?}")],
+            serde_json::json!({ "0x01": "0x0a", "0x02": "0x0b" })
+        );
+        assert_eq!(known_accounts[format!("{paymaster:?}")], serde_json::json!({ "0x03": "0x0c" }));
+    }
+}
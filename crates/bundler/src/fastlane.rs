@@ -1,18 +1,12 @@
-use crate::bundler::SendBundleOp;
+use crate::{bundler::SendBundleOp, conditional::conditional_options};
 use ethers::{
     middleware::SignerMiddleware,
     providers::Middleware,
     signers::LocalWallet,
-    types::{
-        transaction::{
-            conditional::{AccountStorage, ConditionalOptions},
-            eip2718::TypedTransaction,
-        },
-        Address, BlockNumber, H256,
-    },
+    types::{transaction::eip2718::TypedTransaction, BlockNumber, H256},
 };
 use silius_primitives::{simulation::StorageMap, Wallet};
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{sync::Arc, time::Duration};
 use tracing::trace;
 
 /// A type alias for the Ethereum Conditional Signer client
@@ -43,22 +37,12 @@ where
     ) -> eyre::Result<H256> {
         trace!("Sending transaction to the conditional endpoint: {bundle:?}");
 
-        let mut known_accounts: HashMap<Address, AccountStorage> = HashMap::default();
-
-        for (k, v) in storage_map.root_hashes {
-            known_accounts.insert(k, AccountStorage::RootHash(v));
-        }
-
-        for (k, v) in storage_map.slots {
-            known_accounts.insert(k, AccountStorage::SlotValues(v));
-        }
-
         let signed_tx = self.client.sign_transaction(bundle).await?;
 
         let prefix: Option<String> = Some("pfl".to_string());
         let block = self.client.get_block(BlockNumber::Latest).await?;
 
-        let mut options = ConditionalOptions { known_accounts, ..Default::default() };
+        let mut options = conditional_options(&[storage_map]);
 
         if let Some(block) = block {
             if let Some(block_number) = block.number {
@@ -2,13 +2,17 @@
 #![allow(dead_code)]
 
 mod bundler;
+#[cfg(feature = "conditional")]
 mod conditional;
 mod ethereum;
+#[cfg(feature = "conditional")]
 mod fastlane;
 mod flashbots;
 
 pub use bundler::{Bundler, SendBundleOp};
-pub use conditional::ConditionalClient;
+#[cfg(feature = "conditional")]
+pub use conditional::{conditional_options, ConditionalClient};
 pub use ethereum::EthereumClient;
+#[cfg(feature = "conditional")]
 pub use fastlane::FastlaneClient;
 pub use flashbots::FlashbotsClient;
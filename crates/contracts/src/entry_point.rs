@@ -1,27 +1,28 @@
 pub use super::{
     error::EntryPointError,
     gen::{
+        entry_point_api::{ValidationResult, ValidationResultWithAggregation},
+        stake_manager_api::DepositInfo,
         EntryPointAPI, EntryPointAPIEvents, StakeManagerAPI, UserOperationEventFilter,
         ValidatePaymasterUserOpReturn, SELECTORS_INDICES, SELECTORS_NAMES,
     },
 };
 use super::{
     gen::{
-        entry_point_api::{
-            EntryPointAPIErrors, SenderAddressResult, UserOperation, ValidationResult,
-            ValidationResultWithAggregation,
-        },
-        stake_manager_api::DepositInfo,
+        aggregator_api,
+        entry_point_api::{EntryPointAPIErrors, SenderAddressResult, UserOperation},
+        AggregatorAPI,
     },
     tracer::JS_TRACER,
 };
 use crate::{error::decode_revert_error, executor_tracer::EXECUTOR_TRACER, gen::ExecutionResult};
 use ethers::{
     prelude::{ContractError, Event},
-    providers::Middleware,
+    providers::{Middleware, MiddlewareError, RawCall},
     types::{
-        spoof, transaction::eip2718::TypedTransaction, Address, Bytes, GethDebugTracerType,
-        GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace, TransactionRequest, U256,
+        spoof, transaction::eip2718::TypedTransaction, Address, BlockId, Bytes,
+        GethDebugTracerType, GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace,
+        TransactionRequest, U256,
     },
 };
 use std::sync::Arc;
@@ -84,7 +85,21 @@ impl<M: Middleware + 'static> EntryPoint<M> {
         &self,
         uo: U,
     ) -> Result<SimulateValidationResult, EntryPointError> {
-        let res = self.entry_point_api.simulate_validation(uo.into()).await;
+        self.simulate_validation_at(uo, None).await
+    }
+
+    /// Simulates the validation of the user operation on the state of the given block (the latest
+    /// block if `None`). Simulating on a historical block requires an archive node.
+    pub async fn simulate_validation_at<U: Into<UserOperation>>(
+        &self,
+        uo: U,
+        block: Option<BlockId>,
+    ) -> Result<SimulateValidationResult, EntryPointError> {
+        let mut call = self.entry_point_api.simulate_validation(uo.into());
+        if let Some(block) = block {
+            call = call.block(block);
+        }
+        let res = call.await;
 
         match res {
             Ok(_) => Err(EntryPointError::NoRevert { function: "simulate_validation".into() }),
@@ -106,6 +121,16 @@ impl<M: Middleware + 'static> EntryPoint<M> {
     pub async fn simulate_validation_trace<U: Into<UserOperation>>(
         &self,
         uo: U,
+    ) -> Result<GethTrace, EntryPointError> {
+        self.simulate_validation_trace_at(uo, None).await
+    }
+
+    /// Traces the simulation of the validation of the user operation on the state of the given
+    /// block (the latest block if `None`). Tracing on a historical block requires an archive node.
+    pub async fn simulate_validation_trace_at<U: Into<UserOperation>>(
+        &self,
+        uo: U,
+        block: Option<BlockId>,
     ) -> Result<GethTrace, EntryPointError> {
         let call = self.entry_point_api.simulate_validation(uo.into());
 
@@ -113,7 +138,7 @@ impl<M: Middleware + 'static> EntryPoint<M> {
             .eth_client
             .debug_trace_call(
                 call.tx,
-                None,
+                block,
                 GethDebugTracingCallOptions {
                     tracing_options: GethDebugTracingOptions {
                         disable_storage: None,
@@ -140,6 +165,29 @@ impl<M: Middleware + 'static> EntryPoint<M> {
         &self,
         uo: U,
     ) -> Result<GethTrace, EntryPointError> {
+        self.simulate_handle_op_trace_with_overrides(uo, None).await
+    }
+
+    /// Traces the simulation of the user operation execution with the given state overrides
+    /// applied on top of the funded zero address the simulation is sent from.
+    pub async fn simulate_handle_op_trace_with_overrides<U: Into<UserOperation>>(
+        &self,
+        uo: U,
+        overrides: Option<spoof::State>,
+    ) -> Result<GethTrace, EntryPointError> {
+        let (tx, options) = self.simulate_handle_op_trace_call(uo, overrides);
+        let res = self.eth_client.debug_trace_call(tx, None, options).await.map_err(|e| {
+            EntryPointError::from_middleware_error::<M>(e).expect_err("trace err is expected")
+        })?;
+
+        Ok(res)
+    }
+
+    fn simulate_handle_op_trace_call<U: Into<UserOperation>>(
+        &self,
+        uo: U,
+        overrides: Option<spoof::State>,
+    ) -> (TypedTransaction, GethDebugTracingCallOptions) {
         let uo = uo.into();
         let max_fee_per_gas = uo.max_fee_per_gas;
         let call = self.entry_point_api.simulate_handle_op(uo, Address::zero(), Bytes::default());
@@ -147,31 +195,26 @@ impl<M: Middleware + 'static> EntryPoint<M> {
         tx.set_from(Address::zero());
         tx.set_gas_price(max_fee_per_gas);
         tx.set_gas(u64::MAX);
-        let res = self
-            .eth_client
-            .debug_trace_call(
-                tx,
-                None,
-                GethDebugTracingCallOptions {
-                    tracing_options: GethDebugTracingOptions {
-                        disable_storage: None,
-                        disable_stack: None,
-                        enable_memory: None,
-                        enable_return_data: None,
-                        tracer: Some(GethDebugTracerType::JsTracer(EXECUTOR_TRACER.into())),
-                        tracer_config: None,
-                        timeout: None,
-                    },
-                    state_overrides: Some(spoof::balance(Address::zero(), UINT96_MAX.into())),
-                    block_overrides: None,
-                },
-            )
-            .await
-            .map_err(|e| {
-                EntryPointError::from_middleware_error::<M>(e).expect_err("trace err is expected")
-            })?;
 
-        Ok(res)
+        let mut state_overrides = overrides.unwrap_or_default();
+        state_overrides.account(Address::zero()).balance(UINT96_MAX.into());
+
+        (
+            tx,
+            GethDebugTracingCallOptions {
+                tracing_options: GethDebugTracingOptions {
+                    disable_storage: None,
+                    disable_stack: None,
+                    enable_memory: None,
+                    enable_return_data: None,
+                    tracer: Some(GethDebugTracerType::JsTracer(EXECUTOR_TRACER.into())),
+                    tracer_config: None,
+                    timeout: None,
+                },
+                state_overrides: Some(state_overrides),
+                block_overrides: None,
+            },
+        )
     }
 
     pub async fn handle_ops<U: Into<UserOperation>>(
@@ -220,6 +263,22 @@ impl<M: Middleware + 'static> EntryPoint<M> {
         }
     }
 
+    pub async fn validate_user_op_signature<U: Into<aggregator_api::UserOperation>>(
+        &self,
+        aggregator: &Address,
+        uo: U,
+    ) -> Result<Bytes, EntryPointError> {
+        let aggregator_api = AggregatorAPI::new(*aggregator, self.eth_client.clone());
+        let res = aggregator_api.validate_user_op_signature(uo.into()).call().await;
+
+        match res {
+            Ok(sig) => Ok(sig),
+            Err(err) => Err(EntryPointError::Other {
+                inner: format!("validate user op signature error: {err:?}"),
+            }),
+        }
+    }
+
     pub async fn get_sender_address(
         &self,
         init_code: Bytes,
@@ -238,6 +297,12 @@ impl<M: Middleware + 'static> EntryPoint<M> {
         }
     }
 
+    /// Simulates the execution phase of the user operation (the call of the sender with the
+    /// call data from the entry point).
+    ///
+    /// # Returns
+    /// The return data of the call, [EntryPointError::ExecutionReverted] with the revert reason
+    /// (empty if not available) if the call reverts.
     pub async fn simulate_execution<U: Into<UserOperation>>(
         &self,
         uo: U,
@@ -254,21 +319,53 @@ impl<M: Middleware + 'static> EntryPoint<M> {
                 None,
             )
             .await
-            .map_err(|err| EntryPointError::Provider { inner: err.to_string() })
+            .map_err(|err| {
+                let reverted = err
+                    .as_error_response()
+                    .is_some_and(|err| err.message.contains("execution reverted"));
+                match EntryPointError::from_middleware_error::<M>(err) {
+                    Ok(EntryPointAPIErrors::RevertString(reason)) => {
+                        EntryPointError::ExecutionReverted(reason)
+                    }
+                    Ok(op) => EntryPointError::ExecutionReverted(format!("{op:?}")),
+                    Err(_) if reverted => EntryPointError::ExecutionReverted(String::new()),
+                    Err(err) => err,
+                }
+            })
     }
 
     pub async fn simulate_handle_op<U: Into<UserOperation>>(
         &self,
         uo: U,
     ) -> Result<ExecutionResult, EntryPointError> {
-        let res = self
-            .entry_point_api
-            .simulate_handle_op(uo.into(), Address::zero(), Bytes::default())
-            .await;
+        self.simulate_handle_op_with_overrides(uo, None).await
+    }
+
+    /// Simulates the execution of the user operation with the given state overrides applied to
+    /// the call (no overrides if `None`).
+    pub async fn simulate_handle_op_with_overrides<U: Into<UserOperation>>(
+        &self,
+        uo: U,
+        overrides: Option<spoof::State>,
+    ) -> Result<ExecutionResult, EntryPointError> {
+        let call =
+            self.entry_point_api.simulate_handle_op(uo.into(), Address::zero(), Bytes::default());
+
+        let res = match overrides {
+            Some(overrides) => self
+                .eth_client
+                .provider()
+                .call_raw(&call.tx)
+                .state(&overrides)
+                .await
+                .map(|_| ())
+                .map_err(|e| EntryPointError::from_provider_error(&e)),
+            None => call.await.map(|_| ()).map_err(Self::deserialize_error_msg),
+        };
 
         match res {
             Ok(_) => Err(EntryPointError::NoRevert { function: "simulate_handle_op".into() }),
-            Err(e) => Self::deserialize_error_msg(e).and_then(|op| match op {
+            Err(e) => e.and_then(|op| match op {
                 EntryPointAPIErrors::FailedOp(err) => Err(EntryPointError::FailedOp(err)),
                 EntryPointAPIErrors::ExecutionResult(res) => Ok(res),
                 _ => Err(EntryPointError::Other {
@@ -290,7 +387,29 @@ impl<M: Middleware + 'static> EntryPoint<M> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ethers::providers::{Http, Provider};
+    use ethers::providers::{Http, MockProvider, Provider};
+
+    #[test]
+    fn simulate_handle_op_trace_overrides() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let ep = EntryPoint::new(Arc::new(provider), Address::random());
+        let sender = Address::random();
+        let uo = UserOperation { sender, ..Default::default() };
+
+        let (_, options) = ep.simulate_handle_op_trace_call(uo.clone(), None);
+        assert_eq!(
+            options.state_overrides,
+            Some(spoof::balance(Address::zero(), UINT96_MAX.into()))
+        );
+
+        // the overrides are included in the call along with the funded zero address
+        let (tx, options) =
+            ep.simulate_handle_op_trace_call(uo, Some(spoof::balance(sender, U256::from(1))));
+        let mut state = spoof::balance(sender, U256::from(1));
+        state.account(Address::zero()).balance(UINT96_MAX.into());
+        assert_eq!(options.state_overrides, Some(state));
+        assert_eq!(tx.from(), Some(&Address::zero()));
+    }
 
     #[tokio::test]
     #[ignore]
@@ -2,9 +2,10 @@ use crate::gen::{EntryPointAPIErrors, FailedOp};
 use ethers::{
     abi::AbiDecode,
     providers::{JsonRpcError, Middleware, MiddlewareError, ProviderError},
-    types::Bytes,
+    types::{Bytes, GethTrace},
 };
 use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::str::FromStr;
 use thiserror::Error;
 
@@ -55,7 +56,91 @@ pub enum EntryPointError {
     },
 }
 
+/// Errors of the conversion of a [GethTrace] into the output of a
+/// JavaScript tracer
+#[derive(Debug, Error, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TraceError {
+    /// The trace is not the output of a JavaScript tracer (e.g. the tracer is misconfigured or
+    /// not supported by the execution client)
+    #[error("unexpected geth trace: {inner}")]
+    Unexpected {
+        /// The inner error message
+        inner: String,
+    },
+
+    /// The output of the tracer doesn't have the expected shape
+    #[error("failed to decode geth trace: {inner}")]
+    Decode {
+        /// The inner error message
+        inner: String,
+    },
+}
+
+impl TraceError {
+    /// Converts the raw output of a JavaScript tracer into the tracer result.
+    pub(crate) fn decode<T: DeserializeOwned>(val: GethTrace) -> Result<T, Self> {
+        match val {
+            GethTrace::Known(val) => Err(TraceError::Unexpected { inner: format!("{val:?}") }),
+            GethTrace::Unknown(val) => serde_json::from_value(val.clone())
+                .map_err(|error| TraceError::Decode { inner: format!("{error}, {val:#}") }),
+        }
+    }
+}
+
+/// Fragments (lowercase, without whitespace) of the provider error messages caused by transient
+/// transport failures
+const TRANSIENT_ERRORS: [&str; 6] = [
+    "timeout",
+    "timedout",
+    "connectionreset",
+    "connectionrefused",
+    "connectionclosed",
+    "brokenpipe",
+];
+
+/// Errors returned by the execution clients when the state of the requested block was pruned
+/// (lowercased, without whitespaces and underscores)
+const MISSING_STATE_ERRORS: [&str; 5] = [
+    "missingtrienode",
+    "headernotfound",
+    "historicalstate",
+    "statenotavailable",
+    "stateunavailable",
+];
+
 impl EntryPointError {
+    /// Whether the error is caused by a transient transport failure (e.g. timeout or connection
+    /// reset), so the request may succeed if retried.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Provider { inner } => {
+                let inner = inner
+                    .chars()
+                    .filter(|c| !c.is_whitespace() && *c != '_')
+                    .collect::<String>()
+                    .to_lowercase();
+                TRANSIENT_ERRORS.iter().any(|err| inner.contains(err))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the error is caused by the provider missing the state of the requested block (the
+    /// provider is not an archive node).
+    pub fn is_missing_state(&self) -> bool {
+        match self {
+            Self::Provider { inner } => {
+                let inner = inner
+                    .chars()
+                    .filter(|c| !c.is_whitespace() && *c != '_')
+                    .collect::<String>()
+                    .to_lowercase();
+                MISSING_STATE_ERRORS.iter().any(|err| inner.contains(err))
+            }
+            _ => false,
+        }
+    }
+
     pub fn from_provider_error(err: &ProviderError) -> Result<EntryPointAPIErrors, Self> {
         match err {
             ProviderError::JsonRpcClientError(err) => err
@@ -168,6 +253,50 @@ pub fn decode_revert_error(data: Bytes) -> Result<EntryPointAPIErrors, EntryPoin
 mod tests {
     use super::*;
 
+    #[test]
+    fn transient_errors() {
+        for inner in [
+            "HTTP error: reqwest::Error { kind: Request, source: TimedOut }",
+            "middleware error: request timed out",
+            "unknown provider error: Connection reset by peer",
+            "HTTP error: ConnectError(\"tcp connect error\", Os { kind: ConnectionRefused })",
+        ] {
+            assert!(EntryPointError::Provider { inner: inner.into() }.is_transient(), "{inner}");
+        }
+
+        assert!(!EntryPointError::Provider { inner: "failed to decode revert error".into() }
+            .is_transient());
+        assert!(!EntryPointError::Other { inner: "timeout".into() }.is_transient());
+    }
+
+    #[test]
+    fn missing_state_errors() {
+        for inner in [
+            "json-rpc error doesn't contain data field: JsonRpcError { code: -32000, message: \"missing trie node 1f2c (path ) state 0x1f2c is not available\", data: None }",
+            "json-rpc error doesn't contain data field: JsonRpcError { code: -32000, message: \"required historical state unavailable (reexec=128)\", data: None }",
+            "json-rpc error doesn't contain data field: JsonRpcError { code: -32000, message: \"header not found\", data: None }",
+        ] {
+            let err = EntryPointError::Provider { inner: inner.into() };
+            assert!(err.is_missing_state(), "{inner}");
+            assert!(!err.is_transient(), "{inner}");
+        }
+
+        assert!(!EntryPointError::Provider { inner: "connection reset".into() }.is_missing_state());
+        assert!(!EntryPointError::Other { inner: "missing trie node".into() }.is_missing_state());
+    }
+
+    #[test]
+    fn malformed_trace() {
+        let trace = GethTrace::Unknown(serde_json::json!({
+            "callsFromEntryPoint": [],
+            "keccak": "0x",
+        }));
+        assert!(matches!(
+            crate::tracer::JsTracerFrame::try_from(trace),
+            Err(TraceError::Decode { .. })
+        ));
+    }
+
     #[test]
     fn deserialize_error_msg() -> eyre::Result<()> {
         let err_msg = Bytes::from_str("0x0000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000001841413934206761732076616c756573206f766572666c6f770000000000000000")?;
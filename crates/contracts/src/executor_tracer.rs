@@ -1,5 +1,5 @@
+use crate::error::TraceError;
 use ethers::types::GethTrace;
-use eyre::format_err;
 use serde::Deserialize;
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
@@ -24,13 +24,9 @@ pub struct ExecutorTracerResult {
     pub error: String,
 }
 impl TryFrom<GethTrace> for ExecutorTracerResult {
-    type Error = eyre::Error;
+    type Error = TraceError;
     fn try_from(val: GethTrace) -> Result<Self, Self::Error> {
-        match val {
-            GethTrace::Known(val) => Err(format_err!("Invalid geth trace: {val:?}")),
-            GethTrace::Unknown(val) => serde_json::from_value(val.clone())
-                .map_err(|error| format_err!("Failed to parse geth trace: {error}, {val:#}")),
-        }
+        TraceError::decode(val)
     }
 }
 pub const EXECUTOR_TRACER: &str = r#"
@@ -8,7 +8,8 @@ pub mod tracer;
 pub mod utils;
 
 pub use entry_point::EntryPoint;
-pub use error::{decode_revert_string, EntryPointError};
+pub use error::{decode_revert_string, EntryPointError, TraceError};
 pub use gen::{
-    ExecutionResult, FailedOp, UserOperationEventFilter, UserOperationRevertReasonFilter,
+    ExecutionResult, FailedOp, GasPriceOracleAPI, NodeInterfaceAPI, UserOperationEventFilter,
+    UserOperationRevertReasonFilter,
 };
@@ -1,5 +1,5 @@
+use crate::error::TraceError;
 use ethers::types::{Address, Bytes, GethTrace, U256};
-use eyre::format_err;
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -15,13 +15,9 @@ pub struct JsTracerFrame {
 }
 
 impl TryFrom<GethTrace> for JsTracerFrame {
-    type Error = eyre::Error;
+    type Error = TraceError;
     fn try_from(val: GethTrace) -> Result<Self, Self::Error> {
-        match val {
-            GethTrace::Known(val) => Err(format_err!("Invalid geth trace: {val:?}")),
-            GethTrace::Unknown(val) => serde_json::from_value(val.clone())
-                .map_err(|error| format_err!("Failed to parse geth trace: {error}, {val:#}")),
-        }
+        TraceError::decode(val)
     }
 }
 
@@ -38,6 +34,9 @@ pub struct TopLevelCallInfo {
     #[serde(rename = "extCodeAccessInfo")]
     pub ext_code_access_info: HashMap<Address, String>,
     pub oog: Option<bool>,
+    /// Program counters of the `GAS` opcodes not immediately followed by a *CALL opcode
+    #[serde(rename = "gasOpcodePcs", default)]
+    pub gas_opcode_pcs: Vec<u64>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
@@ -94,6 +93,7 @@ pub const JS_TRACER: &str = r#"
     logs: [],
     debug: [],
     lastOp: '',
+    lastPc: 0,
     lastThreeOpcodes: [],
     // event sent after all validations are done: keccak("BeforeExecution()")
     stopCollectingTopic: 'bb47ee3e183a558b1a2ff0874b079f3fc5478b7454eacf2bfc5af2ff5878f972',
@@ -203,7 +203,8 @@ pub const JS_TRACER: &str = r#"
                     access: {},
                     opcodes: {},
                     extCodeAccessInfo: {},
-                    contractSize: {}
+                    contractSize: {},
+                    gasOpcodePcs: []
                 };
                 this.topLevelCallCounter++;
             } else if (opcode === 'LOG1') {
@@ -255,9 +256,10 @@ pub const JS_TRACER: &str = r#"
             }
         }
         // [OP-012] - GAS opcode is allowed, but only if followed immediately by *CALL instructions
-        if (this.lastOp === 'GAS' && !opcode.includes('CALL')) {
+        if (this.lastOp === 'GAS' && opcode.match(/^(CALL|CALLCODE|DELEGATECALL|STATICCALL)$/) == null) {
             // count "GAS" opcode only if not followed by "CALL"
             this.countSlot(this.currentLevel.opcodes, 'GAS');
+            this.currentLevel.gasOpcodePcs.push(this.lastPc);
         }
         if (opcode !== 'GAS') {
             // ignore "unimportant" opcodes:
@@ -266,6 +268,7 @@ pub const JS_TRACER: &str = r#"
             }
         }
         this.lastOp = opcode;
+        this.lastPc = log.getPC();
         if (opcode === 'SLOAD' || opcode === 'SSTORE') {
             const slot = toWord(log.stack.peek(0).toString(16));
             const slotHex = toHex(slot);
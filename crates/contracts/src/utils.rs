@@ -1,5 +1,13 @@
-use crate::gen::entry_point_api::{self, EntryPointAPICalls};
-use ethers::{abi::AbiDecode, types::Bytes};
+use crate::gen::{
+    aggregator_api,
+    entry_point_api::{self, EntryPointAPICalls},
+    UserOperationEventFilter,
+};
+use ethers::{
+    abi::{AbiDecode, RawLog},
+    contract::EthEvent,
+    types::{Address, Bytes, Log},
+};
 use silius_primitives::UserOperationSigned;
 
 impl From<UserOperationSigned> for entry_point_api::UserOperation {
@@ -38,6 +46,24 @@ impl From<entry_point_api::UserOperation> for UserOperationSigned {
     }
 }
 
+impl From<UserOperationSigned> for aggregator_api::UserOperation {
+    fn from(uo: UserOperationSigned) -> Self {
+        Self {
+            sender: uo.sender,
+            nonce: uo.nonce,
+            init_code: uo.init_code,
+            call_data: uo.call_data,
+            call_gas_limit: uo.call_gas_limit,
+            verification_gas_limit: uo.verification_gas_limit,
+            pre_verification_gas: uo.pre_verification_gas,
+            max_fee_per_gas: uo.max_fee_per_gas,
+            max_priority_fee_per_gas: uo.max_priority_fee_per_gas,
+            paymaster_and_data: uo.paymaster_and_data,
+            signature: uo.signature,
+        }
+    }
+}
+
 pub fn parse_from_input_data(data: Bytes) -> Option<Vec<UserOperationSigned>> {
     EntryPointAPICalls::decode(data).ok().and_then(|call| match call {
         EntryPointAPICalls::HandleOps(ops) => {
@@ -47,6 +73,27 @@ pub fn parse_from_input_data(data: Bytes) -> Option<Vec<UserOperationSigned>> {
     })
 }
 
+/// Decodes the `UserOperationEvent` logs emitted by the entry point (the other logs are skipped).
+///
+/// # Arguments
+/// * `entry_point` - The address of the entry point
+/// * `logs` - The logs (e.g. of a block)
+///
+/// # Returns
+/// * `Vec<UserOperationEventFilter>` - The decoded events
+pub fn parse_user_operation_events(
+    entry_point: &Address,
+    logs: &[Log],
+) -> Vec<UserOperationEventFilter> {
+    logs.iter()
+        .filter(|log| {
+            log.address == *entry_point &&
+                log.topics.first() == Some(&UserOperationEventFilter::signature())
+        })
+        .filter_map(|log| UserOperationEventFilter::decode_log(&RawLog::from(log.clone())).ok())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
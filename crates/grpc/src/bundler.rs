@@ -80,8 +80,11 @@ where
         // FIXME: Because currently the bundler support multiple bundler and
         // we don't have a way to know which bundler is the one that is
         Ok((
-            user_operations.first().expect("At least one bundler must be present").to_vec(),
-            tx_hashes.into_iter().next().expect("At least one bundler must be present"),
+            user_operations
+                .into_iter()
+                .next()
+                .ok_or_else(|| eyre::eyre!("At least one bundler must be present"))?,
+            tx_hashes.into_iter().next().flatten(),
         ))
     }
 
@@ -169,6 +172,15 @@ where
         &self,
         _req: Request<()>,
     ) -> Result<Response<SendBundleNowResponse>, Status> {
+        // in auto mode the bundles are sent on the interval, so the manual trigger is a no-op
+        if self.is_running() {
+            return Ok(Response::new(SendBundleNowResponse {
+                res: None,
+                result: SendBundleNowResult::AutoMode.into(),
+                uos: vec![],
+            }));
+        }
+
         let (uos, tx_hash) = self
             .send_bundles()
             .await
@@ -189,7 +201,7 @@ where
                         self.uopool_grpc_client
                             .clone()
                             .remove(Request::new(RemoveRequest {
-                                uos: uos.into_iter().map(|uo| uo.into()).collect(),
+                                uos: uos.iter().map(|uo| uo.clone().into()).collect(),
                                 ep: Some(
                                     self.bundlers
                                         .first()
@@ -206,7 +218,11 @@ where
             }
         }
 
-        Ok(Response::new(SendBundleNowResponse { res: Some(tx_hash.unwrap_or_default().into()) }))
+        Ok(Response::new(SendBundleNowResponse {
+            res: Some(tx_hash.unwrap_or_default().into()),
+            result: SendBundleNowResult::Sent.into(),
+            uos: uos.into_iter().map(|uo| uo.into()).collect(),
+        }))
     }
 }
 
@@ -257,3 +273,42 @@ pub fn bundler_service_run<M, S>(
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::{MockProvider, Provider};
+    use silius_bundler::EthereumClient;
+    use tonic::transport::Channel;
+
+    #[tokio::test]
+    async fn send_bundle_now_bundling_mode() {
+        // no bundlers, so a manual bundle can't be sent (without touching the network)
+        let service =
+            &BundlerService::<Provider<MockProvider>, EthereumClient<Provider<MockProvider>>>::new(
+                vec![],
+                UoPoolClient::new(Channel::from_static("http://127.0.0.1:3002").connect_lazy()),
+            );
+        let set_mode = move |mode: Mode| {
+            bundler_server::Bundler::set_bundler_mode(
+                service,
+                Request::new(SetModeRequest { mode: mode.into(), interval: 10 }),
+            )
+        };
+
+        set_mode(Mode::Auto).await.unwrap();
+        assert!(service.is_running());
+        let res = bundler_server::Bundler::send_bundle_now(service, Request::new(()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(res.result(), SendBundleNowResult::AutoMode);
+        assert!(res.res.is_none() && res.uos.is_empty());
+
+        set_mode(Mode::Manual).await.unwrap();
+        assert!(!service.is_running());
+        let status =
+            bundler_server::Bundler::send_bundle_now(service, Request::new(())).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+}
@@ -0,0 +1,13 @@
+//! Gas price oracle suggesting the fees returned along with the gas estimates.
+use async_trait::async_trait;
+use ethers::types::U256;
+
+/// Suggests the fees (`max_fee_per_gas` and `max_priority_fee_per_gas`) of user operations.
+#[async_trait]
+pub trait GasOracle: Send + Sync {
+    /// Gets the suggested fees.
+    ///
+    /// # Returns
+    /// The suggested `max_fee_per_gas` and `max_priority_fee_per_gas`.
+    async fn suggest_fees(&self) -> eyre::Result<(U256, U256)>;
+}
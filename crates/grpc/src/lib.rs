@@ -1,10 +1,12 @@
 #![allow(dead_code)]
 
 mod bundler;
+mod gas_oracle;
 mod proto;
 mod uopool;
 mod utils;
 
 pub use bundler::{bundler_service_run, BundlerService};
+pub use gas_oracle::GasOracle;
 pub use proto::{bundler::*, types::*, uopool::*};
-pub use uopool::{uopool_service_run, UoPoolService};
+pub use uopool::{uopool_service_run, UoPoolService, UoPoolServiceConfig};
@@ -425,7 +425,20 @@ pub mod types {
 }
 
 pub mod uopool {
+    use silius_mempool::validate::metrics;
+
     tonic::include_proto!("uopool");
+
+    impl From<metrics::ValidationPhase> for ValidationPhase {
+        fn from(value: metrics::ValidationPhase) -> Self {
+            match value {
+                metrics::ValidationPhase::Sanity => Self::Sanity,
+                metrics::ValidationPhase::Simulation => Self::Simulation,
+                metrics::ValidationPhase::SimulationTrace => Self::SimulationTrace,
+                metrics::ValidationPhase::Execution => Self::Execution,
+            }
+        }
+    }
 }
 
 pub mod bundler {
@@ -1,33 +1,54 @@
 use crate::{
+    gas_oracle::GasOracle,
     proto::{
-        types::{GetChainIdResponse, GetSupportedEntryPointsResponse},
+        types::{GetChainIdResponse, GetSupportedEntryPointsResponse, SupportedEntryPoint},
         uopool::*,
     },
-    utils::{parse_addr, parse_hash, parse_uo},
+    utils::{parse_addr, parse_hash, parse_state_override, parse_uo, serialize_data},
 };
 use alloy_chains::Chain;
 use async_trait::async_trait;
+use enumset::EnumSet;
 use ethers::{
     providers::Middleware,
     types::{Address, U256},
 };
 use eyre::Result;
-use futures::{channel::mpsc::unbounded, StreamExt};
-use parking_lot::RwLock;
+use futures::{channel::mpsc::unbounded, Stream, StreamExt};
+use parking_lot::{Mutex, RwLock};
 use silius_mempool::{
-    mempool_id, validate::validator::StandardUserOperationValidator, Mempool, MempoolErrorKind,
-    MempoolId, Reputation, SanityCheck, SimulationCheck, SimulationTraceCheck,
-    UoPool as UserOperationPool, UoPoolBuilder,
+    mempool_id,
+    validate::{
+        sanity::denylist::Denylist, validator::StandardUserOperationValidator,
+        UserOperationValidatorMode,
+    },
+    Mempool, MempoolErrorKind, MempoolId, Reputation, SanityCheck, SimulationCheck,
+    SimulationTraceCheck, UoPool as UserOperationPool, UoPoolBuilder,
 };
 use silius_metrics::grpc::MetricsLayer;
 use silius_p2p::{
     config::Config,
     service::{MempoolChannel, Network},
 };
-use silius_primitives::{p2p::NetworkMessage, provider::BlockStream, UoPoolMode};
-use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
+use silius_primitives::{
+    constants::grpc::{HEALTH_CHECK_CACHE_TTL, MAX_CONCURRENT_VALIDATIONS, SUBSCRIPTION_CAPACITY},
+    p2p::NetworkMessage,
+    provider::BlockStream,
+    UoPoolMode, UserOperation, UserOperationHash, UserOperationRequest,
+};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    Semaphore, SemaphorePermit,
+};
 use tonic::{Code, Request, Response, Status};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 type StandardUserPool<M, SanCk, SimCk, SimTrCk> =
     UserOperationPool<M, StandardUserOperationValidator<M, SanCk, SimCk, SimTrCk>>;
@@ -43,7 +64,24 @@ where
     SimTrCk: SimulationTraceCheck<M>,
 {
     pub uopools: UoPoolMaps<M, SanCk, SimCk, SimTrCk>,
-    pub chain: Chain,
+    /// Chain of the user operation pools (changed by [rekey_mempools](Self::rekey_mempools))
+    pub chain: Arc<RwLock<Chain>>,
+    /// Publishes the hash and the entry point of every user operation added through the service
+    pub added: broadcast::Sender<(UserOperationHash, Address)>,
+    /// Validation phases per entry point (all phases for entry points not in the map)
+    pub validator_modes: HashMap<Address, EnumSet<UserOperationValidatorMode>>,
+    /// Bounds the number of concurrent validations (simulations), excess requests are queued
+    pub validations: Arc<Semaphore>,
+    /// Client used by the health check (the provider is reported unreachable if not set)
+    pub eth_client: Option<Arc<M>>,
+    /// Last health check result and the time it was computed
+    pub health: Arc<Mutex<Option<(Instant, HealthCheckResponse)>>>,
+    /// Oracle suggesting the fees returned along with the gas estimates (the fee history of the
+    /// provider is used if not set)
+    pub gas_oracle: Option<Arc<dyn GasOracle>>,
+    /// Denied addresses updated through the debug endpoint (shared with the [Denylist] sanity
+    /// check of the validators)
+    pub denylist: Option<Denylist>,
 }
 
 impl<M, SanCk, SimCk, SimTrCk> UoPoolService<M, SanCk, SimCk, SimTrCk>
@@ -54,7 +92,200 @@ where
     SimTrCk: SimulationTraceCheck<M> + Clone + 'static,
 {
     pub fn new(uopools: UoPoolMaps<M, SanCk, SimCk, SimTrCk>, chain: Chain) -> Self {
-        Self { uopools, chain }
+        let (added, _) = broadcast::channel(SUBSCRIPTION_CAPACITY);
+        Self {
+            uopools,
+            chain: Arc::new(RwLock::new(chain)),
+            added,
+            validator_modes: HashMap::new(),
+            validations: Arc::new(Semaphore::new(MAX_CONCURRENT_VALIDATIONS)),
+            eth_client: None,
+            health: Arc::new(Mutex::new(None)),
+            gas_oracle: None,
+            denylist: None,
+        }
+    }
+
+    /// Sets the client whose connectivity is reported by the health check.
+    pub fn with_eth_client(mut self, eth_client: Arc<M>) -> Self {
+        self.eth_client = Some(eth_client);
+        self
+    }
+
+    /// Checks whether the provider is reachable by fetching the latest block number. The result
+    /// is cached for [HEALTH_CHECK_CACHE_TTL] seconds.
+    ///
+    /// # Returns
+    /// The [HealthCheckResponse](HealthCheckResponse), degraded (with the error message) if the
+    /// provider call fails.
+    async fn health_check_cached(&self) -> HealthCheckResponse {
+        let cached = self
+            .health
+            .lock()
+            .as_ref()
+            .filter(|(checked_at, _)| {
+                checked_at.elapsed() < Duration::from_secs(HEALTH_CHECK_CACHE_TTL)
+            })
+            .map(|(_, res)| res.clone());
+        if let Some(res) = cached {
+            return res;
+        }
+
+        let block_number = match self.eth_client.as_ref() {
+            Some(eth_client) => eth_client.get_block_number().await.map_err(|err| err.to_string()),
+            None => Err("Ethereum client is not configured".into()),
+        };
+
+        let mut res =
+            HealthCheckResponse { chain_id: self.chain.read().id(), ..Default::default() };
+        match block_number {
+            Ok(block_number) => {
+                res.set_status(HealthStatus::Healthy);
+                res.block_number = block_number.as_u64();
+            }
+            Err(err) => {
+                res.set_status(HealthStatus::Degraded);
+                res.error = err;
+            }
+        }
+
+        *self.health.lock() = Some((Instant::now(), res.clone()));
+        res
+    }
+
+    /// Sets the denylist updated through the `UpdateDenylist` endpoint. The lists are shared with
+    /// the clones of the denylist, so pass a clone of the [Denylist] sanity check of the
+    /// validators.
+    pub fn with_denylist(mut self, denylist: Denylist) -> Self {
+        self.denylist = Some(denylist);
+        self
+    }
+
+    /// Sets the oracle suggesting the fees returned along with the gas estimates.
+    pub fn with_gas_oracle(mut self, gas_oracle: Arc<dyn GasOracle>) -> Self {
+        self.gas_oracle = Some(gas_oracle);
+        self
+    }
+
+    /// Gets the suggested fees from the [GasOracle], or from the fee history of the provider if
+    /// no oracle is set.
+    ///
+    /// # Returns
+    /// The suggested `max_fee_per_gas` and `max_priority_fee_per_gas`, None if the fees can't be
+    /// suggested (the estimation still succeeds without them).
+    async fn suggest_fees(&self) -> Option<(U256, U256)> {
+        let fees = match (self.gas_oracle.as_ref(), self.eth_client.as_ref()) {
+            (Some(gas_oracle), _) => gas_oracle.suggest_fees().await,
+            (None, Some(eth_client)) => {
+                eth_client.estimate_eip1559_fees(None).await.map_err(|err| eyre::eyre!("{err}"))
+            }
+            (None, None) => return None,
+        };
+
+        fees.map_err(|err| warn!("Failed to suggest fees: {err:?}")).ok()
+    }
+
+    /// Sets the maximum number of validations (simulations) run concurrently.
+    pub fn with_max_concurrent_validations(mut self, max_concurrent_validations: usize) -> Self {
+        self.validations = Arc::new(Semaphore::new(max_concurrent_validations));
+        self
+    }
+
+    /// Waits for a free validation slot. The slot is released when the permit is dropped.
+    async fn validation_permit(&self) -> tonic::Result<SemaphorePermit<'_>> {
+        self.validations
+            .acquire()
+            .await
+            .map_err(|err| Status::internal(format!("Validation slot unavailable: {err}")))
+    }
+
+    /// Sets the validation phases run for the user operations sent to the given entry points.
+    pub fn with_validator_modes(
+        mut self,
+        validator_modes: HashMap<Address, EnumSet<UserOperationValidatorMode>>,
+    ) -> Self {
+        self.validator_modes = validator_modes;
+        self
+    }
+
+    /// Gets the validation phases run for the user operations sent to the entry point.
+    fn validator_mode(&self, ep: &Address) -> EnumSet<UserOperationValidatorMode> {
+        validator_mode(&self.validator_modes, ep)
+    }
+
+    /// Switches the service to a new chain ID (e.g. after a fork) and recomputes the
+    /// [mempool IDs](mempool_id) of all user operation pools. The mempool and the reputation of an
+    /// entry point are owned by the same pool, so they are always rekeyed together. The tasks
+    /// spawned for the pools follow the new chain as well. The method is exposed through the
+    /// `RekeyMempools` endpoint.
+    ///
+    /// The pools are rekeyed into a new map which replaces the current one (along with the chain
+    /// of the service) only after all pools were migrated, so a failure (e.g. the provider being
+    /// unreachable during the re-validation) leaves the service on the previous chain.
+    ///
+    /// # Arguments
+    /// `new_chain_id` - The new chain ID.
+    /// `migrate` - Whether the user operations and the reputation entries are kept (otherwise the
+    /// stale mempools and reputation are cleared). The hash of a user operation commits to the
+    /// chain ID, so the migrated user operations are rehashed and validated again on the new chain
+    /// (e.g. their signatures are checked against the new hash), the invalid ones are dropped.
+    ///
+    /// # Returns
+    /// The previous and the new hashes of the migrated user operations (None for the dropped ones).
+    pub async fn rekey_mempools(
+        &self,
+        new_chain_id: U256,
+        migrate: bool,
+    ) -> Result<Vec<(UserOperationHash, Option<UserOperationHash>)>, MempoolErrorKind> {
+        let chain = Chain::from(new_chain_id.as_u64());
+        let prev_chain = *self.chain.read();
+
+        let mut uopools = self
+            .uopools
+            .read()
+            .values()
+            .map(|builder| (builder.uopool_on(chain), vec![]))
+            .collect::<Vec<_>>();
+        if migrate {
+            for (uopool, rehashed) in uopools.iter_mut() {
+                *rehashed = uopool.revalidate_rehashed_user_operations(prev_chain).await?;
+            }
+        }
+
+        let mut migrated = vec![];
+        for (mut uopool, rehashed) in uopools {
+            if migrate {
+                uopool.migrate_user_operations(&rehashed)?;
+                migrated.extend(
+                    rehashed
+                        .into_iter()
+                        .map(|uo| (uo.prev_hash, uo.revalidated.map(|(uo, _)| uo.hash))),
+                );
+            } else {
+                uopool.clear();
+            }
+        }
+
+        let mut uopools = self.uopools.write();
+        let rekeyed = uopools
+            .drain()
+            .map(|(_, builder)| {
+                let builder = builder.with_chain(chain);
+                (mempool_id(&builder.entry_point_address(), chain.id()), builder)
+            })
+            .collect();
+        *uopools = rekeyed;
+
+        info!("Rekeyed {} mempools to chain {chain}", uopools.len());
+        *self.chain.write() = chain;
+        Ok(migrated)
+    }
+
+    /// Notifies the subscribers about the added user operation. Slow subscribers never block the
+    /// sender, they lose the oldest notifications instead.
+    fn notify_added(&self, uo_hash: UserOperationHash, ep: Address) {
+        // sending fails only if there are no subscribers
+        let _ = self.added.send((uo_hash, ep));
     }
 
     #[allow(clippy::type_complexity)]
@@ -62,7 +293,7 @@ where
         &self,
         ep: &Address,
     ) -> tonic::Result<StandardUserPool<M, SanCk, SimCk, SimTrCk>> {
-        let m_id = mempool_id(ep, self.chain.id());
+        let m_id = mempool_id(ep, self.chain.read().id());
         self.uopools
             .read()
             .get(&m_id)
@@ -79,25 +310,51 @@ where
     SimCk: SimulationCheck + Clone + 'static,
     SimTrCk: SimulationTraceCheck<M> + Clone + 'static,
 {
+    type SubscribeAddedStream =
+        Pin<Box<dyn Stream<Item = Result<SubscribeAddedResponse, Status>> + Send + 'static>>;
+
     async fn add(&self, req: Request<AddRequest>) -> Result<Response<AddResponse>, Status> {
         let req = req.into_inner();
 
         let uo = parse_uo(req.uo)?;
         let ep = parse_addr(req.ep)?;
 
+        let mut uopool = self.get_uopool(&ep)?;
+        let uo = uopool.hash_user_operation(uo.user_operation).map_err(|e| {
+            Status::new(Code::InvalidArgument, format!("Invalid user operation: {e}"))
+        })?;
+
+        // resubmissions of identical user operations aren't validated again
+        if uopool.mempool.contains(&uo.hash) {
+            return Ok(Response::new(AddResponse {
+                res: AddResult::AlreadyKnown as i32,
+                data: serde_json::to_string(&uo.hash)
+                    .map_err(|err| Status::internal(format!("Failed to serialize hash: {err}")))?,
+            }));
+        }
+
         let res = {
-            let uopool = self.get_uopool(&ep)?;
-            uopool.validate_user_operation(&uo, None).await
+            let _permit = self.validation_permit().await?;
+            uopool.validate_user_operation(&uo, None, self.validator_mode(&ep)).await
         };
 
-        let mut uopool = self.get_uopool(&ep)?;
+        // the user operation isn't rejected if the provider is down, the client can retry
+        if let Err(err) = res.as_ref() {
+            if err.is_rpc_down() {
+                return Err(Status::unavailable(format!("Provider unavailable: {err}")));
+            }
+        }
 
         match uopool.add_user_operation(uo, res).await {
-            Ok(uo_hash) => Ok(Response::new(AddResponse {
-                res: AddResult::Added as i32,
-                data: serde_json::to_string(&uo_hash)
-                    .map_err(|err| Status::internal(format!("Failed to serialize hash: {err}")))?,
-            })),
+            Ok(uo_hash) => {
+                self.notify_added(uo_hash, ep);
+                Ok(Response::new(AddResponse {
+                    res: AddResult::Added as i32,
+                    data: serde_json::to_string(&uo_hash).map_err(|err| {
+                        Status::internal(format!("Failed to serialize hash: {err}"))
+                    })?,
+                }))
+            }
             Err(err) => match err.kind {
                 MempoolErrorKind::InvalidUserOperation(_) => Ok(Response::new(AddResponse {
                     res: AddResult::NotAdded as i32,
@@ -121,23 +378,49 @@ where
         Ok(Response::new(()))
     }
 
+    async fn remove_by_hash(
+        &self,
+        req: Request<RemoveByHashRequest>,
+    ) -> Result<Response<RemoveByHashResponse>, Status> {
+        let req = req.into_inner();
+
+        let ep = parse_addr(req.ep)?;
+        let mut uopool = self.get_uopool(&ep)?;
+
+        let uo_hashes: Vec<UserOperationHash> =
+            req.hashes.into_iter().map(|hash| hash.into()).collect();
+
+        let (removed, not_found) = uopool
+            .remove_user_operations_by_hash(&uo_hashes)
+            .map_err(|err| Status::internal(format!("Remove by hash internal error: {err:?}")))?;
+
+        Ok(Response::new(RemoveByHashResponse {
+            removed: removed as u64,
+            not_found: not_found as u64,
+        }))
+    }
+
     async fn get_chain_id(
         &self,
         _req: Request<()>,
     ) -> Result<Response<GetChainIdResponse>, Status> {
-        Ok(Response::new(GetChainIdResponse { chain_id: self.chain.id() }))
+        Ok(Response::new(GetChainIdResponse { chain_id: self.chain.read().id() }))
     }
 
     async fn get_supported_entry_points(
         &self,
         _req: Request<()>,
     ) -> Result<Response<GetSupportedEntryPointsResponse>, Status> {
+        let uopools = self.uopools.read();
+
         Ok(Response::new(GetSupportedEntryPointsResponse {
-            eps: self
-                .uopools
-                .read()
+            eps: uopools.values().map(|builder| builder.entry_point_address().into()).collect(),
+            entry_points: uopools
                 .values()
-                .map(|mempool| mempool.uopool().entry_point.address().into())
+                .map(|builder| SupportedEntryPoint {
+                    ep: Some(builder.entry_point_address().into()),
+                    version: builder.entry_point_version().into(),
+                })
                 .collect(),
         }))
     }
@@ -150,21 +433,38 @@ where
 
         let uo = parse_uo(req.uo)?;
         let ep = parse_addr(req.ep)?;
+        let overrides = parse_state_override(&req.state_override)?;
 
         let uopool = self.get_uopool(&ep)?;
-
-        Ok(Response::new(match uopool.estimate_user_operation_gas(&uo).await {
-            Ok(gas) => EstimateUserOperationGasResponse {
-                res: EstimateUserOperationGasResult::Estimated as i32,
-                data: serde_json::to_string(&gas)
-                    .map_err(|err| Status::internal(format!("Failed to serialize gas: {err}")))?,
-            },
-            Err(err) => EstimateUserOperationGasResponse {
-                res: EstimateUserOperationGasResult::NotEstimated as i32,
-                data: serde_json::to_string(&err)
-                    .map_err(|err| Status::internal(format!("Failed to serialize error: {err}")))?,
+        let uo = uopool.hash_user_operation(uo.user_operation).map_err(|e| {
+            Status::new(Code::InvalidArgument, format!("Invalid user operation: {e}"))
+        })?;
+        let _permit = self.validation_permit().await?;
+
+        Ok(Response::new(
+            match uopool.estimate_user_operation_gas_with_overrides(&uo, overrides).await {
+                Ok(mut gas) => {
+                    if let Some((max_fee_per_gas, max_priority_fee_per_gas)) =
+                        self.suggest_fees().await
+                    {
+                        gas.max_fee_per_gas = Some(max_fee_per_gas);
+                        gas.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+                    }
+                    EstimateUserOperationGasResponse {
+                        res: EstimateUserOperationGasResult::Estimated as i32,
+                        data: serde_json::to_string(&gas).map_err(|err| {
+                            Status::internal(format!("Failed to serialize gas: {err}"))
+                        })?,
+                    }
+                }
+                Err(err) => EstimateUserOperationGasResponse {
+                    res: EstimateUserOperationGasResult::NotEstimated as i32,
+                    data: serde_json::to_string(&err).map_err(|err| {
+                        Status::internal(format!("Failed to serialize error: {err}"))
+                    })?,
+                },
             },
-        }))
+        ))
     }
 
     async fn get_sorted_user_operations(
@@ -177,9 +477,10 @@ where
 
         let uos = {
             let uopool = self.get_uopool(&ep)?;
-            uopool.get_sorted_user_operations().map_err(|e| {
-                tonic::Status::internal(format!("Get sorted uos internal error: {e:?}"))
-            })?
+            uopool
+                .select_user_operations()
+                .await
+                .map_err(|e| tonic::Status::internal(format!("Select uos internal error: {e:?}")))?
         };
 
         let (uos_valid, storage_map) = {
@@ -205,24 +506,49 @@ where
         let uo_hash = parse_hash(req.hash)?;
 
         let keys: Vec<MempoolId> = self.uopools.read().keys().cloned().collect();
-        for key in keys {
+
+        // pending user operations (still in the mempool)
+        for key in keys.iter() {
             let uopool = {
                 let uopools_ref = self.uopools.read();
-                let uopool_builder = uopools_ref.get(&key).expect("key must exist");
+                let uopool_builder = uopools_ref.get(key).expect("key must exist");
+                uopool_builder.uopool()
+            };
+            if let Ok(Some(uo)) = uopool.mempool.get(&uo_hash.into()) {
+                return Ok(Response::new(GetUserOperationByHashResponse {
+                    user_operation: Some(uo.user_operation.into()),
+                    entry_point: Some(uopool.entry_point.address().into()),
+                    pending: true,
+                    res: GetUserOperationByHashResult::Found as i32,
+                    ..Default::default()
+                }));
+            }
+        }
+
+        // user operations already included on chain
+        for key in keys.iter() {
+            let uopool = {
+                let uopools_ref = self.uopools.read();
+                let uopool_builder = uopools_ref.get(key).expect("key must exist");
                 uopool_builder.uopool()
             };
             if let Ok(uo_by_hash) = uopool.get_user_operation_by_hash(&uo_hash.into()).await {
                 return Ok(Response::new(GetUserOperationByHashResponse {
                     user_operation: Some(uo_by_hash.user_operation.into()),
                     entry_point: Some(uo_by_hash.entry_point.into()),
-                    transaction_hash: Some(uo_by_hash.transaction_hash.into()),
-                    block_hash: Some(uo_by_hash.block_hash.into()),
-                    block_number: uo_by_hash.block_number.as_u64(),
+                    transaction_hash: uo_by_hash.transaction_hash.map(|hash| hash.into()),
+                    block_hash: uo_by_hash.block_hash.map(|hash| hash.into()),
+                    block_number: uo_by_hash.block_number.unwrap_or_default().as_u64(),
+                    pending: false,
+                    res: GetUserOperationByHashResult::Found as i32,
                 }));
             }
         }
 
-        Err(tonic::Status::not_found("User operation not found"))
+        Ok(Response::new(GetUserOperationByHashResponse {
+            res: GetUserOperationByHashResult::NotFound as i32,
+            ..Default::default()
+        }))
     }
 
     async fn get_user_operation_receipt(
@@ -239,7 +565,7 @@ where
                 let uopool_builder = uopools_ref.get(&key).expect("key must exist");
                 uopool_builder.uopool()
             };
-            if let Ok(uo_receipt) = uopool.get_user_operation_receipt(&uo_hash.into()).await {
+            if let Ok(Some(uo_receipt)) = uopool.get_user_operation_receipt(&uo_hash.into()).await {
                 return Ok(Response::new(GetUserOperationReceiptResponse {
                     user_operation_hash: Some(uo_receipt.user_operation_hash.into()),
                     sender: Some(uo_receipt.sender.into()),
@@ -274,6 +600,24 @@ where
         }
     }
 
+    async fn dump_mempool(
+        &self,
+        req: Request<DumpMempoolRequest>,
+    ) -> Result<Response<DumpMempoolResponse>, Status> {
+        let req = req.into_inner();
+
+        let ep = parse_addr(req.ep)?;
+        let uopool = self.get_uopool(&ep)?;
+        match uopool.get_sorted_user_operations().await {
+            Ok(uos) => Ok(Response::new(DumpMempoolResponse {
+                data: dump_user_operations(uos).map_err(|err| {
+                    Status::internal(format!("Failed to serialize user operations: {err}"))
+                })?,
+            })),
+            Err(err) => Err(Status::unknown(format!("Internal error: {err:?}"))),
+        }
+    }
+
     async fn clear_mempool(&self, _req: Request<()>) -> Result<Response<()>, Status> {
         self.uopools.read().values().for_each(|uopool| {
             uopool.uopool().clear_mempool();
@@ -339,7 +683,11 @@ where
 
         let res = Response::new(AddMempoolResponse {
             res: match uopool
-                .add_user_operations(req.uos.into_iter().map(|uo| uo.into()).collect(), None)
+                .add_user_operations(
+                    req.uos.into_iter().map(|uo| uo.into()).collect(),
+                    None,
+                    self.validator_mode(&ep),
+                )
                 .await
             {
                 Ok(_) => AddMempoolResult::AddedMempool as i32,
@@ -350,6 +698,27 @@ where
         Ok(res)
     }
 
+    async fn get_validation_outcome(
+        &self,
+        req: Request<GetValidationOutcomeRequest>,
+    ) -> Result<Response<GetValidationOutcomeResponse>, Status> {
+        let req = req.into_inner();
+
+        let ep = parse_addr(req.ep)?;
+        let uo_hash = parse_hash(req.hash)?;
+        let uopool = self.get_uopool(&ep)?;
+
+        match uopool.get_validation_outcome(&uo_hash.into()) {
+            Some(out) => Ok(Response::new(GetValidationOutcomeResponse {
+                pre_fund: Some(out.pre_fund.into()),
+                verification_gas_limit: Some(out.verification_gas_limit.into()),
+                staked: out.staked,
+                aggregator: out.aggregator.map(|aggregator| aggregator.into()),
+            })),
+            None => Err(Status::not_found("User operation not found in the mempool")),
+        }
+    }
+
     async fn get_stake_info(
         &self,
         req: Request<GetStakeInfoRequest>,
@@ -369,22 +738,220 @@ where
             is_staked: res.is_staked,
         }))
     }
+
+    async fn get_reputation_status(
+        &self,
+        req: Request<GetReputationStatusRequest>,
+    ) -> Result<Response<GetReputationStatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let ep = parse_addr(req.ep)?;
+        let addr = parse_addr(req.addr)?;
+        let uopool = self.get_uopool(&ep)?;
+
+        let entry = uopool.get_reputation_entry(&addr).map_err(|e| {
+            tonic::Status::internal(format!("Get reputation status internal error: {e}"))
+        })?;
+
+        Ok(Response::new(match entry {
+            Some(entry) => {
+                let stake_info = uopool.get_stake_info(&addr).await.map_err(|e| {
+                    tonic::Status::internal(format!("Get stake info internal error: {e}"))
+                })?;
+                GetReputationStatusResponse {
+                    res: GetReputationStatusResult::FoundEntity as i32,
+                    entry: Some(entry.into()),
+                    is_staked: stake_info.is_staked,
+                }
+            }
+            None => GetReputationStatusResponse {
+                res: GetReputationStatusResult::NotFoundEntity as i32,
+                entry: None,
+                is_staked: false,
+            },
+        }))
+    }
+
+    async fn validate_user_operation(
+        &self,
+        req: Request<ValidateUserOperationRequest>,
+    ) -> Result<Response<ValidateUserOperationResponse>, Status> {
+        let req = req.into_inner();
+
+        let format = req.format();
+        let uo = parse_uo(req.uo)?;
+        let ep = parse_addr(req.ep)?;
+
+        // the detached validator keeps the shared caches untouched
+        let mut uopool = self.get_uopool(&ep)?;
+        uopool.validator = uopool.validator.detached();
+        let uo = uopool.hash_user_operation(uo.user_operation).map_err(|e| {
+            Status::new(Code::InvalidArgument, format!("Invalid user operation: {e}"))
+        })?;
+
+        Ok(Response::new(match uopool.dry_run_user_operation(&uo).await {
+            Ok(out) => {
+                let (data, data_bin) = serialize_data(&out, format)?;
+                ValidateUserOperationResponse {
+                    res: ValidateUserOperationResult::Valid as i32,
+                    data,
+                    data_bin,
+                    ..Default::default()
+                }
+            }
+            Err((phase, err)) => {
+                let (data, data_bin) = serialize_data(&err, format)?;
+                ValidateUserOperationResponse {
+                    res: ValidateUserOperationResult::Invalid as i32,
+                    data,
+                    data_bin,
+                    phase: ValidationPhase::from(phase) as i32,
+                }
+            }
+        }))
+    }
+
+    async fn subscribe_added(
+        &self,
+        _req: Request<()>,
+    ) -> Result<Response<Self::SubscribeAddedStream>, Status> {
+        let stream = futures::stream::unfold(self.added.subscribe(), |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok((uo_hash, ep)) => {
+                        return Some((
+                            Ok(SubscribeAddedResponse {
+                                hash: Some(uo_hash.into()),
+                                ep: Some(ep.into()),
+                            }),
+                            rx,
+                        ))
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Subscriber lagged behind, skipped {skipped} added user operations")
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn update_denylist(
+        &self,
+        req: Request<UpdateDenylistRequest>,
+    ) -> Result<Response<()>, Status> {
+        let req = req.into_inner();
+
+        let denylist = self
+            .denylist
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Denylist is not enabled"))?;
+        let list = match req.entity() {
+            DenylistEntity::DenylistSender => &denylist.senders,
+            DenylistEntity::DenylistPaymaster => &denylist.paymasters,
+            DenylistEntity::DenylistFactory => &denylist.factories,
+        };
+
+        let mut list = list.write();
+        for addr in req.add {
+            list.insert(addr.into());
+        }
+        for addr in req.remove {
+            list.remove(&Address::from(addr));
+        }
+
+        Ok(Response::new(()))
+    }
+
+    async fn rekey_mempools(
+        &self,
+        req: Request<RekeyMempoolsRequest>,
+    ) -> Result<Response<RekeyMempoolsResponse>, Status> {
+        let req = req.into_inner();
+
+        let rehashed = self
+            .rekey_mempools(U256::from(req.chain_id), req.migrate)
+            .await
+            .map_err(|e| Status::internal(format!("Rekey mempools error: {e:?}")))?;
+
+        Ok(Response::new(RekeyMempoolsResponse {
+            rehashed: rehashed
+                .into_iter()
+                .map(|(prev_hash, hash)| RehashedUserOperation {
+                    prev_hash: Some(prev_hash.into()),
+                    hash: hash.map(|hash| hash.into()),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn health_check(
+        &self,
+        _req: Request<()>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        Ok(Response::new(self.health_check_cached().await))
+    }
+
+    async fn get_stats(&self, _req: Request<()>) -> Result<Response<GetStatsResponse>, Status> {
+        let uopools = self.uopools.read();
+
+        Ok(Response::new(GetStatsResponse {
+            stats: uopools
+                .values()
+                .map(|builder| {
+                    let stats = builder.uopool().mempool.get_stats();
+                    MempoolStats {
+                        ep: Some(builder.entry_point_address().into()),
+                        pending: stats.pending as u64,
+                        senders: stats.senders as u64,
+                        paymasters: stats.paymasters as u64,
+                        size: stats.size as u64,
+                    }
+                })
+                .collect(),
+        }))
+    }
+}
+
+/// Options of the UoPool gRPC service started by [uopool_service_run].
+#[derive(Clone)]
+pub struct UoPoolServiceConfig {
+    /// Address the gRPC server listens on
+    pub addr: SocketAddr,
+    /// The [UoPoolMode] of the user operation pools
+    pub mode: UoPoolMode,
+    /// The [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID
+    pub chain: Chain,
+    /// The maximum gas limit for user operation gas verification
+    pub max_verification_gas: U256,
+    /// Configuration of the p2p network (None if not enabled)
+    pub p2p_config: Option<Config>,
+    /// Whether the gRPC metrics are collected
+    pub enable_metrics: bool,
+    /// Validation phases run per entry point (all phases for the entry points not listed)
+    pub validator_modes: HashMap<Address, EnumSet<UserOperationValidatorMode>>,
+    /// Maximum number of user operations validated concurrently
+    pub max_concurrent_validations: usize,
+    /// The maximum number of blocks queried in a single `eth_getLogs` call
+    pub max_log_range: u64,
+    /// The gas reserved for the paymaster `postOp` call during the gas estimation
+    pub post_op_gas: U256,
+    /// Interval and batch size of the periodic revalidation of the mempool (None if disabled)
+    pub revalidation: Option<(Duration, usize)>,
+    /// Denylist shared with the validator, updated by the `UpdateDenylist` endpoint
+    pub denylist: Option<Denylist>,
 }
 
-#[allow(clippy::too_many_arguments)]
 pub async fn uopool_service_run<M, SanCk, SimCk, SimTrCk>(
-    addr: SocketAddr,
-    mode: UoPoolMode,
+    config: UoPoolServiceConfig,
     eps: Vec<Address>,
     eth_client: Arc<M>,
     block_streams: Vec<BlockStream>,
-    chain: Chain,
-    max_verification_gas: U256,
     mempool: Mempool,
     reputation: Reputation,
     validator: StandardUserOperationValidator<M, SanCk, SimCk, SimTrCk>,
-    p2p_config: Option<Config>,
-    enable_metrics: bool,
 ) -> Result<()>
 where
     M: Middleware + Clone + 'static,
@@ -392,6 +959,21 @@ where
     SimCk: SimulationCheck + Clone + 'static,
     SimTrCk: SimulationTraceCheck<M> + Clone + 'static,
 {
+    let UoPoolServiceConfig {
+        addr,
+        mode,
+        chain,
+        max_verification_gas,
+        p2p_config,
+        enable_metrics,
+        validator_modes,
+        max_concurrent_validations,
+        max_log_range,
+        post_op_gas,
+        revalidation,
+        denylist,
+    } = config;
+
     tokio::spawn(async move {
         let mut builder = tonic::transport::Server::builder();
 
@@ -416,20 +998,40 @@ where
                     reputation.clone(),
                     validator.clone(),
                     Some(mempool_sender),
-                );
+                )
+                .with_max_log_range(max_log_range)
+                .with_post_op_gas(post_op_gas);
                 uo_builder.register_block_updates(block_stream);
                 uo_builder.register_reputation_updates();
+                if let Some((interval, batch_size)) = revalidation {
+                    uo_builder.register_revalidation(interval, batch_size);
+                }
 
                 let (network_sender, mut network_receiver) = unbounded::<NetworkMessage>();
                 let mut uo_pool = uo_builder.uopool();
+                let uo_chain = uo_builder.chain_handle();
+                let mode = validator_mode(&validator_modes, &ep);
 
                 // spawn a task which would consume user operations received from p2p network
                 tokio::spawn(async move {
                     while let Some(msg) = network_receiver.next().await {
                         if let NetworkMessage::Validate { user_operation, validation_config } = msg
                         {
+                            UoPoolBuilder::follow_chain(&uo_chain, &mut uo_pool);
+                            let user_operation =
+                                match uo_pool.hash_user_operation(user_operation.user_operation) {
+                                    Ok(user_operation) => user_operation,
+                                    Err(e) => {
+                                        error!("Invalid user operation from p2p: {e:?}");
+                                        continue;
+                                    }
+                                };
                             let res = uo_pool
-                                .validate_user_operation(&user_operation, Some(validation_config))
+                                .validate_user_operation(
+                                    &user_operation,
+                                    Some(validation_config),
+                                    mode,
+                                )
                                 .await;
                             match uo_pool.add_user_operation(user_operation, res).await {
                                 Ok(_) => {}
@@ -471,17 +1073,27 @@ where
                     reputation.clone(),
                     validator.clone(),
                     None,
-                );
+                )
+                .with_max_log_range(max_log_range)
+                .with_post_op_gas(post_op_gas);
                 uo_builder.register_block_updates(block_stream);
                 uo_builder.register_reputation_updates();
+                if let Some((interval, batch_size)) = revalidation {
+                    uo_builder.register_revalidation(interval, batch_size);
+                }
                 m_map.insert(id, uo_builder);
             }
         };
 
         let uopool_map = Arc::new(RwLock::new(m_map));
-        let svc = uo_pool_server::UoPoolServer::new(
-            UoPoolService::<M, SanCk, SimCk, SimTrCk>::new(uopool_map, chain),
-        );
+        let mut service = UoPoolService::<M, SanCk, SimCk, SimTrCk>::new(uopool_map, chain)
+            .with_validator_modes(validator_modes)
+            .with_max_concurrent_validations(max_concurrent_validations)
+            .with_eth_client(eth_client);
+        if let Some(denylist) = denylist {
+            service = service.with_denylist(denylist);
+        }
+        let svc = uo_pool_server::UoPoolServer::new(service);
 
         if enable_metrics {
             builder.layer(MetricsLayer).add_service(svc).serve(addr).await
@@ -494,3 +1106,859 @@ where
 
     Ok(())
 }
+
+/// Gets the validation phases run for the user operations sent to the entry point (all phases
+/// unless configured otherwise).
+fn validator_mode(
+    validator_modes: &HashMap<Address, EnumSet<UserOperationValidatorMode>>,
+    ep: &Address,
+) -> EnumSet<UserOperationValidatorMode> {
+    validator_modes.get(ep).copied().unwrap_or_else(EnumSet::all)
+}
+
+/// Serializes the user operations into the JSON array returned by `debug_bundler_dumpMempool`.
+/// The user operations are expected in the order in which they are bundled.
+fn dump_user_operations(uos: Vec<UserOperation>) -> serde_json::Result<String> {
+    serde_json::to_string(
+        &uos.into_iter()
+            .map(|uo| UserOperationRequest::from(uo.user_operation))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::AbiEncode,
+        providers::{JsonRpcError, MockProvider, MockResponse, Provider},
+        types::{Block, H256},
+    };
+    use silius_contracts::{entry_point::ValidationResult, EntryPoint};
+    use silius_mempool::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::{simulation::signature::Signature, UserOperationValidationOutcome},
+    };
+    use silius_primitives::{
+        constants::validation::reputation::{
+            BAN_SLACK, MIN_INCLUSION_RATE_DENOMINATOR, THROTTLING_SLACK,
+        },
+        reputation::ReputationEntry,
+        simulation::CodeHash,
+        UserOperationSigned,
+    };
+    use std::{
+        collections::HashSet,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    #[tokio::test]
+    async fn remove_by_hash_unsupported_entry_point() {
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        );
+
+        let status = uo_pool_server::UoPool::remove_by_hash(
+            &service,
+            Request::new(RemoveByHashRequest {
+                hashes: vec![ethers::types::H256::random().into()],
+                ep: Some(Address::random().into()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(status.code(), Code::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn remove_by_hash_missing_entry_point() {
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        );
+
+        let status = uo_pool_server::UoPool::remove_by_hash(
+            &service,
+            Request::new(RemoveByHashRequest { hashes: vec![], ep: None }),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn remove_by_hash_not_found() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let (mut mempool, ep) = (shared_mempool(), Address::random());
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::from([(
+                mempool_id(&ep, Chain::dev().id()),
+                uopool_builder(Arc::new(provider), ep, mempool.clone(), memory_reputation()),
+            )]))),
+            Chain::dev(),
+        );
+
+        let uo_signed = UserOperationSigned::default().sender(Address::random());
+        let uo_hash = uo_signed.hash(&ep, Chain::dev().id());
+        mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo_signed)).unwrap();
+
+        let res = uo_pool_server::UoPool::remove_by_hash(
+            &service,
+            Request::new(RemoveByHashRequest {
+                hashes: vec![uo_hash.into(), H256::random().into()],
+                ep: Some(ep.into()),
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+
+        assert_eq!((res.removed, res.not_found), (1, 1));
+        assert!(mempool.get_all().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_user_operation_by_hash_not_found() {
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        );
+
+        let res = uo_pool_server::UoPool::get_user_operation_by_hash(
+            &service,
+            Request::new(UserOperationHashRequest {
+                hash: Some(ethers::types::H256::random().into()),
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+
+        assert_eq!(res.res, GetUserOperationByHashResult::NotFound as i32);
+        assert!(res.user_operation.is_none());
+        assert!(!res.pending);
+    }
+
+    #[tokio::test]
+    async fn get_user_operation_by_hash_pending() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let mut mempool = shared_mempool();
+        let ep = Address::random();
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::from([(
+                mempool_id(&ep, Chain::dev().id()),
+                uopool_builder(Arc::new(provider), ep, mempool.clone(), shared_reputation()),
+            )]))),
+            Chain::dev(),
+        );
+
+        let uo_signed = UserOperationSigned::default().sender(Address::random());
+        let uo = UserOperation::from_user_operation_signed(
+            uo_signed.hash(&ep, Chain::dev().id()),
+            uo_signed,
+        );
+        mempool.add(uo.clone()).unwrap();
+
+        let res = uo_pool_server::UoPool::get_user_operation_by_hash(
+            &service,
+            Request::new(UserOperationHashRequest { hash: Some(uo.hash.into()) }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+
+        assert_eq!(res.res, GetUserOperationByHashResult::Found as i32);
+        assert!(res.pending);
+        assert_eq!(res.entry_point, Some(ep.into()));
+        assert!(res.transaction_hash.is_none());
+        assert!(res.block_hash.is_none());
+    }
+
+    #[tokio::test]
+    async fn subscribe_added_receives_added() {
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        );
+
+        let mut stream = uo_pool_server::UoPool::subscribe_added(&service, Request::new(()))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let uo_hash = UserOperationHash::from(ethers::types::H256::random());
+        let ep = Address::random();
+        service.notify_added(uo_hash, ep);
+
+        let res = stream.next().await.unwrap().unwrap();
+        assert_eq!(res.hash, Some(uo_hash.into()));
+        assert_eq!(res.ep, Some(ep.into()));
+    }
+
+    #[tokio::test]
+    async fn subscribe_added_slow_subscriber() {
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        );
+
+        let mut stream = uo_pool_server::UoPool::subscribe_added(&service, Request::new(()))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // the sender never blocks, the oldest notifications are dropped instead
+        let ep = Address::random();
+        let uo_hashes: Vec<UserOperationHash> =
+            (0..SUBSCRIPTION_CAPACITY + 1).map(|_| ethers::types::H256::random().into()).collect();
+        for uo_hash in uo_hashes.iter() {
+            service.notify_added(*uo_hash, ep);
+        }
+
+        let res = stream.next().await.unwrap().unwrap();
+        assert_eq!(res.hash, Some(uo_hashes[1].into()));
+    }
+
+    #[tokio::test]
+    async fn validate_user_operation_unsupported_entry_point() {
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        );
+
+        let status = uo_pool_server::UoPool::validate_user_operation(
+            &service,
+            Request::new(ValidateUserOperationRequest {
+                uo: Some(
+                    UserOperation::from_user_operation_signed(
+                        Default::default(),
+                        UserOperationSigned::default(),
+                    )
+                    .into(),
+                ),
+                ep: Some(Address::random().into()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(status.code(), Code::Unavailable);
+    }
+
+    fn uopool_builder(
+        eth_client: Arc<Provider<MockProvider>>,
+        ep: Address,
+        mempool: Mempool,
+        reputation: Reputation,
+    ) -> UoPoolBuilder<Provider<MockProvider>, (), (Signature,), ()> {
+        UoPoolBuilder::new(
+            UoPoolMode::Standard,
+            eth_client.clone(),
+            ep,
+            Chain::dev(),
+            U256::from(5_000_000),
+            mempool,
+            reputation,
+            StandardUserOperationValidator::new(
+                EntryPoint::new(eth_client, ep),
+                Chain::dev(),
+                (),
+                (Signature,),
+                (),
+                None,
+            ),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn get_supported_entry_points_versions() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let eth_client = Arc::new(provider);
+        let (mempool, reputation) = (memory_mempool(), memory_reputation());
+
+        let versions = HashMap::from([(Address::random(), "0.6.0"), (Address::random(), "0.6.1")]);
+        let uopools: HashMap<_, _> = versions
+            .iter()
+            .map(|(ep, version)| {
+                let builder =
+                    uopool_builder(eth_client.clone(), *ep, mempool.clone(), reputation.clone())
+                        .with_entry_point_version(*version)
+                        .unwrap();
+                (mempool_id(ep, Chain::dev().id()), builder)
+            })
+            .collect();
+
+        // the user operations of entry point v0.7 can't be validated yet
+        assert!(uopool_builder(eth_client.clone(), Address::random(), mempool, reputation)
+            .with_entry_point_version("0.7.0")
+            .is_err());
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(uopools)),
+            Chain::dev(),
+        );
+
+        let res = uo_pool_server::UoPool::get_supported_entry_points(&service, Request::new(()))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(res.eps.len(), versions.len());
+        assert_eq!(res.entry_points.len(), versions.len());
+        for (ep, supported) in res.eps.into_iter().zip(res.entry_points) {
+            let ep: Address = ep.into();
+            assert_eq!(supported.ep.map(Address::from), Some(ep));
+            assert_eq!(supported.version, versions[&ep]);
+        }
+    }
+
+    #[tokio::test]
+    async fn add_provider_down() {
+        // the mock provider has no responses so fetching the latest block fails
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let (ep, mempool) = (Address::random(), memory_mempool());
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::from([(
+                mempool_id(&ep, Chain::dev().id()),
+                uopool_builder(Arc::new(provider), ep, mempool.clone(), memory_reputation()),
+            )]))),
+            Chain::dev(),
+        );
+
+        let status = uo_pool_server::UoPool::add(
+            &service,
+            Request::new(AddRequest {
+                uo: Some(
+                    UserOperation::from_user_operation_signed(
+                        Default::default(),
+                        UserOperationSigned::default().sender(Address::random()),
+                    )
+                    .into(),
+                ),
+                ep: Some(ep.into()),
+            }),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(status.code(), Code::Unavailable);
+        assert!(mempool.get_all().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn add_already_known() {
+        // the mock provider has no responses, so the duplicate is rejected before any validation
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let (mut mempool, ep) = (shared_mempool(), Address::random());
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::from([(
+                mempool_id(&ep, Chain::dev().id()),
+                uopool_builder(Arc::new(provider), ep, mempool.clone(), memory_reputation()),
+            )]))),
+            Chain::dev(),
+        );
+
+        let uo_signed = UserOperationSigned::default().sender(Address::random());
+        let uo_hash = uo_signed.hash(&ep, Chain::dev().id());
+        assert!(!mempool.contains(&uo_hash));
+        mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo_signed.clone())).unwrap();
+        assert!(mempool.contains(&uo_hash));
+
+        // the hash sent by the client is ignored
+        let res = uo_pool_server::UoPool::add(
+            &service,
+            Request::new(AddRequest {
+                uo: Some(
+                    UserOperation::from_user_operation_signed(Default::default(), uo_signed).into(),
+                ),
+                ep: Some(ep.into()),
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+
+        assert_eq!(res.res, AddResult::AlreadyKnown as i32);
+        assert_eq!(serde_json::from_str::<UserOperationHash>(&res.data).unwrap(), uo_hash);
+        assert_eq!(mempool.get_all().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn get_reputation_status_not_found() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let ep = Address::random();
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::from([(
+                mempool_id(&ep, Chain::dev().id()),
+                uopool_builder(Arc::new(provider), ep, memory_mempool(), memory_reputation()),
+            )]))),
+            Chain::dev(),
+        );
+
+        let res = uo_pool_server::UoPool::get_reputation_status(
+            &service,
+            Request::new(GetReputationStatusRequest {
+                addr: Some(Address::random().into()),
+                ep: Some(ep.into()),
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+
+        assert_eq!(res.res, GetReputationStatusResult::NotFoundEntity as i32);
+        assert!(res.entry.is_none());
+        assert!(!res.is_staked);
+    }
+
+    #[tokio::test]
+    async fn health_check_provider_failure() {
+        let (eth_client, _) = Provider::mocked();
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        )
+        .with_eth_client(Arc::new(eth_client));
+
+        // the mock provider has no responses so the block number request fails
+        let res = uo_pool_server::UoPool::health_check(&service, Request::new(()))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(res.status, HealthStatus::Degraded as i32);
+        assert_eq!(res.chain_id, Chain::dev().id());
+        assert!(!res.error.is_empty());
+        assert!(service.health.lock().is_some());
+    }
+
+    /// Mempool with the storage shared between the clones (as in the bundler)
+    fn shared_mempool() -> Mempool {
+        Mempool::new(
+            Box::new(Arc::new(RwLock::new(
+                HashMap::<UserOperationHash, UserOperationSigned>::default(),
+            ))),
+            Box::new(Arc::new(RwLock::new(
+                HashMap::<Address, HashSet<UserOperationHash>>::default(),
+            ))),
+            Box::new(Arc::new(RwLock::new(
+                HashMap::<Address, HashSet<UserOperationHash>>::default(),
+            ))),
+            Box::new(Arc::new(RwLock::new(HashMap::<UserOperationHash, Vec<CodeHash>>::default()))),
+        )
+    }
+
+    /// Reputation with the storage shared between the clones (as in the bundler)
+    fn shared_reputation() -> Reputation {
+        Reputation::new(
+            MIN_INCLUSION_RATE_DENOMINATOR,
+            THROTTLING_SLACK,
+            BAN_SLACK,
+            U256::from(1),
+            U256::from(0),
+            Arc::new(RwLock::new(HashSet::<Address>::default())),
+            Arc::new(RwLock::new(HashSet::<Address>::default())),
+            Box::new(Arc::new(RwLock::new(HashMap::<Address, ReputationEntry>::default()))),
+        )
+    }
+
+    #[tokio::test]
+    async fn clear_reputation_keeps_mempool() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let (mut mempool, mut reputation) = (shared_mempool(), shared_reputation());
+        let ep = Address::random();
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::from([(
+                mempool_id(&ep, Chain::dev().id()),
+                uopool_builder(Arc::new(provider), ep, mempool.clone(), reputation.clone()),
+            )]))),
+            Chain::dev(),
+        );
+
+        let uo_signed = UserOperationSigned::default().sender(Address::random());
+        let uo = UserOperation::from_user_operation_signed(
+            uo_signed.hash(&ep, Chain::dev().id()),
+            uo_signed,
+        );
+        mempool.add(uo.clone()).unwrap();
+        reputation.increment_seen(&uo.sender).unwrap();
+
+        uo_pool_server::UoPool::clear_reputation(&service, Request::new(())).await.unwrap();
+
+        assert_eq!(mempool.get_all().unwrap(), vec![uo.clone()]);
+        assert!(reputation.get_all().unwrap().is_empty());
+
+        uo_pool_server::UoPool::clear(&service, Request::new(())).await.unwrap();
+        assert!(mempool.get_all().unwrap().is_empty());
+    }
+
+    /// Mocks the validation of a user operation on the latest block, the simulation reports
+    /// whether the signature is valid and the prefund
+    fn mock_validation(mock: &MockProvider, sig_failed: bool, pre_fund: U256) {
+        // the mocked responses are returned in reverse order
+        let mut res = ValidationResult::default();
+        res.return_info.1 = pre_fund;
+        res.return_info.2 = sig_failed;
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: 3,
+            message: "execution reverted".into(),
+            data: Some(serde_json::json!(ethers::types::Bytes::from(AbiEncode::encode(res)))),
+        }));
+        mock.push(Block::<H256> {
+            hash: Some(H256::random()),
+            number: Some(1.into()),
+            ..Default::default()
+        })
+        .unwrap();
+    }
+
+    /// Service with a single user operation pool, the user operation is added to its mempool
+    /// along with the entry of its sender in the reputation
+    fn rekey_service(
+        provider: Provider<MockProvider>,
+    ) -> (UoPoolService<Provider<MockProvider>, (), (Signature,), ()>, Address, UserOperation) {
+        let (mut mempool, mut reputation) = (shared_mempool(), shared_reputation());
+        let ep = Address::random();
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::from([(
+                mempool_id(&ep, Chain::dev().id()),
+                uopool_builder(Arc::new(provider), ep, mempool.clone(), reputation.clone()),
+            )]))),
+            Chain::dev(),
+        );
+
+        let uo_signed = UserOperationSigned::default().sender(Address::random());
+        let uo = UserOperation::from_user_operation_signed(
+            uo_signed.hash(&ep, Chain::dev().id()),
+            uo_signed,
+        );
+        mempool.add(uo.clone()).unwrap();
+        mempool.set_pre_fund(&uo.hash, U256::from(100));
+        reputation.increment_seen(&uo.sender).unwrap();
+
+        (service, ep, uo)
+    }
+
+    #[tokio::test]
+    async fn rekey_mempools_migrate() {
+        let (provider, mock) = Provider::<MockProvider>::mocked();
+        let (service, ep, uo) = rekey_service(provider);
+        mock_validation(&mock, false, U256::from(200));
+
+        let chain_id = Chain::dev().id() + 1;
+        let res = uo_pool_server::UoPool::rekey_mempools(
+            &service,
+            Request::new(RekeyMempoolsRequest { chain_id, migrate: true }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+
+        // the migrated user operation is rehashed and validated again on the new chain
+        let uo_hash = uo.user_operation.hash(&ep, chain_id);
+        assert_ne!(uo_hash, uo.hash);
+        assert_eq!(res.rehashed.len(), 1);
+        assert_eq!(res.rehashed[0].prev_hash, Some(uo.hash.into()));
+        assert_eq!(res.rehashed[0].hash, Some(uo_hash.into()));
+
+        assert_eq!(service.chain.read().id(), chain_id);
+        let uopools = service.uopools.read();
+        assert_eq!(uopools.keys().cloned().collect::<Vec<_>>(), vec![mempool_id(&ep, chain_id)]);
+        let uopool = uopools.get(&mempool_id(&ep, chain_id)).unwrap().uopool();
+        assert_eq!(uopool.chain.id(), chain_id);
+        assert_eq!(
+            uopool.get_all().unwrap(),
+            vec![UserOperation::from_user_operation_signed(uo_hash, uo.user_operation.clone())]
+        );
+        assert_eq!(uopool.mempool.get_pre_fund(&uo_hash), Some(U256::from(200)));
+        assert_eq!(uopool.reputation.get(&uo.sender).unwrap().uo_seen, 1);
+    }
+
+    #[tokio::test]
+    async fn rekey_mempools_migrate_invalid_signature() {
+        let (provider, mock) = Provider::<MockProvider>::mocked();
+        let (service, ep, uo) = rekey_service(provider);
+        mock_validation(&mock, true, U256::from(200));
+
+        let chain_id = Chain::dev().id() + 1;
+        let rehashed = service.rekey_mempools(U256::from(chain_id), true).await.unwrap();
+
+        // the signature commits to the previous hash, so the user operation is dropped
+        assert_eq!(rehashed, vec![(uo.hash, None)]);
+        assert_eq!(service.chain.read().id(), chain_id);
+        let uopool = service.uopools.read().get(&mempool_id(&ep, chain_id)).unwrap().uopool();
+        assert!(uopool.get_all().unwrap().is_empty());
+        assert_eq!(uopool.reputation.get(&uo.sender).unwrap().uo_seen, 1);
+    }
+
+    #[tokio::test]
+    async fn rekey_mempools_provider_failure() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let (service, ep, uo) = rekey_service(provider);
+
+        // the mock provider has no responses so the re-validation fails
+        let chain_id = Chain::dev().id() + 1;
+        assert!(service.rekey_mempools(U256::from(chain_id), true).await.is_err());
+
+        // the service stays on the previous chain with the mempool unchanged
+        assert_eq!(service.chain.read().id(), Chain::dev().id());
+        let uopools = service.uopools.read();
+        assert_eq!(
+            uopools.keys().cloned().collect::<Vec<_>>(),
+            vec![mempool_id(&ep, Chain::dev().id())]
+        );
+        let uopool = uopools.get(&mempool_id(&ep, Chain::dev().id())).unwrap().uopool();
+        assert_eq!(uopool.chain.id(), Chain::dev().id());
+        assert_eq!(uopool.get_all().unwrap(), vec![uo.clone()]);
+        assert_eq!(uopool.mempool.get_pre_fund(&uo.hash), Some(U256::from(100)));
+    }
+
+    #[tokio::test]
+    async fn rekey_mempools_clear() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let (mut mempool, reputation) = (shared_mempool(), shared_reputation());
+        let ep = Address::random();
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::from([(
+                mempool_id(&ep, Chain::dev().id()),
+                uopool_builder(Arc::new(provider), ep, mempool.clone(), reputation.clone()),
+            )]))),
+            Chain::dev(),
+        );
+
+        let uo_signed = UserOperationSigned::default().sender(Address::random());
+        mempool
+            .add(UserOperation::from_user_operation_signed(
+                uo_signed.hash(&ep, Chain::dev().id()),
+                uo_signed,
+            ))
+            .unwrap();
+
+        let chain_id = Chain::dev().id() + 1;
+        assert!(service.rekey_mempools(U256::from(chain_id), false).await.unwrap().is_empty());
+        assert!(mempool.get_all().unwrap().is_empty());
+        assert_eq!(service.chain.read().id(), chain_id);
+    }
+
+    #[tokio::test]
+    async fn update_denylist() {
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        );
+        let (paymaster, paymaster_other) = (Address::random(), Address::random());
+        let req = || UpdateDenylistRequest {
+            entity: DenylistEntity::DenylistPaymaster as i32,
+            add: vec![paymaster.into(), paymaster_other.into()],
+            remove: vec![],
+        };
+
+        let status = uo_pool_server::UoPool::update_denylist(&service, Request::new(req()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        let denylist = Denylist::default();
+        let service = service.with_denylist(denylist.clone());
+        uo_pool_server::UoPool::update_denylist(&service, Request::new(req())).await.unwrap();
+        uo_pool_server::UoPool::update_denylist(
+            &service,
+            Request::new(UpdateDenylistRequest { remove: vec![paymaster_other.into()], ..req() }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(*denylist.paymasters.read(), HashSet::from([paymaster]));
+        assert!(denylist.senders.read().is_empty());
+        assert!(denylist.factories.read().is_empty());
+    }
+
+    struct FixedGasOracle;
+
+    #[async_trait]
+    impl GasOracle for FixedGasOracle {
+        async fn suggest_fees(&self) -> eyre::Result<(U256, U256)> {
+            Ok((U256::from(30_000_000_000_u64), U256::from(2_000_000_000)))
+        }
+    }
+
+    #[tokio::test]
+    async fn suggest_fees_gas_oracle() {
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        );
+        assert_eq!(service.suggest_fees().await, None);
+
+        // the oracle takes precedence over the fee history of the provider
+        let (eth_client, _) = Provider::mocked();
+        let service =
+            service.with_eth_client(Arc::new(eth_client)).with_gas_oracle(Arc::new(FixedGasOracle));
+        assert_eq!(
+            service.suggest_fees().await,
+            Some((U256::from(30_000_000_000_u64), U256::from(2_000_000_000)))
+        );
+    }
+
+    #[tokio::test]
+    async fn get_stats_counts() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let (ep, mut mempool) = (Address::random(), memory_mempool());
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::from([(
+                mempool_id(&ep, Chain::dev().id()),
+                uopool_builder(Arc::new(provider), ep, mempool.clone(), memory_reputation()),
+            )]))),
+            Chain::dev(),
+        );
+
+        let (sender, paymaster) = (Address::random(), Address::random());
+        let uos: Vec<UserOperation> = [
+            UserOperationSigned::default().sender(sender).nonce(0.into()),
+            UserOperationSigned::default()
+                .sender(sender)
+                .nonce(1.into())
+                .paymaster_and_data(paymaster.as_bytes().to_vec().into()),
+            UserOperationSigned::default().sender(Address::random()),
+        ]
+        .into_iter()
+        .map(|uo| UserOperation::from_user_operation_signed(uo.hash(&ep, 5), uo))
+        .collect();
+        for uo in uos.iter() {
+            mempool.add(uo.clone()).unwrap();
+        }
+
+        async fn stats(
+            service: &UoPoolService<Provider<MockProvider>, (), (Signature,), ()>,
+        ) -> Vec<MempoolStats> {
+            uo_pool_server::UoPool::get_stats(service, Request::new(()))
+                .await
+                .unwrap()
+                .into_inner()
+                .stats
+        }
+
+        let res = stats(&service).await;
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].ep, Some(ep.into()));
+        assert_eq!((res[0].pending, res[0].senders, res[0].paymasters), (3, 2, 1));
+        assert_eq!(res[0].size, uos.iter().map(|uo| uo.pack().len() as u64).sum::<u64>());
+
+        mempool.remove(&uos[1].hash).unwrap();
+        let res = stats(&service).await;
+        assert_eq!((res[0].pending, res[0].senders, res[0].paymasters), (2, 2, 0));
+
+        mempool.remove(&uos[2].hash).unwrap();
+        let res = stats(&service).await;
+        assert_eq!((res[0].pending, res[0].senders, res[0].paymasters), (1, 1, 0));
+        assert_eq!(res[0].size, uos[0].pack().len() as u64);
+    }
+
+    #[test]
+    fn validator_mode_per_entry_point() {
+        let (ep_full, ep_fast) = (Address::random(), Address::random());
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        )
+        .with_validator_modes(HashMap::from([(
+            ep_fast,
+            UserOperationValidatorMode::Sanity | UserOperationValidatorMode::Simulation,
+        )]));
+
+        assert_eq!(service.validator_mode(&ep_full), EnumSet::all());
+        assert!(!service
+            .validator_mode(&ep_fast)
+            .contains(UserOperationValidatorMode::SimulationTrace));
+        assert!(service.validator_mode(&ep_fast).contains(UserOperationValidatorMode::Simulation));
+    }
+
+    #[tokio::test]
+    async fn validation_permits_bound_concurrency() {
+        let service = UoPoolService::<Provider<MockProvider>, (), (Signature,), ()>::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            Chain::dev(),
+        )
+        .with_max_concurrent_validations(2);
+
+        let (running, max_running) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let res = futures::future::join_all((0..8).map(|_| async {
+            let _permit = service.validation_permit().await?;
+            let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(n, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok::<_, Status>(())
+        }))
+        .await;
+
+        // the requests over the limit are queued, not rejected
+        assert!(res.iter().all(|res| res.is_ok()));
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+        assert_eq!(service.validations.available_permits(), 2);
+    }
+
+    #[test]
+    fn dump_user_operations_format() {
+        let uo = UserOperationSigned {
+            sender: "0x1111111111111111111111111111111111111111".parse().unwrap(),
+            nonce: 1.into(),
+            call_data: "0x1234".parse().unwrap(),
+            call_gas_limit: 100_000.into(),
+            verification_gas_limit: 200_000.into(),
+            pre_verification_gas: 50_000.into(),
+            max_fee_per_gas: 3_000_000_000_u64.into(),
+            max_priority_fee_per_gas: 1_000_000_000.into(),
+            signature: "0xabcd".parse().unwrap(),
+            ..Default::default()
+        };
+        let uo = UserOperation::from_user_operation_signed(Default::default(), uo);
+
+        let dump: serde_json::Value =
+            serde_json::from_str(&dump_user_operations(vec![uo]).unwrap()).unwrap();
+        let golden: serde_json::Value =
+            serde_json::from_str(include_str!("../fixtures/dump_mempool.json")).unwrap();
+        assert_eq!(dump, golden);
+    }
+
+    #[test]
+    fn serialize_data_round_trip() {
+        let mut out = UserOperationValidationOutcome {
+            pre_fund: U256::from(1_000_000),
+            verification_gas_limit: U256::from(100_000),
+            aggregator: Some(Address::random()),
+            valid_after: Some(U256::from(1)),
+            code_hashes: Some(
+                (0..100)
+                    .map(|_| CodeHash { address: Address::random(), hash: H256::random() })
+                    .collect(),
+            ),
+            revert_reason: Some("revert".into()),
+            ..Default::default()
+        };
+        for _ in 0..100 {
+            let addr = Address::random();
+            out.storage_map.root_hashes.insert(addr, H256::random());
+            out.storage_map.slots.insert(
+                addr,
+                (0..100).map(|i| (format!("{i:#x}"), format!("{:?}", H256::random()))).collect(),
+            );
+        }
+        let expected = serde_json::to_value(&out).unwrap();
+
+        let (data, data_bin) = serialize_data(&out, DataFormat::Json).unwrap();
+        assert!(data_bin.is_empty());
+        let decoded: UserOperationValidationOutcome = serde_json::from_str(&data).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), expected);
+
+        let (data, data_bin) = serialize_data(&out, DataFormat::Bincode).unwrap();
+        assert!(data.is_empty());
+        let decoded: UserOperationValidationOutcome = bincode::deserialize(&data_bin).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), expected);
+    }
+}
@@ -1,4 +1,6 @@
-use ethers::types::{Address, H256};
+use crate::proto::uopool::DataFormat;
+use ethers::types::{spoof, Address, H256};
+use serde::Serialize;
 use silius_primitives::UserOperation;
 use tonic::{Code, Status};
 
@@ -22,3 +24,32 @@ pub fn parse_uo(uo: Option<crate::UserOperation>) -> Result<UserOperation, Statu
         None => Err(Status::new(Code::InvalidArgument, "User operation is not valid")),
     }
 }
+
+/// Parses the JSON encoded state overrides (an empty string means no overrides).
+pub fn parse_state_override(state_override: &str) -> Result<Option<spoof::State>, Status> {
+    if state_override.is_empty() {
+        return Ok(None);
+    }
+
+    serde_json::from_str(state_override).map(Some).map_err(|err| {
+        Status::new(Code::InvalidArgument, format!("State override is not valid: {err}"))
+    })
+}
+
+/// Serializes the value in the format requested by the client. JSON is returned in the `data`
+/// field (string) and bincode in the `data_bin` field (bytes) of the response. Only used for the
+/// ValidateUserOperation response, the values must not skip fields (e.g. `skip_serializing_if`),
+/// which bincode can't deserialize.
+pub fn serialize_data<T: Serialize>(
+    value: &T,
+    format: DataFormat,
+) -> Result<(String, Vec<u8>), Status> {
+    match format {
+        DataFormat::Json => serde_json::to_string(value)
+            .map(|data| (data, vec![]))
+            .map_err(|err| Status::internal(format!("Failed to serialize data: {err}"))),
+        DataFormat::Bincode => bincode::serialize(value)
+            .map(|data_bin| (String::new(), data_bin))
+            .map_err(|err| Status::internal(format!("Failed to serialize data: {err}"))),
+    }
+}
@@ -3,21 +3,31 @@ use crate::{
         validator::StandardUserOperationValidator, SanityCheck, SimulationCheck,
         SimulationTraceCheck,
     },
-    Mempool, Reputation, UoPool,
+    L1DataFee, L1Gas, Mempool, Reputation, UoPool, POST_OP_GAS,
 };
 use alloy_chains::Chain;
 use ethers::{
+    contract::EthEvent,
     providers::Middleware,
-    types::{Address, H256, U256},
+    types::{Address, Filter, H256, U256},
 };
+use eyre::format_err;
 use futures::channel::mpsc::UnboundedSender;
 use futures_util::StreamExt;
-use silius_contracts::EntryPoint;
+use parking_lot::RwLock;
+use silius_contracts::{utils::parse_user_operation_events, EntryPoint, UserOperationEventFilter};
 use silius_primitives::{
-    p2p::NetworkMessage, provider::BlockStream, UoPoolMode, UserOperation, UserOperationSigned,
+    constants::{
+        entry_point::VERSION,
+        mempool::{ESTIMATION_MAX_ITERATIONS, MAX_LOG_RANGE},
+        validation::reputation::DECAY_INTERVAL,
+    },
+    p2p::NetworkMessage,
+    provider::BlockStream,
+    user_operation_hasher, UoPoolMode, UserOperationHasher,
 };
 use std::{sync::Arc, time::Duration};
-use tracing::warn;
+use tracing::{debug, warn};
 
 type StandardUoPool<M, SanCk, SimCk, SimTrCk> =
     UoPool<M, StandardUserOperationValidator<M, SanCk, SimCk, SimTrCk>>;
@@ -32,11 +42,28 @@ where
     mode: UoPoolMode,
     eth_client: Arc<M>,
     entrypoint: Address,
-    chain: Chain,
+    // Version of the entry point (e.g. 0.6.0)
+    entrypoint_version: String,
+    // Hashing scheme of the user operations (selected by the version of the entry point)
+    hasher: Arc<dyn UserOperationHasher>,
+    // Chain of the user operation pool (shared with the spawned tasks, so they follow a rekey)
+    chain: Arc<RwLock<Chain>>,
     max_verification_gas: U256,
     mempool: Mempool,
     reputation: Reputation,
     validator: StandardUserOperationValidator<M, SanCk, SimCk, SimTrCk>,
+    // Oracle of the L1 data fee (None if not an OP-stack chain)
+    l1_data_fee: Option<L1DataFee>,
+    // Node interface estimating the L1 gas (None if not an Arbitrum chain)
+    l1_gas: Option<L1Gas>,
+    // Maximum number of simulations when searching for the call gas limit during the gas
+    // estimation
+    estimation_max_iterations: u64,
+    // Maximum number of blocks queried in a single `eth_getLogs` call
+    max_log_range: u64,
+    // Gas reserved for the paymaster `postOp` call of sponsored user operations during the gas
+    // estimation
+    post_op_gas: U256,
     // Channel to publish to p2p network (None if not enabled)
     network: Option<UnboundedSender<NetworkMessage>>,
 }
@@ -64,42 +91,137 @@ where
             mode,
             eth_client,
             entrypoint,
-            chain,
+            entrypoint_version: VERSION.into(),
+            hasher: user_operation_hasher(VERSION),
+            chain: Arc::new(RwLock::new(chain)),
             max_verification_gas,
             mempool,
             reputation,
             validator,
+            l1_data_fee: L1DataFee::from_chain(&chain),
+            l1_gas: L1Gas::from_chain(&chain),
+            estimation_max_iterations: ESTIMATION_MAX_ITERATIONS,
+            max_log_range: MAX_LOG_RANGE,
+            post_op_gas: U256::from(POST_OP_GAS),
             network,
         }
     }
 
+    /// Overrides the `GasPriceOracle` used to get the L1 data fee (e.g. for forks of the OP-stack
+    /// with the oracle at another address). Passing None disables the L1 data fee estimation.
+    pub fn with_l1_data_fee(mut self, l1_data_fee: Option<L1DataFee>) -> Self {
+        self.l1_data_fee = l1_data_fee;
+        self
+    }
+
+    /// Overrides the `NodeInterface` used to get the L1 gas (e.g. for Arbitrum chains not known
+    /// by [L1Gas::from_chain]). Passing None disables the L1 gas estimation.
+    pub fn with_l1_gas(mut self, l1_gas: Option<L1Gas>) -> Self {
+        self.l1_gas = l1_gas;
+        self
+    }
+
+    /// Sets the maximum number of simulations of the binary search for the call gas limit during
+    /// the gas estimation.
+    pub fn with_estimation_max_iterations(mut self, estimation_max_iterations: u64) -> Self {
+        self.estimation_max_iterations = estimation_max_iterations;
+        self
+    }
+
+    /// Sets the maximum number of blocks queried in a single `eth_getLogs` call (larger block
+    /// ranges are split into chunks).
+    pub fn with_max_log_range(mut self, max_log_range: u64) -> Self {
+        self.max_log_range = max_log_range;
+        self
+    }
+
+    /// Sets the gas reserved for the paymaster `postOp` call of sponsored user operations during
+    /// the gas estimation (defaults to [POST_OP_GAS](POST_OP_GAS)).
+    pub fn with_post_op_gas(mut self, post_op_gas: U256) -> Self {
+        self.post_op_gas = post_op_gas;
+        self
+    }
+
+    /// Sets the version of the entry point (defaults to [VERSION](VERSION)) and the hashing scheme
+    /// of the user operations matching the version. The validator simulates the user operations
+    /// against entry point v0.6 only, so the other versions (e.g. 0.7.0) are rejected until their
+    /// user operations can be validated.
+    pub fn with_entry_point_version(mut self, version: impl Into<String>) -> eyre::Result<Self> {
+        let version = version.into();
+        if !version.starts_with("0.6") {
+            return Err(format_err!(
+                "Entry point version {version} is not supported (its user operations can't be validated)"
+            ));
+        }
+
+        self.hasher = user_operation_hasher(&version);
+        self.entrypoint_version = version;
+        Ok(self)
+    }
+
+    /// Sets the chain of the user operation pool and its validator (e.g. after the chain ID
+    /// changed). The tasks spawned by the builder switch to the chain on their next run.
+    pub fn with_chain(mut self, chain: Chain) -> Self {
+        *self.chain.write() = chain;
+        self.validator = self.validator.with_chain(chain);
+        self
+    }
+
+    /// Gets the chain of the user operation pool.
+    pub fn chain(&self) -> Chain {
+        *self.chain.read()
+    }
+
+    /// Gets the chain shared with the tasks spawned by the builder (updated by
+    /// [with_chain](UoPoolBuilder::with_chain)).
+    pub fn chain_handle(&self) -> Arc<RwLock<Chain>> {
+        self.chain.clone()
+    }
+
+    /// Switches the user operation pool held by a spawned task (and its validator) to the current
+    /// chain of the builder.
+    ///
+    /// # Arguments
+    /// `chain` - The chain shared by the builder (see [chain_handle](UoPoolBuilder::chain_handle))
+    /// `uopool` - The user operation pool of the task
+    pub fn follow_chain(
+        chain: &RwLock<Chain>,
+        uopool: &mut StandardUoPool<M, SanCk, SimCk, SimTrCk>,
+    ) {
+        let chain = *chain.read();
+        if uopool.chain != chain {
+            uopool.chain = chain;
+            uopool.validator = uopool.validator.clone().with_chain(chain);
+        }
+    }
+
+    /// Gets the address of the entry point.
+    pub fn entry_point_address(&self) -> Address {
+        self.entrypoint
+    }
+
+    /// Gets the version of the entry point.
+    pub fn entry_point_version(&self) -> &str {
+        &self.entrypoint_version
+    }
+
+    /// Removes the user operations included in the block (reported by the `UserOperationEvent`
+    /// logs of the entry point) from the mempool.
     async fn handle_block_update(
         hash: H256,
         uopool: &mut StandardUoPool<M, SanCk, SimCk, SimTrCk>,
     ) -> eyre::Result<()> {
-        let txs =
-            uopool.entry_point.eth_client().get_block_with_txs(hash).await?.map(|b| b.transactions);
-
-        if let Some(txs) = txs {
-            for tx in txs {
-                if tx.to == Some(uopool.entry_point.address()) {
-                    let dec: Result<(Vec<UserOperationSigned>, Address), _> =
-                        uopool.entry_point.entry_point_api().decode("handleOps", tx.input);
-
-                    if let Ok((uos, _)) = dec {
-                        uopool.remove_user_operations(
-                            uos.iter()
-                                .map(|uo| {
-                                    UserOperation::from_user_operation_signed(
-                                        uo.hash(&uopool.entry_point.address(), uopool.chain.id()),
-                                        uo.clone(),
-                                    )
-                                })
-                                .collect(),
-                        );
-                    }
-                }
-            }
+        let entry_point = uopool.entry_point.address();
+        let filter = Filter::new()
+            .at_block_hash(hash)
+            .address(entry_point)
+            .topic0(UserOperationEventFilter::signature());
+        let logs = uopool.entry_point.eth_client().get_logs(&filter).await?;
+
+        let removed = uopool
+            .remove_included_user_operations(&parse_user_operation_events(&entry_point, &logs));
+        if !removed.is_empty() {
+            debug!("Removed user operations included in block {hash:?}: {removed:?}");
         }
 
         Ok(())
@@ -119,31 +241,81 @@ where
         });
     }
 
+    /// Spawns a task which decays the reputation of all entities every
+    /// [DECAY_INTERVAL](DECAY_INTERVAL) seconds. The first decay happens one interval after the
+    /// registration, so restarting the node doesn't decay the persisted reputation.
     pub fn register_reputation_updates(&self) {
         let mut uopool = self.uopool();
         tokio::spawn(async move {
             loop {
+                tokio::time::sleep(Duration::from_secs(DECAY_INTERVAL)).await;
                 let _ = uopool
                     .reputation
                     .update_hourly()
                     .map_err(|e| warn!("Failed to update hourly reputation: {:?}", e));
-                tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+            }
+        });
+    }
+
+    /// Spawns a task which purges the user operations verified more than `max_age` blocks ago
+    /// every `interval`.
+    pub fn register_stale_purge(&self, max_age: u64, grace_period: Duration, interval: Duration) {
+        let mut uopool = self.uopool();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let _ = uopool
+                    .purge_stale_user_operations(max_age, grace_period)
+                    .await
+                    .map_err(|e| warn!("Failed to purge stale user operations: {:?}", e));
+            }
+        });
+    }
+
+    /// Spawns a task which re-validates a batch of at most `batch_size` pending user operations
+    /// every `interval` and removes those which became invalid. The task runs on its own handle
+    /// of the user operation pool, so adding user operations is not blocked.
+    pub fn register_revalidation(&self, interval: Duration, batch_size: usize) {
+        let mut uopool = self.uopool();
+        let chain = self.chain_handle();
+        tokio::spawn(async move {
+            let mut cursor = 0;
+            loop {
+                tokio::time::sleep(interval).await;
+                Self::follow_chain(&chain, &mut uopool);
+                let _ = uopool
+                    .revalidate_user_operations(batch_size, &mut cursor)
+                    .await
+                    .map_err(|e| warn!("Failed to re-validate user operations: {:?}", e));
             }
         });
     }
 
     pub fn uopool(&self) -> StandardUoPool<M, SanCk, SimCk, SimTrCk> {
+        self.uopool_on(self.chain())
+    }
+
+    /// Creates a user operation pool for the given chain without switching the builder to the
+    /// chain (e.g. to validate the user operations on the new chain before a rekey).
+    pub fn uopool_on(&self, chain: Chain) -> StandardUoPool<M, SanCk, SimCk, SimTrCk> {
         let entry_point = EntryPoint::<M>::new(self.eth_client.clone(), self.entrypoint);
 
-        UoPool::<M, StandardUserOperationValidator<M, SanCk, SimCk, SimTrCk>>::new(
+        let mut uopool = UoPool::<M, StandardUserOperationValidator<M, SanCk, SimCk, SimTrCk>>::new(
             self.mode,
             entry_point,
-            self.validator.clone(),
+            self.validator.clone().with_chain(chain),
             self.mempool.clone(),
             self.reputation.clone(),
             self.max_verification_gas,
-            self.chain,
+            chain,
             self.network.as_ref().cloned(),
-        )
+        );
+        uopool.l1_data_fee = self.l1_data_fee.clone();
+        uopool.l1_gas = self.l1_gas.clone();
+        uopool.estimation_max_iterations = self.estimation_max_iterations;
+        uopool.max_log_range = self.max_log_range;
+        uopool.post_op_gas = self.post_op_gas;
+        uopool.hasher = self.hasher.clone();
+        uopool
     }
 }
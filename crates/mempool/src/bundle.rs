@@ -0,0 +1,298 @@
+//! Selection of the user operations included in a bundle.
+use crate::{
+    validate::utils::{storage_map_conflicts, StorageWrites},
+    Mempool, MempoolErrorKind, Reputation,
+};
+use ethers::types::{Address, U256};
+use silius_primitives::{
+    reputation::Status, simulation::StorageMap, UserOperation, UserOperationHash,
+};
+use std::collections::HashMap;
+
+/// Limits of the bundle built by [build_bundle].
+#[derive(Debug, Clone, Copy)]
+pub struct BundleLimits {
+    /// Maximum aggregate gas of the user operations in the bundle
+    pub max_gas: U256,
+    /// Maximum number of user operations of the same sender in the bundle
+    pub max_uos_per_sender: usize,
+    /// Maximum number of user operations of the same throttled entity in the bundle
+    pub max_uos_per_throttled_entity: usize,
+}
+
+impl BundleLimits {
+    /// Creates new [BundleLimits] with the given gas limit, which allow a single user operation
+    /// per sender and per throttled entity.
+    pub fn new(max_gas: U256) -> Self {
+        Self { max_gas, max_uos_per_sender: 1, max_uos_per_throttled_entity: 1 }
+    }
+}
+
+/// Gets the priority fee per gas the bundler receives for the user operation at the given base
+/// fee.
+///
+/// # Arguments
+/// `uo` - The [UserOperation](UserOperation)
+/// `base_fee` - The base fee of the block
+///
+/// # Returns
+/// The effective priority fee per gas.
+pub fn effective_priority_fee(uo: &UserOperation, base_fee: U256) -> U256 {
+    uo.effective_gas_price(base_fee).saturating_sub(base_fee)
+}
+
+/// Gets the gas limit of the user operation counted against the gas limit of the bundle.
+fn gas_limit(uo: &UserOperation) -> U256 {
+    uo.verification_gas_limit
+        .saturating_add(uo.call_gas_limit)
+        .saturating_add(uo.pre_verification_gas)
+}
+
+/// Builds a bundle from the user operations in the mempool. The user operations are sorted by
+/// the effective gas price (see [Mempool::get_sorted_by_fee]) and included greedily while they
+/// respect the per-sender and throttled-entity limits, fit the remaining gas of the bundle and
+/// don't conflict with the storage accesses of the user operations already in the bundle (see
+/// [storage_map_conflicts]). The user
+/// operations whose sender, factory or paymaster is banned are skipped.
+///
+/// # Arguments
+/// `mempool` - The [Mempool](Mempool) with the pending user operations
+/// `reputation` - The [Reputation](Reputation) of the entities
+/// `storage_accesses` - The [StorageMaps](StorageMap) accessed and the
+/// [StorageWrites](StorageWrites) written during the validation of the user operations (user
+/// operations without them don't conflict with any other)
+/// `base_fee` - The base fee of the block the bundle targets
+/// `limits` - The [BundleLimits](BundleLimits)
+///
+/// # Returns
+/// The ordered user operations of the bundle and their aggregate gas.
+pub fn build_bundle(
+    mempool: &Mempool,
+    reputation: &Reputation,
+    storage_accesses: &HashMap<UserOperationHash, (StorageMap, StorageWrites)>,
+    base_fee: U256,
+    limits: BundleLimits,
+) -> Result<(Vec<UserOperation>, U256), MempoolErrorKind> {
+    let uos = mempool.get_sorted_by_fee(base_fee)?;
+
+    let storage_access_empty = (StorageMap::default(), StorageWrites::new());
+    let mut bundle = vec![];
+    let mut gas_total = U256::zero();
+    let mut storage_accesses_bundle: Vec<&(StorageMap, StorageWrites)> = vec![];
+    let mut sender_c: HashMap<Address, usize> = HashMap::new();
+    let mut throttled_c: HashMap<Address, usize> = HashMap::new();
+
+    'uos: for uo in uos {
+        if sender_c.get(&uo.sender).cloned().unwrap_or(0) >= limits.max_uos_per_sender {
+            continue;
+        }
+
+        let (sender, factory, paymaster) = uo.get_entities();
+        let mut throttled = vec![];
+        for entity in [Some(sender), factory, paymaster].into_iter().flatten() {
+            match Status::from(reputation.get_status(&entity)?) {
+                Status::BANNED => continue 'uos,
+                Status::THROTTLED => {
+                    if throttled_c.get(&entity).cloned().unwrap_or(0) >=
+                        limits.max_uos_per_throttled_entity
+                    {
+                        continue 'uos;
+                    }
+                    throttled.push(entity);
+                }
+                Status::OK => (),
+            }
+        }
+
+        let storage_access = storage_accesses.get(&uo.hash).unwrap_or(&storage_access_empty);
+        let (storage_map, writes) = storage_access;
+        if storage_accesses_bundle.iter().any(|(other, other_writes)| {
+            !storage_map_conflicts(storage_map, writes, other, other_writes).is_empty()
+        }) {
+            continue;
+        }
+
+        let gas_total_new = gas_total.saturating_add(gas_limit(&uo));
+        if gas_total_new > limits.max_gas {
+            continue;
+        }
+
+        gas_total = gas_total_new;
+        storage_accesses_bundle.push(storage_access);
+        *sender_c.entry(uo.sender).or_default() += 1;
+        for entity in throttled {
+            *throttled_c.entry(entity).or_default() += 1;
+        }
+        bundle.push(uo);
+    }
+
+    Ok((bundle, gas_total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{memory_mempool, memory_reputation};
+    use silius_primitives::UserOperationSigned;
+
+    fn user_operation(sender: Address, nonce: u64, priority_fee: u64) -> UserOperation {
+        let uo = UserOperationSigned::default()
+            .sender(sender)
+            .nonce(nonce.into())
+            .call_gas_limit(100_000.into())
+            .verification_gas_limit(100_000.into())
+            .pre_verification_gas(50_000.into())
+            .max_fee_per_gas(100.into())
+            .max_priority_fee_per_gas(priority_fee.into());
+        UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo)
+    }
+
+    fn storage_write(addr: Address, slot: &str) -> (StorageMap, StorageWrites) {
+        (
+            StorageMap {
+                root_hashes: HashMap::new(),
+                slots: HashMap::from([(
+                    addr,
+                    HashMap::from([(slot.to_string(), "0x1".to_string())]),
+                )]),
+            },
+            HashMap::from([(addr, [slot.to_string()].into())]),
+        )
+    }
+
+    #[test]
+    fn build_bundle_sender_limit() {
+        let mut mempool = memory_mempool();
+        let sender = Address::random();
+        let uo_low = user_operation(sender, 0, 5);
+        let uo_high = user_operation(sender, 1, 10);
+        let uo_other = user_operation(Address::random(), 0, 1);
+        for uo in [uo_low.clone(), uo_high.clone(), uo_other.clone()] {
+            mempool.add(uo).unwrap();
+        }
+
+        let limits = BundleLimits::new(U256::from(10_000_000));
+        let (bundle, gas) =
+            build_bundle(&mempool, &memory_reputation(), &HashMap::new(), U256::zero(), limits)
+                .unwrap();
+        assert_eq!(
+            bundle.iter().map(|uo| uo.hash).collect::<Vec<_>>(),
+            vec![uo_high.hash, uo_other.hash]
+        );
+        assert_eq!(gas, U256::from(500_000));
+
+        let limits = BundleLimits { max_uos_per_sender: 2, ..limits };
+        let (bundle, _) =
+            build_bundle(&mempool, &memory_reputation(), &HashMap::new(), U256::zero(), limits)
+                .unwrap();
+        assert_eq!(
+            bundle.iter().map(|uo| uo.hash).collect::<Vec<_>>(),
+            vec![uo_high.hash, uo_low.hash, uo_other.hash]
+        );
+
+        // the user operations which don't fit the remaining gas are skipped
+        let limits = BundleLimits::new(U256::from(400_000));
+        let (bundle, gas) =
+            build_bundle(&mempool, &memory_reputation(), &HashMap::new(), U256::zero(), limits)
+                .unwrap();
+        assert_eq!(bundle.iter().map(|uo| uo.hash).collect::<Vec<_>>(), vec![uo_high.hash]);
+        assert_eq!(gas, U256::from(250_000));
+    }
+
+    #[test]
+    fn build_bundle_gas_limit() {
+        let mut mempool = memory_mempool();
+        let uo_large = user_operation(Address::random(), 0, 10);
+        let uo_too_large = user_operation(Address::random(), 0, 5);
+        let uo_small = {
+            let uo = UserOperationSigned::default()
+                .sender(Address::random())
+                .call_gas_limit(50_000.into())
+                .verification_gas_limit(50_000.into())
+                .pre_verification_gas(50_000.into())
+                .max_fee_per_gas(100.into())
+                .max_priority_fee_per_gas(1.into());
+            UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo)
+        };
+        for uo in [uo_large.clone(), uo_too_large.clone(), uo_small.clone()] {
+            mempool.add(uo).unwrap();
+        }
+
+        let (bundle, gas) = build_bundle(
+            &mempool,
+            &memory_reputation(),
+            &HashMap::new(),
+            U256::zero(),
+            BundleLimits::new(U256::from(400_000)),
+        )
+        .unwrap();
+        assert_eq!(
+            bundle.iter().map(|uo| uo.hash).collect::<Vec<_>>(),
+            vec![uo_large.hash, uo_small.hash]
+        );
+        assert_eq!(gas, U256::from(400_000));
+    }
+
+    #[test]
+    fn build_bundle_banned_sender() {
+        let mut mempool = memory_mempool();
+        let uo_banned = user_operation(Address::random(), 0, 10);
+        let uo_other = user_operation(Address::random(), 0, 5);
+        for uo in [uo_banned.clone(), uo_other.clone()] {
+            mempool.add(uo).unwrap();
+        }
+
+        let mut reputation = memory_reputation();
+        reputation.add_blacklist(&uo_banned.sender);
+        let (bundle, _) = build_bundle(
+            &mempool,
+            &reputation,
+            &HashMap::new(),
+            U256::zero(),
+            BundleLimits::new(U256::from(10_000_000)),
+        )
+        .unwrap();
+        assert_eq!(bundle.iter().map(|uo| uo.hash).collect::<Vec<_>>(), vec![uo_other.hash]);
+    }
+
+    #[test]
+    fn build_bundle_storage_conflict() {
+        let mut mempool = memory_mempool();
+        let (uo_first, uo_conflict, uo_disjoint) = (
+            user_operation(Address::random(), 0, 10),
+            user_operation(Address::random(), 0, 5),
+            user_operation(Address::random(), 0, 1),
+        );
+        for uo in [uo_first.clone(), uo_conflict.clone(), uo_disjoint.clone()] {
+            mempool.add(uo).unwrap();
+        }
+
+        let token = Address::random();
+        let storage_accesses = HashMap::from([
+            (uo_first.hash, storage_write(token, "0x01")),
+            (uo_conflict.hash, storage_write(token, "0x01")),
+            (uo_disjoint.hash, storage_write(token, "0x02")),
+        ]);
+
+        let (bundle, _) = build_bundle(
+            &mempool,
+            &memory_reputation(),
+            &storage_accesses,
+            U256::zero(),
+            BundleLimits::new(U256::from(10_000_000)),
+        )
+        .unwrap();
+        assert_eq!(
+            bundle.iter().map(|uo| uo.hash).collect::<Vec<_>>(),
+            vec![uo_first.hash, uo_disjoint.hash]
+        );
+    }
+
+    #[test]
+    fn effective_priority_fee_capped_by_max_fee() {
+        let uo = user_operation(Address::random(), 0, 10);
+        assert_eq!(effective_priority_fee(&uo, U256::from(50)), U256::from(10));
+        assert_eq!(effective_priority_fee(&uo, U256::from(95)), U256::from(5));
+        assert_eq!(effective_priority_fee(&uo, U256::from(200)), U256::zero());
+    }
+}
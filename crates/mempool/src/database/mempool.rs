@@ -1,16 +1,19 @@
 use super::{
     env::DatabaseError,
-    tables::{CodeHashes, UserOperations, UserOperationsByEntity, UserOperationsBySender},
+    tables::{
+        CodeHashes, UserOperations, UserOperationsByEntity, UserOperationsBySender,
+        UserOperationsMetadata,
+    },
     utils::{
         WrapAddress, WrapCodeHash, WrapCodeHashVec, WrapUserOpSet, WrapUserOperationHash,
-        WrapUserOperationSigned,
+        WrapUserOperationMetadata, WrapUserOperationSigned,
     },
     DatabaseTable,
 };
 use crate::{
     mempool::{
         AddRemoveUserOp, AddRemoveUserOpHash, ClearOp, UserOperationAddrOp,
-        UserOperationCodeHashOp, UserOperationOp,
+        UserOperationCodeHashOp, UserOperationMetadata, UserOperationMetadataOp, UserOperationOp,
     },
     MempoolErrorKind,
 };
@@ -220,6 +223,45 @@ impl<E: EnvironmentKind> UserOperationCodeHashOp for DatabaseTable<E, CodeHashes
     }
 }
 
+impl<E: EnvironmentKind> UserOperationMetadataOp for DatabaseTable<E, UserOperationsMetadata> {
+    fn set_metadata(
+        &mut self,
+        uo_hash: &UserOperationHash,
+        metadata: UserOperationMetadata,
+    ) -> Result<(), MempoolErrorKind> {
+        let uo_hash_wrap: WrapUserOperationHash = (*uo_hash).into();
+        let metadata_wrap: WrapUserOperationMetadata = metadata.into();
+
+        let tx = self.env.tx_mut()?;
+        tx.put::<UserOperationsMetadata>(uo_hash_wrap, metadata_wrap)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn get_all_metadata(
+        &self,
+    ) -> Result<Vec<(UserOperationHash, UserOperationMetadata)>, MempoolErrorKind> {
+        let tx = self.env.tx()?;
+        let mut c = tx.cursor_read::<UserOperationsMetadata>()?;
+        let mut res = Vec::new();
+        while let Some((hash, metadata)) = c.next()? {
+            res.push((hash.into(), metadata.into()))
+        }
+
+        Ok(res)
+    }
+
+    fn remove_metadata(&mut self, uo_hash: &UserOperationHash) -> Result<bool, MempoolErrorKind> {
+        let uo_hash_wrap: WrapUserOperationHash = (*uo_hash).into();
+
+        let tx = self.env.tx_mut()?;
+        let original_value = tx.get::<UserOperationsMetadata>(uo_hash_wrap.clone())?;
+        tx.delete::<UserOperationsMetadata>(uo_hash_wrap, None)?;
+        tx.commit()?;
+        Ok(original_value.is_some())
+    }
+}
+
 macro_rules! impl_clear {
     ($table: ident) => {
         impl<E: EnvironmentKind> ClearOp for DatabaseTable<E, $table> {
@@ -239,20 +281,29 @@ impl_clear!(UserOperations);
 impl_clear!(UserOperationsBySender);
 impl_clear!(UserOperationsByEntity);
 impl_clear!(CodeHashes);
+impl_clear!(UserOperationsMetadata);
 
 #[cfg(test)]
 mod tests {
     use crate::{
         database::{
             init_env,
-            tables::{CodeHashes, UserOperations, UserOperationsByEntity, UserOperationsBySender},
+            tables::{
+                CodeHashes, UserOperations, UserOperationsByEntity, UserOperationsBySender,
+                UserOperationsMetadata,
+            },
             DatabaseTable,
         },
         utils::tests::mempool_test_case,
         Mempool,
     };
+    use ethers::types::Address;
     use reth_libmdbx::WriteMap;
-    use std::sync::Arc;
+    use silius_primitives::{UserOperation, UserOperationSigned};
+    use std::{
+        sync::Arc,
+        time::{Duration, UNIX_EPOCH},
+    };
     use tempdir::TempDir;
 
     #[allow(clippy::unit_cmp)]
@@ -279,4 +330,82 @@ mod tests {
 
         mempool_test_case(mempool);
     }
+
+    #[tokio::test]
+    async fn database_mempool_restore() {
+        let dir = TempDir::new("test-silius-db").unwrap();
+        let env = Arc::new(init_env::<WriteMap>(dir.into_path()).unwrap());
+        let new_mempool = || {
+            let mut mempool = Mempool::new(
+                Box::new(DatabaseTable::<WriteMap, UserOperations>::new(env.clone())),
+                Box::new(DatabaseTable::<WriteMap, UserOperationsBySender>::new(env.clone())),
+                Box::new(DatabaseTable::<WriteMap, UserOperationsByEntity>::new(env.clone())),
+                Box::new(DatabaseTable::<WriteMap, CodeHashes>::new(env.clone())),
+            );
+            mempool
+                .set_user_operations_metadata(Box::new(DatabaseTable::<
+                    WriteMap,
+                    UserOperationsMetadata,
+                >::new(env.clone())))
+                .unwrap();
+            mempool
+        };
+
+        let mut mempool = new_mempool();
+        let aggregator = Address::random();
+        let mut uo_hashes = vec![];
+        for _ in 0..3 {
+            let uo = UserOperationSigned {
+                max_priority_fee_per_gas: 1.into(),
+                ..UserOperationSigned::random()
+            };
+            let uo_hash = uo.hash(&Address::random(), 5);
+            mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo)).unwrap();
+            uo_hashes.push(uo_hash);
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        mempool.set_verified_block(&uo_hashes[1], 10);
+        mempool.set_staked(&uo_hashes[0]);
+        mempool.set_pre_fund(&uo_hashes[2], 7.into());
+        mempool.set_aggregator(&uo_hashes[2], aggregator);
+        mempool.set_max_size_unstaked(Some(2));
+        assert!(mempool.is_full_for(false).unwrap());
+
+        // the insertion order, times, verified blocks and staked partition survive the restart
+        let mut restored = new_mempool();
+        restored.set_max_size_unstaked(Some(2));
+        assert!(restored.is_staked(&uo_hashes[0]));
+        assert!(!restored.is_staked(&uo_hashes[1]));
+        assert!(restored.is_full_for(false).unwrap());
+        assert!(!restored.is_full_for(true).unwrap());
+        assert_eq!(restored.get_pre_fund(&uo_hashes[2]), Some(7.into()));
+        assert_eq!(restored.get_aggregator(&uo_hashes[2]), Some(aggregator));
+        assert_eq!(restored.get_aggregator(&uo_hashes[0]), None);
+        assert_eq!(restored.get_stats(), mempool.get_stats());
+        assert_eq!(
+            restored.get_sorted().unwrap().iter().map(|uo| uo.hash).collect::<Vec<_>>(),
+            uo_hashes
+        );
+        assert_eq!(restored.get_eviction_candidate().unwrap().unwrap().hash, uo_hashes[1]);
+        for uo_hash in uo_hashes.iter() {
+            let millis = |mempool: &Mempool| {
+                mempool
+                    .get_added_at(uo_hash)
+                    .unwrap()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis()
+            };
+            assert_eq!(millis(&restored), millis(&mempool));
+        }
+        // the grace period counts from the persisted insertion time
+        assert!(restored.purge_stale(100, 50, Duration::from_secs(60)).unwrap().is_empty());
+        assert_eq!(restored.purge_stale(100, 50, Duration::ZERO).unwrap(), vec![uo_hashes[1]]);
+
+        // the metadata of the removed user operations is dropped
+        restored.remove(&uo_hashes[0]).unwrap();
+        let restored = new_mempool();
+        assert_eq!(restored.get_stats().pending, 1);
+        assert_eq!(restored.get_eviction_candidate().unwrap().unwrap().hash, uo_hashes[2]);
+    }
 }
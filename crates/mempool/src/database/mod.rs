@@ -10,6 +10,7 @@ use std::{path::PathBuf, sync::Arc};
 mod env;
 pub mod mempool;
 pub mod reputation;
+pub mod storage_map;
 pub mod tables;
 mod utils;
 
@@ -0,0 +1,92 @@
+use super::{env::DatabaseError, tables::StorageMaps, utils::WrapUserOperationHash, DatabaseTable};
+use reth_db::{
+    database::Database,
+    mdbx::EnvironmentKind,
+    transaction::{DbTx, DbTxMut},
+};
+use silius_primitives::{simulation::StorageMap, UserOperationHash};
+
+impl<E: EnvironmentKind> DatabaseTable<E, StorageMaps> {
+    /// Stores the storage map accessed during the validation of the user operation.
+    ///
+    /// # Arguments
+    /// `uo_hash` - The [UserOperationHash](UserOperationHash) of the user operation
+    /// `storage_map` - The [StorageMap](StorageMap) from the validation outcome
+    ///
+    /// # Returns
+    /// `Ok(())` if the storage map was stored, otherwise a [DatabaseError](DatabaseError)
+    pub fn insert(
+        &mut self,
+        uo_hash: UserOperationHash,
+        storage_map: StorageMap,
+    ) -> Result<(), DatabaseError> {
+        let tx = self.env.tx_mut()?;
+        tx.put::<StorageMaps>(uo_hash.into(), storage_map.into())?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Gets the storage map accessed during the validation of the user operation.
+    ///
+    /// # Arguments
+    /// `uo_hash` - The [UserOperationHash](UserOperationHash) of the user operation
+    ///
+    /// # Returns
+    /// The [StorageMap](StorageMap) if it is stored, otherwise `None`
+    pub fn get(&self, uo_hash: &UserOperationHash) -> Result<Option<StorageMap>, DatabaseError> {
+        let uo_hash_wrap: WrapUserOperationHash = (*uo_hash).into();
+
+        let tx = self.env.tx()?;
+        let res = tx.get::<StorageMaps>(uo_hash_wrap)?;
+        tx.commit()?;
+        Ok(res.map(|o| o.into()))
+    }
+
+    /// Removes the storage map of the user operation.
+    ///
+    /// # Arguments
+    /// `uo_hash` - The [UserOperationHash](UserOperationHash) of the user operation
+    ///
+    /// # Returns
+    /// `true` if the storage map was stored, otherwise `false`
+    pub fn remove(&mut self, uo_hash: &UserOperationHash) -> Result<bool, DatabaseError> {
+        let uo_hash_wrap: WrapUserOperationHash = (*uo_hash).into();
+
+        let tx = self.env.tx_mut()?;
+        let original = tx.get::<StorageMaps>(uo_hash_wrap.clone())?;
+        tx.delete::<StorageMaps>(uo_hash_wrap, None)?;
+        tx.commit()?;
+        Ok(original.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::{init_env, tables::StorageMaps, DatabaseTable};
+    use ethers::types::{Address, H256};
+    use reth_libmdbx::WriteMap;
+    use silius_primitives::{simulation::StorageMap, UserOperationHash};
+    use std::{collections::HashMap, sync::Arc};
+    use tempdir::TempDir;
+
+    #[test]
+    fn database_storage_map() {
+        let dir = TempDir::new("test-silius-db").unwrap();
+
+        let env = Arc::new(init_env::<WriteMap>(dir.into_path()).unwrap());
+        let mut storage_maps: DatabaseTable<WriteMap, StorageMaps> = DatabaseTable::new(env);
+
+        let addr = Address::random();
+        let storage_map = StorageMap {
+            root_hashes: HashMap::from([(addr, H256::random())]),
+            slots: HashMap::from([(addr, HashMap::from([("0x01".into(), "0x02".into())]))]),
+        };
+        let uo_hash: UserOperationHash = H256::random().into();
+
+        assert_eq!(storage_maps.get(&uo_hash).unwrap(), None);
+        storage_maps.insert(uo_hash, storage_map.clone()).unwrap();
+        assert_eq!(storage_maps.get(&uo_hash).unwrap(), Some(storage_map));
+        assert!(storage_maps.remove(&uo_hash).unwrap());
+        assert_eq!(storage_maps.get(&uo_hash).unwrap(), None);
+    }
+}
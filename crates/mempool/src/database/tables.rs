@@ -1,6 +1,6 @@
 use super::utils::{
-    WrapAddress, WrapCodeHashVec, WrapReputationEntry, WrapUserOpSet, WrapUserOperationHash,
-    WrapUserOperationSigned,
+    WrapAddress, WrapCodeHashVec, WrapReputationEntry, WrapStorageMap, WrapUserOpSet,
+    WrapUserOperationHash, WrapUserOperationMetadata, WrapUserOperationSigned,
 };
 use reth_db::{table, TableType};
 
@@ -30,11 +30,23 @@ table!(
     ( EntitiesReputation ) WrapAddress | WrapReputationEntry
 );
 
+table!(
+    /// Stores the storage maps from the validation of user operations
+    ( StorageMaps ) WrapUserOperationHash | WrapStorageMap
+);
+
+table!(
+    /// Stores the insertion metadata of the user operations (restored on startup)
+    ( UserOperationsMetadata ) WrapUserOperationHash | WrapUserOperationMetadata
+);
+
 /// Tables that should be present inside database
-pub const TABLES: [(TableType, &str); 5] = [
+pub const TABLES: [(TableType, &str); 7] = [
     (TableType::Table, UserOperations::const_name()),
     (TableType::Table, UserOperationsBySender::const_name()),
     (TableType::Table, UserOperationsByEntity::const_name()),
     (TableType::Table, CodeHashes::const_name()),
     (TableType::Table, EntitiesReputation::const_name()),
+    (TableType::Table, StorageMaps::const_name()),
+    (TableType::Table, UserOperationsMetadata::const_name()),
 ];
@@ -1,3 +1,4 @@
+use crate::mempool::UserOperationMetadata;
 use bin_layout::{Decoder, Encoder};
 use ethers::{
     abi::{AbiDecode, AbiEncode},
@@ -7,7 +8,9 @@ use ethers::{
 use reth_db::table::{Compress, Decode, Decompress, Encode};
 use serde::{Deserialize, Serialize};
 use silius_primitives::{
-    reputation::ReputationEntry, simulation::CodeHash, UserOperationHash, UserOperationSigned,
+    reputation::{ReputationEntry, Status},
+    simulation::{CodeHash, StorageMap},
+    UserOperationHash, UserOperationSigned,
 };
 use std::{collections::HashSet, fmt::Debug};
 
@@ -110,6 +113,90 @@ construct_wrap_hash!(UserOperationHash, WrapUserOperationHash, 32);
 construct_wrap_struct!(CodeHash, WrapCodeHash);
 construct_wrap_struct!(UserOperationSigned, WrapUserOperationSigned);
 construct_wrap_struct!(ReputationEntry, WrapReputationEntry);
+construct_wrap_struct!(UserOperationMetadata, WrapUserOperationMetadata);
+
+/// Compression & decompression wrapper for the reputation [Status](Status), encoded as a single
+/// byte (the [ReputationStatus](silius_primitives::reputation::ReputationStatus) value).
+#[derive(Default, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+pub struct WrapReputationStatus(Status);
+
+impl Decode for WrapReputationStatus {
+    fn decode<B: Into<prost::bytes::Bytes>>(value: B) -> Result<Self, reth_db::Error> {
+        match value.into().as_ref() {
+            [0] => Ok(Status::OK.into()),
+            [1] => Ok(Status::THROTTLED.into()),
+            [2] => Ok(Status::BANNED.into()),
+            _ => Err(reth_db::Error::DecodeError),
+        }
+    }
+}
+
+impl Encode for WrapReputationStatus {
+    type Encoded = [u8; 1];
+    fn encode(self) -> Self::Encoded {
+        match self.0 {
+            Status::OK => [0],
+            Status::THROTTLED => [1],
+            Status::BANNED => [2],
+        }
+    }
+}
+
+impl From<Status> for WrapReputationStatus {
+    fn from(value: Status) -> Self {
+        Self(value)
+    }
+}
+
+impl From<WrapReputationStatus> for Status {
+    fn from(value: WrapReputationStatus) -> Self {
+        value.0
+    }
+}
+
+impl Compress for WrapReputationStatus {
+    type Compressed = Bytes;
+    fn compress(self) -> Self::Compressed {
+        <Self as Encode>::encode(self).to_vec().into()
+    }
+}
+
+impl Decompress for WrapReputationStatus {
+    fn decompress<B: Into<prost::bytes::Bytes>>(value: B) -> Result<Self, reth_db::Error> {
+        <Self as Decode>::decode(value.into())
+    }
+}
+
+/// Compression & decompression wrapper for the [StorageMap](StorageMap). Unlike the types wrapped
+/// by [construct_wrap_struct], the storage map is not ordered, so the wrapper can't be used as a
+/// key.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, EthAbiCodec, EthAbiType)]
+pub struct WrapStorageMap(pub StorageMap);
+
+impl Compress for WrapStorageMap {
+    type Compressed = Bytes;
+    fn compress(self) -> Self::Compressed {
+        <Self as AbiEncode>::encode(self).into()
+    }
+}
+
+impl Decompress for WrapStorageMap {
+    fn decompress<B: Into<prost::bytes::Bytes>>(value: B) -> Result<Self, reth_db::Error> {
+        <Self as AbiDecode>::decode(value.into()).map_err(|_e| reth_db::Error::DecodeError)
+    }
+}
+
+impl From<StorageMap> for WrapStorageMap {
+    fn from(value: StorageMap) -> Self {
+        Self(value)
+    }
+}
+
+impl From<WrapStorageMap> for StorageMap {
+    fn from(value: WrapStorageMap) -> Self {
+        value.0
+    }
+}
 
 impl<'de> Decoder<'de> for WrapUserOperationHash {
     fn decoder(data: &mut &'de [u8]) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
@@ -213,3 +300,51 @@ impl Decompress for WrapCodeHashVec {
         Ok(decoded.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::H256;
+    use std::collections::HashMap;
+
+    #[test]
+    fn storage_map_compress_decompress() {
+        let (account, token) = (Address::random(), Address::random());
+        let storage_map = StorageMap {
+            root_hashes: HashMap::from([(account, H256::random())]),
+            slots: HashMap::from([
+                (
+                    account,
+                    HashMap::from([
+                        ("0x00".to_string(), "0x01".to_string()),
+                        ("0x01".to_string(), "0x".to_string()),
+                    ]),
+                ),
+                (token, HashMap::from([("0xabcd".to_string(), "0x10".to_string())])),
+            ]),
+        };
+
+        let compressed = WrapStorageMap::from(storage_map.clone()).compress();
+        let decompressed: StorageMap =
+            WrapStorageMap::decompress(compressed.to_vec()).unwrap().into();
+        assert_eq!(decompressed, storage_map);
+    }
+
+    #[test]
+    fn reputation_status_encode_decode() {
+        for (status, byte) in [(Status::OK, 0), (Status::THROTTLED, 1), (Status::BANNED, 2)] {
+            let encoded = WrapReputationStatus::from(status.clone()).encode();
+            assert_eq!(encoded, [byte]);
+            let decoded: Status = WrapReputationStatus::decode(encoded.to_vec()).unwrap().into();
+            assert_eq!(decoded, status);
+
+            let compressed = WrapReputationStatus::from(status.clone()).compress();
+            let decompressed: Status =
+                WrapReputationStatus::decompress(compressed.to_vec()).unwrap().into();
+            assert_eq!(decompressed, status);
+        }
+
+        assert!(WrapReputationStatus::decode(vec![3]).is_err());
+        assert!(WrapReputationStatus::decompress(vec![0, 0]).is_err());
+    }
+}
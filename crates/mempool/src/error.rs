@@ -3,6 +3,7 @@ use crate::DatabaseError;
 use ethers::types::{Address, U256};
 use serde::{Deserialize, Serialize};
 use silius_contracts::EntryPointError;
+pub use silius_contracts::TraceError;
 use silius_primitives::UserOperationHash;
 use thiserror::Error;
 
@@ -93,6 +94,21 @@ pub enum InvalidMempoolUserOperationError {
     Simulation(#[from] SimulationError),
 }
 
+impl InvalidMempoolUserOperationError {
+    /// Whether the validation failed because the provider is unreachable or too slow (provider
+    /// error or timeout) rather than because the user operation is invalid, so the request can be
+    /// retried.
+    pub fn is_rpc_down(&self) -> bool {
+        matches!(
+            self,
+            Self::Sanity(SanityError::Provider { .. } | SanityError::Timeout { .. }) |
+                Self::Simulation(
+                    SimulationError::Provider { .. } | SimulationError::Timeout { .. }
+                )
+        )
+    }
+}
+
 /// Error related to reputation of the entities
 #[derive(Debug, Error, Serialize, Deserialize)]
 pub enum ReputationError {
@@ -100,8 +116,8 @@ pub enum ReputationError {
     #[error("{entity} {address:?} is banned")]
     BannedEntity { entity: String, address: Address },
     /// Entity is throttled
-    #[error("{entity} {address:?} is throttled")]
-    ThrottledEntity { entity: String, address: Address },
+    #[error("{entity} {address:?} is throttled: {reason}")]
+    ThrottledEntity { entity: String, address: Address, reason: String },
     /// Stake of the entity is too low
     #[error("{entity} {address:?} stake {stake} is too low {min_stake}")]
     StakeTooLow { entity: String, address: Address, stake: U256, min_stake: U256 },
@@ -139,14 +155,29 @@ pub enum SanityError {
         verification_gas_limit_expected: U256,
     },
     /// Pre verification gas is too low
-    #[error("preVerificationGas too low: expected at least {pre_verification_gas_expected}")]
-    PreVerificationGasTooLow { pre_verification_gas: U256, pre_verification_gas_expected: U256 },
+    #[error("preVerificationGas too low: expected at least {pre_verification_gas_expected} (calculated {pre_verification_gas_calculated})")]
+    PreVerificationGasTooLow {
+        pre_verification_gas: U256,
+        pre_verification_gas_expected: U256,
+        pre_verification_gas_calculated: U256,
+    },
+    /// Total gas limit of the user operation doesn't fit in any bundle
+    #[error("total gas limit {gas_limit} too high: expected at most {max_bundle_gas}")]
+    GasLimitTooHighForBundle { gas_limit: U256, max_bundle_gas: U256 },
+    /// Dynamic field of the user operation is larger than allowed
+    #[error("{field} too large: {size} bytes, expected at most {max_size}")]
+    FieldTooLarge { field: String, size: usize, max_size: usize },
     /// Call gas limit is too low
     #[error("callGasLimit too low: expected at least {call_gas_limit_expected}")]
     CallGasLimitTooLow { call_gas_limit: U256, call_gas_limit_expected: U256 },
-    /// Max fee per gas is too low (lower than current base fee per gas)
-    #[error("maxFeePerGas too low: expected at least {base_fee_per_gas}")]
-    MaxFeePerGasTooLow { max_fee_per_gas: U256, base_fee_per_gas: U256 },
+    /// Max fee per gas is too low (lower than current base fee per gas plus the minimum priority
+    /// fee per gas)
+    #[error("maxFeePerGas too low: expected at least base fee {base_fee_per_gas} plus priority fee {min_priority_fee_per_gas}")]
+    MaxFeePerGasTooLow {
+        max_fee_per_gas: U256,
+        base_fee_per_gas: U256,
+        min_priority_fee_per_gas: U256,
+    },
     /// Max priority fee per gas is too high (higher than max fee per gas)
     #[error("maxPriorityFeePerGas too high: expected at most {max_fee_per_gas}")]
     MaxPriorityFeePerGasTooHigh { max_priority_fee_per_gas: U256, max_fee_per_gas: U256 },
@@ -156,18 +187,36 @@ pub enum SanityError {
         max_priority_fee_per_gas: U256,
         max_priority_fee_per_gas_expected: U256,
     },
+    /// Mempool is full and the user operation can't evict any user operation from it
+    #[error("mempool is full: maxPriorityFeePerGas must be higher than {max_priority_fee_per_gas_expected}")]
+    MempoolFull { max_priority_fee_per_gas: U256, max_priority_fee_per_gas_expected: U256 },
+    /// Nonce is too far ahead of the sender's on-chain nonce
+    #[error("nonce too high: expected at most {nonce_expected}")]
+    NonceGap { nonce: U256, nonce_expected: U256 },
+    /// Entry point is not supported by the validator
+    #[error("entry point {entry_point:?} is not supported")]
+    UnsupportedEntryPoint { entry_point: Address },
     /// Paymaster validation failed
     #[error("{inner}")]
     Paymaster { inner: String },
     /// Sender validation failed
     #[error("{inner}")]
     Sender { inner: String },
+    /// Entity is on the denylist of the bundler
+    #[error("{entity} {address:?} is denied")]
+    Denied { entity: String, address: Address },
     /// Entity role validation
     #[error("A {entity} at {address:?} in this user operation is used as a {entity_other} entity in another useroperation currently in mempool")]
     EntityRoles { entity: String, address: Address, entity_other: String },
+    /// Validation trace can't be converted into the output of the tracer
+    #[error(transparent)]
+    Trace(TraceError),
     /// Reputation error
     #[error(transparent)]
     Reputation(ReputationError),
+    /// External call of the validation stage timed out
+    #[error("{stage} timed out after {timeout_ms} ms")]
+    Timeout { stage: String, timeout_ms: u64 },
     /// Provider error
     #[error("provider error: {inner}")]
     Provider {
@@ -207,6 +256,15 @@ pub enum SimulationError {
     /// Signature verification failed
     #[error("Invalid userop signature or paymaster signature")]
     Signature,
+    /// Account or paymaster rejected the signature (the `sigFailed` flag is set)
+    #[error("Signature rejected by the account or paymaster (sigFailed flag is set)")]
+    SignatureFailed,
+    /// Length of the signature is out of the expected range
+    #[error("signature length {length} is out of the expected range [{min}, {max}]")]
+    SignatureLength { length: usize, min: usize, max: usize },
+    /// Signature aggregator is not on the allowlist
+    #[error("signature aggregator {aggregator:?} is not supported")]
+    Aggregator { aggregator: Address },
     /// User operation timestamp invalid
     #[error("{inner}")]
     Timestamp { inner: String },
@@ -216,9 +274,18 @@ pub enum SimulationError {
     /// Error during user operation execution
     #[error("{inner}")]
     Execution { inner: String },
+    /// Deposit and balance of the account don't cover the prefund of the user operation
+    #[error("account funds {funds} don't cover the prefund {pre_fund}")]
+    Prefund { pre_fund: U256, funds: U256 },
     /// Opcode error
     #[error("{entity} uses banned opcode: {opcode}")]
     Opcode { entity: String, opcode: String },
+    /// GAS opcode not immediately followed by a *CALL opcode
+    #[error("{entity} uses GAS opcode at pc {pc} not followed by a *CALL opcode")]
+    GasOpcode { entity: String, pc: u64 },
+    /// Forbidden contract creation or destruction (SELFDESTRUCT, CREATE, disallowed CREATE2)
+    #[error("{entity} {inner}")]
+    ContractCreation { entity: String, inner: String },
     /// Storage access error
     #[error("Storage access validation failed for slot: {slot}")]
     StorageAccess { slot: String },
@@ -228,15 +295,37 @@ pub enum SimulationError {
     /// Errors related to calls
     #[error("Illegal call into {inner}")]
     CallStack { inner: String },
+    /// Call into a banned precompile
+    #[error("Illegal call into banned precompile {address:?}")]
+    Precompile { address: Address },
     /// Codes hashes changed between the first and the second simulations
     #[error("Code hashes changed between the first and the second simulations")]
     CodeHashes,
     /// User operation out of gas
     #[error("User operation out of gas")]
     OutOfGas,
+    /// Verification gas limit is too low to cover the cold storage accesses
+    #[error("verificationGasLimit too low: expected at least {storage_gas} to cover {cold_slots} cold storage slots")]
+    VerificationGasLimitTooLowForStorage {
+        verification_gas_limit: U256,
+        storage_gas: U256,
+        cold_slots: U256,
+    },
+    /// Validation trace exceeds the limit (number of steps or call depth)
+    #[error("validation trace {limit} {value} exceeds the limit {max}")]
+    TraceLimit { limit: String, value: usize, max: usize },
+    /// Validation trace can't be converted into the output of the tracer
+    #[error(transparent)]
+    Trace(TraceError),
     /// Reputation error
     #[error(transparent)]
     Reputation(ReputationError),
+    /// External call of the validation stage timed out
+    #[error("{stage} timed out after {timeout_ms} ms")]
+    Timeout { stage: String, timeout_ms: u64 },
+    /// State of the historical block to validate on is not available (no archive node)
+    #[error("state of block {block} is not available: {inner}")]
+    HistoricalState { block: u64, inner: String },
     /// Provider error
     #[error("provider error: {inner}")]
     Provider {
@@ -261,6 +350,12 @@ impl From<ReputationError> for SimulationError {
     }
 }
 
+impl From<TraceError> for SimulationError {
+    fn from(err: TraceError) -> Self {
+        SimulationError::Trace(err)
+    }
+}
+
 impl From<EntryPointError> for SimulationError {
     fn from(err: EntryPointError) -> Self {
         match err {
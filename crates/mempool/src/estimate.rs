@@ -4,7 +4,7 @@ use ethers::{
     abi::{Hash, RawLog},
     contract::EthLogDecode,
     providers::Middleware,
-    types::{Bytes, U256},
+    types::{spoof, Bytes, U256},
 };
 use silius_contracts::{
     decode_revert_string,
@@ -13,7 +13,7 @@ use silius_contracts::{
     UserOperationRevertReasonFilter,
 };
 use silius_primitives::UserOperationSigned;
-use std::str::FromStr;
+use std::{future::Future, str::FromStr};
 
 const FALL_BACK_BINARY_SEARCH_CUT_OFF: u64 = 30000;
 const BASE_VGL_BUFFER: u64 = 25;
@@ -109,8 +109,11 @@ fn parse_user_op_event<T: Debug + EthLogDecode>(event: &LogInfo) -> Result<T, En
 async fn trace_simulate_handle_op<M: Middleware>(
     user_op: &UserOperationSigned,
     entry_point: &EntryPoint<M>,
+    overrides: Option<&spoof::State>,
 ) -> Result<TraceOutput, EntryPointError> {
-    let geth_trace = entry_point.simulate_handle_op_trace(user_op.clone()).await?;
+    let geth_trace = entry_point
+        .simulate_handle_op_trace_with_overrides(user_op.clone(), overrides.cloned())
+        .await?;
 
     let tracer_result: ExecutorTracerResult =
         ExecutorTracerResult::try_from(geth_trace).map_err(|e| EntryPointError::Other {
@@ -149,9 +152,70 @@ async fn trace_simulate_handle_op<M: Middleware>(
     Ok(TraceOutput { tracer_result, execution_result, user_op_event, user_op_revert_event })
 }
 
+/// Binary searches for the minimal call gas limit for which the simulation of the user operation
+/// succeeds.
+///
+/// # Arguments
+/// `lower` - The lower bound of the call gas limit
+/// `upper` - The upper bound of the call gas limit
+/// `max_iterations` - The maximum number of simulations
+/// `simulate` - Simulates the user operation with the given call gas limit
+///
+/// # Returns
+/// The tightest call gas limit that passed the simulation.
+async fn search_call_gas_limit<F, Fut>(
+    lower: u64,
+    upper: u64,
+    max_iterations: u64,
+    mut simulate: F,
+) -> Result<u64, EntryPointError>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<(), EntryPointError>>,
+{
+    let mut l = lower;
+    let mut r = upper;
+    let mut f = None;
+    let mut iteration: u64 = 0;
+
+    while r >= l && r - l >= FALL_BACK_BINARY_SEARCH_CUT_OFF && iteration < max_iterations {
+        iteration += 1;
+        let m = l + (r - l) / 2;
+        match simulate(m).await {
+            Ok(_) => {
+                r = m - 1;
+                f = Some(m);
+            }
+            Err(err) => {
+                if is_prefund_not_paid(&err) {
+                    r = m - 1;
+                } else if is_execution_oog(&err) || is_execution_revert(&err) {
+                    l = m + 1;
+                } else {
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    f.ok_or(EntryPointError::Other { inner: "Could not find a valid call gas limit".to_string() })
+}
+
+/// Estimates the verification gas limit and the call gas limit of the user operation.
+///
+/// # Arguments
+/// `user_op_ori` - The user operation to estimate the gas limits for
+/// `entry_point` - The entry point the user operation is simulated against
+/// `max_iterations` - The maximum number of simulations when searching for the call gas limit
+/// `overrides` - The state overrides applied to every simulation (e.g. to fund the sender)
+///
+/// # Returns
+/// The verification gas limit and the call gas limit.
 pub async fn estimate_user_op_gas<M: Middleware>(
     user_op_ori: &UserOperationSigned,
     entry_point: &EntryPoint<M>,
+    max_iterations: u64,
+    overrides: Option<&spoof::State>,
 ) -> Result<(U256, U256), EntryPointError> {
     let mut iteration: u64 = 0;
 
@@ -172,7 +236,10 @@ pub async fn estimate_user_op_gas<M: Middleware>(
     while r - l >= FALL_BACK_BINARY_SEARCH_CUT_OFF {
         let m = (l + r) / 2;
         user_op.verification_gas_limit = m.into();
-        match entry_point.simulate_handle_op(user_op.clone()).await {
+        match entry_point
+            .simulate_handle_op_with_overrides(user_op.clone(), overrides.cloned())
+            .await
+        {
             // VGL too high
             Ok(_) => {
                 r = m - 1;
@@ -207,7 +274,7 @@ pub async fn estimate_user_op_gas<M: Middleware>(
         user_op.max_fee_per_gas = 0u64.into();
         user_op.max_priority_fee_per_gas = 0u64.into();
         user_op.call_gas_limit = MAX_CALL_GAS_LIMIT.into(); // max block gas limit, better set as a config parameter
-        match trace_simulate_handle_op(&user_op, entry_point).await {
+        match trace_simulate_handle_op(&user_op, entry_point, overrides).await {
             Ok(o) => {
                 out = o;
                 break;
@@ -220,7 +287,7 @@ pub async fn estimate_user_op_gas<M: Middleware>(
         }
     }
     let verification_gas_limit = user_op.verification_gas_limit;
-    let mut call_gas_limit = if out.tracer_result.execution_gas_limit < NON_ZERO_GAS {
+    let call_gas_limit = if out.tracer_result.execution_gas_limit < NON_ZERO_GAS {
         NON_ZERO_GAS
     } else {
         out.tracer_result.execution_gas_limit
@@ -230,47 +297,62 @@ pub async fn estimate_user_op_gas<M: Middleware>(
     user_op.max_fee_per_gas = user_op_ori.max_fee_per_gas;
     user_op.verification_gas_limit = verification_gas_limit;
     user_op.call_gas_limit = call_gas_limit.into();
-    loop {
-        match trace_simulate_handle_op(&user_op, entry_point).await {
-            Ok(_) => break,
-            Err(e) => {
-                if is_execution_oog(&e) || is_execution_revert(&e) {
-                    let mut l = call_gas_limit;
-                    let mut r = u64::MAX;
-                    let mut f = 0u64;
-                    while r - l >= FALL_BACK_BINARY_SEARCH_CUT_OFF {
-                        let m = (l + r) / 2;
-                        user_op.call_gas_limit = m.into();
-                        let res = trace_simulate_handle_op(&user_op, entry_point).await;
-                        match res {
-                            Ok(_) => {
-                                r = m - 1;
-                                f = m;
-                                continue;
-                            }
-                            Err(err) => {
-                                if is_prefund_not_paid(&err) {
-                                    r = m - 1;
-                                    continue;
-                                } else if is_execution_oog(&err) || is_execution_revert(&err) {
-                                    l = m + 1;
-                                    continue;
-                                } else {
-                                    return Err(EntryPointError::Other {
-                                        inner: "Could not find a valid call gas limit".to_string(),
-                                    });
-                                }
-                            }
-                        }
-                    }
-                    call_gas_limit = f;
-                } else {
-                    return Err(EntryPointError::Other {
-                        inner: format!("Trace handle op return unhandled error: {:?}", &e),
-                    });
+    let call_gas_limit = match trace_simulate_handle_op(&user_op, entry_point, overrides).await {
+        Ok(_) => call_gas_limit,
+        Err(e) if is_execution_oog(&e) || is_execution_revert(&e) => {
+            search_call_gas_limit(call_gas_limit, MAX_CALL_GAS_LIMIT, max_iterations, |m| {
+                let mut user_op = user_op.clone();
+                user_op.call_gas_limit = m.into();
+                async move {
+                    trace_simulate_handle_op(&user_op, entry_point, overrides).await.map(|_| ())
                 }
-            }
+            })
+            .await?
         }
-    }
+        Err(e) => {
+            return Err(EntryPointError::Other {
+                inner: format!("Trace handle op return unhandled error: {:?}", &e),
+            })
+        }
+    };
+
     Ok((verification_gas_limit, call_gas_limit.into()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn search(threshold: u64, max_iterations: u64) -> Result<u64, EntryPointError> {
+        search_call_gas_limit(21000, MAX_CALL_GAS_LIMIT, max_iterations, |m| async move {
+            if m >= threshold {
+                Ok(())
+            } else {
+                Err(EntryPointError::Other { inner: EXECUTION_OOG.to_string() })
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn search_call_gas_limit_tightest_value() {
+        let call_gas_limit = search(1_000_000, 100).await.unwrap();
+        assert!(call_gas_limit >= 1_000_000);
+        assert!(call_gas_limit - 1_000_000 < FALL_BACK_BINARY_SEARCH_CUT_OFF);
+    }
+
+    #[tokio::test]
+    async fn search_call_gas_limit_iteration_cap() {
+        let mut iterations = 0;
+        let res = search_call_gas_limit(21000, MAX_CALL_GAS_LIMIT, 3, |_| {
+            iterations += 1;
+            async { Err(EntryPointError::Other { inner: EXECUTION_OOG.to_string() }) }
+        })
+        .await;
+        assert!(res.is_err());
+        assert_eq!(iterations, 3);
+
+        // a single simulation cannot pass below the upper bound
+        assert!(search(MAX_CALL_GAS_LIMIT, 1).await.is_err());
+    }
+}
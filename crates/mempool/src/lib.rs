@@ -2,6 +2,7 @@
 #![allow(dead_code)]
 
 mod builder;
+mod bundle;
 #[cfg(feature = "mdbx")]
 mod database;
 pub mod error;
@@ -10,30 +11,34 @@ mod memory;
 mod mempool;
 pub mod metrics;
 mod reputation;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 mod uopool;
 mod utils;
 pub mod validate;
 
 pub use builder::UoPoolBuilder;
+pub use bundle::{build_bundle, effective_priority_fee, BundleLimits};
 #[cfg(feature = "mdbx")]
 pub use database::{
     init_env,
     tables::{
         CodeHashes, EntitiesReputation, UserOperations, UserOperationsByEntity,
-        UserOperationsBySender,
+        UserOperationsBySender, UserOperationsMetadata,
     },
     DatabaseError, DatabaseTable, WriteMap,
 };
 pub use error::{
     InvalidMempoolUserOperationError, MempoolError, MempoolErrorKind, ReputationError, SanityError,
-    SimulationError,
+    SimulationError, TraceError,
 };
 pub use mempool::{
-    mempool_id, AddRemoveUserOp, AddRemoveUserOpHash, ClearOp, Mempool, MempoolId,
+    mempool_id, AddRemoveUserOp, AddRemoveUserOpHash, ClearOp, Mempool, MempoolId, MempoolStats,
     UserOperationAct, UserOperationAddrAct, UserOperationAddrOp, UserOperationCodeHashAct,
-    UserOperationCodeHashOp, UserOperationOp,
+    UserOperationCodeHashOp, UserOperationMetadata, UserOperationMetadataAct,
+    UserOperationMetadataOp, UserOperationOp,
 };
 pub use reputation::{HashSetOp, Reputation, ReputationEntryOp};
-pub use uopool::UoPool;
-pub use utils::Overhead;
+pub use uopool::{RehashedUserOperation, UoPool};
+pub use utils::{L1DataFee, L1Gas, Overhead, POST_OP_GAS};
 pub use validate::{SanityCheck, SimulationCheck, SimulationTraceCheck};
@@ -1,7 +1,7 @@
 use crate::{
     mempool::{
         AddRemoveUserOp, AddRemoveUserOpHash, ClearOp, UserOperationAddrOp,
-        UserOperationCodeHashOp, UserOperationOp,
+        UserOperationCodeHashOp, UserOperationMetadata, UserOperationMetadataOp, UserOperationOp,
     },
     MempoolErrorKind,
 };
@@ -135,6 +135,33 @@ impl UserOperationCodeHashOp for HashMap<UserOperationHash, Vec<CodeHash>> {
     }
 }
 
+impl UserOperationMetadataOp for HashMap<UserOperationHash, UserOperationMetadata> {
+    fn set_metadata(
+        &mut self,
+        uo_hash: &UserOperationHash,
+        metadata: UserOperationMetadata,
+    ) -> Result<(), MempoolErrorKind> {
+        self.insert(*uo_hash, metadata);
+        Ok(())
+    }
+
+    fn get_all_metadata(
+        &self,
+    ) -> Result<Vec<(UserOperationHash, UserOperationMetadata)>, MempoolErrorKind> {
+        Ok(self.iter().map(|(uo_hash, metadata)| (*uo_hash, metadata.clone())).collect())
+    }
+
+    fn remove_metadata(&mut self, uo_hash: &UserOperationHash) -> Result<bool, MempoolErrorKind> {
+        Ok(self.remove(uo_hash).is_some())
+    }
+}
+
+impl ClearOp for HashMap<UserOperationHash, UserOperationMetadata> {
+    fn clear(&mut self) {
+        self.clear()
+    }
+}
+
 impl ClearOp for HashMap<UserOperationHash, Vec<CodeHash>> {
     fn clear(&mut self) {
         self.clear()
@@ -156,17 +183,276 @@ impl ClearOp for HashMap<Address, HashSet<UserOperationHash>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{utils::tests::mempool_test_case, Mempool};
+    use crate::{test_utils, utils::tests::mempool_test_case, Mempool, MempoolStats};
+    use ethers::types::U256;
+    use parking_lot::RwLock;
+    use std::{sync::Arc, time::Duration};
 
     #[allow(clippy::unit_cmp)]
     #[tokio::test]
     async fn memory_mempool() {
-        let mempool = Mempool::new(
-            Box::new(HashMap::<UserOperationHash, UserOperationSigned>::default()),
-            Box::new(HashMap::<Address, HashSet<UserOperationHash>>::default()),
-            Box::new(HashMap::<Address, HashSet<UserOperationHash>>::default()),
-            Box::new(HashMap::<UserOperationHash, Vec<CodeHash>>::default()),
-        );
+        let mempool = test_utils::memory_mempool();
         mempool_test_case(mempool);
     }
+
+    #[tokio::test]
+    async fn memory_mempool_eviction() {
+        let mut mempool = test_utils::memory_mempool();
+        mempool.set_max_size(Some(3));
+
+        let mut uo_hashes = vec![];
+        for fee in [2_u64, 1, 1] {
+            let uo = UserOperationSigned {
+                max_priority_fee_per_gas: fee.into(),
+                ..UserOperationSigned::random()
+            };
+            let uo_hash = uo.hash(&Address::random(), 5);
+            mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo)).unwrap();
+            uo_hashes.push(uo_hash);
+        }
+        assert!(mempool.is_full().unwrap());
+
+        // lowest fee, oldest-inserted on tie
+        assert_eq!(mempool.get_eviction_candidate().unwrap().unwrap().hash, uo_hashes[1]);
+
+        // staked user operations are never evicted
+        mempool.set_staked(&uo_hashes[1]);
+        assert_eq!(mempool.get_eviction_candidate().unwrap().unwrap().hash, uo_hashes[2]);
+
+        mempool.remove(&uo_hashes[2]).unwrap();
+        assert!(!mempool.is_full().unwrap());
+    }
+
+    #[test]
+    fn memory_mempool_unstaked_partition() {
+        let mut mempool = test_utils::memory_mempool();
+        mempool.set_max_size(Some(5));
+        mempool.set_max_size_unstaked(Some(2));
+
+        let mut uo_hashes = vec![];
+        for (fee, staked) in [(1_u64, true), (1, true), (2, false), (3, false)] {
+            let uo = UserOperationSigned {
+                max_priority_fee_per_gas: fee.into(),
+                ..UserOperationSigned::random()
+            };
+            let uo_hash = uo.hash(&Address::random(), 5);
+            mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo)).unwrap();
+            if staked {
+                mempool.set_staked(&uo_hash);
+            }
+            uo_hashes.push(uo_hash);
+        }
+
+        // the unstaked partition is full, the staked one isn't
+        assert!(!mempool.is_full().unwrap());
+        assert!(mempool.is_full_for(false).unwrap());
+        assert!(!mempool.is_full_for(true).unwrap());
+
+        // the cheaper staked user operations are not evicted
+        assert_eq!(mempool.get_eviction_candidate().unwrap().unwrap().hash, uo_hashes[2]);
+        mempool.remove(&uo_hashes[2]).unwrap();
+        assert!(!mempool.is_full_for(false).unwrap());
+    }
+
+    #[tokio::test]
+    async fn memory_mempool_fifo() {
+        let mut mempool = test_utils::memory_mempool();
+
+        let mut uo_hashes = vec![];
+        for fee in [1_u64, 2, 1, 1] {
+            let uo = UserOperationSigned {
+                max_priority_fee_per_gas: fee.into(),
+                ..UserOperationSigned::random()
+            };
+            let uo_hash = uo.hash(&Address::random(), 5);
+            mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo)).unwrap();
+            uo_hashes.push(uo_hash);
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+
+        for pair in uo_hashes.windows(2) {
+            assert!(
+                mempool.get_added_at(&pair[0]).unwrap() < mempool.get_added_at(&pair[1]).unwrap()
+            );
+        }
+        assert!(mempool.get_age(&uo_hashes[0]).unwrap() >= Duration::from_millis(6));
+
+        // highest fee first, oldest-added first on tie
+        assert_eq!(
+            mempool.get_sorted().unwrap().iter().map(|uo| uo.hash).collect::<Vec<_>>(),
+            vec![uo_hashes[1], uo_hashes[0], uo_hashes[2], uo_hashes[3]]
+        );
+
+        mempool.remove(&uo_hashes[0]).unwrap();
+        assert!(mempool.get_added_at(&uo_hashes[0]).is_none());
+    }
+
+    #[test]
+    fn memory_mempool_sorted_by_fee() {
+        let mut mempool = test_utils::memory_mempool();
+
+        // (max fee, max priority fee), the effective gas price at base fee 10 is 13, 15, 12, 15
+        let mut uo_hashes = vec![];
+        for (max_fee, priority_fee) in [(20_u64, 3_u64), (15, 10), (30, 2), (25, 5)] {
+            let uo = UserOperationSigned {
+                max_fee_per_gas: max_fee.into(),
+                max_priority_fee_per_gas: priority_fee.into(),
+                ..UserOperationSigned::random()
+            };
+            let uo_hash = uo.hash(&Address::random(), 5);
+            mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo)).unwrap();
+            uo_hashes.push(uo_hash);
+        }
+
+        // highest price first, first-added first on tie
+        assert_eq!(
+            mempool
+                .get_sorted_by_fee(10.into())
+                .unwrap()
+                .iter()
+                .map(|uo| uo.hash)
+                .collect::<Vec<_>>(),
+            vec![uo_hashes[1], uo_hashes[3], uo_hashes[0], uo_hashes[2]]
+        );
+
+        // at base fee 20 the max fee caps the price (20, 15, 22, 25)
+        assert_eq!(
+            mempool
+                .get_sorted_by_fee(20.into())
+                .unwrap()
+                .iter()
+                .map(|uo| uo.hash)
+                .collect::<Vec<_>>(),
+            vec![uo_hashes[3], uo_hashes[2], uo_hashes[0], uo_hashes[1]]
+        );
+    }
+
+    #[tokio::test]
+    async fn memory_mempool_nonce_keys() {
+        let mut mempool = test_utils::memory_mempool();
+
+        // same sender and sequence, different nonce keys (2D nonce)
+        let sender = Address::random();
+        let mut uo_hashes = vec![];
+        for key in [1_u64, 2] {
+            let uo = UserOperationSigned {
+                sender,
+                nonce: U256::from(key) << 64,
+                ..UserOperationSigned::random()
+            };
+            let uo_hash = uo.hash(&Address::random(), 5);
+            mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo)).unwrap();
+            uo_hashes.push(uo_hash);
+        }
+        assert_eq!(mempool.get_all_by_sender(&sender).len(), 2);
+
+        // only the operation with the same nonce key is the predecessor
+        let uo = UserOperationSigned {
+            sender,
+            nonce: U256::from(2) << 64,
+            ..UserOperationSigned::random()
+        };
+        let uo = UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo);
+        assert_eq!(mempool.get_prev_by_sender(&uo).unwrap().hash, uo_hashes[1]);
+
+        let uo = UserOperationSigned {
+            sender,
+            nonce: U256::from(3) << 64,
+            ..UserOperationSigned::random()
+        };
+        let uo = UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo);
+        assert!(mempool.get_prev_by_sender(&uo).is_none());
+    }
+
+    #[tokio::test]
+    async fn memory_mempool_purge_stale() {
+        let mut mempool = test_utils::memory_mempool();
+
+        let mut uo_hashes = vec![];
+        for block_number in [10_u64, 100] {
+            let uo = UserOperationSigned::random();
+            let uo_hash = uo.hash(&Address::random(), 5);
+            mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo)).unwrap();
+            mempool.set_verified_block(&uo_hash, block_number);
+            uo_hashes.push(uo_hash);
+        }
+
+        // freshly added user operations are kept within the grace period
+        assert!(mempool.purge_stale(120, 50, Duration::from_secs(60)).unwrap().is_empty());
+        assert_eq!(mempool.get_all().unwrap().len(), 2);
+
+        assert_eq!(mempool.purge_stale(120, 50, Duration::ZERO).unwrap(), vec![uo_hashes[0]]);
+        assert!(mempool.get(&uo_hashes[0]).unwrap().is_none());
+        assert!(mempool.get(&uo_hashes[1]).unwrap().is_some());
+    }
+
+    #[test]
+    fn memory_mempool_restore() {
+        let user_operations =
+            Arc::new(RwLock::new(HashMap::<UserOperationHash, UserOperationSigned>::default()));
+        let user_operations_by_sender =
+            Arc::new(RwLock::new(HashMap::<Address, HashSet<UserOperationHash>>::default()));
+        let user_operations_by_entity =
+            Arc::new(RwLock::new(HashMap::<Address, HashSet<UserOperationHash>>::default()));
+        let user_operations_code_hashes =
+            Arc::new(RwLock::new(HashMap::<UserOperationHash, Vec<CodeHash>>::default()));
+        let new_mempool = || {
+            Mempool::new(
+                Box::new(user_operations.clone()),
+                Box::new(user_operations_by_sender.clone()),
+                Box::new(user_operations_by_entity.clone()),
+                Box::new(user_operations_code_hashes.clone()),
+            )
+        };
+
+        let mut mempool = new_mempool();
+        let paymaster = Address::random();
+        for _ in 0..3 {
+            let uo = UserOperationSigned {
+                paymaster_and_data: paymaster.as_bytes().to_vec().into(),
+                ..UserOperationSigned::random()
+            };
+            let uo_hash = uo.hash(&Address::random(), 5);
+            mempool.add(UserOperation::from_user_operation_signed(uo_hash, uo)).unwrap();
+        }
+        let stats = mempool.get_stats();
+        assert_eq!(stats.pending, 3);
+        assert_eq!(stats.paymasters, 1);
+
+        // the counters of a mempool over the same storage are rebuilt from the storage tables
+        let mut restored = new_mempool();
+        assert_eq!(restored.get_stats(), stats);
+        assert!(restored.get_eviction_candidate().unwrap().is_some());
+
+        let uo = restored.get_all().unwrap()[0].clone();
+        restored.remove(&uo.hash).unwrap();
+        assert_eq!(
+            restored.get_stats(),
+            MempoolStats { pending: 2, size: stats.size - uo.pack().len(), ..stats }
+        );
+    }
+
+    #[test]
+    fn memory_mempool_pre_fund_by_paymaster() {
+        let mut mempool = test_utils::memory_mempool();
+
+        let paymaster = Address::random();
+        let uo = UserOperationSigned {
+            paymaster_and_data: paymaster.as_bytes().to_vec().into(),
+            call_gas_limit: 100_000.into(),
+            verification_gas_limit: 100_000.into(),
+            pre_verification_gas: 100_000.into(),
+            max_fee_per_gas: 10.into(),
+            ..UserOperationSigned::random()
+        };
+        let uo = UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo);
+        mempool.add(uo.clone()).unwrap();
+
+        // without a recorded prefund (e.g. restored from the storage) it is derived from the gas
+        // limits: (100k + 3 * 100k + 100k) * 10
+        assert_eq!(mempool.get_pre_fund_by_paymaster(&paymaster), U256::from(5_000_000));
+
+        mempool.set_pre_fund(&uo.hash, 1_000.into());
+        assert_eq!(mempool.get_pre_fund_by_paymaster(&paymaster), U256::from(1_000));
+    }
 }
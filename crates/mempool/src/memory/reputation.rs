@@ -49,33 +49,93 @@ impl ReputationEntryOp for HashMap<Address, ReputationEntry> {
 }
 #[cfg(test)]
 mod tests {
-    use crate::{utils::tests::reputation_test_case, Reputation};
-    use ethers::types::{Address, U256};
-    use parking_lot::RwLock;
-    use silius_primitives::{
-        constants::validation::reputation::{
-            BAN_SLACK, MIN_INCLUSION_RATE_DENOMINATOR, THROTTLING_SLACK,
-        },
-        reputation::ReputationEntry,
-    };
-    use std::{
-        collections::{HashMap, HashSet},
-        sync::Arc,
-    };
+    use crate::{test_utils, utils::tests::reputation_test_case};
+    use ethers::types::Address;
+    use silius_primitives::reputation::{ReputationEntry, Status};
 
     #[tokio::test]
     async fn memory_reputation() {
-        let entry: Box<HashMap<Address, ReputationEntry>> = Box::new(HashMap::default());
-        let reputation = Reputation::new(
-            MIN_INCLUSION_RATE_DENOMINATOR,
-            THROTTLING_SLACK,
-            BAN_SLACK,
-            U256::from(1),
-            U256::from(0),
-            Arc::new(RwLock::new(HashSet::<Address>::default())),
-            Arc::new(RwLock::new(HashSet::<Address>::default())),
-            entry,
-        );
-        reputation_test_case(reputation);
+        reputation_test_case(test_utils::memory_reputation());
+    }
+
+    #[test]
+    fn memory_reputation_find() {
+        let mut reputation = test_utils::memory_reputation();
+        let entries: Vec<ReputationEntry> = [(10, 1), (200, 0), (1000, 0)]
+            .into_iter()
+            .map(|(uo_seen, uo_included)| ReputationEntry {
+                uo_seen,
+                uo_included,
+                ..ReputationEntry::default_with_addr(Address::random())
+            })
+            .collect();
+        reputation.set_entities(entries.clone()).unwrap();
+
+        for (entry, status) in entries.iter().zip([Status::OK, Status::THROTTLED, Status::BANNED]) {
+            let found = reputation.find(&entry.address).unwrap().unwrap();
+            assert_eq!((found.uo_seen, found.uo_included), (entry.uo_seen, entry.uo_included));
+            assert_eq!(Status::from(found.status), status);
+        }
+
+        assert_eq!(reputation.find(&Address::random()).unwrap(), None);
+    }
+
+    #[test]
+    fn memory_reputation_allowed_in_bundle() {
+        let mut reputation = test_utils::memory_reputation();
+        let entries: Vec<ReputationEntry> = [(10, 1), (200, 0), (1000, 0)]
+            .into_iter()
+            .map(|(uo_seen, uo_included)| ReputationEntry {
+                uo_seen,
+                uo_included,
+                ..ReputationEntry::default_with_addr(Address::random())
+            })
+            .collect();
+        reputation.set_entities(entries.clone()).unwrap();
+
+        for (entry, allowed) in
+            entries.iter().zip([[true, true, true], [true, false, false], [false, false, false]])
+        {
+            for (count, allowed) in allowed.into_iter().enumerate() {
+                assert_eq!(
+                    reputation.is_allowed_in_bundle(&entry.address, count).unwrap(),
+                    allowed
+                );
+            }
+        }
+
+        // unknown entities are ok
+        assert!(reputation.is_allowed_in_bundle(&Address::random(), 5).unwrap());
+    }
+
+    #[test]
+    fn memory_reputation_snapshot_restore() {
+        let mut reputation = test_utils::memory_reputation();
+        let entries: Vec<ReputationEntry> = [(10, 1), (200, 0), (1000, 0)]
+            .into_iter()
+            .map(|(uo_seen, uo_included)| ReputationEntry {
+                uo_seen,
+                uo_included,
+                ..ReputationEntry::default_with_addr(Address::random())
+            })
+            .collect();
+        reputation.set_entities(entries.clone()).unwrap();
+
+        let mut snapshot = reputation.snapshot();
+        snapshot.sort_by_key(|entry| entry.uo_seen);
+        assert_eq!(snapshot, entries);
+
+        // entities missing from the snapshot are removed
+        let mut restored = test_utils::memory_reputation();
+        let other = Address::random();
+        restored.increment_seen(&other).unwrap();
+        restored.restore(snapshot).unwrap();
+        assert_eq!(restored.find(&other).unwrap(), None);
+
+        for entry in entries.iter() {
+            let found = restored.find(&entry.address).unwrap().unwrap();
+            assert_eq!((found.uo_seen, found.uo_included), (entry.uo_seen, entry.uo_included));
+            assert_eq!(found.status, reputation.get_status(&entry.address).unwrap());
+        }
     }
 }
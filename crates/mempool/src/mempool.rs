@@ -1,13 +1,21 @@
-use crate::MempoolErrorKind;
+use crate::{validate::sanity::paymaster::Paymaster, MempoolErrorKind};
 use dyn_clone::DynClone;
 use ethers::{
     abi::AbiEncode,
+    prelude::{EthAbiCodec, EthAbiType},
     types::{Address, H256, U256},
     utils::{keccak256, to_checksum},
 };
 use parking_lot::RwLock;
-use silius_primitives::{simulation::CodeHash, UserOperation, UserOperationHash};
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use silius_primitives::{get_address, simulation::CodeHash, UserOperation, UserOperationHash};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
 
 pub type MempoolId = H256;
 
@@ -302,6 +310,101 @@ impl<T: UserOperationCodeHashOp> UserOperationCodeHashOp for Arc<RwLock<T>> {
     }
 }
 
+/// Insertion metadata of a user operation which is persisted along with the user operation, so the
+/// eviction policy and the statistics of the mempool survive a restart
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    EthAbiCodec,
+    EthAbiType,
+)]
+pub struct UserOperationMetadata {
+    /// Sequence number of the insertion
+    pub order: u64,
+    /// Time the user operation was added to the mempool (milliseconds since the unix epoch)
+    pub added_at: u64,
+    /// Number of the block the user operation is verified on (0 if not recorded)
+    pub verified_block: u64,
+    /// Whether the user operation has at least one staked entity (its partition of the mempool)
+    pub staked: bool,
+    /// Prefund the entry point requires for the user operation (0 if not recorded)
+    pub pre_fund: U256,
+    /// Verification gas limit returned by the simulation (0 if not recorded)
+    pub verification_gas_limit: U256,
+    /// Signature aggregator of the user operation (zero address if not aggregated)
+    pub aggregator: Address,
+}
+
+/// Trait for persisting the insertion metadata of the user operations.
+pub trait UserOperationMetadataOp {
+    /// Sets the insertion metadata of the user operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `uo_hash` - The hash of the user operation.
+    /// * `metadata` - The [UserOperationMetadata](UserOperationMetadata) of the user operation.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the metadata was stored, otherwise an error of type
+    /// `MempoolErrorKind`.
+    fn set_metadata(
+        &mut self,
+        uo_hash: &UserOperationHash,
+        metadata: UserOperationMetadata,
+    ) -> Result<(), MempoolErrorKind>;
+
+    /// Retrieves the insertion metadata of all user operations.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(Vec<(UserOperationHash, UserOperationMetadata)>)` containing the metadata of
+    /// all user operations, or an `Err(MempoolErrorKind)` if an error occurs.
+    fn get_all_metadata(
+        &self,
+    ) -> Result<Vec<(UserOperationHash, UserOperationMetadata)>, MempoolErrorKind>;
+
+    /// Removes the insertion metadata of the user operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `uo_hash` - The hash of the user operation.
+    ///
+    /// # Returns
+    ///
+    /// - If the metadata was removed, `Ok(true)` is returned.
+    /// - If the user operation has no metadata, `Ok(false)` is returned.
+    /// - If an error occurs during the operation, an `Err` variant is returned.
+    fn remove_metadata(&mut self, uo_hash: &UserOperationHash) -> Result<bool, MempoolErrorKind>;
+}
+
+impl<T: UserOperationMetadataOp> UserOperationMetadataOp for Arc<RwLock<T>> {
+    fn set_metadata(
+        &mut self,
+        uo_hash: &UserOperationHash,
+        metadata: UserOperationMetadata,
+    ) -> Result<(), MempoolErrorKind> {
+        self.write().set_metadata(uo_hash, metadata)
+    }
+
+    fn get_all_metadata(
+        &self,
+    ) -> Result<Vec<(UserOperationHash, UserOperationMetadata)>, MempoolErrorKind> {
+        self.read().get_all_metadata()
+    }
+
+    fn remove_metadata(&mut self, uo_hash: &UserOperationHash) -> Result<bool, MempoolErrorKind> {
+        self.write().remove_metadata(uo_hash)
+    }
+}
+
 /// A trait for clearing operation.
 pub trait ClearOp {
     /// Clears the operation.
@@ -344,12 +447,145 @@ impl<T> UserOperationCodeHashAct for T where
 {
 }
 
+pub trait UserOperationMetadataAct:
+    UserOperationMetadataOp + ClearOp + Send + Sync + DynClone
+{
+}
+dyn_clone::clone_trait_object!(UserOperationMetadataAct);
+impl<T> UserOperationMetadataAct for T where
+    T: UserOperationMetadataOp + ClearOp + Send + Sync + Clone
+{
+}
+
+/// Insertion metadata of the user operations (used by the eviction policy)
+#[derive(Default, Debug)]
+struct Insertions {
+    /// Sequence number of the next inserted user operation
+    next: u64,
+    /// Sequence numbers of the inserted user operations
+    order: HashMap<UserOperationHash, u64>,
+    /// Time the user operations were added to the mempool
+    added_at: HashMap<UserOperationHash, SystemTime>,
+    /// User operations with at least one staked entity (never evicted)
+    staked: HashSet<UserOperationHash>,
+    /// Number of the block the user operations are verified on
+    verified_blocks: HashMap<UserOperationHash, u64>,
+    /// Prefund the entry point requires for the user operations (committed from the deposit of
+    /// the paymaster)
+    pre_funds: HashMap<UserOperationHash, U256>,
+    /// Verification gas limits of the user operations returned by the simulation
+    verification_gas_limits: HashMap<UserOperationHash, U256>,
+    /// Signature aggregators of the user operations (only for the aggregated user operations)
+    aggregators: HashMap<UserOperationHash, Address>,
+    /// Max priority fees per gas of the user operations (used to select the eviction candidate
+    /// without loading the user operations)
+    priority_fees: HashMap<UserOperationHash, U256>,
+    /// Number of user operations per sender
+    senders: HashMap<Address, usize>,
+    /// Number of user operations per paymaster
+    paymasters: HashMap<Address, usize>,
+    /// Aggregate size (in bytes) of the packed user operations
+    size: usize,
+}
+
+impl Insertions {
+    /// Records the insertion of the user operation (its sequence number and the counters of the
+    /// senders, paymasters and size)
+    fn insert(&mut self, uo: &UserOperation, added_at: SystemTime) {
+        let (sender, _, paymaster) = uo.get_entities();
+        self.order.insert(uo.hash, self.next);
+        self.added_at.insert(uo.hash, added_at);
+        self.priority_fees.insert(uo.hash, uo.max_priority_fee_per_gas);
+        self.next += 1;
+        *self.senders.entry(sender).or_default() += 1;
+        if let Some(paymaster) = paymaster {
+            *self.paymasters.entry(paymaster).or_default() += 1;
+        }
+        self.size += uo.pack().len();
+    }
+
+    /// Records the insertion of the user operation restored from the storage with its persisted
+    /// metadata (the user operations without metadata are treated as just added)
+    fn restore(&mut self, uo: &UserOperation, metadata: Option<&UserOperationMetadata>) {
+        let added_at = metadata
+            .map(|metadata| UNIX_EPOCH + Duration::from_millis(metadata.added_at))
+            .unwrap_or_else(SystemTime::now);
+        self.insert(uo, added_at);
+        if let Some(metadata) = metadata {
+            if metadata.verified_block != 0 {
+                self.verified_blocks.insert(uo.hash, metadata.verified_block);
+            }
+            if metadata.staked {
+                self.staked.insert(uo.hash);
+            }
+            if !metadata.pre_fund.is_zero() {
+                self.pre_funds.insert(uo.hash, metadata.pre_fund);
+            }
+            if !metadata.verification_gas_limit.is_zero() {
+                self.verification_gas_limits.insert(uo.hash, metadata.verification_gas_limit);
+            }
+            if !metadata.aggregator.is_zero() {
+                self.aggregators.insert(uo.hash, metadata.aggregator);
+            }
+        }
+    }
+
+    /// Gets the insertion metadata of the user operation to persist
+    fn metadata(&self, uo_hash: &UserOperationHash) -> Option<UserOperationMetadata> {
+        Some(UserOperationMetadata {
+            order: *self.order.get(uo_hash)?,
+            added_at: self
+                .added_at
+                .get(uo_hash)
+                .and_then(|added_at| added_at.duration_since(UNIX_EPOCH).ok())
+                .map(|added_at| added_at.as_millis() as u64)
+                .unwrap_or_default(),
+            verified_block: self.verified_blocks.get(uo_hash).cloned().unwrap_or_default(),
+            staked: self.staked.contains(uo_hash),
+            pre_fund: self.pre_funds.get(uo_hash).cloned().unwrap_or_default(),
+            verification_gas_limit: self
+                .verification_gas_limits
+                .get(uo_hash)
+                .cloned()
+                .unwrap_or_default(),
+            aggregator: self.aggregators.get(uo_hash).cloned().unwrap_or_default(),
+        })
+    }
+
+    /// Decrements the number of user operations of the address (and drops it once it reaches 0)
+    fn decrement(counts: &mut HashMap<Address, usize>, addr: &Address) {
+        if let Some(count) = counts.get_mut(addr) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                counts.remove(addr);
+            }
+        }
+    }
+}
+
+/// Statistics of the user operations in the mempool
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MempoolStats {
+    /// Number of pending user operations
+    pub pending: usize,
+    /// Number of distinct senders
+    pub senders: usize,
+    /// Number of distinct paymasters
+    pub paymasters: usize,
+    /// Aggregate size (in bytes) of the packed user operations
+    pub size: usize,
+}
+
 #[derive(Clone)]
 pub struct Mempool {
     user_operations: Box<dyn UserOperationAct>,
     user_operations_by_sender: Box<dyn UserOperationAddrAct>,
     user_operations_by_entity: Box<dyn UserOperationAddrAct>,
     user_operations_code_hashes: Box<dyn UserOperationCodeHashAct>,
+    user_operations_metadata: Option<Box<dyn UserOperationMetadataAct>>,
+    max_size: Option<usize>,
+    max_size_unstaked: Option<usize>,
+    insertions: Arc<RwLock<Insertions>>,
 }
 
 impl Mempool {
@@ -359,17 +595,270 @@ impl Mempool {
         user_operations_by_entity: Box<dyn UserOperationAddrAct>,
         user_operations_code_hashes: Box<dyn UserOperationCodeHashAct>,
     ) -> Self {
-        Self {
+        let mut mempool = Self {
             user_operations,
             user_operations_by_sender,
             user_operations_by_entity,
             user_operations_code_hashes,
+            user_operations_metadata: None,
+            max_size: None,
+            max_size_unstaked: None,
+            insertions: Default::default(),
+        };
+        if let Err(err) = mempool.restore() {
+            warn!("Failed to restore the insertion metadata of the mempool: {err:?}");
+        }
+        mempool
+    }
+    /// Sets the storage the insertion metadata of the user operations is persisted to (and
+    /// restores the insertion metadata from it), so the insertion order, the insertion times, the
+    /// verified blocks, the staked/unstaked partition and the simulation results (prefund,
+    /// verification gas limit and aggregator) survive a restart of a persistent backend
+    pub fn set_user_operations_metadata(
+        &mut self,
+        user_operations_metadata: Box<dyn UserOperationMetadataAct>,
+    ) -> Result<(), MempoolErrorKind> {
+        self.user_operations_metadata = Some(user_operations_metadata);
+        self.restore()
+    }
+    /// Rebuilds the insertion metadata from the user operations in the storage tables and the
+    /// persisted [UserOperationMetadata], so the user operations restored by a persistent backend
+    /// (e.g. the database after a restart) are counted in the [MempoolStats] and covered by the
+    /// eviction policy. The user operations without persisted metadata are restored after the
+    /// others, as if they were just added.
+    pub fn restore(&mut self) -> Result<(), MempoolErrorKind> {
+        let mut metadata: HashMap<UserOperationHash, UserOperationMetadata> =
+            match self.user_operations_metadata.as_ref() {
+                Some(user_operations_metadata) => {
+                    user_operations_metadata.get_all_metadata()?.into_iter().collect()
+                }
+                None => HashMap::new(),
+            };
+
+        let mut uos = self.user_operations.get_all()?;
+        uos.sort_by_key(|uo| {
+            (metadata.get(&uo.hash).map(|metadata| metadata.order).unwrap_or(u64::MAX), uo.hash)
+        });
+
+        let mut insertions = Insertions::default();
+        for uo in uos.iter() {
+            insertions.restore(uo, metadata.remove(&uo.hash).as_ref());
+        }
+        *self.insertions.write() = insertions;
+
+        // the metadata left over belongs to user operations which are no longer stored
+        if let Some(user_operations_metadata) = self.user_operations_metadata.as_mut() {
+            for uo_hash in metadata.keys() {
+                user_operations_metadata.remove_metadata(uo_hash)?;
+            }
+        }
+        for uo in uos.iter() {
+            self.persist_metadata(&uo.hash);
+        }
+
+        Ok(())
+    }
+    /// Writes the insertion metadata of the user operation through to the metadata storage (if
+    /// set). A failed write is logged, the in-memory metadata stays authoritative until restart.
+    fn persist_metadata(&mut self, uo_hash: &UserOperationHash) {
+        if let Some(user_operations_metadata) = self.user_operations_metadata.as_mut() {
+            if let Some(metadata) = self.insertions.read().metadata(uo_hash) {
+                if let Err(err) = user_operations_metadata.set_metadata(uo_hash, metadata) {
+                    warn!("Failed to persist the metadata of user operation {uo_hash:?}: {err:?}");
+                }
+            }
+        }
+    }
+    /// Sets the maximum number of user operations in the mempool (`None` means unlimited)
+    pub fn set_max_size(&mut self, max_size: Option<usize>) {
+        self.max_size = max_size;
+    }
+    /// Checks whether the mempool reached its maximum size
+    pub fn is_full(&self) -> Result<bool, MempoolErrorKind> {
+        match self.max_size {
+            Some(max_size) => Ok(self.insertions.read().order.len() >= max_size),
+            None => Ok(false),
+        }
+    }
+    /// Sets the maximum number of user operations without a staked entity in the mempool (`None`
+    /// means only the maximum size of the whole mempool applies), so the unstaked user operations
+    /// can't crowd out the staked ones
+    pub fn set_max_size_unstaked(&mut self, max_size: Option<usize>) {
+        self.max_size_unstaked = max_size;
+    }
+    /// Checks whether the partition (staked or unstaked) the user operation is added to reached
+    /// its maximum size or the whole mempool is full
+    pub fn is_full_for(&self, staked: bool) -> Result<bool, MempoolErrorKind> {
+        if self.is_full()? {
+            return Ok(true);
+        }
+
+        match self.max_size_unstaked {
+            Some(max_size) if !staked => {
+                let insertions = self.insertions.read();
+                Ok(insertions.order.len().saturating_sub(insertions.staked.len()) >= max_size)
+            }
+            _ => Ok(false),
+        }
+    }
+    /// Marks the user operation as one with a staked entity, so it is never evicted
+    pub fn set_staked(&mut self, uo_hash: &UserOperationHash) {
+        self.insertions.write().staked.insert(*uo_hash);
+        self.persist_metadata(uo_hash);
+    }
+    /// Records the number of the block which the user operation is verified on (used to purge
+    /// stale user operations)
+    pub fn set_verified_block(&mut self, uo_hash: &UserOperationHash, block_number: u64) {
+        self.insertions.write().verified_blocks.insert(*uo_hash, block_number);
+        self.persist_metadata(uo_hash);
+    }
+    /// Records the prefund which the entry point requires for the user operation
+    pub fn set_pre_fund(&mut self, uo_hash: &UserOperationHash, pre_fund: U256) {
+        self.insertions.write().pre_funds.insert(*uo_hash, pre_fund);
+        self.persist_metadata(uo_hash);
+    }
+    /// Records the verification gas limit of the user operation returned by the simulation
+    pub fn set_verification_gas_limit(
+        &mut self,
+        uo_hash: &UserOperationHash,
+        verification_gas_limit: U256,
+    ) {
+        self.insertions.write().verification_gas_limits.insert(*uo_hash, verification_gas_limit);
+        self.persist_metadata(uo_hash);
+    }
+    /// Records the signature aggregator of the user operation
+    pub fn set_aggregator(&mut self, uo_hash: &UserOperationHash, aggregator: Address) {
+        self.insertions.write().aggregators.insert(*uo_hash, aggregator);
+        self.persist_metadata(uo_hash);
+    }
+    /// Gets the recorded signature aggregator of the user operation
+    pub fn get_aggregator(&self, uo_hash: &UserOperationHash) -> Option<Address> {
+        self.insertions.read().aggregators.get(uo_hash).cloned()
+    }
+    /// Gets the recorded prefund of the user operation
+    pub fn get_pre_fund(&self, uo_hash: &UserOperationHash) -> Option<U256> {
+        self.insertions.read().pre_funds.get(uo_hash).cloned()
+    }
+    /// Gets the recorded verification gas limit of the user operation
+    pub fn get_verification_gas_limit(&self, uo_hash: &UserOperationHash) -> Option<U256> {
+        self.insertions.read().verification_gas_limits.get(uo_hash).cloned()
+    }
+    /// Checks whether the user operation is marked as one with a staked entity
+    pub fn is_staked(&self, uo_hash: &UserOperationHash) -> bool {
+        self.insertions.read().staked.contains(uo_hash)
+    }
+    /// Gets the time the user operation was added to the mempool. A replacement user operation
+    /// (same sender and nonce) is a new entry, so it gets a fresh timestamp and is queued behind
+    /// the user operations with the same fee that were added before it.
+    pub fn get_added_at(&self, uo_hash: &UserOperationHash) -> Option<SystemTime> {
+        self.insertions.read().added_at.get(uo_hash).cloned()
+    }
+    /// Gets the time elapsed since the user operation was added to the mempool
+    pub fn get_age(&self, uo_hash: &UserOperationHash) -> Option<Duration> {
+        self.get_added_at(uo_hash).map(|added_at| added_at.elapsed().unwrap_or_default())
+    }
+    /// Gets the total prefund of the user operations in the mempool sponsored by the paymaster.
+    /// The prefund of a user operation without a recorded one (e.g. stored before the prefund was
+    /// persisted) is derived from its gas limits.
+    pub fn get_pre_fund_by_paymaster(&self, paymaster: &Address) -> U256 {
+        let insertions = self.insertions.read();
+        self.user_operations_by_entity
+            .get_all_by_address(paymaster)
+            .iter()
+            .flat_map(|uo_hash| self.get(uo_hash))
+            .flatten()
+            .filter(|uo| get_address(&uo.paymaster_and_data) == Some(*paymaster))
+            .fold(U256::zero(), |total, uo| {
+                total.saturating_add(
+                    insertions
+                        .pre_funds
+                        .get(&uo.hash)
+                        .cloned()
+                        .unwrap_or_else(|| Paymaster::required_pre_fund(&uo)),
+                )
+            })
+    }
+    /// Gets the number of the user operations in the mempool deploying the account through the
+    /// factory which were added after the given time.
+    pub fn get_number_by_factory_since(&self, factory: &Address, since: SystemTime) -> usize {
+        let insertions = self.insertions.read();
+        self.user_operations_by_entity
+            .get_all_by_address(factory)
+            .iter()
+            .filter(|uo_hash| {
+                insertions.added_at.get(uo_hash).is_some_and(|added_at| *added_at >= since)
+            })
+            .flat_map(|uo_hash| self.get(uo_hash))
+            .flatten()
+            .filter(|uo| get_address(&uo.init_code) == Some(*factory))
+            .count()
+    }
+    /// Removes the user operations which were verified more than `max_age` blocks before the
+    /// `head` block, so they have to be re-validated on resubmission. User operations added to the
+    /// mempool less than `grace_period` ago are kept regardless of the block age (on fast chains
+    /// many blocks can pass between the validation and the insertion).
+    ///
+    /// # Returns
+    /// * `Ok(Vec<UserOperationHash>)` - The hashes of the purged user operations
+    /// * `Err(MempoolErrorKind)` - If there are some internal errors
+    pub fn purge_stale(
+        &mut self,
+        head: u64,
+        max_age: u64,
+        grace_period: Duration,
+    ) -> Result<Vec<UserOperationHash>, MempoolErrorKind> {
+        let stale = {
+            let insertions = self.insertions.read();
+            insertions
+                .verified_blocks
+                .iter()
+                .filter(|(uo_hash, block_number)| {
+                    head.saturating_sub(**block_number) > max_age &&
+                        insertions.added_at.get(uo_hash).is_some_and(|added_at| {
+                            added_at.elapsed().unwrap_or_default() >= grace_period
+                        })
+                })
+                .map(|(uo_hash, _)| *uo_hash)
+                .collect::<Vec<_>>()
+        };
+
+        for uo_hash in stale.iter() {
+            self.remove(uo_hash)?;
+        }
+
+        Ok(stale)
+    }
+    /// Gets the user operation which should be evicted first when the mempool is full: the one
+    /// with the lowest max_priority_fee_per_gas, and the oldest-inserted one when fees tie. User
+    /// operations with staked entities are never evicted.
+    pub fn get_eviction_candidate(&self) -> Result<Option<UserOperation>, MempoolErrorKind> {
+        let candidate = {
+            let insertions = self.insertions.read();
+            insertions
+                .order
+                .iter()
+                .filter(|(uo_hash, _)| !insertions.staked.contains(uo_hash))
+                .min_by_key(|(uo_hash, order)| {
+                    (
+                        insertions.priority_fees.get(uo_hash).cloned().unwrap_or_default(),
+                        **order,
+                        **uo_hash,
+                    )
+                })
+                .map(|(uo_hash, _)| *uo_hash)
+        };
+
+        match candidate {
+            Some(uo_hash) => self.user_operations.get_by_uo_hash(&uo_hash),
+            None => Ok(None),
         }
     }
     pub fn add(&mut self, uo: UserOperation) -> Result<UserOperationHash, MempoolErrorKind> {
         let (sender, factory, paymaster) = uo.get_entities();
         let uo_hash = uo.hash;
-        self.user_operations.add(uo)?;
+        self.user_operations.add(uo.clone())?;
+        self.insertions.write().insert(&uo, SystemTime::now());
+        self.persist_metadata(&uo_hash);
         self.user_operations_by_sender.add(&sender, uo_hash)?;
         if let Some(factory) = factory {
             self.user_operations_by_entity.add(&factory, uo_hash)?;
@@ -385,6 +874,10 @@ impl Mempool {
     ) -> Result<Option<UserOperation>, MempoolErrorKind> {
         self.user_operations.get_by_uo_hash(uo_hash)
     }
+    /// Checks whether the user operation with the hash is in the mempool.
+    pub fn contains(&self, uo_hash: &UserOperationHash) -> bool {
+        matches!(self.user_operations.get_by_uo_hash(uo_hash), Ok(Some(_)))
+    }
     pub fn get_all_by_sender(&self, addr: &Address) -> Vec<UserOperation> {
         let uos_by_sender = self.user_operations_by_sender.get_all_by_address(addr);
         uos_by_sender
@@ -399,6 +892,9 @@ impl Mempool {
     pub fn get_number_by_entity(&self, addr: &Address) -> usize {
         self.user_operations_by_entity.get_number_by_address(addr)
     }
+    /// Gets the user operation which the given user operation would replace, i.e. the user
+    /// operation of the same sender with the same nonce. The nonce includes the nonce key (high
+    /// 192 bits), so user operations on different nonce keys never replace each other.
     pub fn get_prev_by_sender(&self, uo: &UserOperation) -> Option<UserOperation> {
         self.user_operations_by_sender
             .get_all_by_address(&uo.sender)
@@ -447,6 +943,27 @@ impl Mempool {
 
         self.user_operations_code_hashes.remove_code_hashes(uo_hash)?;
 
+        if let Some(user_operations_metadata) = self.user_operations_metadata.as_mut() {
+            user_operations_metadata.remove_metadata(uo_hash)?;
+        }
+
+        {
+            let mut insertions = self.insertions.write();
+            insertions.order.remove(uo_hash);
+            insertions.added_at.remove(uo_hash);
+            insertions.staked.remove(uo_hash);
+            insertions.verified_blocks.remove(uo_hash);
+            insertions.pre_funds.remove(uo_hash);
+            insertions.verification_gas_limits.remove(uo_hash);
+            insertions.aggregators.remove(uo_hash);
+            insertions.priority_fees.remove(uo_hash);
+            Insertions::decrement(&mut insertions.senders, &sender);
+            if let Some(paymaster) = paymaster {
+                Insertions::decrement(&mut insertions.paymasters, &paymaster);
+            }
+            insertions.size = insertions.size.saturating_sub(uo.pack().len());
+        }
+
         Ok(true)
     }
     pub fn remove_by_entity(&mut self, entity: &Address) -> Result<(), MempoolErrorKind> {
@@ -458,17 +975,62 @@ impl Mempool {
 
         Ok(())
     }
-    // Get UserOperations sorted by max_priority_fee_per_gas without dup sender
+    /// Gets the user operations sorted by max_priority_fee_per_gas. User operations with the same
+    /// fee are ordered first-in-first-out by the time they were added to the mempool.
     pub fn get_sorted(&self) -> Result<Vec<UserOperation>, MempoolErrorKind> {
-        self.user_operations.get_sorted()
+        let mut uos = self.user_operations.get_sorted()?;
+        let insertions = self.insertions.read();
+        uos.sort_by_key(|uo| {
+            (
+                Reverse(uo.max_priority_fee_per_gas),
+                insertions.added_at.get(&uo.hash).cloned(),
+                insertions.order.get(&uo.hash).cloned(),
+            )
+        });
+        Ok(uos)
+    }
+    /// Gets the user operations sorted by the effective gas price at the given base fee (see
+    /// [effective_gas_price](silius_primitives::UserOperationSigned::effective_gas_price)), highest
+    /// first. User operations with the same price are ordered first-in-first-out by the time they
+    /// were added to the mempool.
+    pub fn get_sorted_by_fee(
+        &self,
+        base_fee: U256,
+    ) -> Result<Vec<UserOperation>, MempoolErrorKind> {
+        let mut uos = self.user_operations.get_all()?;
+        let insertions = self.insertions.read();
+        uos.sort_by_key(|uo| {
+            (
+                Reverse(uo.effective_gas_price(base_fee)),
+                insertions.added_at.get(&uo.hash).cloned(),
+                insertions.order.get(&uo.hash).cloned(),
+            )
+        });
+        Ok(uos)
     }
     pub fn get_all(&self) -> Result<Vec<UserOperation>, MempoolErrorKind> {
         self.user_operations.get_all()
     }
+    /// Gets the [MempoolStats] of the user operations in the mempool (read from the insertion
+    /// metadata, which is rebuilt from the storage tables on [restore](Mempool::restore), so the
+    /// user operations are not loaded)
+    pub fn get_stats(&self) -> MempoolStats {
+        let insertions = self.insertions.read();
+        MempoolStats {
+            pending: insertions.order.len(),
+            senders: insertions.senders.len(),
+            paymasters: insertions.paymasters.len(),
+            size: insertions.size,
+        }
+    }
     pub fn clear(&mut self) {
         self.user_operations.clear();
         self.user_operations_by_sender.clear();
         self.user_operations_by_entity.clear();
         self.user_operations_code_hashes.clear();
+        if let Some(user_operations_metadata) = self.user_operations_metadata.as_mut() {
+            user_operations_metadata.clear();
+        }
+        *self.insertions.write() = Insertions::default();
     }
 }
@@ -244,6 +244,22 @@ impl Reputation {
         }
     }
 
+    /// Get an entity's [ReputationEntry](ReputationEntry) (with the current status) by address,
+    /// without falling back to a default entry for unknown entities
+    ///
+    /// # Arguments
+    /// * `addr` - The address to get
+    ///
+    /// # Returns
+    /// * `Ok(Some(ReputationEntry))` if the address exists
+    /// * `Ok(None)` if the address does not exist
+    pub fn find(&self, addr: &Address) -> Result<Option<ReputationEntry>, ReputationError> {
+        match self.entities.get_entry(addr)? {
+            Some(ent) => Ok(Some(ReputationEntry { status: self.get_status(addr)?, ..ent })),
+            None => Ok(None),
+        }
+    }
+
     /// Increase the number of times an entity's address has been seen
     ///
     /// # Arguments
@@ -261,6 +277,22 @@ impl Reputation {
         Ok(())
     }
 
+    /// Decrease the number of times an entity's address has been seen (e.g. when its user
+    /// operation is evicted from the full mempool, which isn't caused by the entity)
+    ///
+    /// # Arguments
+    /// * `addr` - The address to decrement
+    ///
+    /// # Returns
+    /// * `Ok(())` if the address was decremented successfully (or isn't tracked)
+    pub fn decrement_seen(&mut self, addr: &Address) -> Result<(), ReputationError> {
+        if let Some(mut ent) = self.entities.get_entry(addr)? {
+            ent.uo_seen = ent.uo_seen.saturating_sub(1);
+            self.entities.set_entry(ent)?;
+        }
+        Ok(())
+    }
+
     /// Increases the number of times an entity successfully includes a
     /// user operation in a block.
     ///
@@ -279,6 +311,27 @@ impl Reputation {
         Ok(())
     }
 
+    /// Recalculates an entity's status from its counters and records it in the entity's
+    /// [ReputationEntry](ReputationEntry), so an entity whose seen user operations exceed the
+    /// included ones by more than the ban slack (scaled by the minimum inclusion denominator) is
+    /// banned. Banned entities are rejected by the sanity checks.
+    ///
+    /// # Arguments
+    /// * `addr` - The address to classify
+    ///
+    /// # Returns
+    /// * `Ok(Status)` - The status of the entity
+    pub fn update_and_classify(&mut self, addr: &Address) -> Result<Status, ReputationError> {
+        let status = self.get_status(addr)?;
+        if let Some(mut ent) = self.entities.get_entry(addr)? {
+            if ent.status != status {
+                ent.status = status;
+                self.entities.set_entry(ent)?;
+            }
+        }
+        Ok(Status::from(status))
+    }
+
     /// Update an entity's status by hours
     ///
     /// # Returns
@@ -377,20 +430,68 @@ impl Reputation {
         }
 
         Ok(match self.entities.get_entry(addr)? {
-            Some(ent) => {
-                let max_seen = ent.uo_seen / self.min_inclusion_denominator;
-                if max_seen > ent.uo_included + self.ban_slack {
-                    Status::BANNED.into()
-                } else if max_seen > ent.uo_included + self.throttling_slack {
-                    Status::THROTTLED.into()
-                } else {
-                    Status::OK.into()
-                }
-            }
+            Some(ent) => Self::calculate_status(
+                &ent,
+                self.min_inclusion_denominator,
+                self.throttling_slack,
+                self.ban_slack,
+            )
+            .into(),
             _ => Status::OK.into(),
         })
     }
 
+    /// Calculates the status of the [ReputationEntry](ReputationEntry) from its seen and included
+    /// user operations with the given thresholds (the whitelist and the blacklist are not
+    /// considered).
+    ///
+    /// # Arguments
+    /// * `ent` - The [ReputationEntry](ReputationEntry) of the entity
+    /// * `min_inclusion_denominator` - Minimum denominator for calculating the minimum expected
+    ///   inclusions (a denominator of zero is treated as one)
+    /// * `throttling_slack` - Constant for calculating the throttling threshold
+    /// * `ban_slack` - Constant for calculating the ban threshold
+    ///
+    /// # Returns
+    /// * The [Status](Status) of the entity
+    pub fn calculate_status(
+        ent: &ReputationEntry,
+        min_inclusion_denominator: u64,
+        throttling_slack: u64,
+        ban_slack: u64,
+    ) -> Status {
+        let max_seen = ent.uo_seen / min_inclusion_denominator.max(1);
+        if max_seen > ent.uo_included + ban_slack {
+            Status::BANNED
+        } else if max_seen > ent.uo_included + throttling_slack {
+            Status::THROTTLED
+        } else {
+            Status::OK
+        }
+    }
+
+    /// Checks whether another user operation of the entity may be added to a bundle. A banned
+    /// entity may not be included at all and a throttled entity may be included in a single user
+    /// operation of the bundle.
+    ///
+    /// # Arguments
+    /// * `addr` - The address of the entity
+    /// * `count` - The number of the user operations of the entity already selected for the bundle
+    ///
+    /// # Returns
+    /// * `Ok(true)` if another user operation of the entity may be added
+    pub fn is_allowed_in_bundle(
+        &self,
+        addr: &Address,
+        count: usize,
+    ) -> Result<bool, ReputationError> {
+        Ok(match Status::from(self.get_status(addr)?) {
+            Status::OK => true,
+            Status::THROTTLED => count == 0,
+            Status::BANNED => false,
+        })
+    }
+
     /// Update an entity's status when the user operation is reverted.
     ///
     /// # Arguments
@@ -437,32 +538,24 @@ impl Reputation {
                 return Ok(());
             }
 
-            let min_stake =
-                if let Some(min_stake) = min_stake { min_stake } else { self.min_stake };
-
-            // TODO: use this value below
-            let _min_unstake_delay = if let Some(min_unstake_delay) = min_unstake_delay {
-                min_unstake_delay
-            } else {
-                self.min_unstake_delay
-            };
+            let min_stake = min_stake.unwrap_or(self.min_stake);
+            let min_unstake_delay = min_unstake_delay.unwrap_or(self.min_unstake_delay);
 
             let err = if info.stake < min_stake {
                 ReputationError::StakeTooLow {
                     entity: entity.into(),
                     address: info.address,
                     stake: info.stake,
-                    min_stake: self.min_stake,
+                    min_stake,
                 }
-            } else if info.unstake_delay < U256::from(2)
-            // TODO: remove this when spec tests are updated!!!!
-            /* min_unstake_delay */
-            {
+            } else if info.unstake_delay < min_unstake_delay {
+                // an entity which can unstake right away isn't accountable, so it's treated as
+                // unstaked
                 ReputationError::UnstakeDelayTooLow {
                     address: info.address,
                     entity: entity.into(),
                     unstake_delay: info.unstake_delay,
-                    min_unstake_delay: self.min_unstake_delay,
+                    min_unstake_delay,
                 }
             } else {
                 return Ok(());
@@ -520,6 +613,32 @@ impl Reputation {
         }
     }
 
+    /// Takes a snapshot of all stored [Reputation Entries](ReputationEntry) (for backups). The
+    /// entries are returned as stored (the status is not recalculated) and the live store is not
+    /// modified.
+    ///
+    /// # Returns
+    /// * All stored [Reputation Entries](ReputationEntry)
+    pub fn snapshot(&self) -> Vec<ReputationEntry> {
+        self.entities.get_all()
+    }
+
+    /// Restores the [Reputation Entries](ReputationEntry) from a snapshot. The snapshot replaces
+    /// the stored entries: entities missing from the snapshot are removed. The whitelist and the
+    /// blacklist are configuration, so they are kept as they are. Stakes are not part of the
+    /// entries (they are read from the entry point).
+    ///
+    /// # Arguments
+    /// * `entries` - The [Reputation Entries](ReputationEntry) from
+    ///   [snapshot](Reputation::snapshot)
+    ///
+    /// # Returns
+    /// * `Ok(())` if the entries were restored successfully
+    pub fn restore(&mut self, entries: Vec<ReputationEntry>) -> Result<(), ReputationError> {
+        self.entities.clear();
+        self.set_entities(entries)
+    }
+
     /// Clear all [Reputation Entries](ReputationEntry)
     pub fn clear(&mut self) {
         self.entities.clear();
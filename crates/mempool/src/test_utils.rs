@@ -0,0 +1,48 @@
+//! Fixtures shared by the tests: an empty in-memory [Mempool](Mempool) and
+//! [Reputation](Reputation).
+use crate::{Mempool, Reputation};
+use ethers::types::{Address, U256};
+use parking_lot::RwLock;
+use silius_primitives::{
+    constants::validation::reputation::{
+        BAN_SLACK, MIN_INCLUSION_RATE_DENOMINATOR, THROTTLING_SLACK,
+    },
+    reputation::ReputationEntry,
+    simulation::CodeHash,
+    UserOperationHash, UserOperationSigned,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// Creates an empty in-memory [Mempool](Mempool).
+pub fn memory_mempool() -> Mempool {
+    Mempool::new(
+        Box::new(HashMap::<UserOperationHash, UserOperationSigned>::default()),
+        Box::new(HashMap::<Address, HashSet<UserOperationHash>>::default()),
+        Box::new(HashMap::<Address, HashSet<UserOperationHash>>::default()),
+        Box::new(HashMap::<UserOperationHash, Vec<CodeHash>>::default()),
+    )
+}
+
+/// Creates an empty in-memory [Reputation](Reputation) with the default slacks, a minimum stake
+/// of 1 and no minimum unstake delay.
+pub fn memory_reputation() -> Reputation {
+    memory_reputation_with_unstake_delay(U256::zero())
+}
+
+/// Creates an empty in-memory [Reputation](Reputation) with the default slacks, a minimum stake
+/// of 1 and the given minimum unstake delay.
+pub fn memory_reputation_with_unstake_delay(min_unstake_delay: U256) -> Reputation {
+    Reputation::new(
+        MIN_INCLUSION_RATE_DENOMINATOR,
+        THROTTLING_SLACK,
+        BAN_SLACK,
+        U256::from(1),
+        min_unstake_delay,
+        Arc::new(RwLock::new(HashSet::<Address>::default())),
+        Arc::new(RwLock::new(HashSet::<Address>::default())),
+        Box::new(HashMap::<Address, ReputationEntry>::default()),
+    )
+}
@@ -1,42 +1,65 @@
 use crate::{
+    build_bundle,
     estimate::estimate_user_op_gas,
     mempool::Mempool,
     mempool_id,
-    utils::div_ceil,
+    utils::{div_ceil, get_logs_in_range, POST_OP_GAS},
     validate::{
-        utils::merge_storage_maps, UserOperationValidationOutcome, UserOperationValidator,
-        UserOperationValidatorMode,
+        metrics::ValidationPhase, utils::merge_storage_maps, UserOperationValidationOutcome,
+        UserOperationValidator, UserOperationValidatorMode,
     },
-    InvalidMempoolUserOperationError, MempoolError, MempoolErrorKind, MempoolId, Overhead,
-    Reputation, ReputationError, SanityError, SimulationError,
+    BundleLimits, InvalidMempoolUserOperationError, L1DataFee, L1Gas, MempoolError,
+    MempoolErrorKind, MempoolId, Overhead, Reputation, ReputationError, SanityError,
+    SimulationError,
 };
 use alloy_chains::Chain;
+use enumset::EnumSet;
 use ethers::{
+    abi::RawLog,
+    contract::{EthEvent, EthLogDecode},
     prelude::LogMeta,
     providers::Middleware,
-    types::{Address, BlockNumber, U256},
+    types::{spoof, Address, BlockNumber, Filter, Log, TransactionReceipt, H256, U256},
 };
 use eyre::format_err;
 use futures::channel::mpsc::UnboundedSender;
 use silius_contracts::{
-    entry_point::UserOperationEventFilter, utils::parse_from_input_data, EntryPoint,
-    EntryPointError,
+    decode_revert_string, entry_point::UserOperationEventFilter, utils::parse_from_input_data,
+    EntryPoint, EntryPointError, UserOperationRevertReasonFilter,
 };
 use silius_primitives::{
-    constants::validation::reputation::THROTTLED_ENTITY_BUNDLE_COUNT,
+    constants::{
+        mempool::{ESTIMATION_MAX_ITERATIONS, MAX_LOG_RANGE},
+        validation::reputation::THROTTLED_ENTITY_BUNDLE_COUNT,
+    },
     get_address,
     p2p::NetworkMessage,
     reputation::{ReputationEntry, StakeInfo, StakeInfoResponse, Status},
-    simulation::{StorageMap, ValidationConfig},
+    simulation::{CodeHash, StorageMap, ValidationConfig},
     UoPoolMode, UserOperation, UserOperationByHash, UserOperationGasEstimation, UserOperationHash,
-    UserOperationReceipt,
+    UserOperationHasher, UserOperationHasherV06, UserOperationReceipt, UserOperationSigned,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
 };
-use std::collections::{HashMap, HashSet};
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
 const FILTER_MAX_DEPTH: u64 = 10;
 const PRE_VERIFICATION_SAFE_RESERVE_PERC: u64 = 10; // percentage how higher pre verification gas we return
 
+/// A [UserOperation](UserOperation) rehashed after the chain of the pool changed (see
+/// [UoPool::revalidate_rehashed_user_operations](UoPool::revalidate_rehashed_user_operations))
+#[derive(Debug, Clone)]
+pub struct RehashedUserOperation {
+    /// The hash of the user operation on the previous chain
+    pub prev_hash: UserOperationHash,
+    /// The rehashed user operation along with its validation outcome on the new chain (None if
+    /// the user operation is no longer valid, e.g. its signature commits to the previous hash)
+    pub revalidated: Option<(UserOperation, UserOperationValidationOutcome)>,
+}
+
 /// The alternative mempool pool implementation that provides functionalities to add, remove,
 /// validate, and serves data requests from the RPC API. Architecturally, the
 /// [UoPool](UoPool) is the backend service managed by the user operation service and serves
@@ -58,6 +81,22 @@ pub struct UoPool<M: Middleware + 'static, V: UserOperationValidator> {
     pub max_verification_gas: U256,
     // The [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID
     pub chain: Chain,
+    // The oracle of the L1 data fee (None if the chain is not an OP-stack chain), set by the
+    // builder
+    pub(crate) l1_data_fee: Option<L1DataFee>,
+    // The node interface estimating the L1 gas (None if the chain is not an Arbitrum chain), set
+    // by the builder
+    pub(crate) l1_gas: Option<L1Gas>,
+    // The maximum number of simulations when searching for the call gas limit during the gas
+    // estimation
+    pub estimation_max_iterations: u64,
+    // The maximum number of blocks queried in a single `eth_getLogs` call
+    pub max_log_range: u64,
+    // The gas reserved for the paymaster `postOp` call of sponsored user operations during the gas
+    // estimation
+    pub post_op_gas: U256,
+    // The hashing scheme of the user operations (matching the version of the entry point)
+    pub hasher: Arc<dyn UserOperationHasher>,
     // Connection to the p2p network (None if not enabled)
     network: Option<UnboundedSender<NetworkMessage>>,
 }
@@ -98,10 +137,209 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
             reputation,
             max_verification_gas,
             chain,
+            l1_data_fee: L1DataFee::from_chain(&chain),
+            l1_gas: L1Gas::from_chain(&chain),
+            estimation_max_iterations: ESTIMATION_MAX_ITERATIONS,
+            max_log_range: MAX_LOG_RANGE,
+            post_op_gas: U256::from(POST_OP_GAS),
+            hasher: Arc::new(UserOperationHasherV06),
             network,
         }
     }
 
+    /// Calculates the hash of the user operation with the hashing scheme of the entry point, so
+    /// the hashes of the user operations in the mempool (used e.g. to replace a user operation)
+    /// don't depend on the hash computed by the client.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperationSigned](UserOperationSigned) to hash
+    ///
+    /// # Returns
+    /// `Result<UserOperation, eyre::Error>` - The [UserOperation](UserOperation) with the hash,
+    /// fails if the user operation can't be represented in the format of the entry point
+    pub fn hash_user_operation(&self, uo: UserOperationSigned) -> eyre::Result<UserOperation> {
+        let uo_hash = self.hasher.hash(&uo, &self.entry_point.address(), self.chain.id())?;
+        Ok(UserOperation::from_user_operation_signed(uo_hash, uo))
+    }
+
+    /// Re-validates the [UserOperations](UserOperation) of the pool after the chain changed. The
+    /// hash of a user operation commits to the chain ID, so the user operations hashed for the
+    /// entry point of the pool on the previous chain are rehashed and validated (sanity and
+    /// simulation) again on the new chain, which rejects e.g. the signatures over the previous
+    /// hash. The pool must be created for the new chain. The mempool isn't changed, the result is
+    /// applied by [migrate_user_operations](UoPool::migrate_user_operations).
+    ///
+    /// As in [revalidate_user_operations](UoPool::revalidate_user_operations), the user
+    /// operations are validated against an empty mempool.
+    ///
+    /// # Arguments
+    /// `prev_chain` - The chain the user operations were hashed for
+    ///
+    /// # Returns
+    /// `Result<Vec<RehashedUserOperation>, MempoolErrorKind>` - The rehashed user operations, fails
+    /// without checking the remaining user operations if the provider is unreachable
+    pub async fn revalidate_rehashed_user_operations(
+        &self,
+        prev_chain: Chain,
+    ) -> Result<Vec<RehashedUserOperation>, MempoolErrorKind> {
+        let ep = self.entry_point.address();
+        let mempool = Mempool::new(
+            Box::new(HashMap::<UserOperationHash, UserOperationSigned>::default()),
+            Box::new(HashMap::<Address, HashSet<UserOperationHash>>::default()),
+            Box::new(HashMap::<Address, HashSet<UserOperationHash>>::default()),
+            Box::new(HashMap::<UserOperationHash, Vec<CodeHash>>::default()),
+        );
+
+        let mut rehashed = vec![];
+        for uo in self.mempool.get_sorted()? {
+            if self.hasher.hash(&uo.user_operation, &ep, prev_chain.id()).ok() != Some(uo.hash) {
+                continue;
+            }
+            let uo_new = self
+                .hash_user_operation(uo.user_operation.clone())
+                .map_err(|err| MempoolErrorKind::Other { inner: err.to_string() })?;
+            if uo_new.hash == uo.hash {
+                continue;
+            }
+
+            let revalidated = match self
+                .validator
+                .validate_user_operation(
+                    &uo_new,
+                    &ep,
+                    &mempool,
+                    &self.reputation,
+                    None,
+                    UserOperationValidatorMode::Sanity | UserOperationValidatorMode::Simulation,
+                )
+                .await
+            {
+                Ok(res) => Some((uo_new, res)),
+                Err(err) if err.is_rpc_down() => return Err(err.into()),
+                Err(err) => {
+                    debug!("User operation {:?} is not valid on {}: {err:?}", uo.hash, self.chain);
+                    None
+                }
+            };
+            rehashed.push(RehashedUserOperation { prev_hash: uo.hash, revalidated });
+        }
+
+        Ok(rehashed)
+    }
+
+    /// Applies the result of
+    /// [revalidate_rehashed_user_operations](UoPool::revalidate_rehashed_user_operations): the
+    /// rehashed user operations which are still valid replace the previous ones along with their
+    /// new validation outcome, the others are dropped. A rehashed user operation is added before
+    /// the previous one is removed, so a failure never loses a user operation. The user
+    /// operations removed in the meantime (e.g. bundled) are skipped.
+    ///
+    /// # Arguments
+    /// `rehashed` - The rehashed user operations
+    ///
+    /// # Returns
+    /// `Result<(), MempoolErrorKind>` - Fails if the mempool can't be updated
+    pub fn migrate_user_operations(
+        &mut self,
+        rehashed: &[RehashedUserOperation],
+    ) -> Result<(), MempoolErrorKind> {
+        for RehashedUserOperation { prev_hash, revalidated } in rehashed {
+            if !self.mempool.contains(prev_hash) {
+                continue;
+            }
+            match revalidated {
+                Some((uo, res)) => {
+                    let uo_hash = self.mempool.add(uo.clone())?;
+                    self.set_validation_outcome(&uo_hash, res);
+                }
+                None => info!("{prev_hash:?} dropped from the mempool {:?} after rekey", self.id),
+            }
+            self.mempool.remove(prev_hash)?;
+        }
+
+        Ok(())
+    }
+
+    /// Records the validation outcome of the [UserOperation](UserOperation) added to the mempool
+    /// (read back by [get_validation_outcome](UoPool::get_validation_outcome)).
+    ///
+    /// # Arguments
+    /// `uo_hash` - The [UserOperationHash](UserOperationHash) of the user operation
+    /// `res` - The [UserOperationValidationOutcome](UserOperationValidationOutcome) of the
+    /// validation
+    fn set_validation_outcome(
+        &mut self,
+        uo_hash: &UserOperationHash,
+        res: &UserOperationValidationOutcome,
+    ) {
+        if res.staked {
+            self.mempool.set_staked(uo_hash);
+        }
+        self.mempool.set_pre_fund(uo_hash, res.pre_fund);
+        self.mempool.set_verification_gas_limit(uo_hash, res.verification_gas_limit);
+        if res.verified_block_number != 0 {
+            self.mempool.set_verified_block(uo_hash, res.verified_block_number);
+        }
+        if let Some(aggregator) = res.aggregator {
+            self.mempool.set_aggregator(uo_hash, aggregator);
+        }
+
+        // TODO: find better way to do it atomically
+        if let Some(code_hashes) = res.code_hashes.clone() {
+            match self.mempool.set_code_hashes(uo_hash, code_hashes) {
+                Ok(_) => (),
+                Err(e) => error!(
+                    "Failed to set code hashes for user operation {uo_hash:?} with error: {e:?}"
+                ),
+            }
+        }
+    }
+
+    /// Gets the entities (sender, factory, paymaster and signature aggregator) whose reputation is
+    /// updated for the [UserOperation](UserOperation). The aggregator is known only for the user
+    /// operations in the mempool.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation)
+    ///
+    /// # Returns
+    /// `Vec<Address>` - The addresses of the entities
+    fn entities(&self, uo: &UserOperation) -> Vec<Address> {
+        let (sender, factory, paymaster) = uo.get_entities();
+        [Some(sender), factory, paymaster, self.mempool.get_aggregator(&uo.hash)]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Gets the validation outcome recorded when the [UserOperation](UserOperation) was added to
+    /// the mempool, without validating it again. Only the prefund, the verification gas limit,
+    /// whether an entity is staked and the signature aggregator are recorded (the other fields are
+    /// left default).
+    ///
+    /// # Arguments
+    /// `uo_hash` - The [UserOperationHash](UserOperationHash) of the user operation
+    ///
+    /// # Returns
+    /// `Option<UserOperationValidationOutcome>` - The recorded outcome, None if the user
+    /// operation is not in the mempool
+    pub fn get_validation_outcome(
+        &self,
+        uo_hash: &UserOperationHash,
+    ) -> Option<UserOperationValidationOutcome> {
+        self.mempool.get(uo_hash).ok().flatten()?;
+        Some(UserOperationValidationOutcome {
+            pre_fund: self.mempool.get_pre_fund(uo_hash).unwrap_or_default(),
+            verification_gas_limit: self
+                .mempool
+                .get_verification_gas_limit(uo_hash)
+                .unwrap_or_default(),
+            staked: self.mempool.is_staked(uo_hash),
+            aggregator: self.mempool.get_aggregator(uo_hash),
+            ..Default::default()
+        })
+    }
+
     /// Returns all of the [UserOperations](UserOperation) in the mempool
     ///
     /// # Returns
@@ -120,6 +358,21 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
         self.reputation.get_all().unwrap_or_default()
     }
 
+    /// Returns the [ReputationEntry](ReputationEntry) of the entity (without modifying the
+    /// reputation).
+    ///
+    /// # Arguments
+    /// `addr` - The address of the entity
+    ///
+    /// # Returns
+    /// `Option<ReputationEntry>` - The [ReputationEntry](ReputationEntry) if the entity is known
+    pub fn get_reputation_entry(
+        &self,
+        addr: &Address,
+    ) -> Result<Option<ReputationEntry>, ReputationError> {
+        self.reputation.find(addr)
+    }
+
     /// Sets the [ReputationEntry](ReputationEntry) for entities
     ///
     /// # Arguments
@@ -165,6 +418,7 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
     /// # Arguments
     /// `user_operations` - The array of [UserOperations](UserOperation) to add
     /// `val_config` - The optional [ValidationConfig](ValidationConfig) object
+    /// `mode` - The [UserOperationValidatorMode](UserOperationValidatorMode) flags
     ///
     /// # Returns
     /// `Result<(), MempoolError>` - Ok if the [UserOperations](UserOperation) are added
@@ -173,9 +427,15 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
         &mut self,
         user_operations: Vec<UserOperation>,
         val_config: Option<ValidationConfig>,
+        mode: EnumSet<UserOperationValidatorMode>,
     ) -> Result<(), MempoolError> {
         for uo in user_operations {
-            let res = self.validate_user_operation(&uo, val_config.clone()).await;
+            let uo_hash = uo.hash;
+            let uo = self.hash_user_operation(uo.user_operation).map_err(|err| MempoolError {
+                hash: uo_hash,
+                kind: MempoolErrorKind::Other { inner: err.to_string() },
+            })?;
+            let res = self.validate_user_operation(&uo, val_config.clone(), mode).await;
             self.add_user_operation(uo, res).await?;
         }
 
@@ -188,6 +448,8 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
     /// # Arguments
     /// `uo` - The [UserOperation](UserOperation) to validate
     /// `val_config` - The optional [ValidationConfig](ValidationConfig) object
+    /// `mode` - The [UserOperationValidatorMode](UserOperationValidatorMode) flags (which
+    /// validation phases are run)
     ///
     /// # Returns
     /// `Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError>` - The validation
@@ -196,20 +458,69 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
         &self,
         uo: &UserOperation,
         val_config: Option<ValidationConfig>,
+        mode: EnumSet<UserOperationValidatorMode>,
     ) -> Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError> {
         self.validator
             .validate_user_operation(
                 uo,
+                &self.entry_point.address(),
                 &self.mempool,
                 &self.reputation,
                 val_config,
-                UserOperationValidatorMode::Sanity |
-                    UserOperationValidatorMode::Simulation |
-                    UserOperationValidatorMode::SimulationTrace,
+                mode,
             )
             .await
     }
 
+    /// Validates a single [UserOperation](UserOperation) phase by phase (sanity, simulation,
+    /// simulation trace) on the same block without adding it into the mempool.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to validate
+    ///
+    /// # Returns
+    /// The complete validation outcome, otherwise the failing
+    /// [ValidationPhase](ValidationPhase) along with the error
+    pub async fn dry_run_user_operation(
+        &self,
+        uo: &UserOperation,
+    ) -> Result<UserOperationValidationOutcome, (ValidationPhase, InvalidMempoolUserOperationError)>
+    {
+        let verified_block = self
+            .validator
+            .get_verified_block()
+            .await
+            .map_err(|err| (ValidationPhase::Sanity, err))?;
+
+        let mut outcomes = Vec::new();
+        for (phase, mode) in [
+            (ValidationPhase::Sanity, UserOperationValidatorMode::Sanity),
+            (ValidationPhase::Simulation, UserOperationValidatorMode::Simulation),
+            (ValidationPhase::SimulationTrace, UserOperationValidatorMode::SimulationTrace),
+        ] {
+            let out = self
+                .validator
+                .validate_user_operation_on_block(
+                    uo,
+                    &self.entry_point.address(),
+                    &self.mempool,
+                    &self.reputation,
+                    None,
+                    mode.into(),
+                    verified_block,
+                )
+                .await
+                .map_err(|err| (phase, err))?;
+            outcomes.push(out);
+        }
+
+        // the simulation trace outcome misses only the result of the simulation checks
+        let mut out = outcomes.pop().expect("outcome of every phase");
+        out.valid_after = outcomes.pop().expect("outcome of every phase").valid_after;
+
+        Ok(out)
+    }
+
     /// Adds a single validated user operation into the pool
     /// Indirectly invoked by RPC API via gRPC sevice to add a [UserOperation](UserOperation) into
     /// the mempool The function first validates the [UserOperation](UserOperation) by calling
@@ -243,8 +554,54 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
             }
         };
 
-        if let Some(uo_hash) = res.prev_hash {
-            self.remove_user_operation(&uo_hash);
+        // the entities of the replaced user operation were already counted as seen
+        let mut entities_seen = HashSet::new();
+        let uo_prev = res.prev_hash.and_then(|uo_hash| self.mempool.get(&uo_hash).ok().flatten());
+        if let Some(ref uo_prev) = uo_prev {
+            entities_seen.extend(self.entities(uo_prev));
+        }
+
+        // the replaced user operation frees its slot, unless the replacement moves from the staked
+        // to the unstaked partition
+        let grows = uo_prev
+            .as_ref()
+            .map_or(true, |uo_prev| !res.staked && self.mempool.is_staked(&uo_prev.hash));
+
+        // the eviction candidate and the replaced user operation are only removed once the user
+        // operation is accepted
+        let mut evicted = None;
+        if grows &&
+            self.mempool
+                .is_full_for(res.staked)
+                .map_err(|e| MempoolError { hash: uo.hash, kind: e })?
+        {
+            let candidate = self
+                .mempool
+                .get_eviction_candidate()
+                .map_err(|e| MempoolError { hash: uo.hash, kind: e })?;
+            match candidate {
+                Some(candidate)
+                    if candidate.max_priority_fee_per_gas < uo.max_priority_fee_per_gas =>
+                {
+                    evicted = Some(candidate);
+                }
+                _ => {
+                    return Err(MempoolError {
+                        hash: uo.hash,
+                        kind: SanityError::MempoolFull {
+                            max_priority_fee_per_gas: uo.max_priority_fee_per_gas,
+                            max_priority_fee_per_gas_expected: candidate
+                                .map(|candidate| candidate.max_priority_fee_per_gas)
+                                .unwrap_or(U256::MAX),
+                        }
+                        .into(),
+                    });
+                }
+            }
+        }
+
+        if let Some(ref uo_prev) = uo_prev {
+            self.remove_user_operation(&uo_prev.hash);
         }
 
         if let Some(ref sender) = self.network {
@@ -259,29 +616,28 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
 
         match self.mempool.add(uo.clone()) {
             Ok(uo_hash) => {
-                // TODO: find better way to do it atomically
-                if let Some(code_hashes) = res.code_hashes {
-                    match self.mempool.set_code_hashes(&uo_hash, code_hashes){
-                        Ok(_) => (),
-                        Err(e) => error!("Failed to set code hashes for user operation {uo_hash:?} with error: {e:?}"),
-                    }
+                if let Some(evicted) = evicted {
+                    self.evict_user_operation(&evicted);
                 }
+                self.set_validation_outcome(&uo_hash, &res);
                 info!("{uo_hash:?} added to the mempool {:?}", self.id);
                 trace!("{uo:?} added to the mempool {:?}", self.id);
 
                 // update reputation
-                self.reputation
-                    .increment_seen(&uo.sender)
-                    .map_err(|e| MempoolError { hash: uo_hash, kind: e.into() })?;
-                if let Some(f_addr) = get_address(&uo.init_code) {
-                    self.reputation
-                        .increment_seen(&f_addr)
-                        .map_err(|e| MempoolError { hash: uo_hash, kind: e.into() })?;
-                }
-                if let Some(p_addr) = get_address(&uo.paymaster_and_data) {
-                    self.reputation
-                        .increment_seen(&p_addr)
-                        .map_err(|e| MempoolError { hash: uo_hash, kind: e.into() })?;
+                for entity in self.entities(&uo) {
+                    if !entities_seen.contains(&entity) {
+                        self.reputation
+                            .increment_seen(&entity)
+                            .map_err(|e| MempoolError { hash: uo_hash, kind: e.into() })?;
+                        if self
+                            .reputation
+                            .update_and_classify(&entity)
+                            .map_err(|e| MempoolError { hash: uo_hash, kind: e.into() })? ==
+                            Status::BANNED
+                        {
+                            warn!("{entity:?} is banned in the mempool {:?}", self.id);
+                        }
+                    }
                 }
 
                 Ok(uo_hash)
@@ -290,17 +646,196 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
         }
     }
 
-    /// Sorts the [UserOperations](UserOperation) in the mempool by calling the
-    /// [Mempool::get_sorted](Mempool::get_sorted) function
+    /// Evicts the [UserOperation](UserOperation) from the full mempool. The removal releases its
+    /// prefund and its counters in the mempool. The eviction isn't caused by its entities, so the
+    /// user operation is no longer counted as seen for them and their status is re-classified.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to evict
+    fn evict_user_operation(&mut self, uo: &UserOperation) {
+        let entities = self.entities(uo);
+        if let Err(e) = self.mempool.remove(&uo.hash) {
+            error!("Failed to evict user operation {:?} with error: {e:?}", uo.hash);
+            return;
+        }
+        info!("{:?} evicted from the full mempool {:?}", uo.hash, self.id);
+
+        for entity in entities {
+            if let Err(e) = self
+                .reputation
+                .decrement_seen(&entity)
+                .and_then(|_| self.reputation.update_and_classify(&entity))
+            {
+                warn!("Failed to update reputation of {entity:?} with error: {e:?}");
+            }
+        }
+    }
+
+    /// Removes the [UserOperations](UserOperation) which were verified more than `max_age` blocks
+    /// ago by calling the [Mempool::purge_stale](Mempool::purge_stale) function. The method is
+    /// meant to be called periodically.
+    ///
+    /// # Arguments
+    /// * `max_age` - The maximum number of blocks since the verification
+    /// * `grace_period` - The time for which newly added user operations are never purged
+    ///
+    /// # Returns
+    /// `Result<Vec<UserOperationHash>, eyre::Error>` - The hashes of the purged
+    /// [UserOperations](UserOperation)
+    pub async fn purge_stale_user_operations(
+        &mut self,
+        max_age: u64,
+        grace_period: Duration,
+    ) -> eyre::Result<Vec<UserOperationHash>> {
+        let head = self.entry_point.eth_client().get_block_number().await?;
+        let purged =
+            self.mempool.purge_stale(head.as_u64(), max_age, grace_period).map_err(|err| {
+                format_err!("Purging stale user operations failed with error: {err:?}")
+            })?;
+        if !purged.is_empty() {
+            debug!("Purged {} stale user operations from the mempool {:?}", purged.len(), self.id);
+        }
+        Ok(purged)
+    }
+
+    /// Re-validates (sanity and simulation) a batch of the pending
+    /// [UserOperations](UserOperation) and removes those which became invalid as the chain moved.
+    /// The batches rotate over the pending user operations (ordered by hash), so all of them are
+    /// eventually re-validated. The method is meant to be called periodically.
+    ///
+    /// The user operations are validated against an empty mempool, so the rules relative to the
+    /// other pending user operations (e.g. replacements or limits per entity), enforced when the
+    /// user operations were added, are not applied again. The removed user operations stay seen
+    /// but never included, so the status of their entities is updated.
+    ///
+    /// # Arguments
+    /// * `batch_size` - The maximum number of user operations re-validated (all of them if there
+    ///   are fewer pending user operations)
+    /// * `cursor` - The position of the batch, advanced after every batch
+    ///
+    /// # Returns
+    /// `Result<Vec<UserOperationHash>, eyre::Error>` - The hashes of the removed
+    /// [UserOperations](UserOperation)
+    pub async fn revalidate_user_operations(
+        &mut self,
+        batch_size: usize,
+        cursor: &mut usize,
+    ) -> eyre::Result<Vec<UserOperationHash>> {
+        let mut uos = self.get_all()?;
+        uos.sort_by_key(|uo| uo.hash);
+        if uos.len() > batch_size {
+            let start = *cursor % uos.len();
+            uos.rotate_left(start);
+            uos.truncate(batch_size);
+            *cursor = start + batch_size;
+        }
+
+        let mempool = Mempool::new(
+            Box::new(HashMap::<UserOperationHash, UserOperationSigned>::default()),
+            Box::new(HashMap::<Address, HashSet<UserOperationHash>>::default()),
+            Box::new(HashMap::<Address, HashSet<UserOperationHash>>::default()),
+            Box::new(HashMap::<UserOperationHash, Vec<CodeHash>>::default()),
+        );
+
+        let mut removed = vec![];
+        for uo in uos {
+            let err = match self
+                .validator
+                .validate_user_operation(
+                    &uo,
+                    &self.entry_point.address(),
+                    &mempool,
+                    &self.reputation,
+                    None,
+                    UserOperationValidatorMode::Sanity | UserOperationValidatorMode::Simulation,
+                )
+                .await
+            {
+                Ok(_) => continue,
+                Err(err) if err.is_rpc_down() => {
+                    warn!("Re-validation of user operations interrupted: {err:?}");
+                    break;
+                }
+                Err(err) => err,
+            };
+
+            debug!("User operation {:?} is no longer valid: {err:?}", uo.hash);
+            let entities = self.entities(&uo);
+            if !self.mempool.remove(&uo.hash).map_err(|err| {
+                format_err!("Removing a user operation {:?} failed with error: {err:?}", uo.hash)
+            })? {
+                continue;
+            }
+            removed.push(uo.hash);
+
+            for entity in entities {
+                let status = self.reputation.update_and_classify(&entity).map_err(|err| {
+                    format_err!("Updating reputation of {entity:?} failed with error: {err:?}")
+                })?;
+                if status == Status::BANNED {
+                    warn!("{entity:?} is banned in the mempool {:?}", self.id);
+                }
+            }
+        }
+
+        if !removed.is_empty() {
+            debug!(
+                "Removed {} invalid user operations from the mempool {:?}",
+                removed.len(),
+                self.id
+            );
+        }
+        Ok(removed)
+    }
+
+    /// Gets the base fee of the latest block (zero on chains without a base fee, where the
+    /// effective gas price of the user operations is their max priority fee).
+    async fn latest_base_fee(&self) -> eyre::Result<U256> {
+        Ok(self
+            .entry_point
+            .eth_client()
+            .get_block(BlockNumber::Latest)
+            .await?
+            .and_then(|block| block.base_fee_per_gas)
+            .unwrap_or_default())
+    }
+
+    /// Sorts the [UserOperations](UserOperation) in the mempool by the effective gas price at the
+    /// base fee of the latest block by calling the
+    /// [Mempool::get_sorted_by_fee](Mempool::get_sorted_by_fee) function
     ///
     /// # Returns
     /// `Result<Vec<UserOperation>, eyre::Error>` - The sorted [UserOperations](UserOperation)
-    pub fn get_sorted_user_operations(&self) -> eyre::Result<Vec<UserOperation>> {
-        self.mempool.get_sorted().map_err(|err| {
+    pub async fn get_sorted_user_operations(&self) -> eyre::Result<Vec<UserOperation>> {
+        let base_fee = self.latest_base_fee().await?;
+        self.mempool.get_sorted_by_fee(base_fee).map_err(|err| {
             format_err!("Getting sorted user operations from mempool failed with error: {err:?}",)
         })
     }
 
+    /// Selects the candidates for the next bundle from the mempool with
+    /// [build_bundle](crate::build_bundle) at the base fee of the latest block (the storage
+    /// conflicts are checked by [bundle_user_operations](Self::bundle_user_operations) after the
+    /// second simulation).
+    ///
+    /// # Returns
+    /// `Result<Vec<UserOperation>, eyre::Error>` - The selected [UserOperations](UserOperation),
+    /// highest effective gas price first
+    pub async fn select_user_operations(&self) -> eyre::Result<Vec<UserOperation>> {
+        let base_fee = self.latest_base_fee().await?;
+
+        let limits = BundleLimits {
+            max_uos_per_throttled_entity: THROTTLED_ENTITY_BUNDLE_COUNT,
+            ..BundleLimits::new(self.max_verification_gas)
+        };
+        let (uos, _) =
+            build_bundle(&self.mempool, &self.reputation, &HashMap::new(), base_fee, limits)
+                .map_err(|err| {
+                    format_err!("Selecting user operations from mempool failed with error: {err:?}")
+                })?;
+        Ok(uos)
+    }
+
     /// Bundles an array of [UserOperations](UserOperation)
     /// The function first checks the reputations of the entities, then validate each
     /// [UserOperation](UserOperation) by calling
@@ -327,6 +862,13 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
 
         let senders_all = uos.iter().map(|uo| uo.sender).collect::<HashSet<_>>();
 
+        // all user operations in the bundle are verified on the same block
+        let verified_block = self
+            .validator
+            .get_verified_block()
+            .await
+            .map_err(|err| format_err!("Getting verified block failed with error: {err:?}"))?;
+
         'uos: for uo in uos {
             if senders.contains(&uo.sender) {
                 continue;
@@ -368,13 +910,15 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
 
             let val_out = self
                 .validator
-                .validate_user_operation(
+                .validate_user_operation_on_block(
                     &uo,
+                    &self.entry_point.address(),
                     &self.mempool,
                     &self.reputation,
                     None,
                     UserOperationValidatorMode::Simulation |
                         UserOperationValidatorMode::SimulationTrace,
+                    verified_block,
                 )
                 .await;
             debug!("Second validation for userop {:?} result: {:?}", uo.hash, val_out);
@@ -465,7 +1009,9 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
 
     /// Estimates the `verification_gas_limit`, `call_gas_limit` and `pre_verification_gas` for a
     /// user operation. The function is indirectly invoked by the `estimate_user_operation_gas`
-    /// JSON RPC method.
+    /// JSON RPC method. If the user operation is sponsored by a paymaster, the `postOp` gas is
+    /// added to the `verification_gas_limit` and the simulation is re-run with the final values.
+    /// On OP-stack chains the L1 data fee of the user operation is returned as well.
     ///
     /// # Arguments
     /// * `uo` - The [UserOperation](UserOperation) to estimate the gas for.
@@ -477,15 +1023,145 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
         &self,
         uo: &UserOperation,
     ) -> Result<UserOperationGasEstimation, MempoolError> {
+        self.estimate_user_operation_gas_with_overrides(uo, None).await
+    }
+
+    /// Estimates the gas for a user operation (see
+    /// [estimate_user_operation_gas](Self::estimate_user_operation_gas)) with the state overrides
+    /// applied to every simulation, e.g. to fund a sender that isn't deployed yet.
+    ///
+    /// # Arguments
+    /// * `uo` - The [UserOperation](UserOperation) to estimate the gas for.
+    /// * `overrides` - The state overrides (no overrides if `None`).
+    ///
+    /// # Returns
+    /// `Result<UserOperationGasEstimation, MempoolError>` - The gas estimation result.
+    pub async fn estimate_user_operation_gas_with_overrides(
+        &self,
+        uo: &UserOperation,
+        overrides: Option<spoof::State>,
+    ) -> Result<UserOperationGasEstimation, MempoolError> {
+        // on Arbitrum the L1 cost of the calldata is charged in L2 gas
+        let l1_gas = match &self.l1_gas {
+            Some(l1_gas) => l1_gas
+                .get_l1_gas(
+                    self.entry_point.eth_client(),
+                    self.entry_point.address(),
+                    &uo.user_operation,
+                )
+                .await
+                .map_err(|e| MempoolError {
+                    hash: uo.hash,
+                    kind: MempoolErrorKind::Provider { inner: format!("{e:?}") },
+                })?,
+            None => U256::zero(),
+        };
         let pre_verification_gas = div_ceil(
-            Overhead::default().calculate_pre_verification_gas(uo).saturating_mul(
-                U256::from(100).saturating_add(PRE_VERIFICATION_SAFE_RESERVE_PERC.into()),
-            ),
+            Overhead::default()
+                .calculate_pre_verification_gas(uo)
+                .saturating_add(l1_gas)
+                .saturating_mul(
+                    U256::from(100).saturating_add(PRE_VERIFICATION_SAFE_RESERVE_PERC.into()),
+                ),
             U256::from(100),
         );
 
         let (verification_gas_limit, call_gas_limit) = match self.mode {
-            UoPoolMode::Standard => estimate_user_op_gas(&uo.user_operation, &self.entry_point)
+            UoPoolMode::Standard => estimate_user_op_gas(
+                &uo.user_operation,
+                &self.entry_point,
+                self.estimation_max_iterations,
+                overrides.as_ref(),
+            )
+            .await
+            .map_err(|e| match e {
+                EntryPointError::FailedOp(f) => MempoolError {
+                    hash: uo.hash,
+                    kind: MempoolErrorKind::InvalidUserOperation(
+                        InvalidMempoolUserOperationError::Simulation(SimulationError::Validation {
+                            inner: format!("{f:?}"),
+                        }),
+                    ),
+                },
+                EntryPointError::ExecutionReverted(e) => MempoolError {
+                    hash: uo.hash,
+                    kind: MempoolErrorKind::InvalidUserOperation(
+                        InvalidMempoolUserOperationError::Simulation(SimulationError::Execution {
+                            inner: e,
+                        }),
+                    ),
+                },
+                EntryPointError::Provider { inner } => {
+                    MempoolError { hash: uo.hash, kind: MempoolErrorKind::Provider { inner } }
+                }
+                _ => MempoolError {
+                    hash: uo.hash,
+                    kind: MempoolErrorKind::Other { inner: format!("{e:?}") },
+                },
+            })?,
+            UoPoolMode::Unsafe => {
+                let ret = self
+                    .entry_point
+                    .simulate_handle_op_with_overrides(uo.clone().user_operation, overrides.clone())
+                    .await
+                    .map_err(|e| match e {
+                        EntryPointError::FailedOp(f) => MempoolError {
+                            hash: uo.hash,
+                            kind: MempoolErrorKind::InvalidUserOperation(
+                                InvalidMempoolUserOperationError::Simulation(
+                                    SimulationError::Validation { inner: format!("{f:?}") },
+                                ),
+                            ),
+                        },
+                        EntryPointError::ExecutionReverted(e) => MempoolError {
+                            hash: uo.hash,
+                            kind: MempoolErrorKind::InvalidUserOperation(
+                                InvalidMempoolUserOperationError::Simulation(
+                                    SimulationError::Execution { inner: e },
+                                ),
+                            ),
+                        },
+                        EntryPointError::Provider { inner } => MempoolError {
+                            hash: uo.hash,
+                            kind: MempoolErrorKind::Provider { inner },
+                        },
+                        _ => MempoolError {
+                            hash: uo.hash,
+                            kind: MempoolErrorKind::Other { inner: format!("{e:?}") },
+                        },
+                    })?;
+
+                let verification_gas_limit = div_ceil(
+                    ret.pre_op_gas.saturating_sub(pre_verification_gas).saturating_mul(3.into()),
+                    2.into(),
+                );
+                // the entry point charges the effective gas price (the max fee if the base fee
+                // is not available)
+                let gas_price = match self.base_fee_per_gas().await {
+                    Ok(base_fee) => uo.effective_gas_price(base_fee),
+                    Err(_) => uo.max_fee_per_gas,
+                };
+                let call_gas_limit = div_ceil(ret.paid, gas_price)
+                    .saturating_sub(ret.pre_op_gas)
+                    .saturating_add(35000.into());
+
+                (verification_gas_limit, call_gas_limit)
+            }
+        };
+
+        // sponsored user operations need additional gas for the paymaster postOp call
+        let post_op_gas = Overhead { post_op: self.post_op_gas, ..Default::default() }
+            .calculate_post_op_gas(&uo.user_operation);
+        let verification_gas_limit = if post_op_gas.is_zero() {
+            verification_gas_limit
+        } else {
+            let verification_gas_limit = verification_gas_limit.saturating_add(post_op_gas);
+            let mut uo_estimated = uo.user_operation.clone();
+            uo_estimated.pre_verification_gas = pre_verification_gas;
+            uo_estimated.verification_gas_limit = verification_gas_limit;
+            uo_estimated.call_gas_limit = call_gas_limit;
+            self.entry_point
+                .simulate_handle_op_with_overrides(uo_estimated, overrides)
                 .await
                 .map_err(|e| match e {
                     EntryPointError::FailedOp(f) => MempoolError {
@@ -511,54 +1187,30 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
                         hash: uo.hash,
                         kind: MempoolErrorKind::Other { inner: format!("{e:?}") },
                     },
-                })?,
-            UoPoolMode::Unsafe => {
-                let ret =
-                    self.entry_point.simulate_handle_op(uo.clone().user_operation).await.map_err(
-                        |e| match e {
-                            EntryPointError::FailedOp(f) => MempoolError {
-                                hash: uo.hash,
-                                kind: MempoolErrorKind::InvalidUserOperation(
-                                    InvalidMempoolUserOperationError::Simulation(
-                                        SimulationError::Validation { inner: format!("{f:?}") },
-                                    ),
-                                ),
-                            },
-                            EntryPointError::ExecutionReverted(e) => MempoolError {
-                                hash: uo.hash,
-                                kind: MempoolErrorKind::InvalidUserOperation(
-                                    InvalidMempoolUserOperationError::Simulation(
-                                        SimulationError::Execution { inner: e },
-                                    ),
-                                ),
-                            },
-                            EntryPointError::Provider { inner } => MempoolError {
-                                hash: uo.hash,
-                                kind: MempoolErrorKind::Provider { inner },
-                            },
-                            _ => MempoolError {
-                                hash: uo.hash,
-                                kind: MempoolErrorKind::Other { inner: format!("{e:?}") },
-                            },
-                        },
-                    )?;
-
-                let verification_gas_limit = div_ceil(
-                    ret.pre_op_gas.saturating_sub(pre_verification_gas).saturating_mul(3.into()),
-                    2.into(),
-                );
-                let call_gas_limit = div_ceil(ret.paid, uo.user_operation.max_fee_per_gas)
-                    .saturating_sub(ret.pre_op_gas)
-                    .saturating_add(35000.into());
+                })?;
+            verification_gas_limit
+        };
 
-                (verification_gas_limit, call_gas_limit)
-            }
+        let l1_data_fee = match &self.l1_data_fee {
+            Some(l1_data_fee) => Some(
+                l1_data_fee
+                    .get_l1_data_fee(self.entry_point.eth_client(), &uo.user_operation)
+                    .await
+                    .map_err(|e| MempoolError {
+                        hash: uo.hash,
+                        kind: MempoolErrorKind::Provider { inner: format!("{e:?}") },
+                    })?,
+            ),
+            None => None,
         };
 
         Ok(UserOperationGasEstimation {
             pre_verification_gas,
             verification_gas_limit,
             call_gas_limit,
+            l1_data_fee,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         })
     }
 
@@ -575,20 +1227,27 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
         &self,
         uo_hash: &UserOperationHash,
     ) -> eyre::Result<Option<(UserOperationEventFilter, LogMeta)>> {
-        let mut event: Option<(UserOperationEventFilter, LogMeta)> = None;
-        let latest_block = self.entry_point.eth_client().get_block_number().await?;
-        let filter = self
-            .entry_point
-            .entry_point_api()
-            .event::<UserOperationEventFilter>()
-            .from_block(latest_block - FILTER_MAX_DEPTH)
+        let latest_block = self.entry_point.eth_client().get_block_number().await?.as_u64();
+        let filter = Filter::new()
+            .address(self.entry_point.address())
+            .topic0(UserOperationEventFilter::signature())
             .topic1(uo_hash.0);
-        let res: Vec<(UserOperationEventFilter, LogMeta)> = filter.query_with_meta().await?;
+        let logs = get_logs_in_range(
+            self.entry_point.eth_client().as_ref(),
+            &filter,
+            latest_block.saturating_sub(FILTER_MAX_DEPTH),
+            latest_block,
+            self.max_log_range,
+        )
+        .await?;
+
         // It is possible have two same user operatation in same bundle
         // see https://twitter.com/leekt216/status/1636414866662785024
-        for log_meta in res.iter() {
-            event = Some(log_meta.clone());
-        }
+        let event = logs.iter().rev().find_map(|log| {
+            UserOperationEventFilter::decode_log(&RawLog::from(log.clone()))
+                .ok()
+                .map(|event| (event, LogMeta::from(log)))
+        });
         Ok(event)
     }
 
@@ -623,9 +1282,9 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
                 return Ok(UserOperationByHash {
                     user_operation: uo,
                     entry_point: ep,
-                    transaction_hash: log_meta.transaction_hash,
-                    block_hash: log_meta.block_hash,
-                    block_number: log_meta.block_number,
+                    transaction_hash: Some(log_meta.transaction_hash),
+                    block_hash: Some(log_meta.block_hash),
+                    block_number: Some(log_meta.block_number),
                 });
             }
         }
@@ -641,37 +1300,30 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
     ///   for.
     ///
     /// # Returns
-    /// `Result<UserOperationReceipt, eyre::Error>` - The user operation receipt, if any.
+    /// `Result<Option<UserOperationReceipt>, eyre::Error>` - The user operation receipt, or None if
+    /// the user operation is not mined (yet).
     pub async fn get_user_operation_receipt(
         &self,
         uo_hash: &UserOperationHash,
-    ) -> eyre::Result<UserOperationReceipt> {
-        let event = self.get_user_operation_event_meta(uo_hash).await?;
-
-        if let Some((event, log_meta)) = event {
-            if let Some(tx_receipt) = self
-                .entry_point
-                .eth_client()
-                .get_transaction_receipt(log_meta.transaction_hash)
-                .await?
-            {
-                let uo = self.get_user_operation_by_hash(uo_hash).await?;
-                return Ok(UserOperationReceipt {
-                    user_operation_hash: *uo_hash,
-                    sender: event.sender,
-                    nonce: event.nonce,
-                    actual_gas_cost: event.actual_gas_cost,
-                    actual_gas_used: event.actual_gas_used,
-                    success: event.success,
-                    tx_receipt: tx_receipt.clone(),
-                    logs: tx_receipt.logs.into_iter().collect(),
-                    paymaster: get_address(&uo.user_operation.paymaster_and_data),
-                    reason: String::new(), // TODO: this must be set to revert reason
-                });
-            }
-        }
+    ) -> eyre::Result<Option<UserOperationReceipt>> {
+        let (event, log_meta) = match self.get_user_operation_event_meta(uo_hash).await? {
+            Some(event) => event,
+            None => return Ok(None),
+        };
 
-        Err(format_err!("No user operation found"))
+        Ok(self
+            .entry_point
+            .eth_client()
+            .get_transaction_receipt(log_meta.transaction_hash)
+            .await?
+            .map(|tx_receipt| {
+                assemble_user_operation_receipt(
+                    &self.entry_point.address(),
+                    uo_hash,
+                    event,
+                    tx_receipt,
+                )
+            }))
     }
 
     /// Removes the [UserOperation](UserOperation) from the user operation mempool
@@ -687,6 +1339,31 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
         None
     }
 
+    /// Removes multiple [UserOperations](UserOperation) from the user operation mempool
+    /// given their [UserOperationHashes](UserOperationHash). Reputation of the involved
+    /// entities is left untouched.
+    ///
+    /// # Arguments
+    /// * `uo_hashes` - The array of [UserOperationHash](UserOperationHash) to remove.
+    ///
+    /// # Returns
+    /// `Result<(usize, usize), MempoolErrorKind>` - The number of removed user operations and
+    /// the number of hashes not found in the mempool.
+    pub fn remove_user_operations_by_hash(
+        &mut self,
+        uo_hashes: &[UserOperationHash],
+    ) -> Result<(usize, usize), MempoolErrorKind> {
+        let mut removed = 0;
+
+        for uo_hash in uo_hashes {
+            if self.mempool.remove(uo_hash)? {
+                removed += 1;
+            }
+        }
+
+        Ok((removed, uo_hashes.len() - removed))
+    }
+
     pub fn remove_user_operation_by_entity(&mut self, entity: &Address) -> Option<()> {
         self.mempool.remove_by_entity(entity).ok();
         None
@@ -703,21 +1380,49 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
     /// `Option<()>` - None
     pub fn remove_user_operations(&mut self, uos: Vec<UserOperation>) -> Option<()> {
         for uo in uos {
+            let entities = self.entities(&uo);
             self.remove_user_operation(&uo.hash);
 
             // update reputations
-            self.reputation.increment_included(&uo.sender).ok();
-
-            if let Some(addr) = get_address(&uo.paymaster_and_data) {
-                self.reputation.increment_included(&addr).ok();
+            for entity in entities {
+                self.reputation.increment_included(&entity).ok();
             }
+        }
+
+        None
+    }
+
+    /// Removes the [UserOperations](UserOperation) included on-chain (reported by the
+    /// `UserOperationEvent` logs of the entry point) from the mempool and increases the number of
+    /// included user operations of their entities. Events of the user operations not in the
+    /// mempool are skipped.
+    ///
+    /// # Arguments
+    /// * `events` - The [UserOperationEventFilter](UserOperationEventFilter) events of the entry
+    ///   point
+    ///
+    /// # Returns
+    /// `Vec<UserOperationHash>` - The hashes of the removed [UserOperations](UserOperation)
+    pub fn remove_included_user_operations(
+        &mut self,
+        events: &[UserOperationEventFilter],
+    ) -> Vec<UserOperationHash> {
+        let mut removed = vec![];
+        for event in events {
+            let uo_hash = UserOperationHash::from(event.user_op_hash);
+            if let Ok(Some(uo)) = self.mempool.get(&uo_hash) {
+                let entities = self.entities(&uo);
+                self.remove_user_operation(&uo_hash);
 
-            if let Some(addr) = get_address(&uo.init_code) {
-                self.reputation.increment_included(&addr).ok();
+                // update reputations
+                for entity in entities {
+                    self.reputation.increment_included(&entity).ok();
+                }
+                removed.push(uo_hash);
             }
         }
 
-        None
+        removed
     }
 
     /// Gets the [StakeInfoResponse](StakeInfoResponse) for entity
@@ -740,3 +1445,532 @@ impl<M: Middleware + 'static, V: UserOperationValidator> UoPool<M, V> {
         })
     }
 }
+
+/// Assembles the receipt of a user operation from the receipt of the transaction (bundle) that
+/// included it. Only the logs emitted during the execution of the user operation are kept, i.e.
+/// the logs between the previous `UserOperationEvent` of the bundle and its own
+/// `UserOperationEvent`.
+///
+/// # Arguments
+/// * `entry_point` - The address of the entry point.
+/// * `uo_hash` - The [UserOperationHash](UserOperationHash) of the user operation.
+/// * `event` - The `UserOperationEvent` emitted for the user operation.
+/// * `tx_receipt` - The [TransactionReceipt](TransactionReceipt) of the bundle.
+///
+/// # Returns
+/// `UserOperationReceipt` - The receipt of the user operation.
+fn assemble_user_operation_receipt(
+    entry_point: &Address,
+    uo_hash: &UserOperationHash,
+    event: UserOperationEventFilter,
+    tx_receipt: TransactionReceipt,
+) -> UserOperationReceipt {
+    let is_entry_point_event = |log: &Log, signature: H256| {
+        log.address == *entry_point && log.topics.first() == Some(&signature)
+    };
+
+    let mut start = 0;
+    let mut end = tx_receipt.logs.len();
+    for (index, log) in tx_receipt.logs.iter().enumerate() {
+        if is_entry_point_event(log, UserOperationEventFilter::signature()) {
+            if log.topics.get(1) == Some(&uo_hash.0) {
+                end = index;
+            } else if end == tx_receipt.logs.len() {
+                start = index + 1;
+            }
+        }
+    }
+    let logs = tx_receipt.logs.get(start..end).map(|logs| logs.to_vec()).unwrap_or_default();
+
+    let reason = logs
+        .iter()
+        .filter(|log| {
+            is_entry_point_event(log, UserOperationRevertReasonFilter::signature()) &&
+                log.topics.get(1) == Some(&uo_hash.0)
+        })
+        .find_map(|log| {
+            UserOperationRevertReasonFilter::decode_log(&RawLog::from(log.clone())).ok()
+        })
+        .map(|revert| {
+            if revert.revert_reason.len() >= 4 {
+                if let Some(reason) = decode_revert_string(revert.revert_reason.clone()) {
+                    return reason;
+                }
+            }
+            revert.revert_reason.to_string()
+        })
+        .unwrap_or_default();
+
+    UserOperationReceipt {
+        user_operation_hash: *uo_hash,
+        sender: event.sender,
+        nonce: event.nonce,
+        paymaster: Some(event.paymaster).filter(|paymaster| !paymaster.is_zero()),
+        actual_gas_cost: event.actual_gas_cost,
+        actual_gas_used: event.actual_gas_used,
+        success: event.success,
+        reason,
+        logs,
+        tx_receipt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::{
+            simulation::signature::Signature, validator::StandardUserOperationValidator,
+            VerifiedBlock,
+        },
+    };
+    use ethers::{
+        abi::Token,
+        providers::{MockProvider, Provider},
+        types::{Block, Bytes},
+    };
+    use parking_lot::RwLock;
+    use silius_contracts::utils::parse_user_operation_events;
+    use std::sync::Arc;
+
+    type MockUoPool = UoPool<
+        Provider<MockProvider>,
+        StandardUserOperationValidator<Provider<MockProvider>, (), (Signature,), ()>,
+    >;
+
+    fn uopool() -> MockUoPool {
+        let (provider, _) = Provider::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let validator = StandardUserOperationValidator::new(
+            entry_point.clone(),
+            Chain::dev(),
+            (),
+            (Signature,),
+            (),
+            None,
+        );
+        uopool_with_validator(entry_point, validator)
+    }
+
+    fn uopool_with_validator<V: UserOperationValidator>(
+        entry_point: EntryPoint<Provider<MockProvider>>,
+        validator: V,
+    ) -> UoPool<Provider<MockProvider>, V> {
+        UoPool::new(
+            UoPoolMode::Standard,
+            entry_point,
+            validator,
+            memory_mempool(),
+            memory_reputation(),
+            U256::from(5_000_000),
+            Chain::dev(),
+            None,
+        )
+    }
+
+    fn user_operation(sender: Address, factory: Address, paymaster: Address) -> UserOperation {
+        let uo = UserOperationSigned::default()
+            .sender(sender)
+            .init_code(Bytes::from(factory.as_bytes().to_vec()))
+            .paymaster_and_data(Bytes::from(paymaster.as_bytes().to_vec()));
+        UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo)
+    }
+
+    #[test]
+    fn remove_included_user_operations() {
+        let mut uopool = uopool();
+        let (sender, factory, paymaster) =
+            (Address::random(), Address::random(), Address::random());
+        let uo = user_operation(sender, factory, paymaster);
+        let uo_pending = user_operation(Address::random(), factory, paymaster);
+        uopool.mempool.add(uo.clone()).unwrap();
+        uopool.mempool.add(uo_pending.clone()).unwrap();
+
+        let ep = uopool.entry_point.address();
+        let log = Log {
+            address: ep,
+            topics: vec![
+                UserOperationEventFilter::signature(),
+                uo.hash.into(),
+                sender.into(),
+                paymaster.into(),
+            ],
+            data: ethers::abi::encode(&[
+                Token::Uint(U256::zero()),
+                Token::Bool(true),
+                Token::Uint(U256::from(100_000)),
+                Token::Uint(U256::from(100_000)),
+            ])
+            .into(),
+            ..Default::default()
+        };
+        // the same event emitted by another contract is ignored
+        let log_other = Log { address: Address::random(), ..log.clone() };
+
+        let events = parse_user_operation_events(&ep, &[log, log_other]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(uopool.remove_included_user_operations(&events), vec![uo.hash]);
+
+        assert!(uopool.mempool.get(&uo.hash).unwrap().is_none());
+        assert!(uopool.mempool.get(&uo_pending.hash).unwrap().is_some());
+        for entity in [sender, factory, paymaster] {
+            assert_eq!(uopool.reputation.get(&entity).unwrap().uo_included, 1);
+        }
+
+        // the user operation is already removed
+        assert!(uopool.remove_included_user_operations(&events).is_empty());
+        assert_eq!(uopool.reputation.get(&sender).unwrap().uo_included, 1);
+    }
+
+    #[test]
+    fn remove_user_operations_by_hash_counts() {
+        let mut uopool = uopool();
+        let uo = user_operation(Address::random(), Address::random(), Address::random());
+        let uo_other = user_operation(Address::random(), Address::random(), Address::random());
+        uopool.mempool.add(uo.clone()).unwrap();
+        uopool.mempool.add(uo_other.clone()).unwrap();
+
+        // a hash not in the mempool and a duplicate are counted as not found
+        assert_eq!(
+            uopool
+                .remove_user_operations_by_hash(&[uo.hash, H256::random().into(), uo.hash])
+                .unwrap(),
+            (1, 2)
+        );
+        assert!(uopool.mempool.get(&uo.hash).unwrap().is_none());
+        assert!(uopool.mempool.get(&uo_other.hash).unwrap().is_some());
+
+        assert_eq!(uopool.remove_user_operations_by_hash(&[]).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn user_operation_receipt() {
+        let ep = Address::random();
+        let (sender, paymaster) = (Address::random(), Address::random());
+        let (uo_hash, uo_hash_prev) = (H256::random(), H256::random());
+
+        let event_log = |uo_hash: H256, success: bool| Log {
+            address: ep,
+            topics: vec![
+                UserOperationEventFilter::signature(),
+                uo_hash,
+                sender.into(),
+                paymaster.into(),
+            ],
+            data: ethers::abi::encode(&[
+                Token::Uint(U256::from(1)),
+                Token::Bool(success),
+                Token::Uint(U256::from(200_000)),
+                Token::Uint(U256::from(100_000)),
+            ])
+            .into(),
+            ..Default::default()
+        };
+        let mut revert_reason = vec![0x08, 0xc3, 0x79, 0xa0];
+        revert_reason.extend(ethers::abi::encode(&[Token::String("transfer failed".into())]));
+        let revert_log = Log {
+            address: ep,
+            topics: vec![UserOperationRevertReasonFilter::signature(), uo_hash, sender.into()],
+            data: ethers::abi::encode(&[Token::Uint(U256::from(1)), Token::Bytes(revert_reason)])
+                .into(),
+            ..Default::default()
+        };
+        let account_log = Log { address: sender, ..Default::default() };
+
+        let tx_receipt = TransactionReceipt {
+            logs: vec![
+                account_log.clone(),
+                event_log(uo_hash_prev, true),
+                account_log.clone(),
+                revert_log.clone(),
+                event_log(uo_hash, false),
+                account_log.clone(),
+            ],
+            ..Default::default()
+        };
+        let event = parse_user_operation_events(&ep, &tx_receipt.logs).remove(1);
+
+        let receipt =
+            assemble_user_operation_receipt(&ep, &uo_hash.into(), event, tx_receipt.clone());
+        assert_eq!(receipt.user_operation_hash, uo_hash.into());
+        assert_eq!(receipt.sender, sender);
+        assert_eq!(receipt.nonce, U256::from(1));
+        assert_eq!(receipt.paymaster, Some(paymaster));
+        assert_eq!(receipt.actual_gas_cost, U256::from(200_000));
+        assert_eq!(receipt.actual_gas_used, U256::from(100_000));
+        assert!(!receipt.success);
+        assert_eq!(receipt.reason, "transfer failed");
+        // only the logs emitted after the previous user operation
+        assert_eq!(receipt.logs, vec![account_log, revert_log]);
+        assert_eq!(receipt.tx_receipt, tx_receipt);
+    }
+
+    #[tokio::test]
+    async fn validation_outcome_recorded() {
+        let mut uopool = uopool();
+        let (sender, factory, paymaster, aggregator) =
+            (Address::random(), Address::random(), Address::random(), Address::random());
+        let uo = user_operation(sender, factory, paymaster);
+        assert!(uopool.get_validation_outcome(&uo.hash).is_none());
+
+        let res = UserOperationValidationOutcome {
+            pre_fund: U256::from(1_000_000),
+            verification_gas_limit: U256::from(150_000),
+            staked: true,
+            aggregator: Some(aggregator),
+            ..Default::default()
+        };
+        uopool.add_user_operation(uo.clone(), Ok(res.clone())).await.unwrap();
+
+        let out = uopool.get_validation_outcome(&uo.hash).unwrap();
+        assert_eq!(out.pre_fund, res.pre_fund);
+        assert_eq!(out.verification_gas_limit, res.verification_gas_limit);
+        assert_eq!(out.staked, res.staked);
+        assert_eq!(out.aggregator, res.aggregator);
+
+        // the outcome is evicted with the user operation
+        uopool.remove_user_operation(&uo.hash);
+        assert!(uopool.get_validation_outcome(&uo.hash).is_none());
+        assert_eq!(uopool.mempool.get_verification_gas_limit(&uo.hash), None);
+    }
+
+    /// Validator which rejects the user operations of the given senders (e.g. after their code
+    /// changed on chain)
+    #[derive(Clone, Default)]
+    struct SenderRejectingValidator {
+        rejected: Arc<RwLock<HashSet<Address>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl UserOperationValidator for SenderRejectingValidator {
+        async fn get_verified_block(
+            &self,
+        ) -> Result<VerifiedBlock, InvalidMempoolUserOperationError> {
+            Ok(VerifiedBlock { hash: U256::one(), number: 1 })
+        }
+
+        async fn validate_user_operation_on_block(
+            &self,
+            uo: &UserOperation,
+            _entry_point: &Address,
+            mempool: &Mempool,
+            _reputation: &Reputation,
+            _val_config: Option<ValidationConfig>,
+            _mode: EnumSet<UserOperationValidatorMode>,
+            verified_block: VerifiedBlock,
+        ) -> Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError> {
+            // the pending user operations are not considered during the re-validation
+            assert!(mempool.get_all().unwrap().is_empty());
+
+            if self.rejected.read().contains(&uo.sender) {
+                return Err(SanityError::Sender { inner: "sender rejected".into() }.into());
+            }
+            Ok(UserOperationValidationOutcome {
+                verified_block: verified_block.hash,
+                verified_block_number: verified_block.number,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn revalidate_user_operations() {
+        let (provider, _) = Provider::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let validator = SenderRejectingValidator::default();
+        let mut uopool = uopool_with_validator(entry_point, validator.clone());
+
+        let (factory, paymaster) = (Address::random(), Address::random());
+        let uos = (0..3)
+            .map(|_| user_operation(Address::random(), factory, paymaster))
+            .collect::<Vec<_>>();
+        for uo in uos.iter() {
+            uopool.add_user_operation(uo.clone(), Ok(Default::default())).await.unwrap();
+        }
+
+        // all user operations are still valid
+        let mut cursor = 0;
+        assert!(uopool.revalidate_user_operations(10, &mut cursor).await.unwrap().is_empty());
+        assert_eq!(uopool.get_all().unwrap().len(), 3);
+
+        validator.rejected.write().insert(uos[1].sender);
+        assert_eq!(
+            uopool.revalidate_user_operations(10, &mut cursor).await.unwrap(),
+            vec![uos[1].hash]
+        );
+        assert!(uopool.mempool.get(&uos[1].hash).unwrap().is_none());
+        assert_eq!(uopool.get_all().unwrap().len(), 2);
+        // the removed user operation stays seen but not included
+        assert_eq!(uopool.reputation.get(&uos[1].sender).unwrap().uo_seen, 1);
+        assert_eq!(uopool.reputation.get(&uos[1].sender).unwrap().uo_included, 0);
+
+        // the batches rotate over the pending user operations
+        validator.rejected.write().extend(uos.iter().map(|uo| uo.sender));
+        let mut removed = uopool.revalidate_user_operations(1, &mut cursor).await.unwrap();
+        assert_eq!(removed.len(), 1);
+        removed.extend(uopool.revalidate_user_operations(1, &mut cursor).await.unwrap());
+        assert_eq!(removed.len(), 2);
+        assert!(uopool.get_all().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reputation_updates() {
+        let mut uopool = uopool();
+        let (sender, factory, paymaster, aggregator) =
+            (Address::random(), Address::random(), Address::random(), Address::random());
+
+        let uo = user_operation(sender, factory, paymaster);
+        uopool
+            .add_user_operation(
+                uo.clone(),
+                Ok(UserOperationValidationOutcome {
+                    aggregator: Some(aggregator),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        for entity in [sender, factory, paymaster, aggregator] {
+            assert_eq!(uopool.reputation.get(&entity).unwrap().uo_seen, 1);
+        }
+
+        // the replacement counts only the entities the replaced user operation didn't have
+        let paymaster_new = Address::random();
+        let uo_new = user_operation(sender, factory, paymaster_new);
+        uopool
+            .add_user_operation(
+                uo_new.clone(),
+                Ok(UserOperationValidationOutcome {
+                    prev_hash: Some(uo.hash),
+                    aggregator: Some(aggregator),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        for entity in [sender, factory, paymaster, paymaster_new, aggregator] {
+            assert_eq!(uopool.reputation.get(&entity).unwrap().uo_seen, 1);
+        }
+
+        uopool.remove_user_operations(vec![uo_new]);
+        for entity in [sender, factory, paymaster_new, aggregator] {
+            assert_eq!(uopool.reputation.get(&entity).unwrap().uo_included, 1);
+        }
+        assert_eq!(uopool.reputation.get(&paymaster).unwrap().uo_included, 0);
+    }
+
+    #[tokio::test]
+    async fn evict_after_acceptance() {
+        let mut uopool = uopool();
+        uopool.mempool.set_max_size(Some(1));
+        let paymaster = Address::random();
+
+        let mut uo = user_operation(Address::random(), Address::random(), paymaster);
+        uo.user_operation.max_priority_fee_per_gas = 1.into();
+        let res = UserOperationValidationOutcome { pre_fund: 100.into(), ..Default::default() };
+        uopool.add_user_operation(uo.clone(), Ok(res.clone())).await.unwrap();
+        assert_eq!(uopool.mempool.get_pre_fund_by_paymaster(&paymaster), 100.into());
+
+        // a user operation which doesn't pay more is rejected and nothing is evicted
+        let uo_cheap = user_operation(Address::random(), Address::random(), paymaster);
+        assert!(uopool.add_user_operation(uo_cheap, Ok(res.clone())).await.is_err());
+        assert!(uopool.mempool.contains(&uo.hash));
+
+        let mut uo_new = user_operation(Address::random(), Address::random(), paymaster);
+        uo_new.user_operation.max_priority_fee_per_gas = 2.into();
+        uopool.add_user_operation(uo_new.clone(), Ok(res)).await.unwrap();
+        assert!(!uopool.mempool.contains(&uo.hash));
+        assert!(uopool.mempool.contains(&uo_new.hash));
+
+        // the evicted user operation releases its prefund and isn't counted as seen anymore
+        assert_eq!(uopool.mempool.get_pre_fund_by_paymaster(&paymaster), 100.into());
+        assert_eq!(uopool.reputation.get(&uo.sender).unwrap().uo_seen, 0);
+        assert_eq!(uopool.reputation.get(&paymaster).unwrap().uo_seen, 1);
+        assert_eq!(uopool.reputation.get(&uo_new.sender).unwrap().uo_seen, 1);
+    }
+
+    #[tokio::test]
+    async fn replace_in_full_mempool() {
+        let mut uopool = uopool();
+        uopool.mempool.set_max_size(Some(1));
+        let sender = Address::random();
+
+        let uo = user_operation(sender, Address::random(), Address::random());
+        let res = UserOperationValidationOutcome { staked: true, ..Default::default() };
+        uopool.add_user_operation(uo.clone(), Ok(res)).await.unwrap();
+
+        // the replacement takes the slot of the replaced user operation
+        let uo_new = user_operation(sender, Address::random(), Address::random());
+        let res = UserOperationValidationOutcome {
+            prev_hash: Some(uo.hash),
+            staked: true,
+            ..Default::default()
+        };
+        uopool.add_user_operation(uo_new.clone(), Ok(res)).await.unwrap();
+        assert!(!uopool.mempool.contains(&uo.hash));
+        assert!(uopool.mempool.contains(&uo_new.hash));
+
+        // the unstaked replacement doesn't fit (the staked user operations are never evicted), so
+        // the replaced user operation is kept
+        let uo_unstaked = user_operation(sender, Address::random(), Address::random());
+        let res =
+            UserOperationValidationOutcome { prev_hash: Some(uo_new.hash), ..Default::default() };
+        assert!(uopool.add_user_operation(uo_unstaked.clone(), Ok(res)).await.is_err());
+        assert!(uopool.mempool.contains(&uo_new.hash));
+        assert!(!uopool.mempool.contains(&uo_unstaked.hash));
+    }
+
+    #[tokio::test]
+    async fn select_user_operations() {
+        let (provider, mock) = Provider::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let validator = StandardUserOperationValidator::new(
+            entry_point.clone(),
+            Chain::dev(),
+            (),
+            (Signature,),
+            (),
+            None,
+        );
+        let mut uopool = uopool_with_validator(entry_point, validator);
+
+        let user_operation = |sender: Address, nonce: u64, max_fee: u64, priority_fee: u64| {
+            let uo = UserOperationSigned::default()
+                .sender(sender)
+                .nonce(nonce.into())
+                .call_gas_limit(100_000.into())
+                .verification_gas_limit(100_000.into())
+                .max_fee_per_gas(max_fee.into())
+                .max_priority_fee_per_gas(priority_fee.into());
+            UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo)
+        };
+        let sender = Address::random();
+        let uo_first = user_operation(sender, 0, 200, 10);
+        let uo_second = user_operation(sender, 1, 200, 20);
+        // the highest priority fee, but capped by the max fee at the base fee of 95
+        let uo_capped = user_operation(Address::random(), 0, 100, 50);
+        for uo in [uo_first.clone(), uo_second.clone(), uo_capped.clone()] {
+            uopool.mempool.add(uo).unwrap();
+        }
+
+        mock.push(Block::<H256> {
+            number: Some(1.into()),
+            base_fee_per_gas: Some(95.into()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        // one user operation per sender
+        assert_eq!(
+            uopool
+                .select_user_operations()
+                .await
+                .unwrap()
+                .iter()
+                .map(|uo| uo.hash)
+                .collect::<Vec<_>>(),
+            vec![uo_second.hash, uo_capped.hash]
+        );
+    }
+}
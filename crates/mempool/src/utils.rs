@@ -1,6 +1,21 @@
-use ethers::types::{Address, H256, U256};
-use silius_primitives::{simulation::CodeHash, UserOperationSigned};
-use std::{collections::HashMap, ops::Deref};
+use alloy_chains::{Chain, NamedChain};
+use ethers::{
+    contract::ContractError,
+    providers::{Middleware, MiddlewareError},
+    types::{Address, Filter, Log, H256, U256},
+};
+use silius_contracts::{GasPriceOracleAPI, NodeInterfaceAPI};
+use silius_primitives::{
+    constants::{l1_data_fee::GAS_PRICE_ORACLE, l1_gas::NODE_INTERFACE},
+    get_address,
+    simulation::CodeHash,
+    UserOperationSigned,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::Deref,
+    sync::Arc,
+};
 
 pub fn equal_code_hashes(hashes: &[CodeHash], hashes_prev: &Vec<CodeHash>) -> bool {
     if hashes_prev.len() != hashes.len() {
@@ -23,6 +38,11 @@ pub fn equal_code_hashes(hashes: &[CodeHash], hashes_prev: &Vec<CodeHash>) -> bo
     true
 }
 
+/// Gas reserved for the paymaster `postOp` call of sponsored user operations. In entry point v0.6
+/// `postOp` is limited by the `verification_gas_limit`, so the estimation adds it on top of the
+/// gas needed for the validation.
+pub const POST_OP_GAS: u64 = 40000;
+
 /// Struct to calculate the pre-verification gas of a user operation
 // https://github.com/eth-infinitism/bundler/blob/main/packages/sdk/src/calcPreVerificationGas.ts#L44-L51
 pub struct Overhead {
@@ -33,6 +53,7 @@ pub struct Overhead {
     pub non_zero_byte: U256,
     pub bundle_size: U256,
     pub sig_size: U256,
+    pub post_op: U256,
 }
 
 impl Default for Overhead {
@@ -45,6 +66,7 @@ impl Default for Overhead {
             non_zero_byte: U256::from(16),
             bundle_size: U256::from(1),
             sig_size: U256::from(65),
+            post_op: U256::from(POST_OP_GAS),
         }
     }
 }
@@ -84,6 +106,137 @@ impl Overhead {
             .saturating_add(self.per_user_op)
             .saturating_add(word_cost)
     }
+
+    /// Calculates the gas that has to be reserved for the paymaster `postOp` call of a
+    /// [UserOperation](UserOperationSigned)
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperationSigned) to calculate the `postOp` gas for
+    ///
+    /// # Returns
+    /// The `postOp` gas if the [UserOperation](UserOperationSigned) is sponsored by a paymaster,
+    /// otherwise zero
+    pub fn calculate_post_op_gas(&self, uo: &UserOperationSigned) -> U256 {
+        if get_address(&uo.paymaster_and_data).is_some() {
+            self.post_op
+        } else {
+            U256::zero()
+        }
+    }
+}
+
+/// The OP-stack `GasPriceOracle` used to get the L1 data fee of a user operation. The fee formula
+/// (and its parameters) changes with the upgrades of the chain (e.g. Ecotone, Fjord), so the fee is
+/// computed by the oracle itself.
+// https://docs.optimism.io/stack/transactions/fees#l1-data-fee
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct L1DataFee {
+    /// Address of the `GasPriceOracle` contract
+    pub oracle: Address,
+}
+
+impl Default for L1DataFee {
+    fn default() -> Self {
+        Self { oracle: GAS_PRICE_ORACLE.parse().expect("valid gas price oracle address") }
+    }
+}
+
+impl L1DataFee {
+    /// Returns the L1 data fee parameters if the chain is an OP-stack chain
+    ///
+    /// # Arguments
+    /// `chain` - The [Chain](Chain) the user operation is submitted to
+    ///
+    /// # Returns
+    /// The default [L1DataFee](L1DataFee) parameters for OP-stack chains, otherwise None
+    pub fn from_chain(chain: &Chain) -> Option<Self> {
+        match chain.named() {
+            Some(
+                NamedChain::Optimism |
+                NamedChain::OptimismSepolia |
+                NamedChain::Base |
+                NamedChain::BaseSepolia,
+            ) => Some(Self::default()),
+            _ => None,
+        }
+    }
+
+    /// Gets the L1 data fee of a [UserOperation](UserOperationSigned) by calling `getL1Fee` of
+    /// the `GasPriceOracle` with the packed user operation (the data the user operation adds to
+    /// the bundle transaction)
+    ///
+    /// # Arguments
+    /// `eth_client` - The client used to call the `GasPriceOracle`
+    /// `uo` - The [UserOperation](UserOperationSigned) to get the L1 data fee for
+    ///
+    /// # Returns
+    /// The L1 data fee (in wei) of the [UserOperation](UserOperationSigned)
+    pub async fn get_l1_data_fee<M: Middleware + 'static>(
+        &self,
+        eth_client: Arc<M>,
+        uo: &UserOperationSigned,
+    ) -> Result<U256, ContractError<M>> {
+        GasPriceOracleAPI::new(self.oracle, eth_client).get_l1_fee(uo.pack()).call().await
+    }
+}
+
+/// The Arbitrum `NodeInterface` used to get the L1 gas of a user operation. On Arbitrum the L1
+/// cost of the calldata is charged in L2 gas (the poster fee divided by the L2 base fee), so it
+/// has to be covered by the pre-verification gas. The amount depends on the current L1 and L2
+/// prices, so it is estimated by the node.
+// https://docs.arbitrum.io/build-decentralized-apps/nodeinterface/reference
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct L1Gas {
+    /// Address of the `NodeInterface` contract
+    pub node_interface: Address,
+}
+
+impl Default for L1Gas {
+    fn default() -> Self {
+        Self { node_interface: NODE_INTERFACE.parse().expect("valid node interface address") }
+    }
+}
+
+impl L1Gas {
+    /// Returns the L1 gas parameters if the chain is an Arbitrum chain
+    ///
+    /// # Arguments
+    /// `chain` - The [Chain](Chain) the user operation is submitted to
+    ///
+    /// # Returns
+    /// The default [L1Gas](L1Gas) parameters for Arbitrum chains, otherwise None
+    pub fn from_chain(chain: &Chain) -> Option<Self> {
+        match chain.named() {
+            Some(NamedChain::Arbitrum | NamedChain::ArbitrumNova | NamedChain::ArbitrumSepolia) => {
+                Some(Self::default())
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the L1 gas (in L2 gas units) of a [UserOperation](UserOperationSigned) by calling
+    /// `gasEstimateL1Component` of the `NodeInterface` with the packed user operation sent to the
+    /// entry point
+    ///
+    /// # Arguments
+    /// `eth_client` - The client used to call the `NodeInterface`
+    /// `entry_point` - The address of the entry point the bundle is sent to
+    /// `uo` - The [UserOperation](UserOperationSigned) to get the L1 gas for
+    ///
+    /// # Returns
+    /// The L1 gas of the [UserOperation](UserOperationSigned)
+    pub async fn get_l1_gas<M: Middleware>(
+        &self,
+        eth_client: Arc<M>,
+        entry_point: Address,
+        uo: &UserOperationSigned,
+    ) -> Result<U256, ContractError<M>> {
+        let (l1_gas, _, _) = NodeInterfaceAPI::new(self.node_interface, eth_client)
+            .gas_estimate_l1_component(entry_point, false, uo.pack())
+            .call()
+            .await?;
+        Ok(U256::from(l1_gas))
+    }
 }
 
 /// Helper function to calculate the valid gas of a [UserOperation](UserOperation)
@@ -146,11 +299,87 @@ pub fn div_ceil(numerator: U256, denominator: U256) -> U256 {
     numerator.checked_div(denominator).unwrap_or_default().saturating_add(rounding_const)
 }
 
+/// Splits the block range `from..=to` into consecutive ranges of at most `max_range` blocks.
+///
+/// # Arguments
+/// `from` - The first block of the range
+/// `to` - The last block of the range
+/// `max_range` - The maximum number of blocks in a range
+///
+/// # Returns
+/// The inclusive ranges (empty if `from` is greater than `to`)
+pub fn split_block_range(from: u64, to: u64, max_range: u64) -> Vec<(u64, u64)> {
+    let max_range = max_range.max(1);
+    let mut ranges = vec![];
+    let mut start = from;
+    while start <= to {
+        let end = start.saturating_add(max_range - 1).min(to);
+        ranges.push((start, end));
+        if end == u64::MAX {
+            break;
+        }
+        start = end + 1;
+    }
+    ranges
+}
+
+/// JSON-RPC error codes returned for `eth_getLogs` when the block range (or the size of the
+/// response) exceeds the limit of the provider: `-32005` (limit exceeded, EIP-1474) and `-32602`
+/// (invalid params, used by some providers for a too large block range).
+const LOG_RANGE_ERROR_CODES: [i64; 2] = [-32005, -32602];
+
+/// Whether the error returned by the provider for `eth_getLogs` reports that the block range (or
+/// the size of the response) exceeds the limit of the provider.
+fn is_log_range_error<E: MiddlewareError>(err: &E) -> bool {
+    err.as_error_response().is_some_and(|err| LOG_RANGE_ERROR_CODES.contains(&err.code))
+}
+
+/// Fetches the logs matching the filter in the block range `from..=to`, in chunks of at most
+/// `max_log_range` blocks. A chunk rejected by the provider because of its range is halved and
+/// retried.
+///
+/// # Arguments
+/// `eth_client` - The provider
+/// `filter` - The [Filter](Filter) of the logs (its block range is ignored)
+/// `from` - The first block of the range
+/// `to` - The last block of the range
+/// `max_log_range` - The maximum number of blocks queried in a single `eth_getLogs` call
+///
+/// # Returns
+/// The logs of all chunks in the order of the blocks
+pub async fn get_logs_in_range<M: Middleware>(
+    eth_client: &M,
+    filter: &Filter,
+    from: u64,
+    to: u64,
+    max_log_range: u64,
+) -> Result<Vec<Log>, M::Error> {
+    let mut ranges = VecDeque::from(split_block_range(from, to, max_log_range));
+    let mut logs = vec![];
+
+    while let Some((start, end)) = ranges.pop_front() {
+        match eth_client.get_logs(&filter.clone().from_block(start).to_block(end)).await {
+            Ok(res) => logs.extend(res),
+            Err(err) if end > start && is_log_range_error(&err) => {
+                let mid = start + (end - start) / 2;
+                ranges.push_front((mid + 1, end));
+                ranges.push_front((start, mid));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(logs)
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
     use crate::{mempool::Mempool, Reputation};
-    use ethers::types::{Address, Bytes, H256, U256};
+    use ethers::{
+        providers::{JsonRpcError, MockResponse, Provider},
+        types::{Address, Bytes, H256, U256},
+    };
     use silius_primitives::{
         reputation::{ReputationEntry, Status},
         UserOperation, UserOperationHash, UserOperationSigned,
@@ -206,6 +435,7 @@ pub mod tests {
             non_zero_byte: U256::from(16),
             bundle_size: U256::from(1),
             sig_size: U256::from(65),
+            post_op: U256::from(POST_OP_GAS),
         };
         let uo = UserOperationSigned {
             sender: "0xAB7e2cbFcFb6A5F33A75aD745C3E5fB48d689B54".parse().unwrap(),
@@ -224,6 +454,62 @@ pub mod tests {
         assert_eq!(gas_oh.calculate_pre_verification_gas(&uo), 1549132.into());
     }
 
+    #[test]
+    fn post_op_gas_calculation() {
+        let gas_oh = Overhead::default();
+        let uo = UserOperationSigned::default().sender(Address::random());
+        assert_eq!(gas_oh.calculate_post_op_gas(&uo), U256::zero());
+
+        let uo = uo.paymaster_and_data(Bytes::from(Address::random().as_bytes().to_vec()));
+        assert_eq!(gas_oh.calculate_post_op_gas(&uo), POST_OP_GAS.into());
+    }
+
+    #[tokio::test]
+    async fn l1_data_fee_from_oracle() {
+        assert_eq!(L1DataFee::from_chain(&Chain::from(NamedChain::Mainnet)), None);
+        assert_eq!(L1DataFee::from_chain(&Chain::from(NamedChain::Arbitrum)), None);
+
+        let l1_data_fee = L1DataFee::from_chain(&Chain::from(NamedChain::Optimism)).unwrap();
+        let (provider, mock) = Provider::mocked();
+        mock.push(Bytes::from(ethers::abi::encode(&[ethers::abi::Token::Uint(U256::from(
+            123_456,
+        ))])))
+        .unwrap();
+
+        // the fee is the one returned by the oracle
+        assert_eq!(
+            l1_data_fee
+                .get_l1_data_fee(Arc::new(provider), &UserOperationSigned::default())
+                .await
+                .unwrap(),
+            U256::from(123_456)
+        );
+    }
+
+    #[tokio::test]
+    async fn l1_gas_from_node_interface() {
+        assert_eq!(L1Gas::from_chain(&Chain::from(NamedChain::Mainnet)), None);
+        assert_eq!(L1Gas::from_chain(&Chain::from(NamedChain::Optimism)), None);
+
+        let l1_gas = L1Gas::from_chain(&Chain::from(NamedChain::Arbitrum)).unwrap();
+        let (provider, mock) = Provider::mocked();
+        mock.push(Bytes::from(ethers::abi::encode(&[
+            ethers::abi::Token::Uint(U256::from(25_000)),
+            ethers::abi::Token::Uint(U256::from(100_000_000)),
+            ethers::abi::Token::Uint(U256::from(30_000_000_000u64)),
+        ])))
+        .unwrap();
+
+        // the L1 gas is the one estimated by the node
+        assert_eq!(
+            l1_gas
+                .get_l1_gas(Arc::new(provider), Address::random(), &UserOperationSigned::default())
+                .await
+                .unwrap(),
+            U256::from(25_000)
+        );
+    }
+
     /// This test occurred overflow when previous `calculate_pre_verification_gas` is used.
     /// previous `calculate_pre_verification_gas` is https://github.com/silius-rs/silius/blob/bd79ea0e610adff8d77ba128f53befa8401a4d77/crates/uopool/src/utils.rs#L63-L84
     #[test]
@@ -236,6 +522,7 @@ pub mod tests {
             non_zero_byte: U256::max_value(),
             bundle_size: U256::from(1), // To avoid division by zero
             sig_size: U256::max_value(),
+            post_op: U256::max_value(),
         };
 
         let uo = UserOperationSigned {
@@ -298,6 +585,55 @@ pub mod tests {
         assert_eq!(div_ceil(U256::from(10), U256::from(3)), 4.into());
     }
 
+    #[test]
+    fn block_range_split() {
+        let ranges = split_block_range(100, 10_099, 2_000);
+        assert_eq!(ranges.len(), 5);
+        assert_eq!(ranges.first(), Some(&(100, 2_099)));
+        assert_eq!(ranges.last(), Some(&(8_100, 10_099)));
+        assert!(ranges.windows(2).all(|r| r[0].1 + 1 == r[1].0));
+
+        // the remainder is queried in a shorter chunk
+        assert_eq!(
+            split_block_range(0, 4_500, 2_000),
+            vec![(0, 1_999), (2_000, 3_999), (4_000, 4_500)]
+        );
+        assert_eq!(split_block_range(7, 7, 2_000), vec![(7, 7)]);
+        assert!(split_block_range(8, 7, 2_000).is_empty());
+    }
+
+    #[tokio::test]
+    async fn logs_range_halved() {
+        let (provider, mock) = Provider::mocked();
+        let filter = Filter::new().address(Address::random());
+        let logs =
+            |block: u64| vec![Log { block_number: Some(block.into()), ..Default::default() }];
+        let error = |code| {
+            MockResponse::Error(JsonRpcError {
+                code,
+                message: "query returned more than 10000 results".into(),
+                data: None,
+            })
+        };
+
+        // the mocked responses are returned in reverse order
+        mock.push(logs(7)).unwrap();
+        mock.push(logs(2)).unwrap();
+        mock.push_response(error(-32005));
+        let res = get_logs_in_range(&provider, &filter, 0, 9, 10).await.unwrap();
+        assert_eq!(res, [logs(2), logs(7)].concat());
+        for (from, to) in [(0, 9), (0, 4), (5, 9)] {
+            mock.assert_request("eth_getLogs", [filter.clone().from_block(from).to_block(to)])
+                .unwrap();
+        }
+
+        // other errors are returned without halving the range
+        mock.push_response(error(-32000));
+        assert!(get_logs_in_range(&provider, &filter, 0, 9, 10).await.is_err());
+        mock.assert_request("eth_getLogs", [filter.clone().from_block(0).to_block(9)]).unwrap();
+        assert!(mock.assert_request("eth_getLogs", ()).is_err());
+    }
+
     pub fn mempool_test_case(mut mempool: Mempool) {
         let ep = Address::random();
         let chain_id = 5_u64;
@@ -472,5 +808,15 @@ pub mod tests {
             assert_eq!(reputation.increment_seen(&addrs[3]).unwrap(), ());
         }
         assert_eq!(Status::from(reputation.get_status(&addrs[3]).unwrap()), Status::BANNED);
+
+        // banned entity recovers after enough decay cycles
+        for _ in 0..24 {
+            assert_eq!(reputation.update_hourly().unwrap(), ());
+        }
+        assert_eq!(Status::from(reputation.get_status(&addrs[3]).unwrap()), Status::THROTTLED);
+        for _ in 0..24 {
+            assert_eq!(reputation.update_hourly().unwrap(), ());
+        }
+        assert_eq!(Status::from(reputation.get_status(&addrs[3]).unwrap()), Status::OK);
     }
 }
@@ -0,0 +1,201 @@
+//! Cache of the deposit and stake info of the entities, which avoids querying the entry point on
+//! every validation.
+use ethers::{providers::Middleware, types::Address};
+use parking_lot::RwLock;
+use silius_contracts::{
+    entry_point::{DepositInfo, EntryPointError},
+    EntryPoint,
+};
+use silius_primitives::constants::validation::DEPOSIT_INFO_CACHE_MAX_ENTRIES;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Deposit info cached per (entry point, entity) pair, shared between clones. The expired entries
+/// are evicted on insert and the number of entries is bounded (the oldest-fetched entry is evicted
+/// when the cache is full).
+#[derive(Clone, Debug)]
+pub struct DepositInfoCache {
+    /// For how long the cached deposit info is valid
+    ttl: Duration,
+    /// Maximum number of cached entries
+    max_entries: usize,
+    /// The cached deposit info and the time it was fetched
+    entries: Arc<RwLock<HashMap<(Address, Address), (DepositInfo, Instant)>>>,
+}
+
+impl DepositInfoCache {
+    /// Creates a new [DepositInfoCache] with the given TTL, holding at most
+    /// [DEPOSIT_INFO_CACHE_MAX_ENTRIES] entries.
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, max_entries: DEPOSIT_INFO_CACHE_MAX_ENTRIES, entries: Default::default() }
+    }
+
+    /// Sets the maximum number of cached entries (the oldest-fetched entry is evicted beyond it).
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Gets the cached deposit info of the entity (None if missing or expired).
+    ///
+    /// # Arguments
+    /// `entry_point` - The address of the entry point.
+    /// `addr` - The address of the entity.
+    ///
+    /// # Returns
+    /// The cached [DepositInfo] if it is still valid.
+    pub fn get(&self, entry_point: &Address, addr: &Address) -> Option<DepositInfo> {
+        self.entries
+            .read()
+            .get(&(*entry_point, *addr))
+            .filter(|(_, fetched_at)| fetched_at.elapsed() < self.ttl)
+            .map(|(info, _)| info.clone())
+    }
+
+    /// Caches the deposit info of the entity. If the cache is full, the expired entries are
+    /// evicted first and then, if still full, the oldest-fetched one.
+    pub fn insert(&self, entry_point: &Address, addr: &Address, info: DepositInfo) {
+        let key = (*entry_point, *addr);
+        let mut entries = self.entries.write();
+
+        if !entries.contains_key(&key) && entries.len() >= self.max_entries {
+            entries.retain(|_, (_, fetched_at)| fetched_at.elapsed() < self.ttl);
+            while entries.len() >= self.max_entries.max(1) {
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, (_, fetched_at))| *fetched_at)
+                    .map(|(key, _)| *key)
+                {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+
+        entries.insert(key, (info, Instant::now()));
+    }
+
+    /// Gets the number of cached entries (including the expired ones not evicted yet).
+    pub fn len(&self) -> usize {
+        self.entries.read().len()
+    }
+
+    /// Checks whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.read().is_empty()
+    }
+
+    /// Invalidates the cached deposit info of the entity on all entry points (e.g. after a
+    /// deposit-changing transaction).
+    pub fn invalidate(&self, addr: &Address) {
+        self.entries.write().retain(|(_, entity), _| entity != addr);
+    }
+
+    /// Copies the current entries into a new cache that is not shared with this one.
+    pub fn snapshot(&self) -> Self {
+        Self {
+            ttl: self.ttl,
+            max_entries: self.max_entries,
+            entries: Arc::new(RwLock::new(self.entries.read().clone())),
+        }
+    }
+
+    /// Gets the deposit info of the entity from the cache or, if missing, from the entry point.
+    ///
+    /// # Arguments
+    /// `entry_point` - The [EntryPoint] object.
+    /// `addr` - The address of the entity.
+    ///
+    /// # Returns
+    /// The [DepositInfo] of the entity, otherwise an [EntryPointError].
+    pub async fn get_deposit_info<M: Middleware + 'static>(
+        &self,
+        entry_point: &EntryPoint<M>,
+        addr: &Address,
+    ) -> Result<DepositInfo, EntryPointError> {
+        if let Some(info) = self.get(&entry_point.address(), addr) {
+            return Ok(info);
+        }
+
+        let info = entry_point.get_deposit_info(addr).await?;
+        self.insert(&entry_point.address(), addr, info.clone());
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deposit_info_cache() {
+        let cache = DepositInfoCache::new(Duration::from_secs(60));
+        let (ep, addr) = (Address::random(), Address::random());
+        let info = DepositInfo { deposit: 1, staked: true, ..Default::default() };
+
+        assert_eq!(cache.get(&ep, &addr), None);
+        cache.insert(&ep, &addr, info.clone());
+        assert_eq!(cache.get(&ep, &addr), Some(info.clone()));
+        assert_eq!(cache.clone().get(&ep, &addr), Some(info));
+        assert_eq!(cache.get(&Address::random(), &addr), None);
+
+        cache.invalidate(&addr);
+        assert_eq!(cache.get(&ep, &addr), None);
+    }
+
+    #[test]
+    fn deposit_info_cache_snapshot() {
+        let cache = DepositInfoCache::new(Duration::from_secs(60));
+        let (ep, addr) = (Address::random(), Address::random());
+        cache.insert(&ep, &addr, DepositInfo::default());
+
+        let snapshot = cache.snapshot();
+        assert_eq!(snapshot.get(&ep, &addr), Some(DepositInfo::default()));
+
+        let other = Address::random();
+        snapshot.insert(&ep, &other, DepositInfo::default());
+        snapshot.invalidate(&addr);
+        assert_eq!(cache.get(&ep, &other), None);
+        assert_eq!(cache.get(&ep, &addr), Some(DepositInfo::default()));
+    }
+
+    #[test]
+    fn deposit_info_cache_expired() {
+        let cache = DepositInfoCache::new(Duration::ZERO);
+        let (ep, addr) = (Address::random(), Address::random());
+
+        cache.insert(&ep, &addr, DepositInfo::default());
+        assert_eq!(cache.get(&ep, &addr), None);
+    }
+
+    #[test]
+    fn deposit_info_cache_bounded() {
+        let cache = DepositInfoCache::new(Duration::ZERO).with_max_entries(2);
+        let ep = Address::random();
+
+        // the expired entries are evicted instead of accumulating
+        for _ in 0..10 {
+            cache.insert(&ep, &Address::random(), DepositInfo::default());
+        }
+        assert_eq!(cache.len(), 2);
+
+        // inserting beyond the capacity evicts the oldest-fetched entry
+        let cache = DepositInfoCache::new(Duration::from_secs(60)).with_max_entries(2);
+        let addrs = [Address::random(), Address::random(), Address::random()];
+        for addr in addrs.iter() {
+            cache.insert(&ep, addr, DepositInfo::default());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&ep, &addrs[0]), None);
+        assert!(cache.get(&ep, &addrs[1]).is_some());
+        assert!(cache.get(&ep, &addrs[2]).is_some());
+
+        // refreshing a cached entry doesn't evict anything
+        cache.insert(&ep, &addrs[1], DepositInfo { deposit: 1, ..Default::default() });
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&ep, &addrs[2]).is_some());
+    }
+}
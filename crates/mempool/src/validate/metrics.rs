@@ -0,0 +1,107 @@
+//! Metrics of the user operation validation, recorded only if the `metrics` feature is enabled.
+use ethers::types::Address;
+#[cfg(feature = "metrics")]
+use metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+#[cfg(feature = "metrics")]
+const VALIDATION_SANITY_FAILED: &str = "silius_validation_sanity_failed";
+#[cfg(feature = "metrics")]
+const VALIDATION_SIMULATION_FAILED: &str = "silius_validation_simulation_failed";
+#[cfg(feature = "metrics")]
+const VALIDATION_SIMULATION_TRACE_FAILED: &str = "silius_validation_simulation_trace_failed";
+#[cfg(feature = "metrics")]
+const VALIDATION_EXECUTION_FAILED: &str = "silius_validation_execution_failed";
+#[cfg(feature = "metrics")]
+const VALIDATION_DURATION: &str = "silius_validation_duration";
+
+/// The phase of the user operation validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationPhase {
+    Sanity,
+    Simulation,
+    SimulationTrace,
+    Execution,
+}
+
+/// Records the failure of the validation phase.
+///
+/// # Arguments
+/// `entry_point` - The address of the entry point the user operation was validated for.
+/// `phase` - The [ValidationPhase](ValidationPhase) that failed.
+#[allow(unused_variables)]
+pub(crate) fn record_failure(entry_point: Address, phase: ValidationPhase) {
+    #[cfg(feature = "metrics")]
+    {
+        let name = match phase {
+            ValidationPhase::Sanity => VALIDATION_SANITY_FAILED,
+            ValidationPhase::Simulation => VALIDATION_SIMULATION_FAILED,
+            ValidationPhase::SimulationTrace => VALIDATION_SIMULATION_TRACE_FAILED,
+            ValidationPhase::Execution => VALIDATION_EXECUTION_FAILED,
+        };
+        counter!(name, "entry_point" => format!("{entry_point:?}")).increment(1);
+    }
+}
+
+/// Creates a function that records the failure of the validation phase and passes the error
+/// through (to be used with [Result::map_err]).
+pub(crate) fn on_failure<E>(entry_point: Address, phase: ValidationPhase) -> impl FnOnce(E) -> E {
+    move |err| {
+        record_failure(entry_point, phase);
+        err
+    }
+}
+
+/// Records the total duration of the validation once dropped.
+pub(crate) struct ValidationTimer {
+    #[cfg(feature = "metrics")]
+    entry_point: Address,
+    #[cfg(feature = "metrics")]
+    start: Instant,
+}
+
+impl ValidationTimer {
+    #[allow(unused_variables)]
+    pub(crate) fn new(entry_point: Address) -> Self {
+        Self {
+            #[cfg(feature = "metrics")]
+            entry_point,
+            #[cfg(feature = "metrics")]
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for ValidationTimer {
+    fn drop(&mut self) {
+        #[cfg(feature = "metrics")]
+        histogram!(VALIDATION_DURATION, "entry_point" => format!("{:?}", self.entry_point))
+            .record(self.start.elapsed().as_secs_f64());
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub fn describe_validation_metrics() {
+    describe_counter!(
+        VALIDATION_SANITY_FAILED,
+        "The number of user operations that failed the sanity checks"
+    );
+    describe_counter!(
+        VALIDATION_SIMULATION_FAILED,
+        "The number of user operations that failed the simulation checks"
+    );
+    describe_counter!(
+        VALIDATION_SIMULATION_TRACE_FAILED,
+        "The number of user operations that failed the simulation trace checks"
+    );
+    describe_counter!(
+        VALIDATION_EXECUTION_FAILED,
+        "The number of user operations whose execution simulation failed"
+    );
+    describe_histogram!(
+        VALIDATION_DURATION,
+        Unit::Seconds,
+        "The duration of the user operation validation"
+    );
+}
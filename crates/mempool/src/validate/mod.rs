@@ -3,46 +3,106 @@ use crate::{
     mempool::Mempool, InvalidMempoolUserOperationError, Reputation, SanityError, SimulationError,
 };
 use alloy_chains::Chain;
+use deposit_cache::DepositInfoCache;
 use enumset::{EnumSet, EnumSetType};
-use ethers::{providers::Middleware, types::U256};
-use silius_contracts::{entry_point::SimulateValidationResult, tracer::JsTracerFrame, EntryPoint};
+use ethers::{
+    providers::Middleware,
+    types::{Address, Block, Bytes, H256, U256},
+};
+use serde::{Deserialize, Serialize};
+use silius_contracts::{
+    entry_point::{DepositInfo, EntryPointError, SimulateValidationResult},
+    tracer::JsTracerFrame,
+    EntryPoint,
+};
 use silius_primitives::{
     constants::validation::entities::NUMBER_OF_LEVELS,
     reputation::StakeInfo,
     simulation::{CodeHash, StorageMap, ValidationConfig},
     UserOperation, UserOperationHash,
 };
+use std::{collections::HashSet, sync::Arc};
 
+pub mod deposit_cache;
+pub mod metrics;
 pub mod sanity;
 pub mod simulation;
 pub mod simulation_trace;
 pub mod utils;
 pub mod validator;
 
+/// The block which the user operations are verified on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerifiedBlock {
+    /// Hash of the block
+    pub hash: U256,
+    /// Number of the block
+    pub number: u64,
+}
+
 /// The outcome of a user operation validation.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UserOperationValidationOutcome {
     // which validation config was used
     pub val_config: ValidationConfig,
     pub prev_hash: Option<UserOperationHash>,
     pub pre_fund: U256,
     pub verification_gas_limit: U256,
+    // whether any entity (factory, sender, paymaster) is staked
+    pub staked: bool,
+    // aggregator and the signature to include in the aggregated signature (ERC-4337 aggregators)
+    pub aggregator: Option<Address>,
+    pub aggregator_signature: Option<Bytes>,
     // Simulation
     pub valid_after: Option<U256>,
     // Simulation trace
     pub code_hashes: Option<Vec<CodeHash>>,
     pub storage_map: StorageMap,
-    // the block which the user operation is verified on
+    // storage slots written during the validation (per contract)
+    pub storage_writes: utils::StorageWrites,
+    // whether any entity reads TIMESTAMP or NUMBER during the validation (only set in the strict
+    // time dependence mode of the opcodes check)
+    pub time_dependent: bool,
+    // the block (hash and number) which the user operation is verified on
     pub verified_block: U256,
+    pub verified_block_number: u64,
+    // Execution: whether the execution phase (call data) reverts and the revert reason (if any)
+    pub would_revert: bool,
+    pub revert_reason: Option<String>,
+}
+
+impl UserOperationValidationOutcome {
+    /// Finds the storage slots written during the validation of one user operation and accessed
+    /// during the validation of the other (see
+    /// [storage_map_conflicts](utils::storage_map_conflicts)). Including both in the same
+    /// bundle may invalidate the second one, so the bundle builder can skip it.
+    ///
+    /// # Arguments
+    /// `other` - The [UserOperationValidationOutcome] of the other user operation
+    ///
+    /// # Returns
+    /// The set of conflicting (address, slot) pairs
+    pub fn conflicts_with(&self, other: &Self) -> HashSet<(Address, String)> {
+        utils::storage_map_conflicts(
+            &self.storage_map,
+            &self.storage_writes,
+            &other.storage_map,
+            &other.storage_writes,
+        )
+    }
 }
 
 /// The mode in which the user operation validator is running.
-/// The validator has three modes: sanity, simulation, and simulation trace.
+/// The validator has four modes: sanity, simulation, simulation trace and execution. The
+/// execution mode simulates the execution phase (the call data) and only flags the user operations
+/// that would revert, it never fails the validation.
 #[derive(EnumSetType, Debug)]
 pub enum UserOperationValidatorMode {
     Sanity,
     Simulation,
     SimulationTrace,
+    Execution,
 }
 
 /// The [UserOperation](UserOperation) validator trait.
@@ -50,14 +110,116 @@ pub enum UserOperationValidatorMode {
 /// to choose validation rules(sanity, simultation, simulation trace) to apply.
 #[async_trait::async_trait]
 pub trait UserOperationValidator: Send + Sync {
-    async fn validate_user_operation(
+    /// Gets the latest block which the user operations are verified on.
+    async fn get_verified_block(&self) -> Result<VerifiedBlock, InvalidMempoolUserOperationError>;
+
+    /// Validates a [UserOperation](UserOperation) sent to the given entry point assuming it is
+    /// verified on the given block.
+    async fn validate_user_operation_on_block(
         &self,
         uo: &UserOperation,
+        entry_point: &Address,
         mempool: &Mempool,
         reputation: &Reputation,
         val_config: Option<ValidationConfig>,
         mode: EnumSet<UserOperationValidatorMode>,
+        verified_block: VerifiedBlock,
     ) -> Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError>;
+
+    /// Validates a [UserOperation](UserOperation) sent to the given entry point on the latest
+    /// block.
+    async fn validate_user_operation(
+        &self,
+        uo: &UserOperation,
+        entry_point: &Address,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        val_config: Option<ValidationConfig>,
+        mode: EnumSet<UserOperationValidatorMode>,
+    ) -> Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError> {
+        let verified_block = self.get_verified_block().await?;
+        self.validate_user_operation_on_block(
+            uo,
+            entry_point,
+            mempool,
+            reputation,
+            val_config,
+            mode,
+            verified_block,
+        )
+        .await
+    }
+
+    /// Validates a [UserOperation](UserOperation) sent to the given entry point on the given
+    /// block instead of the latest one (the block is not fetched). Useful for validating many user
+    /// operations against the same pinned block.
+    async fn validate_user_operation_with_block(
+        &self,
+        uo: &UserOperation,
+        entry_point: &Address,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        val_config: Option<ValidationConfig>,
+        mode: EnumSet<UserOperationValidatorMode>,
+        block: Block<H256>,
+    ) -> Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError> {
+        let verified_block = VerifiedBlock {
+            hash: U256::from(
+                block
+                    .hash
+                    .ok_or(SanityError::Provider {
+                        inner: "block has no hash (pending block)".into(),
+                    })?
+                    .0,
+            ),
+            number: block.number.unwrap_or_default().as_u64(),
+        };
+        self.validate_user_operation_on_block(
+            uo,
+            entry_point,
+            mempool,
+            reputation,
+            val_config,
+            mode,
+            verified_block,
+        )
+        .await
+    }
+
+    /// Validates multiple [UserOperations](UserOperation) sent to the given entry point one after
+    /// another. The latest block is fetched only once and shared across all validations, so an
+    /// error fetching it fails the whole batch (and is returned only once).
+    async fn validate_user_operation_batch(
+        &self,
+        uos: &[&UserOperation],
+        entry_point: &Address,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        val_config: Option<ValidationConfig>,
+        mode: EnumSet<UserOperationValidatorMode>,
+    ) -> Result<
+        Vec<Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError>>,
+        InvalidMempoolUserOperationError,
+    > {
+        let verified_block = self.get_verified_block().await?;
+
+        let mut outs = Vec::with_capacity(uos.len());
+        for uo in uos {
+            outs.push(
+                self.validate_user_operation_on_block(
+                    uo,
+                    entry_point,
+                    mempool,
+                    reputation,
+                    val_config.clone(),
+                    mode,
+                    verified_block,
+                )
+                .await,
+            );
+        }
+        Ok(outs)
+    }
 }
 
 /// The [UserOperation] sanity check helper trait.
@@ -65,6 +227,14 @@ pub struct SanityHelper<'a, M: Middleware + 'static> {
     entry_point: &'a EntryPoint<M>,
     chain: Chain,
     val_config: ValidationConfig,
+    deposit_cache: &'a DepositInfoCache,
+}
+
+impl<'a, M: Middleware + 'static> SanityHelper<'a, M> {
+    /// Gets the deposit info of the entity (served from the [DepositInfoCache] if possible).
+    pub async fn get_deposit_info(&self, addr: &Address) -> Result<DepositInfo, EntryPointError> {
+        self.deposit_cache.get_deposit_info(self.entry_point, addr).await
+    }
 }
 
 #[async_trait::async_trait]
@@ -101,6 +271,33 @@ pub trait SanityCheck<M: Middleware>: Send + Sync {
         reputation: &Reputation,
         helper: &SanityHelper<M>,
     ) -> Result<(), SanityError>;
+
+    /// Performs the sanity checks on a user operation without stopping at the first failure.
+    ///
+    /// [check_user_operation](SanityCheck::check_user_operation) stays the default (and cheaper)
+    /// mode used by the validator; this method is meant for reporting every problem of the user
+    /// operation at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `uo` - The user operation to be checked.
+    /// * `mempool` - The mempool to verify the user operation against.
+    /// * `reputation` - The reputation system to consider during the sanity check.
+    /// * `helper` - The `SanityHelper` instance that provides additional sanity checks.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SanityError`s of all failed checks in the order of the checks (empty if the
+    /// user operation passes all sanity checks).
+    async fn check_all(
+        &self,
+        uo: &UserOperation,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        helper: &SanityHelper<M>,
+    ) -> Vec<SanityError> {
+        self.check_user_operation(uo, mempool, reputation, helper).await.err().into_iter().collect()
+    }
 }
 
 macro_rules! sanity_check_impls {
@@ -121,6 +318,20 @@ macro_rules! sanity_check_impls {
                     ($($name.check_user_operation(uo, mempool, reputation, helper).await?,)+);
                     Ok(())
                 }
+
+            async fn check_all(
+                &self,
+                uo: &UserOperation,
+                mempool: &Mempool,
+                reputation: &Reputation,
+                helper: &SanityHelper<M>,
+            ) -> Vec<SanityError>
+                {
+                    let ($($name,)+) = self;
+                    let mut errs = vec![];
+                    $(errs.extend($name.check_all(uo, mempool, reputation, helper).await);)+
+                    errs
+                }
         }
     };
 }
@@ -138,6 +349,72 @@ impl<M: Middleware> SanityCheck<M> for () {
     }
 }
 
+/// Sanity checks composed at runtime (see
+/// [StandardUserOperationValidator::add_sanity_check](validator::StandardUserOperationValidator::add_sanity_check)).
+pub type SanityChecks<M> = Vec<Arc<dyn SanityCheck<M>>>;
+
+macro_rules! sanity_check_ptr_impls {
+    ( $( $ptr:ident )+ ) => {
+        $(
+            #[async_trait::async_trait]
+            impl<M: Middleware, C: SanityCheck<M> + ?Sized> SanityCheck<M> for $ptr<C> {
+                async fn check_user_operation(
+                    &self,
+                    uo: &UserOperation,
+                    mempool: &Mempool,
+                    reputation: &Reputation,
+                    helper: &SanityHelper<M>,
+                ) -> Result<(), SanityError> {
+                    (**self).check_user_operation(uo, mempool, reputation, helper).await
+                }
+
+                async fn check_all(
+                    &self,
+                    uo: &UserOperation,
+                    mempool: &Mempool,
+                    reputation: &Reputation,
+                    helper: &SanityHelper<M>,
+                ) -> Vec<SanityError> {
+                    (**self).check_all(uo, mempool, reputation, helper).await
+                }
+            }
+        )+
+    };
+}
+
+// Boxed and shared checks (e.g. `Box<dyn SanityCheck<M>>`) are checks themselves.
+sanity_check_ptr_impls! { Box Arc }
+
+#[async_trait::async_trait]
+impl<M: Middleware, C: SanityCheck<M>> SanityCheck<M> for Vec<C> {
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        helper: &SanityHelper<M>,
+    ) -> Result<(), SanityError> {
+        for check in self {
+            check.check_user_operation(uo, mempool, reputation, helper).await?;
+        }
+        Ok(())
+    }
+
+    async fn check_all(
+        &self,
+        uo: &UserOperation,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        helper: &SanityHelper<M>,
+    ) -> Vec<SanityError> {
+        let mut errs = vec![];
+        for check in self {
+            errs.extend(check.check_all(uo, mempool, reputation, helper).await);
+        }
+        errs
+    }
+}
+
 // These macro enable people to chain sanity check implementations:
 // `(SanityCheck1, SanityCheck2, SanityCheck3, ...).check_user_operation(uo, mempool, reputation,
 // helper)`` SanityCheck1,2,3 could be any data type which implement SanityCheck trait.
@@ -151,12 +428,16 @@ sanity_check_impls! { A B C D F G I }
 sanity_check_impls! { A B C D F G I J }
 sanity_check_impls! { A B C D F G I J K }
 sanity_check_impls! { A B C D F G I J K L }
+sanity_check_impls! { A B C D F G I J K L N }
 
 /// The [UserOperation] simulation check helper trait.
 pub struct SimulationHelper<'a> {
     simulate_validation_result: &'a SimulateValidationResult,
     val_config: ValidationConfig,
     valid_after: Option<U256>,
+    /// The deposit of the sender in the entry point plus its balance, i.e. the funds the account
+    /// can cover the prefund with (None if the user operation uses a paymaster)
+    sender_funds: Option<U256>,
 }
 
 /// Trait for performing simulation checks on user operations.
@@ -177,6 +458,12 @@ pub trait SimulationCheck: Send + Sync {
         uo: &UserOperation,
         helper: &mut SimulationHelper,
     ) -> Result<(), SimulationError>;
+
+    /// Whether the check needs the funds of the sender (see [SimulationHelper]), which cost extra
+    /// calls to the execution client and are only fetched if some check needs them.
+    fn needs_sender_funds(&self) -> bool {
+        false
+    }
 }
 
 macro_rules! simulation_check_impls {
@@ -195,10 +482,77 @@ macro_rules! simulation_check_impls {
                     ($($name.check_user_operation(uo, helper)?,)+);
                     Ok(())
                 }
+
+            fn needs_sender_funds(&self) -> bool {
+                let ($($name,)+) = self;
+                false $(|| $name.needs_sender_funds())+
+            }
         }
     };
 }
 
+/// Simulation checks composed at runtime (see
+/// [StandardUserOperationValidator::add_simulation_check](validator::StandardUserOperationValidator::add_simulation_check)).
+pub type SimulationChecks = Vec<Arc<dyn SimulationCheck>>;
+
+macro_rules! simulation_check_ptr_impls {
+    ( $( $ptr:ident )+ ) => {
+        $(
+            impl<C: SimulationCheck + ?Sized> SimulationCheck for $ptr<C> {
+                fn check_user_operation(
+                    &self,
+                    uo: &UserOperation,
+                    helper: &mut SimulationHelper,
+                ) -> Result<(), SimulationError> {
+                    (**self).check_user_operation(uo, helper)
+                }
+
+                fn needs_sender_funds(&self) -> bool {
+                    (**self).needs_sender_funds()
+                }
+            }
+        )+
+    };
+}
+
+// Boxed and shared checks (e.g. `Box<dyn SimulationCheck>`) are checks themselves.
+simulation_check_ptr_impls! { Box Arc }
+
+impl<C: SimulationCheck> SimulationCheck for Vec<C> {
+    fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        helper: &mut SimulationHelper,
+    ) -> Result<(), SimulationError> {
+        for check in self {
+            check.check_user_operation(uo, helper)?;
+        }
+        Ok(())
+    }
+
+    fn needs_sender_funds(&self) -> bool {
+        self.iter().any(|check| check.needs_sender_funds())
+    }
+}
+
+// Optional checks (e.g. opt-in limits) pass when unset.
+impl<C: SimulationCheck> SimulationCheck for Option<C> {
+    fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        helper: &mut SimulationHelper,
+    ) -> Result<(), SimulationError> {
+        match self {
+            Some(check) => check.check_user_operation(uo, helper),
+            None => Ok(()),
+        }
+    }
+
+    fn needs_sender_funds(&self) -> bool {
+        self.as_ref().is_some_and(|check| check.needs_sender_funds())
+    }
+}
+
 // These macro enable people to chain simulation check implementations:
 // `(SimulationCheck1, SimulationCheck2, SimulationCheck3, ...).check_user_operation(uo, helper)``
 // SimulationCheck1,2,3 could be any data type which implement SimulationCheck trait.
@@ -222,6 +576,7 @@ pub struct SimulationTraceHelper<'a, M: Middleware + Send + Sync + 'static> {
     val_config: ValidationConfig,
     stake_info: Option<[StakeInfo; NUMBER_OF_LEVELS]>,
     code_hashes: Option<Vec<CodeHash>>,
+    time_dependent: bool,
 }
 
 #[async_trait::async_trait]
@@ -293,6 +648,67 @@ impl<M: Middleware> SimulationTraceCheck<M> for () {
     }
 }
 
+/// Simulation trace checks composed at runtime (see
+/// [StandardUserOperationValidator::add_trace_check](validator::StandardUserOperationValidator::add_trace_check)).
+pub type SimulationTraceChecks<M> = Vec<Arc<dyn SimulationTraceCheck<M>>>;
+
+macro_rules! simulation_trace_check_ptr_impls {
+    ( $( $ptr:ident )+ ) => {
+        $(
+            #[async_trait::async_trait]
+            impl<M: Middleware, C: SimulationTraceCheck<M> + ?Sized> SimulationTraceCheck<M>
+                for $ptr<C>
+            {
+                async fn check_user_operation(
+                    &self,
+                    uo: &UserOperation,
+                    mempool: &Mempool,
+                    reputation: &Reputation,
+                    helper: &mut SimulationTraceHelper<M>,
+                ) -> Result<(), SimulationError> {
+                    (**self).check_user_operation(uo, mempool, reputation, helper).await
+                }
+            }
+        )+
+    };
+}
+
+// Boxed and shared checks (e.g. `Box<dyn SimulationTraceCheck<M>>`) are checks themselves.
+simulation_trace_check_ptr_impls! { Box Arc }
+
+#[async_trait::async_trait]
+impl<M: Middleware, C: SimulationTraceCheck<M>> SimulationTraceCheck<M> for Vec<C> {
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        for check in self {
+            check.check_user_operation(uo, mempool, reputation, helper).await?;
+        }
+        Ok(())
+    }
+}
+
+// Optional checks (e.g. opt-in restrictions) pass when unset.
+#[async_trait::async_trait]
+impl<M: Middleware, C: SimulationTraceCheck<M>> SimulationTraceCheck<M> for Option<C> {
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        match self {
+            Some(check) => check.check_user_operation(uo, mempool, reputation, helper).await,
+            None => Ok(()),
+        }
+    }
+}
+
 // These macro enable people to chain simulation check implementations:
 // `(SimulationTraceCheck1, SimulationTraceCheck2, SimulationTraceCheck3,
 // ...).check_user_operation(uo, mempool, reputeation helper)`` SimulationTraceCheck1,2,3 could be
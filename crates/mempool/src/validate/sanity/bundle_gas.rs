@@ -0,0 +1,101 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SanityCheck, SanityHelper},
+    Reputation, SanityError,
+};
+use ethers::{providers::Middleware, types::U256};
+use silius_primitives::UserOperation;
+
+#[derive(Clone)]
+pub struct BundleGas {
+    /// Maximum aggregate gas of the user operations in one bundle
+    pub max_bundle_gas: U256,
+}
+
+impl BundleGas {
+    /// Calculates the total gas limit of the user operation counted against the bundle gas.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    ///
+    /// # Returns
+    /// The sum of the verification gas limit, call gas limit and pre-verification gas.
+    pub fn total_gas_limit(uo: &UserOperation) -> U256 {
+        uo.verification_gas_limit
+            .saturating_add(uo.call_gas_limit)
+            .saturating_add(uo.pre_verification_gas)
+    }
+
+    /// Checks whether the user operation fits in a bundle.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    ///
+    /// # Returns
+    /// Nothing if the user operation fits, otherwise a [SanityError](SanityError) is returned.
+    pub fn check_gas_limit(&self, uo: &UserOperation) -> Result<(), SanityError> {
+        let gas_limit = Self::total_gas_limit(uo);
+
+        if gas_limit > self.max_bundle_gas {
+            return Err(SanityError::GasLimitTooHighForBundle {
+                gas_limit,
+                max_bundle_gas: self.max_bundle_gas,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SanityCheck<M> for BundleGas {
+    /// The method implementation that checks whether the total gas limit of the user operation
+    /// fits in a bundle.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `helper` - The [sanity check helper](SanityHelper) that contains the necessary data to
+    /// perform the sanity check.
+    ///
+    /// # Returns
+    /// Nothing if the sanity check is successful, otherwise a [SanityError](SanityError)
+    /// is returned.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        _helper: &SanityHelper<M>,
+    ) -> Result<(), SanityError> {
+        self.check_gas_limit(uo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Address;
+    use silius_primitives::UserOperationSigned;
+
+    #[test]
+    fn bundle_gas_limit() {
+        let check = BundleGas { max_bundle_gas: U256::from(1_000_000) };
+        let uo = |verification_gas_limit: u64| {
+            UserOperation::from_user_operation_signed(
+                Default::default(),
+                UserOperationSigned::default()
+                    .sender(Address::random())
+                    .verification_gas_limit(verification_gas_limit.into())
+                    .call_gas_limit(200_000.into())
+                    .pre_verification_gas(50_000.into()),
+            )
+        };
+
+        assert!(check.check_gas_limit(&uo(750_000)).is_ok());
+        assert!(matches!(
+            check.check_gas_limit(&uo(750_001)),
+            Err(SanityError::GasLimitTooHighForBundle { gas_limit, max_bundle_gas })
+                if gas_limit == U256::from(1_000_001) && max_bundle_gas == U256::from(1_000_000)
+        ));
+    }
+}
@@ -9,23 +9,16 @@ use silius_primitives::UserOperation;
 #[derive(Clone)]
 pub struct CallGas;
 
-#[async_trait::async_trait]
-impl<M: Middleware> SanityCheck<M> for CallGas {
-    /// The `check_user_operation` method implementation for the `CallGas` sanity check.
+impl CallGas {
+    /// Checks whether the call gas limit covers at least the cost of a CALL with non-zero value.
     ///
     /// # Arguments
-    /// `uo` - The user operation to check.
-    /// `helper` - The helper struct that contains the entry point and the Ethereum client.
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
     ///
     /// # Returns
-    /// None if the sanity check passes, otherwise [SanityError].
-    async fn check_user_operation(
-        &self,
-        uo: &UserOperation,
-        _mempool: &Mempool,
-        _reputation: &Reputation,
-        _helper: &SanityHelper<M>,
-    ) -> Result<(), SanityError> {
+    /// Nothing if the call gas limit is high enough, otherwise a [SanityError](SanityError) is
+    /// returned.
+    pub fn check_call_gas_limit(uo: &UserOperation) -> Result<(), SanityError> {
         // call gas limit is at least the cost of a CALL with non-zero value
         // https://github.com/wolflo/evm-opcodes/blob/main/gas.md#aa-1-call
         // gas_cost = 100 + 9000
@@ -41,3 +34,24 @@ impl<M: Middleware> SanityCheck<M> for CallGas {
         })
     }
 }
+
+#[async_trait::async_trait]
+impl<M: Middleware> SanityCheck<M> for CallGas {
+    /// The `check_user_operation` method implementation for the `CallGas` sanity check.
+    ///
+    /// # Arguments
+    /// `uo` - The user operation to check.
+    /// `helper` - The helper struct that contains the entry point and the Ethereum client.
+    ///
+    /// # Returns
+    /// None if the sanity check passes, otherwise [SanityError].
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        _helper: &SanityHelper<M>,
+    ) -> Result<(), SanityError> {
+        Self::check_call_gas_limit(uo)
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SanityCheck, SanityHelper},
+    Reputation, SanityError,
+};
+use ethers::providers::Middleware;
+use silius_primitives::{
+    constants::validation::calldata_size::{
+        MAX_CALL_DATA_SIZE, MAX_INIT_CODE_SIZE, MAX_PAYMASTER_AND_DATA_SIZE, MAX_SIGNATURE_SIZE,
+    },
+    UserOperation,
+};
+
+#[derive(Clone)]
+pub struct MaxCalldataSize {
+    /// Maximum size (in bytes) of the call data
+    pub max_call_data: usize,
+    /// Maximum size (in bytes) of the init code
+    pub max_init_code: usize,
+    /// Maximum size (in bytes) of the paymaster and data
+    pub max_paymaster_and_data: usize,
+    /// Maximum size (in bytes) of the signature
+    pub max_signature: usize,
+}
+
+impl MaxCalldataSize {
+    pub fn new(
+        max_call_data: usize,
+        max_init_code: usize,
+        max_paymaster_and_data: usize,
+        max_signature: usize,
+    ) -> Self {
+        Self { max_call_data, max_init_code, max_paymaster_and_data, max_signature }
+    }
+
+    /// Checks whether the dynamic fields of the user operation fit in the size limits.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    ///
+    /// # Returns
+    /// Nothing if all fields fit, otherwise a [SanityError](SanityError) for the first field
+    /// that doesn't.
+    pub fn check_sizes(&self, uo: &UserOperation) -> Result<(), SanityError> {
+        for (field, size, max_size) in [
+            ("callData", uo.call_data.len(), self.max_call_data),
+            ("initCode", uo.init_code.len(), self.max_init_code),
+            ("paymasterAndData", uo.paymaster_and_data.len(), self.max_paymaster_and_data),
+            ("signature", uo.signature.len(), self.max_signature),
+        ] {
+            if size > max_size {
+                return Err(SanityError::FieldTooLarge { field: field.into(), size, max_size });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for MaxCalldataSize {
+    fn default() -> Self {
+        Self::new(
+            MAX_CALL_DATA_SIZE,
+            MAX_INIT_CODE_SIZE,
+            MAX_PAYMASTER_AND_DATA_SIZE,
+            MAX_SIGNATURE_SIZE,
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SanityCheck<M> for MaxCalldataSize {
+    /// The method implementation that rejects user operations with oversized dynamic fields
+    /// (they make the simulation and the serialization expensive).
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `helper` - The [sanity check helper](SanityHelper) that contains the necessary data to
+    /// perform the sanity check.
+    ///
+    /// # Returns
+    /// Nothing if the sanity check is successful, otherwise a [SanityError](SanityError)
+    /// is returned.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        _helper: &SanityHelper<M>,
+    ) -> Result<(), SanityError> {
+        self.check_sizes(uo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, Bytes};
+    use silius_primitives::UserOperationSigned;
+
+    #[test]
+    fn calldata_size_limits() {
+        let check = MaxCalldataSize::new(100, 60, 40, 20);
+        let uo =
+            |call_data: usize, init_code: usize, paymaster_and_data: usize, signature: usize| {
+                UserOperation::from_user_operation_signed(
+                    Default::default(),
+                    UserOperationSigned::default()
+                        .sender(Address::random())
+                        .call_data(Bytes::from(vec![1; call_data]))
+                        .init_code(Bytes::from(vec![1; init_code]))
+                        .paymaster_and_data(Bytes::from(vec![1; paymaster_and_data]))
+                        .signature(Bytes::from(vec![1; signature])),
+                )
+            };
+
+        // exactly at the limits
+        assert!(check.check_sizes(&uo(100, 60, 40, 20)).is_ok());
+
+        for (uo, field) in [
+            (uo(101, 60, 40, 20), "callData"),
+            (uo(100, 61, 40, 20), "initCode"),
+            (uo(100, 60, 41, 20), "paymasterAndData"),
+            (uo(100, 60, 40, 21), "signature"),
+        ] {
+            assert!(matches!(
+                check.check_sizes(&uo),
+                Err(SanityError::FieldTooLarge { field: f, size, max_size })
+                    if f == field && size == max_size + 1
+            ));
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SanityCheck, SanityHelper},
+    Reputation, SanityError,
+};
+use ethers::{providers::Middleware, types::Address};
+use parking_lot::RwLock;
+use silius_primitives::{
+    constants::validation::entities::{FACTORY, PAYMASTER, SENDER},
+    UserOperation,
+};
+use std::{collections::HashSet, sync::Arc};
+
+/// Denied addresses per entity role. Clones share the lists, so the lists can be updated at
+/// runtime (e.g. through the gRPC service) while the check is used by the validator.
+#[derive(Clone, Default, Debug)]
+pub struct Denylist {
+    /// Denied senders
+    pub senders: Arc<RwLock<HashSet<Address>>>,
+    /// Denied paymasters
+    pub paymasters: Arc<RwLock<HashSet<Address>>>,
+    /// Denied factories
+    pub factories: Arc<RwLock<HashSet<Address>>>,
+}
+
+impl Denylist {
+    /// Checks the entities of the user operation against the denied addresses.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    ///
+    /// # Returns
+    /// Nothing if no entity is denied, otherwise a [SanityError](SanityError) with the first denied
+    /// entity.
+    pub fn check_entities(&self, uo: &UserOperation) -> Result<(), SanityError> {
+        let (sender, factory, paymaster) = uo.get_entities();
+
+        for (entity, address, list) in [
+            (SENDER, Some(sender), &self.senders),
+            (FACTORY, factory, &self.factories),
+            (PAYMASTER, paymaster, &self.paymasters),
+        ] {
+            if let Some(address) = address.filter(|addr| list.read().contains(addr)) {
+                return Err(SanityError::Denied { entity: entity.into(), address });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SanityCheck<M> for Denylist {
+    /// The method implementation that checks whether the sender, the factory or the paymaster of
+    /// the user operation is denied (regardless of its reputation).
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `helper` - The [sanity check helper](SanityHelper) that contains the necessary data to
+    /// perform the sanity check.
+    ///
+    /// # Returns
+    /// Nothing if the sanity check is successful, otherwise a [SanityError](SanityError)
+    /// is returned.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        _helper: &SanityHelper<M>,
+    ) -> Result<(), SanityError> {
+        self.check_entities(uo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Bytes;
+    use silius_primitives::UserOperationSigned;
+
+    fn user_operation(sender: Address, factory: Address, paymaster: Address) -> UserOperation {
+        UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default()
+                .sender(sender)
+                .init_code(Bytes::from(factory.as_bytes().to_vec()))
+                .paymaster_and_data(Bytes::from(paymaster.as_bytes().to_vec())),
+        )
+    }
+
+    fn assert_denied(res: Result<(), SanityError>, entity_expected: &str, addr: Address) {
+        assert!(matches!(
+            res,
+            Err(SanityError::Denied { entity, address })
+                if entity == entity_expected && address == addr
+        ));
+    }
+
+    #[test]
+    fn denylist_sender() {
+        let (sender, factory, paymaster) =
+            (Address::random(), Address::random(), Address::random());
+        let denylist = Denylist::default();
+        assert!(denylist.check_entities(&user_operation(sender, factory, paymaster)).is_ok());
+
+        denylist.senders.write().insert(sender);
+        assert_denied(
+            denylist.check_entities(&user_operation(sender, factory, paymaster)),
+            SENDER,
+            sender,
+        );
+    }
+
+    #[test]
+    fn denylist_factory() {
+        let (sender, factory, paymaster) =
+            (Address::random(), Address::random(), Address::random());
+        let denylist = Denylist::default();
+        // the lists are per role, so the factory address is allowed as a sender
+        denylist.factories.write().insert(factory);
+        assert!(denylist.check_entities(&user_operation(factory, sender, paymaster)).is_ok());
+        assert_denied(
+            denylist.check_entities(&user_operation(sender, factory, paymaster)),
+            FACTORY,
+            factory,
+        );
+    }
+
+    #[test]
+    fn denylist_paymaster() {
+        let (sender, factory, paymaster) =
+            (Address::random(), Address::random(), Address::random());
+        let denylist = Denylist::default();
+        // clones share the lists
+        denylist.clone().paymasters.write().insert(paymaster);
+        assert_denied(
+            denylist.check_entities(&user_operation(sender, factory, paymaster)),
+            PAYMASTER,
+            paymaster,
+        );
+
+        denylist.paymasters.write().remove(&paymaster);
+        assert!(denylist.check_entities(&user_operation(sender, factory, paymaster)).is_ok());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SanityCheck, SanityHelper},
+    Reputation, ReputationError, SanityError,
+};
+use ethers::{providers::Middleware, types::U256};
+use silius_primitives::{
+    constants::validation::entities::FACTORY, get_address, reputation::StakeInfo, UserOperation,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Clone)]
+pub struct DupeByInitCode {
+    /// Maximum number of user operations deploying through the same unstaked factory (without any
+    /// included user operations) in one window
+    pub max_uos_per_window: u64,
+    /// Length of the window
+    pub window: Duration,
+}
+
+impl DupeByInitCode {
+    pub fn new(max_uos_per_window: u64, window: Duration) -> Self {
+        Self { max_uos_per_window, window }
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SanityCheck<M> for DupeByInitCode {
+    /// The method implementation that throttles user operations deploying through an unstaked
+    /// factory which exceeded the allowed rate without any included user operations.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `helper` - The [sanity check helper](SanityHelper) that contains the necessary data to
+    /// perform the sanity check.
+    ///
+    /// # Returns
+    /// Nothing if the sanity check is successful, otherwise a [SanityError](SanityError)
+    /// is returned.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        helper: &SanityHelper<M>,
+    ) -> Result<(), SanityError> {
+        let factory = match get_address(&uo.init_code) {
+            Some(factory) => factory,
+            None => return Ok(()),
+        };
+
+        // staked factories are exempt
+        let info = helper.get_deposit_info(&factory).await?;
+        if reputation
+            .verify_stake(
+                FACTORY,
+                Some(StakeInfo {
+                    address: factory,
+                    stake: U256::from(info.stake),
+                    unstake_delay: U256::from(info.unstake_delay_sec),
+                }),
+                helper.val_config.min_stake,
+                helper.val_config.min_unstake_delay,
+            )
+            .is_ok()
+        {
+            return Ok(());
+        }
+
+        // factories with included user operations are exempt
+        if reputation.get(&factory)?.uo_included > 0 {
+            return Ok(());
+        }
+
+        // the user operations are counted from the mempool, so the count is bounded by the
+        // mempool and drops as soon as they are included or evicted; a replacement user operation
+        // doesn't add a deployment
+        let since = SystemTime::now().checked_sub(self.window).unwrap_or(UNIX_EPOCH);
+        let count = mempool.get_number_by_factory_since(&factory, since) as u64 +
+            u64::from(mempool.get_prev_by_sender(uo).is_none());
+        if count > self.max_uos_per_window {
+            return Err(ReputationError::ThrottledEntity {
+                entity: FACTORY.into(),
+                address: factory,
+                reason: format!(
+                    "{count} account deployments within {:?}, at most {} allowed",
+                    self.window, self.max_uos_per_window
+                ),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::deposit_cache::DepositInfoCache,
+    };
+    use alloy_chains::Chain;
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::{Address, Bytes},
+    };
+    use silius_contracts::{entry_point::DepositInfo, EntryPoint};
+    use silius_primitives::{
+        constants::validation::reputation::MIN_UNSTAKE_DELAY, reputation::ReputationEntry,
+        UserOperationSigned,
+    };
+    use std::sync::Arc;
+
+    fn user_operation(factory: &Address) -> UserOperation {
+        let uo = UserOperationSigned::default()
+            .sender(Address::random())
+            .init_code(Bytes::from([factory.as_bytes(), &[1, 2, 3, 4]].concat()));
+        UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo)
+    }
+
+    #[tokio::test]
+    async fn dupe_by_init_code_limit() {
+        let (mut mempool, mut reputation) = (memory_mempool(), memory_reputation());
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::from_secs(60));
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+        let check = DupeByInitCode::new(2, Duration::from_secs(60));
+
+        let (factory, factory_staked) = (Address::random(), Address::random());
+        deposit_cache.insert(&entry_point.address(), &factory, DepositInfo::default());
+        deposit_cache.insert(
+            &entry_point.address(),
+            &factory_staked,
+            DepositInfo {
+                staked: true,
+                stake: 1,
+                unstake_delay_sec: MIN_UNSTAKE_DELAY as u32,
+                ..Default::default()
+            },
+        );
+
+        for factory in [factory, factory_staked] {
+            for _ in 0..2 {
+                let uo = user_operation(&factory);
+                assert!(check
+                    .check_user_operation(&uo, &mempool, &reputation, &helper)
+                    .await
+                    .is_ok());
+                mempool.add(uo).unwrap();
+            }
+        }
+
+        // the unstaked factory reached the limit
+        let uo = user_operation(&factory);
+        assert!(matches!(
+            check.check_user_operation(&uo, &mempool, &reputation, &helper).await,
+            Err(SanityError::Reputation(ReputationError::ThrottledEntity { address, .. }))
+                if address == factory
+        ));
+
+        // the staked factory is exempt
+        let uo_staked = user_operation(&factory_staked);
+        assert!(check
+            .check_user_operation(&uo_staked, &mempool, &reputation, &helper)
+            .await
+            .is_ok());
+
+        // a factory with included user operations is exempt
+        reputation
+            .set_entities(vec![ReputationEntry {
+                uo_seen: 1,
+                uo_included: 1,
+                ..ReputationEntry::default_with_addr(factory)
+            }])
+            .unwrap();
+        assert!(check.check_user_operation(&uo, &mempool, &reputation, &helper).await.is_ok());
+
+        // user operations added before the window don't count
+        let check = DupeByInitCode::new(2, Duration::ZERO);
+        reputation.set_entities(vec![ReputationEntry::default_with_addr(factory)]).unwrap();
+        assert!(check.check_user_operation(&uo, &mempool, &reputation, &helper).await.is_ok());
+    }
+}
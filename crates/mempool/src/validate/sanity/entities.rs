@@ -55,13 +55,16 @@ impl Entities {
         mempool: &Mempool,
         _reputation: &Reputation,
     ) -> Result<(), SanityError> {
-        if *status == Status::THROTTLED &&
-            (mempool.get_number_by_sender(addr) + mempool.get_number_by_entity(addr)) >=
-                THROTTLED_ENTITY_MEMPOOL_COUNT
-        {
-            return Err(
-                ReputationError::ThrottledEntity { entity: entity.into(), address: *addr }.into()
-            );
+        let count = mempool.get_number_by_sender(addr) + mempool.get_number_by_entity(addr);
+        if *status == Status::THROTTLED && count >= THROTTLED_ENTITY_MEMPOOL_COUNT {
+            return Err(ReputationError::ThrottledEntity {
+                entity: entity.into(),
+                address: *addr,
+                reason: format!(
+                    "{count} user operations in the mempool, at most {THROTTLED_ENTITY_MEMPOOL_COUNT} allowed"
+                ),
+            }
+            .into());
         }
 
         Ok(())
@@ -112,3 +115,136 @@ impl<M: Middleware> SanityCheck<M> for Entities {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::deposit_cache::DepositInfoCache,
+    };
+    use alloy_chains::Chain;
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::{Bytes, U256},
+    };
+    use parking_lot::RwLock;
+    use silius_contracts::EntryPoint;
+    use silius_primitives::{
+        constants::validation::reputation::{
+            BAN_SLACK, MIN_INCLUSION_RATE_DENOMINATOR, THROTTLING_SLACK,
+        },
+        reputation::ReputationEntry,
+        UserOperationSigned,
+    };
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+        time::Duration,
+    };
+
+    fn user_operation(paymaster: &Address) -> UserOperation {
+        let uo = UserOperationSigned::random()
+            .paymaster_and_data(Bytes::from(paymaster.as_bytes().to_vec()));
+        UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo)
+    }
+
+    #[tokio::test]
+    async fn entities_reputation_transitions() {
+        let mut mempool = memory_mempool();
+        let mut reputation = memory_reputation();
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::ZERO);
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+
+        let paymaster = Address::random();
+        let uo = user_operation(&paymaster);
+        let set_seen = |reputation: &mut Reputation, uo_seen: u64| {
+            reputation
+                .set_entities(vec![ReputationEntry {
+                    address: paymaster,
+                    uo_seen,
+                    uo_included: 0,
+                    status: Status::OK.into(),
+                }])
+                .unwrap();
+        };
+
+        // ok
+        assert!(Entities.check_user_operation(&uo, &mempool, &reputation, &helper).await.is_ok());
+
+        // throttled, but still below the mempool limit
+        set_seen(&mut reputation, (THROTTLING_SLACK + 1) * MIN_INCLUSION_RATE_DENOMINATOR);
+        assert_eq!(Status::from(reputation.get_status(&paymaster).unwrap()), Status::THROTTLED);
+        assert!(Entities.check_user_operation(&uo, &mempool, &reputation, &helper).await.is_ok());
+
+        // throttled and at the mempool limit
+        for _ in 0..THROTTLED_ENTITY_MEMPOOL_COUNT {
+            mempool.add(user_operation(&paymaster)).unwrap();
+        }
+        assert!(matches!(
+            Entities.check_user_operation(&uo, &mempool, &reputation, &helper).await,
+            Err(SanityError::Reputation(ReputationError::ThrottledEntity { .. }))
+        ));
+
+        // banned
+        set_seen(&mut reputation, (BAN_SLACK + 1) * MIN_INCLUSION_RATE_DENOMINATOR);
+        assert!(matches!(
+            Entities.check_user_operation(&uo, &mempool, &reputation, &helper).await,
+            Err(SanityError::Reputation(ReputationError::BannedEntity { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn entities_auto_ban() {
+        let mempool = memory_mempool();
+        // with the denominator 10, the throttling slack 1 and the ban slack 2, an entity without
+        // any inclusion is throttled after 20 and banned after 30 seen user operations
+        let mut reputation = Reputation::new(
+            10,
+            1,
+            2,
+            U256::from(1),
+            U256::from(0),
+            Arc::new(RwLock::new(HashSet::<Address>::default())),
+            Arc::new(RwLock::new(HashSet::<Address>::default())),
+            Box::new(HashMap::<Address, ReputationEntry>::default()),
+        );
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::ZERO);
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+
+        let paymaster = Address::random();
+        let uo = user_operation(&paymaster);
+
+        for _ in 0..20 {
+            reputation.increment_seen(&paymaster).unwrap();
+        }
+        assert_eq!(reputation.update_and_classify(&paymaster).unwrap(), Status::THROTTLED);
+        assert!(Entities.check_user_operation(&uo, &mempool, &reputation, &helper).await.is_ok());
+
+        // crosses the ban threshold
+        for _ in 0..10 {
+            reputation.increment_seen(&paymaster).unwrap();
+        }
+        assert_eq!(reputation.update_and_classify(&paymaster).unwrap(), Status::BANNED);
+        assert_eq!(Status::from(reputation.snapshot().first().unwrap().status), Status::BANNED);
+        assert!(matches!(
+            Entities.check_user_operation(&uo, &mempool, &reputation, &helper).await,
+            Err(SanityError::Reputation(ReputationError::BannedEntity { address, .. }))
+                if address == paymaster
+        ));
+    }
+}
@@ -14,6 +14,57 @@ pub struct MaxFee {
     pub min_priority_fee_per_gas: U256,
 }
 
+impl MaxFee {
+    /// Checks whether the max priority fee per gas doesn't exceed the max fee per gas.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    ///
+    /// # Returns
+    /// Nothing if the fees are consistent, otherwise a [SanityError](SanityError) is returned.
+    pub fn check_fee_cap(uo: &UserOperation) -> Result<(), SanityError> {
+        if uo.max_priority_fee_per_gas > uo.max_fee_per_gas {
+            return Err(SanityError::MaxPriorityFeePerGasTooHigh {
+                max_priority_fee_per_gas: uo.max_priority_fee_per_gas,
+                max_fee_per_gas: uo.max_fee_per_gas,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the max fee per gas covers the base fee and the minimum priority fee, so
+    /// the user operation is includable in the next block.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `base_fee_per_gas` - The base fee of the latest block (`None` on pre-EIP-1559 chains, where
+    /// the check is skipped)
+    ///
+    /// # Returns
+    /// Nothing if the max fee is high enough, otherwise a [SanityError](SanityError) is returned.
+    pub fn check_fee_floor(
+        &self,
+        uo: &UserOperation,
+        base_fee_per_gas: Option<U256>,
+    ) -> Result<(), SanityError> {
+        let base_fee_per_gas = match base_fee_per_gas {
+            Some(base_fee_per_gas) => base_fee_per_gas,
+            None => return Ok(()),
+        };
+
+        if uo.max_fee_per_gas < base_fee_per_gas.saturating_add(self.min_priority_fee_per_gas) {
+            return Err(SanityError::MaxFeePerGasTooLow {
+                max_fee_per_gas: uo.max_fee_per_gas,
+                base_fee_per_gas,
+                min_priority_fee_per_gas: self.min_priority_fee_per_gas,
+            });
+        }
+
+        Ok(())
+    }
+}
+
 #[async_trait::async_trait]
 impl<M: Middleware> SanityCheck<M> for MaxFee {
     /// The method implementation that checks the max fee.
@@ -31,10 +82,12 @@ impl<M: Middleware> SanityCheck<M> for MaxFee {
         _reputation: &Reputation,
         helper: &SanityHelper<M>,
     ) -> Result<(), SanityError> {
-        if uo.max_priority_fee_per_gas > uo.max_fee_per_gas {
-            return Err(SanityError::MaxPriorityFeePerGasTooHigh {
+        Self::check_fee_cap(uo)?;
+
+        if uo.max_priority_fee_per_gas < self.min_priority_fee_per_gas {
+            return Err(SanityError::MaxPriorityFeePerGasTooLow {
                 max_priority_fee_per_gas: uo.max_priority_fee_per_gas,
-                max_fee_per_gas: uo.max_fee_per_gas,
+                max_priority_fee_per_gas_expected: self.min_priority_fee_per_gas,
             });
         }
 
@@ -45,23 +98,69 @@ impl<M: Middleware> SanityCheck<M> for MaxFee {
             .await
             .map_err(|err| SanityError::Provider { inner: err.to_string() })?
             .ok_or(SanityError::Other { inner: "No block found".into() })?;
-        let base_fee_per_gas =
-            block.base_fee_per_gas.ok_or(SanityError::Other { inner: "No base fee".into() })?;
 
-        if base_fee_per_gas > uo.max_fee_per_gas {
-            return Err(SanityError::MaxFeePerGasTooLow {
-                max_fee_per_gas: uo.max_fee_per_gas,
-                base_fee_per_gas,
-            });
-        }
+        self.check_fee_floor(uo, block.base_fee_per_gas)
+    }
+}
 
-        if uo.max_priority_fee_per_gas < self.min_priority_fee_per_gas {
-            return Err(SanityError::MaxPriorityFeePerGasTooLow {
-                max_priority_fee_per_gas: uo.max_priority_fee_per_gas,
-                max_priority_fee_per_gas_expected: self.min_priority_fee_per_gas,
-            });
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::deposit_cache::DepositInfoCache,
+    };
+    use alloy_chains::Chain;
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::{Address, Block, TxHash},
+    };
+    use silius_contracts::EntryPoint;
+    use silius_primitives::UserOperationSigned;
+    use std::{sync::Arc, time::Duration};
 
-        Ok(())
+    #[tokio::test]
+    async fn max_fee_below_base_fee_floor() {
+        let mempool = memory_mempool();
+        let reputation = memory_reputation();
+        let (provider, mock) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::ZERO);
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+
+        let check = MaxFee { min_priority_fee_per_gas: U256::from(10) };
+        let uo = |max_fee_per_gas: u64| {
+            UserOperation::from_user_operation_signed(
+                Default::default(),
+                UserOperationSigned::default()
+                    .sender(Address::random())
+                    .max_fee_per_gas(max_fee_per_gas.into())
+                    .max_priority_fee_per_gas(10.into()),
+            )
+        };
+        let block = |base_fee_per_gas: Option<u64>| Block::<TxHash> {
+            base_fee_per_gas: base_fee_per_gas.map(U256::from),
+            ..Default::default()
+        };
+
+        // covers the base fee, but not the minimum priority fee on top of it
+        mock.push(block(Some(100))).unwrap();
+        assert!(matches!(
+            check.check_user_operation(&uo(105), &mempool, &reputation, &helper).await,
+            Err(SanityError::MaxFeePerGasTooLow { max_fee_per_gas, base_fee_per_gas, .. })
+                if max_fee_per_gas == U256::from(105) && base_fee_per_gas == U256::from(100)
+        ));
+
+        mock.push(block(Some(100))).unwrap();
+        assert!(check.check_user_operation(&uo(110), &mempool, &reputation, &helper).await.is_ok());
+
+        // pre-EIP-1559 chains have no base fee
+        mock.push(block(None)).unwrap();
+        assert!(check.check_user_operation(&uo(10), &mempool, &reputation, &helper).await.is_ok());
     }
 }
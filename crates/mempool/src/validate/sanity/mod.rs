@@ -1,9 +1,149 @@
 //! Sanity module performs call gas limit, verification gas limit, max priority fee, paymaster
 //! verification, sender vericiation, and UserOperation type checks
+use self::{
+    bundle_gas::BundleGas, call_gas::CallGas, max_fee::MaxFee, verification_gas::VerificationGas,
+};
+use crate::{Overhead, SanityError};
+use alloy_chains::Chain;
+use ethers::types::U256;
+use silius_primitives::{
+    constants::{bundler::MAX_BUNDLE_GAS, validation::MAX_VERIFICATION_GAS},
+    UserOperation,
+};
+
+pub mod bundle_gas;
 pub mod call_gas;
+pub mod calldata_size;
+pub mod denylist;
+pub mod dupe_init_code;
 pub mod entities;
 pub mod max_fee;
+pub mod nonce_gap;
 pub mod paymaster;
+pub mod paymaster_limit;
 pub mod sender;
 pub mod unstaked_entities;
 pub mod verification_gas;
+
+/// Runs the subset of the sanity checks that don't need the chain, the mempool or the
+/// reputation, so it is cheap enough for property testing and fuzzing. The checks (with the
+/// default limits) are:
+/// * [MaxFee](MaxFee) - max priority fee per gas doesn't exceed max fee per gas (the base fee and
+///   the minimum priority fee are not checked)
+/// * [CallGas](CallGas) - call gas limit covers a CALL with non-zero value
+/// * [VerificationGas](VerificationGas) - verification gas limit is at most
+///   [MAX_VERIFICATION_GAS](MAX_VERIFICATION_GAS) and pre-verification gas covers the calldata of
+///   the user operation (with the default [Overhead](Overhead)), which also bounds its size
+/// * [BundleGas](BundleGas) - total gas limit is at most [MAX_BUNDLE_GAS](MAX_BUNDLE_GAS)
+///
+/// # Arguments
+/// `uo` - The [UserOperation](UserOperation) to be checked.
+///
+/// # Returns
+/// Nothing if all checks pass, otherwise the [SanityError](SanityError) of the first failed check.
+pub fn quick_sanity(uo: &UserOperation) -> Result<(), SanityError> {
+    MaxFee::check_fee_cap(uo)?;
+    CallGas::check_call_gas_limit(uo)?;
+    VerificationGas::new(U256::from(MAX_VERIFICATION_GAS)).check_gas_limits(
+        uo,
+        &Chain::mainnet(),
+        &Overhead::default(),
+        U256::zero(),
+    )?;
+    BundleGas { max_bundle_gas: U256::from(MAX_BUNDLE_GAS) }.check_gas_limit(uo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::{deposit_cache::DepositInfoCache, SanityCheck, SanityHelper},
+    };
+    use alloy_chains::Chain;
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::{Address, Bytes},
+    };
+    use silius_contracts::EntryPoint;
+    use silius_primitives::UserOperationSigned;
+    use std::{sync::Arc, time::Duration};
+
+    fn user_operation(uo: UserOperationSigned) -> UserOperation {
+        UserOperation::from_user_operation_signed(Default::default(), uo)
+    }
+
+    #[test]
+    fn quick_sanity_checks() {
+        let uo = UserOperationSigned::default()
+            .sender(Address::random())
+            .call_gas_limit(100_000.into())
+            .verification_gas_limit(100_000.into())
+            .pre_verification_gas(60_000.into())
+            .max_fee_per_gas(100.into())
+            .max_priority_fee_per_gas(10.into());
+        assert!(quick_sanity(&user_operation(uo.clone())).is_ok());
+
+        assert!(matches!(
+            quick_sanity(&user_operation(uo.clone().max_priority_fee_per_gas(101.into()))),
+            Err(SanityError::MaxPriorityFeePerGasTooHigh { .. })
+        ));
+        assert!(matches!(
+            quick_sanity(&user_operation(uo.clone().call_gas_limit(9_000.into()))),
+            Err(SanityError::CallGasLimitTooLow { .. })
+        ));
+        assert!(matches!(
+            quick_sanity(&user_operation(uo.clone().verification_gas_limit(U256::MAX))),
+            Err(SanityError::VerificationGasLimitTooHigh { .. })
+        ));
+        assert!(matches!(
+            quick_sanity(&user_operation(uo.clone().call_data(Bytes::from(vec![1; 10_000])))),
+            Err(SanityError::PreVerificationGasTooLow { .. })
+        ));
+        assert!(matches!(
+            quick_sanity(&user_operation(uo.call_gas_limit(U256::MAX))),
+            Err(SanityError::GasLimitTooHighForBundle { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn check_all_reports_every_failure() {
+        let mempool = memory_mempool();
+        let reputation = memory_reputation();
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::ZERO);
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+
+        let checks = (
+            MaxFee { min_priority_fee_per_gas: U256::zero() },
+            CallGas,
+            BundleGas { max_bundle_gas: U256::from(1_000_000) },
+        );
+        let uo = user_operation(
+            UserOperationSigned::default()
+                .sender(Address::random())
+                .call_gas_limit(9_000.into())
+                .verification_gas_limit(2_000_000.into())
+                .max_fee_per_gas(1.into())
+                .max_priority_fee_per_gas(2.into()),
+        );
+
+        // the default mode stops at the first failure
+        assert!(matches!(
+            checks.check_user_operation(&uo, &mempool, &reputation, &helper).await,
+            Err(SanityError::MaxPriorityFeePerGasTooHigh { .. })
+        ));
+
+        let errs = checks.check_all(&uo, &mempool, &reputation, &helper).await;
+        assert_eq!(errs.len(), 3);
+        assert!(matches!(errs[0], SanityError::MaxPriorityFeePerGasTooHigh { .. }));
+        assert!(matches!(errs[1], SanityError::CallGasLimitTooLow { .. }));
+        assert!(matches!(errs[2], SanityError::GasLimitTooHighForBundle { .. }));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SanityCheck, SanityHelper},
+    Reputation, SanityError,
+};
+use ethers::{providers::Middleware, types::U256};
+use silius_primitives::UserOperation;
+
+/// Number of bits of the nonce sequence (low 64 bits), the rest (high 192 bits) is the nonce key
+const NONCE_SEQUENCE_BITS: usize = 64;
+
+#[derive(Clone)]
+pub struct NonceGap {
+    /// Maximum gap between the current nonce (sequence) of the sender and the nonce of the user
+    /// operation
+    pub max_nonce_gap: U256,
+}
+
+impl NonceGap {
+    /// Splits the 2D nonce into the key (high 192 bits) and the sequence (low 64 bits).
+    ///
+    /// # Arguments
+    /// `nonce` - The nonce to split.
+    ///
+    /// # Returns
+    /// The key and the sequence of the nonce.
+    pub fn split_nonce(nonce: U256) -> (U256, U256) {
+        (nonce >> NONCE_SEQUENCE_BITS, U256::from(nonce.low_u64()))
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SanityCheck<M> for NonceGap {
+    /// The method implementation that checks whether the nonce of the user operation is not too
+    /// far ahead of the current nonce of the sender (for the same nonce key).
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `helper` - The [sanity check helper](SanityHelper) that contains the necessary data to
+    /// perform the sanity check.
+    ///
+    /// # Returns
+    /// Nothing if the sanity check is successful, otherwise a [SanityError](SanityError)
+    /// is returned.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &SanityHelper<M>,
+    ) -> Result<(), SanityError> {
+        let (key, seq) = Self::split_nonce(uo.nonce);
+
+        let nonce = helper
+            .entry_point
+            .get_nonce(&uo.sender, key)
+            .await
+            .map_err(|e| SanityError::Provider { inner: e.to_string() })?;
+        let (_, seq_current) = Self::split_nonce(nonce);
+
+        let seq_max = seq_current.saturating_add(self.max_nonce_gap).min(U256::from(u64::MAX));
+        if seq > seq_max {
+            return Err(SanityError::NonceGap {
+                nonce: uo.nonce,
+                nonce_expected: (key << NONCE_SEQUENCE_BITS) | seq_max,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::deposit_cache::DepositInfoCache,
+    };
+    use alloy_chains::Chain;
+    use ethers::{
+        abi::{encode, Token},
+        providers::{MockProvider, Provider},
+        types::{Address, Bytes},
+    };
+    use silius_contracts::EntryPoint;
+    use silius_primitives::UserOperationSigned;
+    use std::{sync::Arc, time::Duration};
+
+    const MAX_NONCE_GAP: u64 = 10;
+
+    /// Checks the user operation with the nonce against the current nonce of the sender (returned
+    /// by the mocked `getNonce`)
+    async fn check(nonce: U256, nonce_current: U256) -> Result<(), SanityError> {
+        let mempool = memory_mempool();
+        let reputation = memory_reputation();
+        let (provider, mock) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::from_secs(60));
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(Address::random()).nonce(nonce),
+        );
+        mock.push(Bytes::from(encode(&[Token::Uint(nonce_current)]))).unwrap();
+
+        let res = NonceGap { max_nonce_gap: MAX_NONCE_GAP.into() }
+            .check_user_operation(&uo, &mempool, &reputation, &helper)
+            .await;
+
+        // the current nonce is queried for the key (lane) of the user operation
+        let (key, _) = NonceGap::split_nonce(nonce);
+        let call = entry_point.entry_point_api().get_nonce(uo.sender, key);
+        mock.assert_request("eth_call", (call.tx, "latest")).unwrap();
+
+        res
+    }
+
+    #[test]
+    fn split_nonce() {
+        assert_eq!(NonceGap::split_nonce(5.into()), (0.into(), 5.into()));
+
+        let nonce = (U256::from(7) << NONCE_SEQUENCE_BITS) | U256::from(3);
+        assert_eq!(NonceGap::split_nonce(nonce), (7.into(), 3.into()));
+    }
+
+    #[tokio::test]
+    async fn nonce_gap_boundary() {
+        let current = U256::from(3);
+
+        assert!(check(current, current).await.is_ok());
+        assert!(check(current + MAX_NONCE_GAP, current).await.is_ok());
+        assert!(matches!(
+            check(current + MAX_NONCE_GAP + 1, current).await,
+            Err(SanityError::NonceGap { nonce_expected, .. })
+                if nonce_expected == current + MAX_NONCE_GAP
+        ));
+    }
+
+    #[tokio::test]
+    async fn nonce_gap_key_lane() {
+        let key = U256::from(7) << NONCE_SEQUENCE_BITS;
+        let current = key | U256::from(3);
+
+        // the gap is measured within the lane, the key isn't part of the sequence
+        assert!(check(key | U256::from(3 + MAX_NONCE_GAP), current).await.is_ok());
+        assert!(matches!(
+            check(key | U256::from(3 + MAX_NONCE_GAP + 1), current).await,
+            Err(SanityError::NonceGap { nonce_expected, .. })
+                if nonce_expected == key | U256::from(3 + MAX_NONCE_GAP)
+        ));
+
+        // a new lane starts at sequence 0
+        let key_other = U256::from(9) << NONCE_SEQUENCE_BITS;
+        assert!(check(key_other | U256::from(MAX_NONCE_GAP), key_other).await.is_ok());
+        assert!(check(key_other | U256::from(MAX_NONCE_GAP + 1), key_other).await.is_err());
+    }
+}
@@ -6,15 +6,41 @@ use crate::{
 use ethers::{providers::Middleware, types::U256};
 use silius_primitives::{get_address, UserOperation};
 
+/// Multiplier of the verification gas limit in the prefund of user operations with a paymaster
+/// (the verification gas limit also limits the postOp call)
+const PAYMASTER_VERIFICATION_GAS_MUL: u64 = 3;
+
 #[derive(Clone)]
 pub struct Paymaster;
 
+impl Paymaster {
+    /// Calculates the prefund the entry point requires from the paymaster for the user
+    /// operation.
+    ///
+    /// # Arguments
+    /// `uo` - The user operation.
+    ///
+    /// # Returns
+    /// The required prefund.
+    pub fn required_pre_fund(uo: &UserOperation) -> U256 {
+        uo.call_gas_limit
+            .saturating_add(
+                uo.verification_gas_limit.saturating_mul(PAYMASTER_VERIFICATION_GAS_MUL.into()),
+            )
+            .saturating_add(uo.pre_verification_gas)
+            .saturating_mul(uo.max_fee_per_gas)
+    }
+}
+
 #[async_trait::async_trait]
 impl<M: Middleware> SanityCheck<M> for Paymaster {
-    /// The method implementation that performs the sanity check on the paymaster.
+    /// The method implementation that performs the sanity check on the paymaster. The deposit of
+    /// the paymaster has to cover the prefund of the user operation along with the prefund
+    /// already committed by the other user operations in the mempool sponsored by the paymaster.
     ///
     /// # Arguments
     /// `uo` - The user operation to be checked.
+    /// `mempool` - The [Mempool](Mempool) with the pending user operations.
     /// `helper` - The [sanity check helper](SanityHelper) that contains the necessary data to
     /// perform the sanity check.
     ///
@@ -23,7 +49,7 @@ impl<M: Middleware> SanityCheck<M> for Paymaster {
     async fn check_user_operation(
         &self,
         uo: &UserOperation,
-        _mempool: &Mempool,
+        mempool: &Mempool,
         _reputation: &Reputation,
         helper: &SanityHelper<M>,
     ) -> Result<(), SanityError> {
@@ -37,10 +63,31 @@ impl<M: Middleware> SanityCheck<M> for Paymaster {
                     .map_err(|e| SanityError::Provider { inner: e.to_string() })?;
 
                 if !code.is_empty() {
-                    let deposit_info = helper.entry_point.get_deposit_info(&addr).await?;
+                    let deposit_info = helper.get_deposit_info(&addr).await?;
+                    let deposit = U256::from(deposit_info.deposit);
+
+                    if deposit >= uo.max_fee_per_gas {
+                        // the user operation replaces the previous one of the sender, so its
+                        // prefund is released
+                        let mut committed = mempool.get_pre_fund_by_paymaster(&addr);
+                        if let Some(uo_prev) = mempool.get_prev_by_sender(uo) {
+                            if get_address(&uo_prev.paymaster_and_data) == Some(addr) {
+                                committed = committed.saturating_sub(
+                                    mempool.get_pre_fund(&uo_prev.hash).unwrap_or_default(),
+                                );
+                            }
+                        }
 
-                    if U256::from(deposit_info.deposit) >= uo.max_fee_per_gas {
-                        return Ok(());
+                        let required = committed.saturating_add(Self::required_pre_fund(uo));
+                        if deposit >= required {
+                            return Ok(());
+                        }
+
+                        return Err(SanityError::Paymaster {
+                            inner: format!(
+                                "paymaster deposit {deposit} too low for pending prefund {required}"
+                            ),
+                        });
                     }
                 }
             }
@@ -51,3 +98,80 @@ impl<M: Middleware> SanityCheck<M> for Paymaster {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::deposit_cache::DepositInfoCache,
+    };
+    use alloy_chains::Chain;
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::{Address, Bytes},
+    };
+    use silius_contracts::{entry_point::DepositInfo, EntryPoint};
+    use silius_primitives::UserOperationSigned;
+    use std::{sync::Arc, time::Duration};
+
+    fn user_operation(paymaster: &Address) -> UserOperation {
+        let uo = UserOperationSigned::default()
+            .sender(Address::random())
+            .call_gas_limit(100_000.into())
+            .verification_gas_limit(100_000.into())
+            .pre_verification_gas(100_000.into())
+            .max_fee_per_gas(1_000_000_000.into())
+            .paymaster_and_data(Bytes::from(paymaster.as_bytes().to_vec()));
+        UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo)
+    }
+
+    #[tokio::test]
+    async fn paymaster_deposit_cumulative_pre_fund() {
+        let mut mempool = memory_mempool();
+        let reputation = memory_reputation();
+        let (provider, mock) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::from_secs(60));
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+
+        let paymaster = Address::random();
+        let (uo, uo_other) = (user_operation(&paymaster), user_operation(&paymaster));
+        let pre_fund = Paymaster::required_pre_fund(&uo);
+        assert_eq!(pre_fund, U256::from(500_000) * U256::from(1_000_000_000));
+
+        // the deposit covers one user operation, but not two
+        deposit_cache.insert(
+            &entry_point.address(),
+            &paymaster,
+            DepositInfo { deposit: (pre_fund.as_u128() * 3) / 2, ..Default::default() },
+        );
+        for _ in 0..3 {
+            mock.push(Bytes::from(vec![0x60, 0x00])).unwrap();
+        }
+
+        assert!(Paymaster.check_user_operation(&uo, &mempool, &reputation, &helper).await.is_ok());
+        assert!(Paymaster
+            .check_user_operation(&uo_other, &mempool, &reputation, &helper)
+            .await
+            .is_ok());
+
+        mempool.add(uo.clone()).unwrap();
+        mempool.set_pre_fund(&uo.hash, pre_fund);
+        assert_eq!(mempool.get_pre_fund_by_paymaster(&paymaster), pre_fund);
+        assert!(matches!(
+            Paymaster.check_user_operation(&uo_other, &mempool, &reputation, &helper).await,
+            Err(SanityError::Paymaster { .. })
+        ));
+
+        // the committed prefund is released once the user operation is removed (or included)
+        mempool.remove(&uo.hash).unwrap();
+        assert_eq!(mempool.get_pre_fund_by_paymaster(&paymaster), U256::zero());
+        assert_eq!(mempool.get_pre_fund(&uo.hash), None);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SanityCheck, SanityHelper},
+    Reputation, ReputationError, SanityError,
+};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use silius_primitives::{
+    constants::validation::entities::PAYMASTER, get_address, reputation::StakeInfo, UserOperation,
+};
+
+#[derive(Clone)]
+pub struct PaymasterLimit {
+    /// Maximum number of pending user operations in the mempool sponsored by the same unstaked
+    /// paymaster
+    pub max_uos_per_paymaster: usize,
+    /// Maximum number of pending user operations in the mempool sponsored by the same staked
+    /// paymaster (None means unlimited)
+    pub max_uos_per_staked_paymaster: Option<usize>,
+}
+
+impl PaymasterLimit {
+    /// Counts the pending user operations sponsored by the paymaster, without the user operation
+    /// which the given user operation replaces.
+    ///
+    /// # Arguments
+    /// `uo` - The user operation to be checked.
+    /// `paymaster` - The address of the paymaster.
+    /// `mempool` - The [Mempool](Mempool) with the pending user operations.
+    ///
+    /// # Returns
+    /// The number of the pending user operations sponsored by the paymaster.
+    fn count(uo: &UserOperation, paymaster: &Address, mempool: &Mempool) -> usize {
+        let count = mempool.get_number_by_entity(paymaster);
+        match mempool.get_prev_by_sender(uo) {
+            Some(uo_prev) if get_address(&uo_prev.paymaster_and_data) == Some(*paymaster) => {
+                count.saturating_sub(1)
+            }
+            _ => count,
+        }
+    }
+
+    /// Checks whether the paymaster is staked.
+    async fn is_staked<M: Middleware>(
+        &self,
+        paymaster: &Address,
+        reputation: &Reputation,
+        helper: &SanityHelper<'_, M>,
+    ) -> Result<bool, SanityError> {
+        let info = helper.get_deposit_info(paymaster).await?;
+        let stake = StakeInfo {
+            address: *paymaster,
+            stake: U256::from(info.stake),
+            unstake_delay: U256::from(info.unstake_delay_sec),
+        };
+
+        Ok(reputation
+            .verify_stake(
+                PAYMASTER,
+                Some(stake),
+                helper.val_config.min_stake,
+                helper.val_config.min_unstake_delay,
+            )
+            .is_ok())
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SanityCheck<M> for PaymasterLimit {
+    /// The method implementation that limits the number of pending user operations sponsored by
+    /// the same paymaster (a staked paymaster may have a higher or no limit).
+    ///
+    /// # Arguments
+    /// `uo` - The user operation to be checked.
+    /// `mempool` - The [Mempool](Mempool) with the pending user operations.
+    /// `reputation` - The [Reputation](Reputation) used to verify the stake of the paymaster.
+    /// `helper` - The [sanity check helper](SanityHelper) that contains the necessary data to
+    /// perform the sanity check.
+    ///
+    /// # Returns
+    /// None if the sanity check is successful, otherwise a [SanityError] is returned.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        helper: &SanityHelper<M>,
+    ) -> Result<(), SanityError> {
+        let paymaster = match uo.get_entities().2 {
+            Some(paymaster) => paymaster,
+            None => return Ok(()),
+        };
+
+        let count = Self::count(uo, &paymaster, mempool);
+        if count < self.max_uos_per_paymaster {
+            return Ok(());
+        }
+
+        // the stake is only fetched once the paymaster reaches the limit of unstaked paymasters
+        let max_uos = if self.is_staked(&paymaster, reputation, helper).await? {
+            match self.max_uos_per_staked_paymaster {
+                Some(max_uos) => max_uos,
+                None => return Ok(()),
+            }
+        } else {
+            self.max_uos_per_paymaster
+        };
+
+        if count >= max_uos {
+            return Err(ReputationError::ThrottledEntity {
+                entity: PAYMASTER.into(),
+                address: paymaster,
+                reason: format!(
+                    "{count} pending user operations sponsored by the paymaster, at most {max_uos} allowed"
+                ),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::deposit_cache::DepositInfoCache,
+    };
+    use alloy_chains::Chain;
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::Bytes,
+    };
+    use silius_contracts::{entry_point::DepositInfo, EntryPoint};
+    use silius_primitives::{
+        constants::validation::reputation::MIN_UNSTAKE_DELAY, UserOperationSigned,
+    };
+    use std::{sync::Arc, time::Duration};
+
+    fn user_operation(paymaster: &Address) -> UserOperation {
+        let uo = UserOperationSigned::default()
+            .sender(Address::random())
+            .paymaster_and_data(Bytes::from(paymaster.as_bytes().to_vec()));
+        UserOperation::from_user_operation_signed(uo.hash(&Address::random(), 5), uo)
+    }
+
+    #[tokio::test]
+    async fn paymaster_limit_boundary() {
+        let (mut mempool, reputation) = (memory_mempool(), memory_reputation());
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::from_secs(60));
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+        let check = PaymasterLimit { max_uos_per_paymaster: 2, max_uos_per_staked_paymaster: None };
+
+        let (paymaster, paymaster_staked) = (Address::random(), Address::random());
+        deposit_cache.insert(&entry_point.address(), &paymaster, DepositInfo::default());
+        deposit_cache.insert(
+            &entry_point.address(),
+            &paymaster_staked,
+            DepositInfo {
+                staked: true,
+                stake: 1,
+                unstake_delay_sec: MIN_UNSTAKE_DELAY as u32,
+                ..Default::default()
+            },
+        );
+
+        for paymaster in [paymaster, paymaster_staked] {
+            for _ in 0..2 {
+                let uo = user_operation(&paymaster);
+                assert!(check
+                    .check_user_operation(&uo, &mempool, &reputation, &helper)
+                    .await
+                    .is_ok());
+                mempool.add(uo).unwrap();
+            }
+        }
+
+        // the unstaked paymaster reached the limit
+        assert!(matches!(
+            check
+                .check_user_operation(&user_operation(&paymaster), &mempool, &reputation, &helper)
+                .await,
+            Err(SanityError::Reputation(ReputationError::ThrottledEntity { address, .. }))
+                if address == paymaster
+        ));
+
+        // the staked paymaster is unlimited or limited by its own cap
+        let uo = user_operation(&paymaster_staked);
+        assert!(check.check_user_operation(&uo, &mempool, &reputation, &helper).await.is_ok());
+        let check = PaymasterLimit { max_uos_per_staked_paymaster: Some(3), ..check };
+        assert!(check.check_user_operation(&uo, &mempool, &reputation, &helper).await.is_ok());
+        mempool.add(uo).unwrap();
+        assert!(check
+            .check_user_operation(
+                &user_operation(&paymaster_staked),
+                &mempool,
+                &reputation,
+                &helper
+            )
+            .await
+            .is_err());
+    }
+}
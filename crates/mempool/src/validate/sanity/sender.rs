@@ -4,11 +4,31 @@ use crate::{
     validate::{SanityCheck, SanityHelper},
     Reputation, SanityError,
 };
-use ethers::providers::Middleware;
-use silius_primitives::{constants::mempool::GAS_INCREASE_PERC, UserOperation};
+use ethers::{providers::Middleware, types::U256};
+use silius_primitives::UserOperation;
 
 #[derive(Clone)]
-pub struct Sender;
+pub struct Sender {
+    /// Gas increase percentage required to replace a user operation of the same sender and nonce
+    pub gas_increase_perc: U256,
+}
+
+impl Sender {
+    /// Checks whether the user operation pays enough to replace the previous user operation of the
+    /// same sender and nonce.
+    ///
+    /// # Arguments
+    /// `uo` - The new [UserOperation](UserOperation).
+    /// `uo_prev` - The [UserOperation](UserOperation) in the mempool.
+    ///
+    /// # Returns
+    /// true if both fees are increased by at least the gas increase percentage.
+    fn is_valid_replacement(&self, uo: &UserOperation, uo_prev: &UserOperation) -> bool {
+        uo.max_fee_per_gas >= calculate_valid_gas(uo_prev.max_fee_per_gas, self.gas_increase_perc) &&
+            uo.max_priority_fee_per_gas >=
+                calculate_valid_gas(uo_prev.max_priority_fee_per_gas, self.gas_increase_perc)
+    }
+}
 
 #[async_trait::async_trait]
 impl<M: Middleware> SanityCheck<M> for Sender {
@@ -62,14 +82,7 @@ impl<M: Middleware> SanityCheck<M> for Sender {
         }
 
         if let Some(uo_prev) = uo_prev {
-            if uo.max_fee_per_gas <
-                calculate_valid_gas(uo_prev.max_fee_per_gas, GAS_INCREASE_PERC.into()) ||
-                uo.max_priority_fee_per_gas <
-                    calculate_valid_gas(
-                        uo_prev.max_priority_fee_per_gas,
-                        GAS_INCREASE_PERC.into(),
-                    )
-            {
+            if !self.is_valid_replacement(uo, &uo_prev) {
                 return Err(SanityError::Sender {
                     inner: format!(
                         "{0} couldn't replace user operation (gas increase too low)",
@@ -82,3 +95,32 @@ impl<M: Middleware> SanityCheck<M> for Sender {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use silius_primitives::{constants::mempool::GAS_INCREASE_PERC, UserOperationSigned};
+
+    fn uo(max_fee_per_gas: u64, max_priority_fee_per_gas: u64) -> UserOperation {
+        UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default()
+                .max_fee_per_gas(max_fee_per_gas.into())
+                .max_priority_fee_per_gas(max_priority_fee_per_gas.into()),
+        )
+    }
+
+    #[test]
+    fn replacement_gas_increase() {
+        let sender = Sender { gas_increase_perc: GAS_INCREASE_PERC.into() };
+        let uo_prev = uo(100, 10);
+
+        // exactly at the threshold
+        assert!(sender.is_valid_replacement(&uo(110, 11), &uo_prev));
+        // below the threshold
+        assert!(!sender.is_valid_replacement(&uo(109, 11), &uo_prev));
+        assert!(!sender.is_valid_replacement(&uo(110, 10), &uo_prev));
+        // above the threshold
+        assert!(sender.is_valid_replacement(&uo(200, 20), &uo_prev));
+    }
+}
@@ -13,15 +13,53 @@ use silius_primitives::{
         entities::{FACTORY, PAYMASTER, SENDER},
         reputation::{
             INCLUSION_RATE_FACTOR, SAME_SENDER_MEMPOOL_COUNT, SAME_UNSTAKED_ENTITY_MEMPOOL_COUNT,
+            THROTTLED_ENTITY_MEMPOOL_COUNT,
         },
     },
-    reputation::{ReputationEntry, StakeInfo},
+    reputation::{ReputationEntry, StakeInfo, Status},
     UserOperation,
 };
 use std::cmp;
 
+/// Minimum stake and unstake delay required for an entity to be considered staked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StakeThreshold {
+    pub min_stake: U256,
+    pub min_unstake_delay: U256,
+}
+
+/// Stake thresholds per entity type. Entity types without a threshold use the uniform minimum of
+/// the validation config (or the reputation), as defined by the spec.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StakeThresholds {
+    pub sender: Option<StakeThreshold>,
+    pub factory: Option<StakeThreshold>,
+    pub paymaster: Option<StakeThreshold>,
+}
+
+impl StakeThresholds {
+    /// Gets the stake threshold of the entity type (if configured).
+    pub fn get(&self, entity: &str) -> Option<StakeThreshold> {
+        match entity {
+            SENDER => self.sender,
+            FACTORY => self.factory,
+            PAYMASTER => self.paymaster,
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone)]
-pub struct UnstakedEntities;
+pub struct UnstakedEntities {
+    /// Denominator of the minimal inclusion rate of the unstaked entities
+    pub min_inclusion_rate_denominator: u64,
+    /// Slack (number of not included user operations) before the unstaked entity is throttled
+    pub throttling_slack: u64,
+    /// Slack (number of not included user operations) before the unstaked entity is banned
+    pub ban_slack: u64,
+    /// Stake thresholds per entity type (the uniform minimum by default)
+    pub stake_thresholds: StakeThresholds,
+}
 
 impl UnstakedEntities {
     /// Gets the deposit info for entity.
@@ -30,7 +68,7 @@ impl UnstakedEntities {
         addr: &Address,
         helper: &SanityHelper<'a, M>,
     ) -> Result<StakeInfo, SanityError> {
-        let info = helper.entry_point.get_deposit_info(addr).await?;
+        let info = helper.get_deposit_info(addr).await?;
 
         Ok(StakeInfo {
             address: *addr,
@@ -39,6 +77,23 @@ impl UnstakedEntities {
         })
     }
 
+    /// Checks whether the entity is staked, with the stake threshold of its entity type if one is
+    /// configured.
+    fn is_staked<M: Middleware>(
+        &self,
+        entity: &str,
+        stake: StakeInfo,
+        reputation: &Reputation,
+        helper: &SanityHelper<M>,
+    ) -> bool {
+        let (min_stake, min_unstake_delay) = match self.stake_thresholds.get(entity) {
+            Some(threshold) => (Some(threshold.min_stake), Some(threshold.min_unstake_delay)),
+            None => (helper.val_config.min_stake, helper.val_config.min_unstake_delay),
+        };
+
+        reputation.verify_stake(entity, Some(stake), min_stake, min_unstake_delay).is_ok()
+    }
+
     /// Gets the reputation entry for entity.
     fn get_entity<M: Middleware>(
         &self,
@@ -49,6 +104,46 @@ impl UnstakedEntities {
         reputation.get(addr).map_err(|e| e.into())
     }
 
+    /// Calculates the status of the unstaked entity with the configured thresholds.
+    fn calculate_status(&self, entity: &ReputationEntry) -> Status {
+        Reputation::calculate_status(
+            entity,
+            self.min_inclusion_rate_denominator,
+            self.throttling_slack,
+            self.ban_slack,
+        )
+    }
+
+    /// Checks whether the unstaked entity is banned or throttled (with the configured
+    /// thresholds).
+    fn check_status(
+        &self,
+        entity: &str,
+        addr: &Address,
+        entry: &ReputationEntry,
+        mempool: &Mempool,
+    ) -> Result<(), SanityError> {
+        match self.calculate_status(entry) {
+            Status::BANNED => {
+                Err(ReputationError::BannedEntity { entity: entity.into(), address: *addr }.into())
+            }
+            Status::THROTTLED
+                if mempool.get_number_by_entity(addr) >= THROTTLED_ENTITY_MEMPOOL_COUNT =>
+            {
+                Err(ReputationError::ThrottledEntity {
+                    entity: entity.into(),
+                    address: *addr,
+                    reason: format!(
+                        "{} user operations in the mempool, at most {THROTTLED_ENTITY_MEMPOOL_COUNT} allowed",
+                        mempool.get_number_by_entity(addr)
+                    ),
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Calculates allowed number of user operations
     fn calculate_allowed_user_operations(entity: ReputationEntry) -> u64 {
         if entity.uo_seen == 0 {
@@ -99,14 +194,7 @@ impl<M: Middleware> SanityCheck<M> for UnstakedEntities {
         // [UREP-010] - UserOperation with unstaked sender are only allowed up to
         // SAME_SENDER_MEMPOOL_COUNT times in the mempool
         let sender_stake = self.get_stake(&sender, helper).await?;
-        if reputation
-            .verify_stake(
-                SENDER,
-                Some(sender_stake),
-                helper.val_config.min_stake,
-                helper.val_config.min_unstake_delay,
-            )
-            .is_err() &&
+        if !self.is_staked(SENDER, sender_stake, reputation, helper) &&
             mempool.get_number_by_sender(&uo.sender) >= SAME_SENDER_MEMPOOL_COUNT
         {
             return Err(ReputationError::UnstakedEntity {
@@ -130,17 +218,10 @@ impl<M: Middleware> SanityCheck<M> for UnstakedEntities {
             }
 
             let factory_stake = self.get_stake(&factory, helper).await?;
-            if reputation
-                .verify_stake(
-                    FACTORY,
-                    Some(factory_stake),
-                    helper.val_config.min_stake,
-                    helper.val_config.min_unstake_delay,
-                )
-                .is_err()
-            {
+            if !self.is_staked(FACTORY, factory_stake, reputation, helper) {
                 // [UREP-020] - for other entities
                 let entity = self.get_entity(&factory, helper, reputation)?;
+                self.check_status(FACTORY, &factory, &entity, mempool)?;
                 let uos_allowed = Self::calculate_allowed_user_operations(entity);
                 if mempool.get_number_by_entity(&factory) as u64 >= uos_allowed {
                     return Err(ReputationError::UnstakedEntity {
@@ -166,17 +247,10 @@ impl<M: Middleware> SanityCheck<M> for UnstakedEntities {
             }
 
             let paymaster_stake = self.get_stake(&paymaster, helper).await?;
-            if reputation
-                .verify_stake(
-                    PAYMASTER,
-                    Some(paymaster_stake),
-                    helper.val_config.min_stake,
-                    helper.val_config.min_unstake_delay,
-                )
-                .is_err()
-            {
+            if !self.is_staked(PAYMASTER, paymaster_stake, reputation, helper) {
                 // [UREP-020] - for other entities
                 let entity = self.get_entity(&paymaster, helper, reputation)?;
+                self.check_status(PAYMASTER, &paymaster, &entity, mempool)?;
                 let uos_allowed = Self::calculate_allowed_user_operations(entity);
                 if mempool.get_number_by_entity(&paymaster) as u64 >= uos_allowed {
                     return Err(ReputationError::UnstakedEntity {
@@ -191,3 +265,139 @@ impl<M: Middleware> SanityCheck<M> for UnstakedEntities {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::memory_reputation_with_unstake_delay, validate::deposit_cache::DepositInfoCache,
+    };
+    use alloy_chains::Chain;
+    use ethers::providers::{MockProvider, Provider};
+    use silius_contracts::EntryPoint;
+    use silius_primitives::constants::validation::reputation::{
+        BAN_SLACK, MIN_INCLUSION_RATE_DENOMINATOR, MIN_UNSTAKE_DELAY, THROTTLING_SLACK,
+    };
+    use std::{sync::Arc, time::Duration};
+
+    fn unstaked_entities(stake_thresholds: StakeThresholds) -> UnstakedEntities {
+        UnstakedEntities {
+            min_inclusion_rate_denominator: MIN_INCLUSION_RATE_DENOMINATOR,
+            throttling_slack: THROTTLING_SLACK,
+            ban_slack: BAN_SLACK,
+            stake_thresholds,
+        }
+    }
+
+    fn stake(stake: u64, unstake_delay: u64) -> StakeInfo {
+        StakeInfo {
+            address: Address::random(),
+            stake: U256::from(stake),
+            unstake_delay: U256::from(unstake_delay),
+        }
+    }
+
+    /// Checks whether the entity is staked with the default validation config (the minimum
+    /// unstake delay of the reputation is MIN_UNSTAKE_DELAY).
+    fn is_staked(check: &UnstakedEntities, entity: &str, stake: StakeInfo) -> bool {
+        let reputation = memory_reputation_with_unstake_delay(U256::from(MIN_UNSTAKE_DELAY));
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::ZERO);
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+        check.is_staked(entity, stake, &reputation, &helper)
+    }
+
+    #[test]
+    fn stake_thresholds_per_entity() {
+        // uniform minimum by default
+        let check = unstaked_entities(Default::default());
+        assert!(is_staked(&check, FACTORY, stake(1_000, MIN_UNSTAKE_DELAY)));
+        assert!(is_staked(&check, PAYMASTER, stake(1_000, MIN_UNSTAKE_DELAY)));
+
+        let check = unstaked_entities(StakeThresholds {
+            factory: Some(StakeThreshold {
+                min_stake: U256::from(2_000),
+                min_unstake_delay: U256::from(MIN_UNSTAKE_DELAY),
+            }),
+            paymaster: Some(StakeThreshold {
+                min_stake: U256::from(500),
+                min_unstake_delay: U256::from(MIN_UNSTAKE_DELAY),
+            }),
+            ..Default::default()
+        });
+        assert!(!is_staked(&check, FACTORY, stake(1_000, MIN_UNSTAKE_DELAY)));
+        assert!(is_staked(&check, PAYMASTER, stake(1_000, MIN_UNSTAKE_DELAY)));
+        assert!(is_staked(&check, SENDER, stake(1_000, MIN_UNSTAKE_DELAY)));
+    }
+
+    #[test]
+    fn unstake_delay_too_short() {
+        let check = unstaked_entities(Default::default());
+
+        // an entity staked with a too short unstake delay is treated as unstaked
+        assert!(is_staked(&check, PAYMASTER, stake(1_000, MIN_UNSTAKE_DELAY)));
+        assert!(!is_staked(&check, PAYMASTER, stake(1_000, MIN_UNSTAKE_DELAY - 1)));
+        assert!(matches!(
+            memory_reputation_with_unstake_delay(U256::from(MIN_UNSTAKE_DELAY)).verify_stake(
+                PAYMASTER,
+                Some(stake(1_000, 1)),
+                None,
+                None
+            ),
+            Err(ReputationError::UnstakeDelayTooLow { min_unstake_delay, .. })
+                if min_unstake_delay == U256::from(MIN_UNSTAKE_DELAY)
+        ));
+    }
+
+    #[test]
+    fn unstaked_entity_status() {
+        let check = UnstakedEntities {
+            throttling_slack: 1,
+            ban_slack: 2,
+            ..unstaked_entities(Default::default())
+        };
+        let entry = |uo_seen: u64| ReputationEntry {
+            address: Address::random(),
+            uo_seen,
+            uo_included: 0,
+            status: Status::OK.into(),
+        };
+
+        // the configured slacks apply instead of the ones of the reputation
+        assert_eq!(check.calculate_status(&entry(MIN_INCLUSION_RATE_DENOMINATOR)), Status::OK);
+        assert_eq!(
+            check.calculate_status(&entry(2 * MIN_INCLUSION_RATE_DENOMINATOR)),
+            Status::THROTTLED
+        );
+        assert_eq!(
+            check.calculate_status(&entry(3 * MIN_INCLUSION_RATE_DENOMINATOR)),
+            Status::BANNED
+        );
+    }
+
+    #[test]
+    fn unstaked_entity_status_zero_denominator() {
+        let check = UnstakedEntities {
+            min_inclusion_rate_denominator: 0,
+            throttling_slack: 1,
+            ban_slack: 2,
+            ..unstaked_entities(Default::default())
+        };
+        let entry = |uo_seen: u64| ReputationEntry {
+            address: Address::random(),
+            uo_seen,
+            uo_included: 0,
+            status: Status::OK.into(),
+        };
+
+        assert_eq!(check.calculate_status(&entry(1)), Status::OK);
+        assert_eq!(check.calculate_status(&entry(2)), Status::THROTTLED);
+        assert_eq!(check.calculate_status(&entry(3)), Status::BANNED);
+    }
+}
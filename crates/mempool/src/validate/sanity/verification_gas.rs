@@ -2,54 +2,242 @@ use crate::{
     mempool::Mempool,
     utils::div_ceil,
     validate::{SanityCheck, SanityHelper},
-    Overhead, Reputation, SanityError,
+    L1Gas, Overhead, Reputation, SanityError,
 };
+use alloy_chains::Chain;
 use ethers::{providers::Middleware, types::U256};
-use silius_primitives::UserOperation;
+use silius_primitives::{
+    constants::validation::PRE_VERIFICATION_GAS_TOLERANCE_PERC, UserOperation,
+};
+use std::collections::HashMap;
 
 #[derive(Clone)]
 pub struct VerificationGas {
+    /// Maximum verification gas limit of a user operation (used on the chains without their own
+    /// limit)
     pub max_verification_gas: U256,
+    /// Maximum verification gas limit of a user operation per chain ID (e.g. for chains with a
+    /// lower block gas limit)
+    pub max_verification_gas_by_chain: HashMap<u64, U256>,
+    /// Percentage by which the pre-verification gas may be lower than the calculated one (absorbs
+    /// small differences between the formulas of the clients and the bundler)
+    pub pre_verification_gas_tolerance_perc: U256,
 }
 
-#[async_trait::async_trait]
-impl<M: Middleware> SanityCheck<M> for VerificationGas {
-    /// The method implementation that performs the check on verification gas.
+impl VerificationGas {
+    /// Creates a new [VerificationGas] check with the default
+    /// [tolerance](PRE_VERIFICATION_GAS_TOLERANCE_PERC) of the pre-verification gas.
+    pub fn new(max_verification_gas: U256) -> Self {
+        Self {
+            max_verification_gas,
+            max_verification_gas_by_chain: HashMap::new(),
+            pre_verification_gas_tolerance_perc: U256::from(PRE_VERIFICATION_GAS_TOLERANCE_PERC),
+        }
+    }
+
+    /// Sets the maximum verification gas limit of a user operation on the chain (overrides the
+    /// default maximum).
+    pub fn with_max_verification_gas_for(
+        mut self,
+        chain: Chain,
+        max_verification_gas: U256,
+    ) -> Self {
+        self.max_verification_gas_by_chain.insert(chain.id(), max_verification_gas);
+        self
+    }
+
+    /// Gets the maximum verification gas limit of a user operation on the chain.
+    ///
+    /// # Arguments
+    /// `chain` - The [Chain](Chain) the user operation is submitted to.
+    ///
+    /// # Returns
+    /// The maximum of the chain, otherwise the default maximum.
+    pub fn max_verification_gas_for(&self, chain: &Chain) -> U256 {
+        self.max_verification_gas_by_chain
+            .get(&chain.id())
+            .cloned()
+            .unwrap_or(self.max_verification_gas)
+    }
+
+    /// Checks the verification gas limit against the maximum of the chain and the
+    /// pre-verification gas against the calldata cost (and the L1 gas) of the user operation.
     ///
     /// # Arguments
     /// `uo` - The [UserOperation](UserOperation) to be checked.
-    /// `helper` - The [sanity check helper](SanityHelper) that contains the necessary data to
-    /// perform the sanity check.
+    /// `chain` - The [Chain](Chain) the user operation is submitted to.
+    /// `overhead` - The [Overhead](Overhead) used to calculate the pre-verification gas.
+    /// `l1_gas` - The L1 gas charged in L2 gas (see [L1Gas](L1Gas)), zero on the other chains.
     ///
     /// # Returns
-    /// Nothing if the sanity check is successful, otherwise a [SanityError](SanityError)
-    /// is returned.
-    async fn check_user_operation(
+    /// Nothing if the gas limits are valid, otherwise a [SanityError](SanityError) is returned.
+    pub fn check_gas_limits(
         &self,
         uo: &UserOperation,
-        _mempool: &Mempool,
-        _reputation: &Reputation,
-        _helper: &SanityHelper<M>,
+        chain: &Chain,
+        overhead: &Overhead,
+        l1_gas: U256,
     ) -> Result<(), SanityError> {
-        if uo.verification_gas_limit > self.max_verification_gas {
+        let max_verification_gas = self.max_verification_gas_for(chain);
+        if uo.verification_gas_limit > max_verification_gas {
             return Err(SanityError::VerificationGasLimitTooHigh {
                 verification_gas_limit: uo.verification_gas_limit,
-                verification_gas_limit_expected: self.max_verification_gas,
+                verification_gas_limit_expected: max_verification_gas,
             });
         }
 
-        // calculate the pvg and allow 10 % deviation
+        // calculate the pvg and allow the configured deviation
+        let pre_gas_calculated = overhead.calculate_pre_verification_gas(uo).saturating_add(l1_gas);
         let pre_gas = div_ceil(
-            Overhead::default().calculate_pre_verification_gas(uo).saturating_mul(U256::from(90)),
+            pre_gas_calculated.saturating_mul(
+                U256::from(100).saturating_sub(self.pre_verification_gas_tolerance_perc),
+            ),
             U256::from(100),
         );
         if uo.pre_verification_gas < pre_gas {
             return Err(SanityError::PreVerificationGasTooLow {
                 pre_verification_gas: uo.pre_verification_gas,
                 pre_verification_gas_expected: pre_gas,
+                pre_verification_gas_calculated: pre_gas_calculated,
             });
         }
 
         Ok(())
     }
 }
+
+#[async_trait::async_trait]
+impl<M: Middleware> SanityCheck<M> for VerificationGas {
+    /// The method implementation that performs the check on verification gas.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `helper` - The [sanity check helper](SanityHelper) that contains the necessary data to
+    /// perform the sanity check.
+    ///
+    /// # Returns
+    /// Nothing if the sanity check is successful, otherwise a [SanityError](SanityError)
+    /// is returned.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &SanityHelper<M>,
+    ) -> Result<(), SanityError> {
+        let l1_gas = match L1Gas::from_chain(&helper.chain) {
+            Some(l1_gas) => l1_gas
+                .get_l1_gas(helper.entry_point.eth_client(), helper.entry_point.address(), uo)
+                .await
+                .map_err(|e| SanityError::Provider { inner: format!("{e:?}") })?,
+            None => U256::zero(),
+        };
+        self.check_gas_limits(uo, &helper.chain, &Overhead::default(), l1_gas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Bytes;
+    use silius_primitives::UserOperationSigned;
+
+    fn user_operation(pre_verification_gas: U256) -> UserOperation {
+        UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default()
+                .call_data(Bytes::from(vec![1; 100]))
+                .pre_verification_gas(pre_verification_gas),
+        )
+    }
+
+    #[test]
+    fn pre_verification_gas_tolerance() {
+        // same cost of the zero and non-zero bytes, so the calculated pre-verification gas doesn't
+        // depend on the pre-verification gas of the user operation
+        let overhead = Overhead { zero_byte: U256::from(16), ..Default::default() };
+        let calculated = overhead.calculate_pre_verification_gas(&user_operation(U256::zero()));
+        let check = VerificationGas {
+            pre_verification_gas_tolerance_perc: U256::from(20),
+            ..VerificationGas::new(U256::MAX)
+        };
+
+        // at most 20 % below the calculated pre-verification gas
+        let min = div_ceil(calculated * 80, U256::from(100));
+        assert!(check
+            .check_gas_limits(&user_operation(calculated), &Chain::dev(), &overhead, U256::zero())
+            .is_ok());
+        assert!(check
+            .check_gas_limits(&user_operation(min), &Chain::dev(), &overhead, U256::zero())
+            .is_ok());
+        assert!(matches!(
+            check.check_gas_limits(&user_operation(min - 1), &Chain::dev(), &overhead, U256::zero()),
+            Err(SanityError::PreVerificationGasTooLow {
+                pre_verification_gas,
+                pre_verification_gas_expected,
+                pre_verification_gas_calculated,
+            }) if pre_verification_gas == min - 1 &&
+                pre_verification_gas_expected == min &&
+                pre_verification_gas_calculated == calculated
+        ));
+
+        // no tolerance
+        let check = VerificationGas { pre_verification_gas_tolerance_perc: U256::zero(), ..check };
+        assert!(check
+            .check_gas_limits(&user_operation(calculated), &Chain::dev(), &overhead, U256::zero())
+            .is_ok());
+        assert!(check
+            .check_gas_limits(
+                &user_operation(calculated - 1),
+                &Chain::dev(),
+                &overhead,
+                U256::zero()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn pre_verification_gas_l1_gas() {
+        let overhead = Overhead { zero_byte: U256::from(16), ..Default::default() };
+        let calculated = overhead.calculate_pre_verification_gas(&user_operation(U256::zero()));
+        let check = VerificationGas {
+            pre_verification_gas_tolerance_perc: U256::zero(),
+            ..VerificationGas::new(U256::MAX)
+        };
+        let l1_gas = U256::from(25_000);
+
+        // the L1 gas is added to the calldata cost
+        assert!(check
+            .check_gas_limits(&user_operation(calculated), &Chain::dev(), &overhead, l1_gas)
+            .is_err());
+        assert!(check
+            .check_gas_limits(
+                &user_operation(calculated + l1_gas),
+                &Chain::dev(),
+                &overhead,
+                l1_gas
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn max_verification_gas_per_chain() {
+        let check = VerificationGas::new(U256::from(5_000_000))
+            .with_max_verification_gas_for(Chain::optimism_mainnet(), U256::from(1_000_000));
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default()
+                .verification_gas_limit(U256::from(2_000_000))
+                .pre_verification_gas(U256::from(100_000)),
+        );
+        let overhead = Overhead::default();
+
+        // the default maximum applies to the chains without their own limit
+        assert!(check.check_gas_limits(&uo, &Chain::mainnet(), &overhead, U256::zero()).is_ok());
+        assert!(matches!(
+            check.check_gas_limits(&uo, &Chain::optimism_mainnet(), &overhead, U256::zero()),
+            Err(SanityError::VerificationGasLimitTooHigh { verification_gas_limit_expected, .. })
+                if verification_gas_limit_expected == U256::from(1_000_000)
+        ));
+    }
+}
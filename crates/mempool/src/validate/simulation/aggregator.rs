@@ -0,0 +1,103 @@
+use crate::{
+    validate::{utils::extract_aggregator, SimulationCheck, SimulationHelper},
+    SimulationError,
+};
+use ethers::types::Address;
+use silius_primitives::UserOperation;
+use std::collections::HashSet;
+
+/// Rejects the user operations whose signature aggregator is not on the allowlist.
+#[derive(Clone, Default)]
+pub struct Aggregator {
+    /// Signature aggregators whose user operations are accepted (`None` accepts any aggregator)
+    pub allowlist: Option<HashSet<Address>>,
+}
+
+impl SimulationCheck for Aggregator {
+    /// The method implementation that checks whether the signature aggregator of the user
+    /// operation is on the allowlist.
+    ///
+    /// # Arguments
+    /// `_uo` - Not used in this check
+    /// `helper` - The [SimulationHelper]
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    fn check_user_operation(
+        &self,
+        _uo: &UserOperation,
+        helper: &mut SimulationHelper,
+    ) -> Result<(), SimulationError> {
+        if let Some(aggregator) = extract_aggregator(helper.simulate_validation_result) {
+            if self.allowlist.as_ref().is_some_and(|allowlist| !allowlist.contains(&aggregator)) {
+                return Err(SimulationError::Aggregator { aggregator });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use silius_contracts::entry_point::{
+        SimulateValidationResult, ValidationResult, ValidationResultWithAggregation,
+    };
+
+    fn check(
+        allowlist: Option<Vec<Address>>,
+        aggregator: Option<Address>,
+    ) -> Result<(), SimulationError> {
+        let sim_res = match aggregator {
+            Some(aggregator) => {
+                let mut res = ValidationResultWithAggregation::default();
+                res.aggregator_info.0 = aggregator;
+                SimulateValidationResult::ValidationResultWithAggregation(res)
+            }
+            None => SimulateValidationResult::ValidationResult(ValidationResult::default()),
+        };
+        let mut helper = SimulationHelper {
+            simulate_validation_result: &sim_res,
+            val_config: Default::default(),
+            valid_after: None,
+            sender_funds: None,
+        };
+
+        let uo = UserOperation::from_user_operation_signed(Default::default(), Default::default());
+        Aggregator { allowlist: allowlist.map(|allowlist| allowlist.into_iter().collect()) }
+            .check_user_operation(&uo, &mut helper)
+    }
+
+    #[test]
+    fn aggregator_allowed() {
+        let aggregator = Address::random();
+        assert!(check(Some(vec![Address::random(), aggregator]), Some(aggregator)).is_ok());
+    }
+
+    #[test]
+    fn aggregator_not_allowed() {
+        let aggregator = Address::random();
+        assert!(matches!(
+            check(Some(vec![Address::random()]), Some(aggregator)),
+            Err(SimulationError::Aggregator { aggregator: a }) if a == aggregator
+        ));
+    }
+
+    #[test]
+    fn aggregator_empty_allowlist() {
+        assert!(matches!(
+            check(Some(vec![]), Some(Address::random())),
+            Err(SimulationError::Aggregator { .. })
+        ));
+        // user operations without an aggregator are not affected
+        assert!(check(Some(vec![]), None).is_ok());
+    }
+
+    #[test]
+    fn aggregator_no_allowlist() {
+        assert!(check(None, Some(Address::random())).is_ok());
+        assert!(check(None, None).is_ok());
+        assert!(Aggregator::default().allowlist.is_none());
+    }
+}
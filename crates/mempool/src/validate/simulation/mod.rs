@@ -1,5 +1,10 @@
 //! `simulation` module performs checks against a user operation's signature and
 //! timestamp via a `eth_call` to the Ethereum execution client.
+pub mod aggregator;
+pub mod prefund;
+pub mod sig_failed;
 pub mod signature;
+pub mod signature_length;
 pub mod timestamp;
+pub mod valid_until_window;
 pub mod verification_extra_gas;
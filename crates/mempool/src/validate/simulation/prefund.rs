@@ -0,0 +1,79 @@
+use crate::{
+    validate::{utils::extract_pre_fund, SimulationCheck, SimulationHelper},
+    SimulationError,
+};
+use silius_primitives::UserOperation;
+
+#[derive(Clone)]
+pub struct Prefund;
+
+impl SimulationCheck for Prefund {
+    /// The method implementation that checks whether the deposit of the account in the entry
+    /// point plus its balance covers the prefund returned by the simulation. User operations with
+    /// a paymaster are skipped (the paymaster deposit is checked in the sanity checks).
+    ///
+    /// # Arguments
+    /// `_uo` - Not used in this check
+    /// `helper` - The [SimulationHelper]
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    fn check_user_operation(
+        &self,
+        _uo: &UserOperation,
+        helper: &mut SimulationHelper,
+    ) -> Result<(), SimulationError> {
+        if let Some(funds) = helper.sender_funds {
+            let pre_fund = extract_pre_fund(helper.simulate_validation_result);
+            if funds < pre_fund {
+                return Err(SimulationError::Prefund { pre_fund, funds });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn needs_sender_funds(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+    use silius_contracts::entry_point::{SimulateValidationResult, ValidationResult};
+
+    fn check(pre_fund: u64, sender_funds: Option<u64>) -> Result<(), SimulationError> {
+        let mut res = ValidationResult::default();
+        res.return_info.1 = pre_fund.into();
+        let sim_res = SimulateValidationResult::ValidationResult(res);
+        let mut helper = SimulationHelper {
+            simulate_validation_result: &sim_res,
+            val_config: Default::default(),
+            valid_after: None,
+            sender_funds: sender_funds.map(U256::from),
+        };
+
+        let uo = UserOperation::from_user_operation_signed(Default::default(), Default::default());
+        Prefund.check_user_operation(&uo, &mut helper)
+    }
+
+    #[test]
+    fn prefund_underfunded_account() {
+        assert!(matches!(
+            check(1_000, Some(999)),
+            Err(SimulationError::Prefund { pre_fund, funds })
+                if pre_fund == U256::from(1_000) && funds == U256::from(999)
+        ));
+    }
+
+    #[test]
+    fn prefund_covered() {
+        assert!(check(1_000, Some(1_000)).is_ok());
+        // user operations with a paymaster are not checked
+        assert!(check(1_000, None).is_ok());
+        assert!(Prefund.needs_sender_funds());
+        assert!((Prefund, Prefund).needs_sender_funds());
+    }
+}
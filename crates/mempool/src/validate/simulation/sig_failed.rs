@@ -0,0 +1,68 @@
+use crate::{
+    validate::{SimulationCheck, SimulationHelper},
+    SimulationError,
+};
+use silius_contracts::entry_point::SimulateValidationResult;
+use silius_primitives::UserOperation;
+
+#[derive(Clone)]
+pub struct SigFailed;
+
+impl SimulationCheck for SigFailed {
+    /// The method implementation that checks the `sigFailed` flag returned by the simulation, i.e.
+    /// whether the account (or the paymaster) rejected the signature of the user operation.
+    ///
+    /// # Arguments
+    /// `_uo` - Not used in this check
+    /// `helper` - The [SimulationHelper]
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    fn check_user_operation(
+        &self,
+        _uo: &UserOperation,
+        helper: &mut SimulationHelper,
+    ) -> Result<(), SimulationError> {
+        let sig_failed = match helper.simulate_validation_result {
+            SimulateValidationResult::ValidationResult(res) => res.return_info.2,
+            SimulateValidationResult::ValidationResultWithAggregation(res) => res.return_info.2,
+        };
+
+        if sig_failed {
+            return Err(SimulationError::SignatureFailed);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use silius_contracts::entry_point::ValidationResult;
+
+    fn check(sig_failed: bool) -> Result<(), SimulationError> {
+        let mut res = ValidationResult::default();
+        res.return_info.2 = sig_failed;
+        let sim_res = SimulateValidationResult::ValidationResult(res);
+        let mut helper = SimulationHelper {
+            simulate_validation_result: &sim_res,
+            val_config: Default::default(),
+            valid_after: None,
+            sender_funds: None,
+        };
+
+        let uo = UserOperation::from_user_operation_signed(Default::default(), Default::default());
+        SigFailed.check_user_operation(&uo, &mut helper)
+    }
+
+    #[test]
+    fn sig_failed_flag_set() {
+        assert!(matches!(check(true), Err(SimulationError::SignatureFailed)));
+    }
+
+    #[test]
+    fn sig_failed_flag_not_set() {
+        assert!(check(false).is_ok());
+    }
+}
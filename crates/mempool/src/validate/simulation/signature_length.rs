@@ -0,0 +1,110 @@
+use crate::{
+    validate::{SimulationCheck, SimulationHelper},
+    SimulationError,
+};
+use silius_contracts::entry_point::SimulateValidationResult;
+use silius_primitives::{
+    constants::validation::signature::{MAX_SIGNATURE_LENGTH, MIN_SIGNATURE_LENGTH},
+    UserOperation,
+};
+
+#[derive(Clone, Debug)]
+pub struct SignatureLength {
+    /// Minimum length (in bytes) of the signature
+    pub min_signature_length: usize,
+    /// Maximum length (in bytes) of the signature
+    pub max_signature_length: usize,
+}
+
+impl Default for SignatureLength {
+    fn default() -> Self {
+        Self {
+            min_signature_length: MIN_SIGNATURE_LENGTH,
+            max_signature_length: MAX_SIGNATURE_LENGTH,
+        }
+    }
+}
+
+impl SimulationCheck for SignatureLength {
+    /// The method implementation that checks that the length of the signature is within the
+    /// expected range. The length is checked only once the simulation accepted the signature (the
+    /// rejected signatures are reported by the [Signature](super::signature::Signature) check).
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to check
+    /// `helper` - The [SimulationHelper]
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        helper: &mut SimulationHelper,
+    ) -> Result<(), SimulationError> {
+        let sig_failed = match helper.simulate_validation_result {
+            SimulateValidationResult::ValidationResult(res) => res.return_info.2,
+            SimulateValidationResult::ValidationResultWithAggregation(res) => res.return_info.2,
+        };
+        if sig_failed {
+            return Ok(());
+        }
+
+        let length = uo.signature.len();
+        if length < self.min_signature_length || length > self.max_signature_length {
+            return Err(SimulationError::SignatureLength {
+                length,
+                min: self.min_signature_length,
+                max: self.max_signature_length,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Bytes;
+    use silius_contracts::entry_point::ValidationResult;
+    use silius_primitives::UserOperationSigned;
+
+    fn check(check: &SignatureLength, length: usize) -> Result<(), SimulationError> {
+        let sim_res = SimulateValidationResult::ValidationResult(ValidationResult::default());
+        let mut helper = SimulationHelper {
+            simulate_validation_result: &sim_res,
+            val_config: Default::default(),
+            valid_after: None,
+            sender_funds: None,
+        };
+
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().signature(Bytes::from(vec![1; length])),
+        );
+        check.check_user_operation(&uo, &mut helper)
+    }
+
+    #[test]
+    fn signature_length_too_short() {
+        assert!(check(&SignatureLength::default(), 65).is_ok());
+        assert!(matches!(
+            check(&SignatureLength::default(), 64),
+            Err(SimulationError::SignatureLength { length: 64, min: 65, max: 65 })
+        ));
+        assert!(check(&SignatureLength::default(), 0).is_err());
+    }
+
+    #[test]
+    fn signature_length_too_long() {
+        assert!(matches!(
+            check(&SignatureLength::default(), 66),
+            Err(SimulationError::SignatureLength { length: 66, min: 65, max: 65 })
+        ));
+
+        // e.g. signatures of smart contract accounts with multiple owners
+        let check_range = SignatureLength { min_signature_length: 65, max_signature_length: 130 };
+        assert!(check(&check_range, 130).is_ok());
+        assert!(check(&check_range, 131).is_err());
+    }
+}
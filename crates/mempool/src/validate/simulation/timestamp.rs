@@ -1,10 +1,11 @@
 use crate::{
-    validate::{utils::extract_timestamps, SimulationCheck, SimulationHelper},
+    validate::{
+        utils::{current_timestamp, extract_timestamps},
+        SimulationCheck, SimulationHelper,
+    },
     SimulationError,
 };
-use ethers::types::U256;
 use silius_primitives::{simulation::EXPIRATION_TIMESTAMP_DIFF, UserOperation};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Clone)]
 pub struct Timestamp;
@@ -25,12 +26,7 @@ impl SimulationCheck for Timestamp {
     ) -> Result<(), SimulationError> {
         let (valid_after, valid_until) = extract_timestamps(helper.simulate_validation_result);
 
-        let now = U256::from(
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .map_err(|err| SimulationError::Other { inner: err.to_string() })?
-                .as_secs(),
-        );
+        let now = current_timestamp()?;
 
         if valid_until < now {
             return Err(SimulationError::Timestamp { inner: "already expired".into() });
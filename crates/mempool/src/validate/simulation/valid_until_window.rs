@@ -0,0 +1,114 @@
+use crate::{
+    validate::{
+        utils::{current_timestamp, extract_timestamps},
+        SimulationCheck, SimulationHelper,
+    },
+    SimulationError,
+};
+use ethers::types::U256;
+use silius_primitives::UserOperation;
+
+/// `validUntil` of the user operations which don't expire (max uint48)
+const VALID_UNTIL_MAX: u64 = 0xffff_ffff_ffff;
+
+/// Rejects the user operations which are valid for too long (opt-in, see
+/// [new_canonical](crate::validate::validator::new_canonical)).
+#[derive(Clone)]
+pub struct ValidUntilWindow {
+    /// Maximum number of seconds the user operation may be valid for (from now)
+    pub max_valid_until_window: U256,
+}
+
+impl SimulationCheck for ValidUntilWindow {
+    /// The method implementation that checks that the user operation is not valid for too long.
+    /// Expiry of the user operation is checked by the [Timestamp](super::timestamp::Timestamp)
+    /// check. `validUntil` of 0 (no expiry) is treated as the maximum uint48 value, so such user
+    /// operations are rejected as well.
+    ///
+    /// # Arguments
+    /// `_uo` - Not used in this check
+    /// `helper` - The [SimulationHelper]
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    fn check_user_operation(
+        &self,
+        _uo: &UserOperation,
+        helper: &mut SimulationHelper,
+    ) -> Result<(), SimulationError> {
+        let (_, valid_until) = extract_timestamps(helper.simulate_validation_result);
+        self.check_valid_until(valid_until, current_timestamp()?)
+    }
+}
+
+impl ValidUntilWindow {
+    /// Checks that `validUntil` is at most [max_valid_until_window](Self::max_valid_until_window)
+    /// seconds after `now`.
+    ///
+    /// # Arguments
+    /// `valid_until` - The `validUntil` of the user operation (0 means no expiry)
+    /// `now` - The current timestamp
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    fn check_valid_until(&self, valid_until: U256, now: U256) -> Result<(), SimulationError> {
+        let valid_until =
+            if valid_until.is_zero() { U256::from(VALID_UNTIL_MAX) } else { valid_until };
+
+        if valid_until > now.saturating_add(self.max_valid_until_window) {
+            return Err(SimulationError::Timestamp {
+                inner: format!(
+                    "valid for too long: validUntil {valid_until} is more than {} seconds in the future",
+                    self.max_valid_until_window
+                ),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use silius_contracts::entry_point::{SimulateValidationResult, ValidationResult};
+
+    const WINDOW: u64 = 3600;
+
+    fn check_window(valid_until: u64, now: u64) -> Result<(), SimulationError> {
+        ValidUntilWindow { max_valid_until_window: WINDOW.into() }
+            .check_valid_until(valid_until.into(), now.into())
+    }
+
+    #[test]
+    fn valid_until_window_boundary() {
+        let now = 1_700_000_000;
+        assert!(check_window(now + WINDOW - 1, now).is_ok());
+        assert!(check_window(now + WINDOW, now).is_ok());
+        assert!(matches!(
+            check_window(now + WINDOW + 1, now),
+            Err(SimulationError::Timestamp { .. })
+        ));
+    }
+
+    #[test]
+    fn valid_until_zero_is_max() {
+        let check = ValidUntilWindow { max_valid_until_window: WINDOW.into() };
+        assert!(matches!(check_window(0, 1_700_000_000), Err(SimulationError::Timestamp { .. })));
+        assert!(check_window(0, VALID_UNTIL_MAX - WINDOW).is_ok());
+
+        // validUntil of 0 as reported by the simulation
+        let sim_res = SimulateValidationResult::ValidationResult(ValidationResult::default());
+        let mut helper = SimulationHelper {
+            simulate_validation_result: &sim_res,
+            val_config: Default::default(),
+            valid_after: None,
+            sender_funds: None,
+        };
+        let uo = UserOperation::from_user_operation_signed(Default::default(), Default::default());
+        assert!(matches!(
+            check.check_user_operation(&uo, &mut helper),
+            Err(SimulationError::Timestamp { .. })
+        ));
+    }
+}
@@ -59,6 +59,38 @@ impl CodeHashes {
 
         Ok(())
     }
+
+    /// Re-checks the code hashes stored from a previous validation (e.g. when the user operation
+    /// is resubmitted): the current code of the same addresses is fetched and compared against
+    /// the stored hashes, which detects a contract redeployed since the last validation.
+    ///
+    /// # Arguments
+    /// `hashes_prev` - The code hashes from a previous
+    /// [UserOperationValidationOutcome](crate::validate::UserOperationValidationOutcome)
+    /// `eth_client` - The Ethereum client
+    ///
+    /// # Returns
+    /// None if none of the code hashes changed, otherwise [SimulationError](SimulationError).
+    pub async fn verify_code_hashes<M: Middleware + 'static>(
+        &self,
+        hashes_prev: &[CodeHash],
+        eth_client: &Arc<M>,
+    ) -> Result<(), SimulationError> {
+        let addrs = hashes_prev.iter().map(|h| h.address).collect::<Vec<Address>>();
+
+        let hashes: &mut Vec<CodeHash> = &mut vec![];
+        self.get_code_hashes(addrs, hashes, eth_client).await?;
+
+        if !equal_code_hashes(hashes, &hashes_prev.to_vec()) {
+            debug!(
+                "Code hashes changed since the last validation: {:?} vs {:?}",
+                hashes, hashes_prev
+            );
+            return Err(SimulationError::CodeHashes {});
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -118,3 +150,40 @@ impl<M: Middleware> SimulationTraceCheck<M> for CodeHashes {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::Bytes,
+    };
+
+    async fn verify(code: Bytes, hashes_prev: Vec<CodeHash>) -> Result<(), SimulationError> {
+        let (provider, mock) = Provider::<MockProvider>::mocked();
+        mock.push(code).unwrap();
+        CodeHashes.verify_code_hashes(&hashes_prev, &Arc::new(provider)).await
+    }
+
+    #[tokio::test]
+    async fn code_hashes_unchanged() {
+        let code = Bytes::from(vec![0x60, 0x00]);
+        let hashes_prev =
+            vec![CodeHash { address: Address::random(), hash: keccak256(&code).into() }];
+
+        assert!(verify(code, hashes_prev).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn code_hashes_changed() {
+        let hashes_prev = vec![CodeHash {
+            address: Address::random(),
+            hash: keccak256(Bytes::from(vec![0x60, 0x00])).into(),
+        }];
+
+        assert!(matches!(
+            verify(Bytes::from(vec![0x60, 0x01]), hashes_prev).await,
+            Err(SimulationError::CodeHashes {})
+        ));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SimulationTraceCheck, SimulationTraceHelper},
+    Reputation, SimulationError,
+};
+use ethers::providers::Middleware;
+use silius_contracts::{entry_point::SELECTORS_INDICES, tracer::JsTracerFrame};
+use silius_primitives::{
+    constants::validation::entities::{FACTORY, FACTORY_LEVEL, LEVEL_TO_ENTITY},
+    simulation::{CREATE2_OPCODE, CREATE_OPCODE, SELFDESTRUCT_OPCODE},
+    UserOperation,
+};
+
+#[derive(Clone)]
+pub struct ContractCreation;
+
+impl ContractCreation {
+    /// Checks the contract creations and destructions during the validation. No entity may use
+    /// `SELFDESTRUCT` or `CREATE`, and `CREATE2` may only be used once by the factory to deploy
+    /// the sender.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `js_trace` - The [JsTracerFrame] of the validation.
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    fn check_trace(
+        &self,
+        uo: &UserOperation,
+        js_trace: &JsTracerFrame,
+    ) -> Result<(), SimulationError> {
+        for call_info in js_trace.calls_from_entry_point.iter() {
+            let level = SELECTORS_INDICES.get(call_info.top_level_method_sig.as_ref()).cloned();
+
+            if let Some(l) = level {
+                let entity = LEVEL_TO_ENTITY[l].to_string();
+                let count = |opcode: &String| call_info.opcodes.get(opcode).cloned().unwrap_or(0);
+
+                if count(&*SELFDESTRUCT_OPCODE) > 0 {
+                    return Err(SimulationError::ContractCreation {
+                        entity,
+                        inner: "uses SELFDESTRUCT".into(),
+                    });
+                }
+
+                if count(&*CREATE_OPCODE) > 0 {
+                    return Err(SimulationError::ContractCreation {
+                        entity,
+                        inner: "uses CREATE (only CREATE2 of the sender is allowed)".into(),
+                    });
+                }
+
+                let create2 = count(&*CREATE2_OPCODE);
+                if create2 > 0 && l != FACTORY_LEVEL {
+                    return Err(SimulationError::ContractCreation {
+                        entity,
+                        inner: "uses CREATE2 outside of the sender deployment".into(),
+                    });
+                }
+                if create2 > 1 {
+                    return Err(SimulationError::ContractCreation {
+                        entity,
+                        inner: format!(
+                            "uses CREATE2 {create2} times (only the sender deployment is allowed)"
+                        ),
+                    });
+                }
+            }
+        }
+
+        // the only CREATE2 must deploy the sender
+        for call in js_trace.calls.iter().filter(|call| call.typ == *CREATE2_OPCODE) {
+            if call.to != Some(uo.sender) {
+                return Err(SimulationError::ContractCreation {
+                    entity: FACTORY.into(),
+                    inner: format!(
+                        "uses CREATE2 to deploy {:?} instead of the sender {:?}",
+                        call.to.unwrap_or_default(),
+                        uo.sender
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SimulationTraceCheck<M> for ContractCreation {
+    /// The method implementation that checks the forbidden `SELFDESTRUCT`, `CREATE` and `CREATE2`
+    /// usage.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `_mempool` - Not used in this check
+    /// `_reputation` - Not used in this check
+    /// `helper` - The [SimulationTraceHelper](crate::validate::SimulationTraceHelper)
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        // [OP-011] - SELFDESTRUCT and CREATE are blocked
+        // [OP-031] - CREATE2 is allowed exactly once in the deployment phase and must deploy code
+        // for the "sender" address
+        self.check_trace(uo, helper.js_trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, Bytes};
+    use silius_contracts::tracer::{Call, TopLevelCallInfo};
+    use silius_primitives::{
+        constants::validation::entities::{PAYMASTER_LEVEL, SENDER_LEVEL},
+        UserOperationSigned,
+    };
+    use std::collections::HashMap;
+
+    fn call_info(level: usize, opcodes: &[(&str, u64)]) -> TopLevelCallInfo {
+        let selector = SELECTORS_INDICES
+            .iter()
+            .find(|(_, l)| **l == level)
+            .map(|(selector, _)| *selector)
+            .unwrap();
+
+        TopLevelCallInfo {
+            top_level_method_sig: Bytes::from(selector.to_vec()),
+            opcodes: opcodes.iter().map(|(op, c)| (op.to_string(), *c)).collect::<HashMap<_, _>>(),
+            ..Default::default()
+        }
+    }
+
+    fn create2(to: Address) -> Call {
+        Call { typ: CREATE2_OPCODE.to_string(), to: Some(to), ..Default::default() }
+    }
+
+    fn user_operation() -> UserOperation {
+        UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(Address::random()),
+        )
+    }
+
+    fn check(
+        uo: &UserOperation,
+        calls_from_entry_point: Vec<TopLevelCallInfo>,
+        calls: Vec<Call>,
+    ) -> Result<(), SimulationError> {
+        ContractCreation
+            .check_trace(uo, &JsTracerFrame { calls_from_entry_point, calls, ..Default::default() })
+    }
+
+    #[test]
+    fn sender_deployment_allowed() {
+        let uo = user_operation();
+        assert!(check(
+            &uo,
+            vec![
+                call_info(FACTORY_LEVEL, &[(CREATE2_OPCODE.as_str(), 1)]),
+                call_info(SENDER_LEVEL, &[])
+            ],
+            vec![create2(uo.sender)],
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn selfdestruct_forbidden() {
+        let uo = user_operation();
+        let err =
+            check(&uo, vec![call_info(SENDER_LEVEL, &[(SELFDESTRUCT_OPCODE.as_str(), 1)])], vec![])
+                .unwrap_err();
+        assert_eq!(err.to_string(), "account uses SELFDESTRUCT");
+    }
+
+    #[test]
+    fn create_forbidden() {
+        let uo = user_operation();
+        let err =
+            check(&uo, vec![call_info(FACTORY_LEVEL, &[(CREATE_OPCODE.as_str(), 1)])], vec![])
+                .unwrap_err();
+        assert!(err.to_string().starts_with("factory uses CREATE "));
+    }
+
+    #[test]
+    fn create2_outside_deployment_forbidden() {
+        let uo = user_operation();
+        let err = check(
+            &uo,
+            vec![call_info(PAYMASTER_LEVEL, &[(CREATE2_OPCODE.as_str(), 1)])],
+            vec![create2(Address::random())],
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "paymaster uses CREATE2 outside of the sender deployment");
+
+        let err = check(
+            &uo,
+            vec![call_info(FACTORY_LEVEL, &[(CREATE2_OPCODE.as_str(), 2)])],
+            vec![create2(uo.sender), create2(Address::random())],
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("factory uses CREATE2 2 times"));
+    }
+
+    #[test]
+    fn create2_of_other_address_forbidden() {
+        let uo = user_operation();
+        let other = Address::random();
+        let err = check(
+            &uo,
+            vec![call_info(FACTORY_LEVEL, &[(CREATE2_OPCODE.as_str(), 1)])],
+            vec![create2(other)],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "factory uses CREATE2 to deploy {other:?} instead of the sender {:?}",
+                uo.sender
+            )
+        );
+    }
+}
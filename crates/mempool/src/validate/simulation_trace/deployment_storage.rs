@@ -0,0 +1,229 @@
+use super::storage_access::StorageAccess;
+use crate::{
+    mempool::Mempool,
+    validate::{utils::extract_stake_info, SimulationTraceCheck, SimulationTraceHelper},
+    Reputation, SimulationError,
+};
+use ethers::{
+    providers::Middleware,
+    types::{Address, Bytes},
+};
+use silius_contracts::{entry_point::SELECTORS_INDICES, tracer::TopLevelCallInfo};
+use silius_primitives::{
+    constants::validation::entities::{FACTORY, FACTORY_LEVEL, NUMBER_OF_LEVELS},
+    reputation::StakeInfo,
+    UserOperation,
+};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone)]
+pub struct DeploymentStorage;
+
+impl DeploymentStorage {
+    /// Checks the storage writes of the factory call of a user operation deploying the account.
+    /// The factory may write the storage of the account being deployed. A staked factory may also
+    /// write its own storage and the storage associated with the account in an external contract
+    /// ([STO-022]).
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to check
+    /// `entry_point` - The address of the entry point
+    /// `stake_info` - The stake info of the entities
+    /// `slots` - The slots associated with the entities
+    /// `calls` - The top level calls from the entry point
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    fn check_calls(
+        &self,
+        uo: &UserOperation,
+        entry_point: &Address,
+        stake_info: &[StakeInfo; NUMBER_OF_LEVELS],
+        slots: &HashMap<Address, HashSet<Bytes>>,
+        calls: &[TopLevelCallInfo],
+    ) -> Result<(), SimulationError> {
+        if uo.init_code.is_empty() {
+            return Ok(());
+        }
+
+        let factory = stake_info[FACTORY_LEVEL];
+
+        for call_info in calls.iter().filter(|call_info| {
+            SELECTORS_INDICES.get(call_info.top_level_method_sig.as_ref()) == Some(&FACTORY_LEVEL)
+        }) {
+            for (addr, acc) in &call_info.access {
+                if *addr == uo.sender || addr == entry_point {
+                    continue;
+                }
+
+                for slot in acc.writes.keys() {
+                    if *addr != factory.address &&
+                        !StorageAccess::associated_with_slot(&uo.sender, slot, slots)?
+                    {
+                        return Err(SimulationError::StorageAccess { slot: slot.clone() });
+                    }
+
+                    if !factory.is_staked() {
+                        return Err(SimulationError::Unstaked {
+                            entity: FACTORY.into(),
+                            address: factory.address,
+                            inner: format!("wrote slot {slot} during the deployment"),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SimulationTraceCheck<M> for DeploymentStorage {
+    /// The method implementation that checks that the factory of a user operation deploying the
+    /// account only writes the storage of the account (or its own and the account's associated
+    /// storage if staked).
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to check
+    /// `helper` - The [SimulationTraceHelper](crate::validate::SimulationTraceHelper)
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        if helper.stake_info.is_none() {
+            helper.stake_info = Some(extract_stake_info(uo, helper.simulate_validation_result));
+        }
+
+        let mut slots = HashMap::new();
+        StorageAccess::parse_slots(
+            helper.js_trace.keccak.clone(),
+            &helper.stake_info.unwrap_or_default(),
+            &mut slots,
+        );
+
+        self.check_calls(
+            uo,
+            &helper.entry_point.address(),
+            &helper.stake_info.unwrap_or_default(),
+            &slots,
+            &helper.js_trace.calls_from_entry_point,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{H256, U256};
+    use silius_contracts::tracer::ReadsAndWrites;
+    use silius_primitives::UserOperationSigned;
+
+    const SLOT: &str = "0x0000000000000000000000000000000000000000000000000000000000000005";
+
+    fn check(
+        sender: Address,
+        factory: Address,
+        factory_staked: bool,
+        slots: &HashMap<Address, HashSet<Bytes>>,
+        writes: &[(Address, &str)],
+    ) -> Result<(), SimulationError> {
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default()
+                .sender(sender)
+                .init_code(Bytes::from(factory.as_bytes().to_vec())),
+        );
+
+        let mut stake_info = [StakeInfo::default(); NUMBER_OF_LEVELS];
+        stake_info[FACTORY_LEVEL].address = factory;
+        if factory_staked {
+            stake_info[FACTORY_LEVEL].stake = U256::from(1);
+            stake_info[FACTORY_LEVEL].unstake_delay = U256::from(1);
+        }
+
+        let selector = SELECTORS_INDICES
+            .iter()
+            .find(|(_, level)| **level == FACTORY_LEVEL)
+            .map(|(selector, _)| *selector)
+            .unwrap();
+
+        let mut access = HashMap::<Address, ReadsAndWrites>::new();
+        for (contract, slot) in writes {
+            access.entry(*contract).or_default().writes.insert(slot.to_string(), 1);
+        }
+
+        let call_info = TopLevelCallInfo {
+            top_level_method_sig: Bytes::from(selector.to_vec()),
+            access,
+            ..Default::default()
+        };
+
+        DeploymentStorage.check_calls(&uo, &Address::random(), &stake_info, slots, &[call_info])
+    }
+
+    /// Slots associated with the sender (e.g. `mapping(address => uint)` keyed by the sender) and
+    /// a slot right after the associated one
+    fn associated(sender: Address) -> (HashMap<Address, HashSet<Bytes>>, String) {
+        let base = H256::random();
+        let slot = format!("{:#x}", U256::from(base.as_bytes()) + 1);
+        (HashMap::from([(sender, HashSet::from([Bytes::from(base.as_bytes().to_vec())]))]), slot)
+    }
+
+    #[test]
+    fn deployment_storage_compliant() {
+        let (sender, factory) = (Address::random(), Address::random());
+        let no_slots = HashMap::new();
+
+        assert!(check(sender, factory, false, &no_slots, &[(sender, SLOT)]).is_ok());
+        assert!(check(sender, factory, true, &no_slots, &[(factory, SLOT)]).is_ok());
+    }
+
+    #[test]
+    fn deployment_storage_associated_slot() {
+        let (sender, factory, contract) = (Address::random(), Address::random(), Address::random());
+        let (slots, slot) = associated(sender);
+
+        // [STO-022] - a staked factory may write the account's associated storage
+        assert!(check(sender, factory, true, &slots, &[(contract, &slot)]).is_ok());
+        assert!(matches!(
+            check(sender, factory, false, &slots, &[(contract, &slot)]),
+            Err(SimulationError::Unstaked { entity, address, .. })
+                if entity == FACTORY && address == factory
+        ));
+    }
+
+    #[test]
+    fn deployment_storage_out_of_bounds_write() {
+        let (sender, factory) = (Address::random(), Address::random());
+        let no_slots = HashMap::new();
+
+        assert!(matches!(
+            check(sender, factory, true, &no_slots, &[(Address::random(), SLOT)]),
+            Err(SimulationError::StorageAccess { slot }) if slot == SLOT
+        ));
+        assert!(matches!(
+            check(sender, factory, false, &no_slots, &[(factory, SLOT)]),
+            Err(SimulationError::Unstaked { entity, address, .. })
+                if entity == FACTORY && address == factory
+        ));
+    }
+
+    #[test]
+    fn deployment_storage_every_written_slot() {
+        let (sender, factory, contract) = (Address::random(), Address::random(), Address::random());
+        let (slots, slot) = associated(sender);
+
+        // the out of bounds write is found whichever slot comes first
+        assert!(matches!(
+            check(sender, factory, true, &slots, &[(contract, &slot), (contract, SLOT)]),
+            Err(SimulationError::StorageAccess { slot }) if slot == SLOT
+        ));
+    }
+}
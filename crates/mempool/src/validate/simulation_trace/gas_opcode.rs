@@ -0,0 +1,96 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SimulationTraceCheck, SimulationTraceHelper},
+    Reputation, SimulationError,
+};
+use ethers::providers::Middleware;
+use silius_contracts::{entry_point::SELECTORS_INDICES, tracer::TopLevelCallInfo};
+use silius_primitives::{constants::validation::entities::LEVEL_TO_ENTITY, UserOperation};
+
+#[derive(Clone)]
+pub struct GasOpcode;
+
+impl GasOpcode {
+    /// Checks the `GAS` opcodes of the top level calls (factory, account, paymaster).
+    ///
+    /// # Arguments
+    /// `calls` - The top level calls from the entry point
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    fn check_calls(&self, calls: &[TopLevelCallInfo]) -> Result<(), SimulationError> {
+        for call_info in calls.iter() {
+            let level = SELECTORS_INDICES.get(call_info.top_level_method_sig.as_ref()).cloned();
+
+            if let Some(l) = level {
+                if let Some(pc) = call_info.gas_opcode_pcs.first() {
+                    return Err(SimulationError::GasOpcode {
+                        entity: LEVEL_TO_ENTITY[l].to_string(),
+                        pc: *pc,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SimulationTraceCheck<M> for GasOpcode {
+    /// The method implementation that checks that every `GAS` opcode is immediately followed by
+    /// `CALL`, `DELEGATECALL`, `CALLCODE` or `STATICCALL`.
+    ///
+    /// # Arguments
+    /// `_uo` - Not used in this check
+    /// `_mempool` - Not used in this check
+    /// `_reputation` - Not used in this check
+    /// `helper` - The [SimulationTraceHelper](crate::validate::SimulationTraceHelper)
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    async fn check_user_operation(
+        &self,
+        _uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        // [OP-012] - GAS opcode is allowed, but only if followed immediately by *CALL instructions
+        self.check_calls(&helper.js_trace.calls_from_entry_point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Bytes;
+    use silius_primitives::constants::validation::entities::SENDER_LEVEL;
+
+    fn call_info(gas_opcode_pcs: Vec<u64>) -> TopLevelCallInfo {
+        let selector = SELECTORS_INDICES
+            .iter()
+            .find(|(_, level)| **level == SENDER_LEVEL)
+            .map(|(selector, _)| *selector)
+            .unwrap();
+
+        TopLevelCallInfo {
+            top_level_method_sig: Bytes::from(selector.to_vec()),
+            gas_opcode_pcs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn gas_opcode_followed_by_call() {
+        assert!(GasOpcode.check_calls(&[call_info(vec![])]).is_ok());
+    }
+
+    #[test]
+    fn gas_opcode_not_followed_by_call() {
+        assert!(matches!(
+            GasOpcode.check_calls(&[call_info(vec![]), call_info(vec![42, 100])]),
+            Err(SimulationError::GasOpcode { pc: 42, .. })
+        ));
+    }
+}
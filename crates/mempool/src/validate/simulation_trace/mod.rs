@@ -3,7 +3,13 @@
 //! `debug_traceCall` to a Ethereum execution client.
 pub mod call_stack;
 pub mod code_hashes;
+pub mod contract_creation;
+pub mod deployment_storage;
 pub mod external_contracts;
 pub mod gas;
+pub mod gas_opcode;
 pub mod opcodes;
+pub mod precompiles;
 pub mod storage_access;
+pub mod storage_gas;
+pub mod trace_limit;
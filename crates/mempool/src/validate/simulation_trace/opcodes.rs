@@ -1,42 +1,87 @@
 use crate::{
-    validate::{SimulationTraceCheck, SimulationTraceHelper},
+    validate::{utils::extract_stake_info, SimulationTraceCheck, SimulationTraceHelper},
     Mempool, Reputation, SimulationError,
 };
 use ethers::providers::Middleware;
-use silius_contracts::entry_point::SELECTORS_INDICES;
+use silius_contracts::{entry_point::SELECTORS_INDICES, tracer::JsTracerFrame};
 use silius_primitives::{
-    constants::validation::entities::{FACTORY, LEVEL_TO_ENTITY},
-    simulation::{CREATE2_OPCODE, FORBIDDEN_OPCODES},
+    constants::validation::entities::{FACTORY, LEVEL_TO_ENTITY, NUMBER_OF_LEVELS},
+    reputation::StakeInfo,
+    simulation::{
+        CREATE2_OPCODE, FORBIDDEN_OPCODES, STAKED_ALLOWED_OPCODES, TIME_DEPENDENT_OPCODES,
+    },
     UserOperation,
 };
 
-#[derive(Clone)]
-pub struct Opcodes;
+#[derive(Clone, Default)]
+pub struct Opcodes {
+    /// Strict time dependence mode: TIMESTAMP and NUMBER read by any entity (staked or not) are
+    /// recorded in the validation outcome (`time_dependent`) instead of rejected, so the bundler
+    /// can treat such user operations cautiously
+    pub flag_time_dependent: bool,
+}
 
-#[async_trait::async_trait]
-impl<M: Middleware> SimulationTraceCheck<M> for Opcodes {
-    /// The method implementation that checks the use of forbidden opcodes
+impl Opcodes {
+    /// Checks whether the entity may not use the opcode during the validation. All opcodes in
+    /// [FORBIDDEN_OPCODES](FORBIDDEN_OPCODES) are banned ([OP-011]: environment and block opcodes
+    /// such as GASPRICE, BASEFEE or TIMESTAMP are banned even for staked entities), except the
+    /// opcodes in [STAKED_ALLOWED_OPCODES](STAKED_ALLOWED_OPCODES) used by a staked entity
+    /// ([OP-080]).
     ///
     /// # Arguments
-    /// `_uo` - Not used
-    /// `helper` - The [SimulationTraceHelper]
+    /// `op` - The name of the opcode
+    /// `staked` - Whether the entity using the opcode is staked
+    ///
+    /// # Returns
+    /// true if the opcode is forbidden for the entity
+    pub fn is_forbidden(op: &str, staked: bool) -> bool {
+        if staked && STAKED_ALLOWED_OPCODES.contains(op) {
+            return false;
+        }
+        FORBIDDEN_OPCODES.contains(op)
+    }
+
+    /// Checks whether any entity reads TIMESTAMP or NUMBER during the validation.
+    ///
+    /// # Arguments
+    /// `js_trace` - The [JsTracerFrame] of the simulation
+    ///
+    /// # Returns
+    /// true if the validation depends on the block it is included in
+    pub fn is_time_dependent(js_trace: &JsTracerFrame) -> bool {
+        js_trace.calls_from_entry_point.iter().any(|call_info| {
+            SELECTORS_INDICES.contains_key(call_info.top_level_method_sig.as_ref()) &&
+                call_info.opcodes.keys().any(|op| TIME_DEPENDENT_OPCODES.contains(op))
+        })
+    }
+
+    /// Checks the use of forbidden opcodes in the trace. Staked entities may use the opcodes in
+    /// [STAKED_ALLOWED_OPCODES](STAKED_ALLOWED_OPCODES) (BALANCE and SELFBALANCE); all other
+    /// forbidden opcodes are rejected regardless of the stake (except TIMESTAMP and NUMBER in the
+    /// [strict time dependence mode](Self::flag_time_dependent)).
+    ///
+    /// # Arguments
+    /// `js_trace` - The [JsTracerFrame] of the simulation
+    /// `stake_info` - The stake info of the entities
     ///
     /// # Returns
     /// None if the check passes, otherwise a [SimulationError] error.
-    async fn check_user_operation(
+    pub fn check_trace(
         &self,
-        _uo: &UserOperation,
-        _mempool: &Mempool,
-        _reputation: &Reputation,
-        helper: &mut SimulationTraceHelper<M>,
+        js_trace: &JsTracerFrame,
+        stake_info: &[StakeInfo; NUMBER_OF_LEVELS],
     ) -> Result<(), SimulationError> {
-        for call_info in helper.js_trace.calls_from_entry_point.iter() {
+        for call_info in js_trace.calls_from_entry_point.iter() {
             let level = SELECTORS_INDICES.get(call_info.top_level_method_sig.as_ref()).cloned();
 
             if let Some(l) = level {
                 // [OP-011] - block opcodes
+                // [OP-080] - BALANCE and SELFBALANCE are allowed only from a staked entity
                 for op in call_info.opcodes.keys() {
-                    if FORBIDDEN_OPCODES.contains(op) {
+                    if self.flag_time_dependent && TIME_DEPENDENT_OPCODES.contains(op) {
+                        continue;
+                    }
+                    if Self::is_forbidden(op, stake_info[l].is_staked()) {
                         return Err(SimulationError::Opcode {
                             entity: LEVEL_TO_ENTITY[l].to_string(),
                             opcode: op.clone(),
@@ -61,3 +106,171 @@ impl<M: Middleware> SimulationTraceCheck<M> for Opcodes {
         Ok(())
     }
 }
+
+#[async_trait::async_trait]
+impl<M: Middleware> SimulationTraceCheck<M> for Opcodes {
+    /// The method implementation that checks the use of forbidden opcodes (and flags the time
+    /// dependent validation in the strict time dependence mode)
+    ///
+    /// # Arguments
+    /// `uo` - The user operation to check
+    /// `helper` - The [SimulationTraceHelper]
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        if helper.stake_info.is_none() {
+            helper.stake_info = Some(extract_stake_info(uo, helper.simulate_validation_result));
+        }
+
+        self.check_trace(helper.js_trace, &helper.stake_info.unwrap_or_default())?;
+
+        if self.flag_time_dependent {
+            helper.time_dependent = Self::is_time_dependent(helper.js_trace);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, Bytes, U256};
+    use silius_contracts::tracer::TopLevelCallInfo;
+    use silius_primitives::constants::validation::entities::{PAYMASTER_LEVEL, SENDER_LEVEL};
+    use std::collections::HashMap;
+
+    fn call_info(level: usize, opcodes: &[&str]) -> TopLevelCallInfo {
+        let selector = SELECTORS_INDICES
+            .iter()
+            .find(|(_, l)| **l == level)
+            .map(|(selector, _)| *selector)
+            .unwrap();
+
+        TopLevelCallInfo {
+            top_level_method_sig: Bytes::from(selector.to_vec()),
+            opcodes: opcodes.iter().map(|op| (op.to_string(), 1)).collect::<HashMap<_, _>>(),
+            ..Default::default()
+        }
+    }
+
+    fn stake_info(staked_level: Option<usize>) -> [StakeInfo; NUMBER_OF_LEVELS] {
+        let mut stake_info = [StakeInfo::default(); NUMBER_OF_LEVELS];
+        for (l, info) in stake_info.iter_mut().enumerate() {
+            info.address = Address::random();
+            if Some(l) == staked_level {
+                info.stake = U256::from(1);
+                info.unstake_delay = U256::from(1);
+            }
+        }
+        stake_info
+    }
+
+    #[test]
+    fn balance_allowed_only_for_staked_entities() {
+        let js_trace = JsTracerFrame {
+            calls_from_entry_point: vec![
+                call_info(SENDER_LEVEL, &[]),
+                call_info(PAYMASTER_LEVEL, &["SELFBALANCE", "BALANCE"]),
+            ],
+            ..Default::default()
+        };
+
+        assert!(Opcodes::default()
+            .check_trace(&js_trace, &stake_info(Some(PAYMASTER_LEVEL)))
+            .is_ok());
+        assert!(matches!(
+            Opcodes::default().check_trace(&js_trace, &stake_info(None)),
+            Err(SimulationError::Opcode { entity, .. }) if entity == "paymaster"
+        ));
+        // the stake of another entity doesn't matter
+        assert!(Opcodes::default()
+            .check_trace(&js_trace, &stake_info(Some(SENDER_LEVEL)))
+            .is_err());
+    }
+
+    #[test]
+    fn environment_opcodes_rejected() {
+        for op in
+            ["GASPRICE", "BASEFEE", "BLOCKHASH", "TIMESTAMP", "NUMBER", "DIFFICULTY", "COINBASE"]
+        {
+            assert!(Opcodes::is_forbidden(op, false), "{op} allowed for unstaked entity");
+            assert!(Opcodes::is_forbidden(op, true), "{op} allowed for staked entity");
+
+            let js_trace = JsTracerFrame {
+                calls_from_entry_point: vec![call_info(SENDER_LEVEL, &[op])],
+                ..Default::default()
+            };
+            for staked_level in [None, Some(SENDER_LEVEL)] {
+                assert!(matches!(
+                    Opcodes::default().check_trace(&js_trace, &stake_info(staked_level)),
+                    Err(SimulationError::Opcode { entity, opcode })
+                        if entity == "account" && opcode == op
+                ));
+            }
+        }
+
+        assert!(!Opcodes::is_forbidden("BALANCE", true));
+        assert!(Opcodes::is_forbidden("BALANCE", false));
+        assert!(!Opcodes::is_forbidden("CALL", false));
+    }
+
+    #[test]
+    fn forbidden_opcodes_rejected_for_staked_entities() {
+        let js_trace = JsTracerFrame {
+            calls_from_entry_point: vec![call_info(PAYMASTER_LEVEL, &["TIMESTAMP"])],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            Opcodes::default().check_trace(&js_trace, &stake_info(Some(PAYMASTER_LEVEL))),
+            Err(SimulationError::Opcode { opcode, .. }) if opcode == "TIMESTAMP"
+        ));
+    }
+
+    #[test]
+    fn time_dependent_flagged() {
+        let opcodes = Opcodes { flag_time_dependent: true };
+        let js_trace = JsTracerFrame {
+            calls_from_entry_point: vec![
+                call_info(SENDER_LEVEL, &["CALL"]),
+                call_info(PAYMASTER_LEVEL, &["SLOAD"]),
+            ],
+            ..Default::default()
+        };
+        assert!(!Opcodes::is_time_dependent(&js_trace));
+
+        for op in ["TIMESTAMP", "NUMBER"] {
+            let js_trace = JsTracerFrame {
+                calls_from_entry_point: vec![
+                    call_info(SENDER_LEVEL, &["CALL"]),
+                    call_info(PAYMASTER_LEVEL, &[op]),
+                ],
+                ..Default::default()
+            };
+            assert!(Opcodes::is_time_dependent(&js_trace), "{op} not flagged");
+
+            // flagged instead of rejected, regardless of the stake
+            for staked_level in [None, Some(PAYMASTER_LEVEL)] {
+                assert!(opcodes.check_trace(&js_trace, &stake_info(staked_level)).is_ok());
+            }
+        }
+
+        // other forbidden opcodes are still rejected
+        let js_trace = JsTracerFrame {
+            calls_from_entry_point: vec![call_info(SENDER_LEVEL, &["TIMESTAMP", "GASPRICE"])],
+            ..Default::default()
+        };
+        assert!(matches!(
+            opcodes.check_trace(&js_trace, &stake_info(None)),
+            Err(SimulationError::Opcode { opcode, .. }) if opcode == "GASPRICE"
+        ));
+    }
+}
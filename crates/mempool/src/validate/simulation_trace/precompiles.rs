@@ -0,0 +1,185 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SimulationTraceCheck, SimulationTraceHelper},
+    Reputation, SimulationError,
+};
+use ethers::{providers::Middleware, types::Address};
+use silius_contracts::tracer::JsTracerFrame;
+use silius_primitives::UserOperation;
+use std::collections::HashSet;
+
+/// Precompiles that are allowed during the validation: `ecrecover` (0x01), `sha256` (0x02) and
+/// `ripemd160` (0x03)
+const ALLOWED_PRECOMPILES: u64 = 3;
+/// Highest precompile address (0x0a - KZG point evaluation, EIP-4844)
+const LAST_PRECOMPILE: u64 = 10;
+
+#[derive(Clone)]
+pub struct BannedPrecompiles {
+    /// Addresses of precompiles that must not be called during the validation
+    pub denylist: HashSet<Address>,
+}
+
+impl Default for BannedPrecompiles {
+    /// Bans every precompile except `ecrecover` and the hashing precompiles (`sha256` and
+    /// `ripemd160`).
+    fn default() -> Self {
+        Self {
+            denylist: (ALLOWED_PRECOMPILES + 1..=LAST_PRECOMPILE)
+                .map(Address::from_low_u64_be)
+                .collect(),
+        }
+    }
+}
+
+impl BannedPrecompiles {
+    /// Finds a banned precompile called during the validation at any depth of the call tree: the
+    /// targets of all call frames of the trace and the contracts called by the entities (recorded
+    /// per entity, including the nested calls).
+    ///
+    /// # Arguments
+    /// `js_trace` - The [js tracer frame](JsTracerFrame) of the validation
+    ///
+    /// # Returns
+    /// The address of the banned precompile, otherwise None.
+    fn find_banned(&self, js_trace: &JsTracerFrame) -> Option<Address> {
+        let called = js_trace.calls.iter().filter_map(|call| call.to);
+        let accessed = js_trace
+            .calls_from_entry_point
+            .iter()
+            .flat_map(|call_info| call_info.contract_size.keys().cloned());
+
+        called.chain(accessed).find(|addr| self.denylist.contains(addr))
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SimulationTraceCheck<M> for BannedPrecompiles {
+    /// The method implementation that checks whether the user operation calls any of the banned
+    /// precompiles during the validation.
+    ///
+    /// # Arguments
+    /// `_uo` - Not used in this check
+    /// `_mempool` - Not used in this check
+    /// `_reputation` - Not used in this check
+    /// `helper` - The [SimulationTraceHelper](crate::validate::SimulationTraceHelper)
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    async fn check_user_operation(
+        &self,
+        _uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        match self.find_banned(helper.js_trace) {
+            Some(address) => Err(SimulationError::Precompile { address }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{memory_mempool, memory_reputation};
+    use alloy_chains::Chain;
+    use ethers::providers::{MockProvider, Provider};
+    use silius_contracts::{
+        entry_point::{SimulateValidationResult, ValidationResult},
+        tracer::{Call, ContractSizeInfo, TopLevelCallInfo},
+        EntryPoint,
+    };
+    use std::{collections::HashMap, sync::Arc};
+
+    async fn check(js_trace: JsTracerFrame) -> Result<(), SimulationError> {
+        let entry_point = EntryPoint::<Provider<MockProvider>>::new(
+            Arc::new(Provider::mocked().0),
+            Address::random(),
+        );
+        let sim_res = SimulateValidationResult::ValidationResult(ValidationResult::default());
+        let mut helper = SimulationTraceHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            simulate_validation_result: &sim_res,
+            js_trace: &js_trace,
+            val_config: Default::default(),
+            stake_info: None,
+            code_hashes: None,
+            time_dependent: false,
+        };
+
+        let uo = UserOperation::from_user_operation_signed(Default::default(), Default::default());
+        BannedPrecompiles::default()
+            .check_user_operation(&uo, &memory_mempool(), &memory_reputation(), &mut helper)
+            .await
+    }
+
+    fn call(to: Address) -> Call {
+        Call { typ: "CALL".into(), to: Some(to), ..Default::default() }
+    }
+
+    fn call_info(called: &[Address]) -> TopLevelCallInfo {
+        TopLevelCallInfo {
+            contract_size: called
+                .iter()
+                .map(|addr| {
+                    (*addr, ContractSizeInfo { opcode: "STATICCALL".into(), contract_size: 0 })
+                })
+                .collect::<HashMap<_, _>>(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn banned_precompile_nested_call() {
+        let (account, library) = (Address::random(), Address::random());
+        let modexp = Address::from_low_u64_be(5);
+
+        // the account calls a library, which calls the precompile
+        let js_trace = JsTracerFrame {
+            calls: vec![call(account), call(library), call(modexp)],
+            ..Default::default()
+        };
+        assert!(matches!(
+            check(js_trace).await,
+            Err(SimulationError::Precompile { address }) if address == modexp
+        ));
+
+        // the call is only recorded for the entity
+        let js_trace = JsTracerFrame {
+            calls_from_entry_point: vec![call_info(&[]), call_info(&[library, modexp])],
+            calls: vec![call(account)],
+            ..Default::default()
+        };
+        assert!(matches!(
+            check(js_trace).await,
+            Err(SimulationError::Precompile { address }) if address == modexp
+        ));
+    }
+
+    #[tokio::test]
+    async fn allowed_precompile_nested_call() {
+        let ecrecover = Address::from_low_u64_be(1);
+        let js_trace = JsTracerFrame {
+            calls_from_entry_point: vec![call_info(&[ecrecover])],
+            calls: vec![call(Address::random()), call(ecrecover)],
+            ..Default::default()
+        };
+
+        assert!(check(js_trace).await.is_ok());
+    }
+
+    #[test]
+    fn banned_precompiles_default() {
+        let banned = BannedPrecompiles::default();
+
+        for addr in 1..=ALLOWED_PRECOMPILES {
+            assert!(!banned.denylist.contains(&Address::from_low_u64_be(addr)));
+        }
+        for addr in ALLOWED_PRECOMPILES + 1..=LAST_PRECOMPILE {
+            assert!(banned.denylist.contains(&Address::from_low_u64_be(addr)));
+        }
+    }
+}
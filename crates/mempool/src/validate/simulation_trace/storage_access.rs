@@ -8,7 +8,7 @@ use ethers::{
     types::{Address, Bytes, U256},
     utils::keccak256,
 };
-use silius_contracts::entry_point::SELECTORS_INDICES;
+use silius_contracts::{entry_point::SELECTORS_INDICES, tracer::TopLevelCallInfo};
 use silius_primitives::{
     constants::validation::entities::{FACTORY_LEVEL, LEVEL_TO_ENTITY, NUMBER_OF_LEVELS},
     reputation::StakeInfo,
@@ -16,10 +16,27 @@ use silius_primitives::{
 };
 use std::collections::{HashMap, HashSet};
 
-#[derive(Clone)]
-pub struct StorageAccess;
+#[derive(Clone, Default)]
+pub struct StorageAccess {
+    /// Storage slots (per contract address) that entities are always allowed to access
+    pub allowed_storage: HashMap<Address, HashSet<U256>>,
+}
 
 impl StorageAccess {
+    /// The helper method that checks if the slot of the contract is on the allowlist.
+    ///
+    /// # Arguments
+    /// `addr` - The address of the contract
+    /// `slot` - The slot to check
+    ///
+    /// # Returns
+    /// true if the slot is allowlisted, otherwise false.
+    fn is_allowed(&self, addr: &Address, slot: &str) -> bool {
+        self.allowed_storage.get(addr).is_some_and(|slots| {
+            U256::from_str_radix(slot, 16).is_ok_and(|slot_num| slots.contains(&slot_num))
+        })
+    }
+
     /// The helper method that parses the slots from the JS trace.
     ///
     /// # Arguments
@@ -29,8 +46,7 @@ impl StorageAccess {
     ///
     /// # Returns
     /// None
-    fn parse_slots(
-        &self,
+    pub(crate) fn parse_slots(
         keccak: Vec<Bytes>,
         info: &[StakeInfo; NUMBER_OF_LEVELS],
         slots: &mut HashMap<Address, HashSet<Bytes>>,
@@ -61,8 +77,7 @@ impl StorageAccess {
     ///
     /// # Returns
     /// true if the slot is associated with the address, otherwise false.
-    fn associated_with_slot(
-        &self,
+    pub(crate) fn associated_with_slot(
         addr: &Address,
         slot: &String,
         slots: &HashMap<Address, HashSet<Bytes>>,
@@ -90,41 +105,30 @@ impl StorageAccess {
 
         Ok(false)
     }
-}
 
-#[async_trait::async_trait]
-impl<M: Middleware> SimulationTraceCheck<M> for StorageAccess {
-    /// The method implementation that checks if the user operation access
-    /// storage other than the one associated with itself.
+    /// The helper method that checks the storage accesses of the top level calls (factory,
+    /// account, paymaster).
     ///
     /// # Arguments
     /// `uo` - The [UserOperation](UserOperation) to check
-    /// `helper` - The [SimulationTraceHelper](crate::validate::SimulationTraceHelper)
+    /// `entry_point` - The address of the entry point
+    /// `stake_info` - The stake info of the entities
+    /// `slots` - The slots associated with the entities
+    /// `calls` - The top level calls from the entry point
     ///
     /// # Returns
     /// None if the check passes, otherwise a [SimulationError] error.
-    async fn check_user_operation(
+    fn check_calls(
         &self,
         uo: &UserOperation,
-        _mempool: &Mempool,
-        _reputation: &Reputation,
-        helper: &mut SimulationTraceHelper<M>,
+        entry_point: &Address,
+        stake_info: &[StakeInfo; NUMBER_OF_LEVELS],
+        slots: &HashMap<Address, HashSet<Bytes>>,
+        calls: &[TopLevelCallInfo],
     ) -> Result<(), SimulationError> {
-        if helper.stake_info.is_none() {
-            helper.stake_info = Some(extract_stake_info(uo, helper.simulate_validation_result));
-        }
-
-        let mut slots = HashMap::new();
-        self.parse_slots(
-            helper.js_trace.keccak.clone(),
-            &helper.stake_info.unwrap_or_default(),
-            &mut slots,
-        );
-
         let mut slot_staked = String::new();
-        let stake_info = helper.stake_info.unwrap_or_default();
 
-        for call_info in helper.js_trace.calls_from_entry_point.iter() {
+        for call_info in calls.iter() {
             let level = SELECTORS_INDICES.get(call_info.top_level_method_sig.as_ref()).cloned();
 
             if let Some(l) = level {
@@ -132,7 +136,7 @@ impl<M: Middleware> SimulationTraceCheck<M> for StorageAccess {
 
                 for (addr, acc) in &call_info.access {
                     // [STO-010] - Access to the "account" storage is always allowed
-                    if *addr == uo.sender || *addr == helper.entry_point.address() {
+                    if *addr == uo.sender || addr == entry_point {
                         continue;
                     }
 
@@ -144,7 +148,11 @@ impl<M: Middleware> SimulationTraceCheck<M> for StorageAccess {
                     ]
                     .concat()
                     {
-                        if self.associated_with_slot(&uo.sender, &slot, &slots)? {
+                        if self.is_allowed(addr, &slot) {
+                            continue;
+                        }
+
+                        if Self::associated_with_slot(&uo.sender, &slot, slots)? {
                             // [STO-021], [STO-022] - Access to associated storage of the account in
                             // an external (non-entity contract) is allowed if either The account
                             // already exists or There is an initCode and the factory contract is
@@ -156,7 +164,7 @@ impl<M: Middleware> SimulationTraceCheck<M> for StorageAccess {
                                 slot_staked.clone_from(&slot);
                             }
                         } else if *addr == stake_info_l.address // [STO-031] - access the entity's own storage (if entity staked)
-                            || self.associated_with_slot(&stake_info_l.address, &slot, &slots)? // [STO-032] - read/write Access to storage slots that is associated with the entity, in any non-entity contract (if entity staked)
+                            || Self::associated_with_slot(&stake_info_l.address, &slot, slots)? // [STO-032] - read/write Access to storage slots that is associated with the entity, in any non-entity contract (if entity staked)
                             || !acc.writes.contains_key(&slot)
                         // [STO-033] - read-only access to any storage in non-entity contract (if
                         // entity staked)
@@ -181,3 +189,116 @@ impl<M: Middleware> SimulationTraceCheck<M> for StorageAccess {
         Ok(())
     }
 }
+
+#[async_trait::async_trait]
+impl<M: Middleware> SimulationTraceCheck<M> for StorageAccess {
+    /// The method implementation that checks if the user operation access
+    /// storage other than the one associated with itself.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to check
+    /// `helper` - The [SimulationTraceHelper](crate::validate::SimulationTraceHelper)
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        if helper.stake_info.is_none() {
+            helper.stake_info = Some(extract_stake_info(uo, helper.simulate_validation_result));
+        }
+
+        let mut slots = HashMap::new();
+        Self::parse_slots(
+            helper.js_trace.keccak.clone(),
+            &helper.stake_info.unwrap_or_default(),
+            &mut slots,
+        );
+
+        self.check_calls(
+            uo,
+            &helper.entry_point.address(),
+            &helper.stake_info.unwrap_or_default(),
+            &slots,
+            &helper.js_trace.calls_from_entry_point,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use silius_contracts::tracer::ReadsAndWrites;
+    use silius_primitives::{
+        constants::validation::entities::PAYMASTER_LEVEL, UserOperationSigned,
+    };
+
+    const SLOT: &str = "0x0000000000000000000000000000000000000000000000000000000000000005";
+
+    fn check(storage_access: &StorageAccess, contract: Address) -> Result<(), SimulationError> {
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(Address::random()),
+        );
+
+        let mut stake_info = [StakeInfo::default(); NUMBER_OF_LEVELS];
+        stake_info[PAYMASTER_LEVEL].address = Address::random();
+
+        let selector = SELECTORS_INDICES
+            .iter()
+            .find(|(_, level)| **level == PAYMASTER_LEVEL)
+            .map(|(selector, _)| *selector)
+            .unwrap();
+
+        let call_info = TopLevelCallInfo {
+            top_level_method_sig: Bytes::from(selector.to_vec()),
+            access: HashMap::from([(
+                contract,
+                ReadsAndWrites {
+                    reads: HashMap::new(),
+                    writes: HashMap::from([(SLOT.to_string(), 1)]),
+                },
+            )]),
+            ..Default::default()
+        };
+
+        storage_access.check_calls(
+            &uo,
+            &Address::random(),
+            &stake_info,
+            &HashMap::new(),
+            &[call_info],
+        )
+    }
+
+    #[test]
+    fn storage_access_allowlisted_slot() {
+        let contract = Address::random();
+        let storage_access = StorageAccess {
+            allowed_storage: HashMap::from([(contract, HashSet::from([U256::from(5)]))]),
+        };
+
+        assert!(check(&storage_access, contract).is_ok());
+    }
+
+    #[test]
+    fn storage_access_not_allowlisted_slot() {
+        let contract = Address::random();
+        let storage_access = StorageAccess {
+            allowed_storage: HashMap::from([(contract, HashSet::from([U256::from(6)]))]),
+        };
+
+        assert!(matches!(
+            check(&storage_access, contract),
+            Err(SimulationError::StorageAccess { slot }) if slot == SLOT
+        ));
+        assert!(matches!(
+            check(&StorageAccess::default(), contract),
+            Err(SimulationError::StorageAccess { .. })
+        ));
+    }
+}
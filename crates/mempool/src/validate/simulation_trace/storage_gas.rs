@@ -0,0 +1,194 @@
+use crate::{
+    mempool::Mempool,
+    utils::div_ceil,
+    validate::{SimulationTraceCheck, SimulationTraceHelper},
+    Reputation, SimulationError,
+};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use silius_contracts::tracer::JsTracerFrame;
+use silius_primitives::UserOperation;
+use std::collections::HashSet;
+
+#[derive(Clone)]
+pub struct StorageGas {
+    /// Gas charged for the first (cold) access of a storage slot (EIP-2929)
+    pub cold_slot_cost: U256,
+    /// Safety margin (in percentage) added on top of the estimated storage gas
+    pub safety_margin_perc: U256,
+}
+
+impl StorageGas {
+    /// Counts the distinct storage slots (read or written) accessed during the validation. Every
+    /// slot is cold on its first access in the bundle transaction.
+    ///
+    /// # Arguments
+    /// `js_trace` - The [js tracer frame](JsTracerFrame) of the validation.
+    ///
+    /// # Returns
+    /// The number of cold storage slots.
+    pub fn count_cold_slots(js_trace: &JsTracerFrame) -> U256 {
+        let mut slots: HashSet<(Address, &String)> = HashSet::new();
+
+        for call_info in js_trace.calls_from_entry_point.iter() {
+            for (addr, access) in call_info.access.iter() {
+                slots.extend(access.reads.keys().map(|slot| (*addr, slot)));
+                slots.extend(access.writes.keys().map(|slot| (*addr, slot)));
+            }
+        }
+
+        U256::from(slots.len())
+    }
+
+    /// Calculates the minimal verification gas limit that covers the cold storage accesses
+    /// (including the safety margin).
+    ///
+    /// # Arguments
+    /// `cold_slots` - The number of cold storage slots.
+    ///
+    /// # Returns
+    /// The minimal verification gas limit.
+    pub fn calculate_storage_gas(&self, cold_slots: U256) -> U256 {
+        let storage_gas = cold_slots.saturating_mul(self.cold_slot_cost);
+        div_ceil(
+            storage_gas.saturating_mul(U256::from(100).saturating_add(self.safety_margin_perc)),
+            U256::from(100),
+        )
+    }
+
+    /// Checks whether the verification gas limit covers the cold storage accesses of the
+    /// validation trace.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `js_trace` - The [js tracer frame](JsTracerFrame) of the validation.
+    ///
+    /// # Returns
+    /// Nothing if the verification gas limit is sufficient, otherwise a
+    /// [SimulationError](SimulationError) is returned.
+    pub fn check_trace(
+        &self,
+        uo: &UserOperation,
+        js_trace: &JsTracerFrame,
+    ) -> Result<(), SimulationError> {
+        let cold_slots = Self::count_cold_slots(js_trace);
+        let storage_gas = self.calculate_storage_gas(cold_slots);
+
+        if uo.verification_gas_limit < storage_gas {
+            return Err(SimulationError::VerificationGasLimitTooLowForStorage {
+                verification_gas_limit: uo.verification_gas_limit,
+                storage_gas,
+                cold_slots,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SimulationTraceCheck<M> for StorageGas {
+    /// The method implementation that checks whether the verification gas limit covers the cold
+    /// storage accesses (EIP-2929) during the validation.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to be checked.
+    /// `helper` - The [SimulationTraceHelper](crate::validate::SimulationTraceHelper)
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    async fn check_user_operation(
+        &self,
+        uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        self.check_trace(uo, helper.js_trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use silius_contracts::tracer::{ReadsAndWrites, TopLevelCallInfo};
+    use silius_primitives::{
+        constants::validation::storage_gas::{COLD_SLOAD_COST, SAFETY_MARGIN_PERC},
+        UserOperationSigned,
+    };
+    use std::collections::HashMap;
+
+    fn storage_gas() -> StorageGas {
+        StorageGas {
+            cold_slot_cost: U256::from(COLD_SLOAD_COST),
+            safety_margin_perc: U256::from(SAFETY_MARGIN_PERC),
+        }
+    }
+
+    fn call_info(access: Vec<(Address, Vec<&str>, Vec<&str>)>) -> TopLevelCallInfo {
+        TopLevelCallInfo {
+            access: access
+                .into_iter()
+                .map(|(addr, reads, writes)| {
+                    (
+                        addr,
+                        ReadsAndWrites {
+                            reads: reads
+                                .into_iter()
+                                .map(|slot| (slot.into(), "0x01".into()))
+                                .collect(),
+                            writes: writes.into_iter().map(|slot| (slot.into(), 1)).collect(),
+                        },
+                    )
+                })
+                .collect::<HashMap<_, _>>(),
+            ..Default::default()
+        }
+    }
+
+    fn user_operation(verification_gas_limit: U256) -> UserOperation {
+        UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().verification_gas_limit(verification_gas_limit),
+        )
+    }
+
+    #[test]
+    fn storage_gas_distinct_slots() {
+        let (entry_point, paymaster) = (Address::random(), Address::random());
+        let js_trace = JsTracerFrame {
+            calls_from_entry_point: vec![
+                call_info(vec![(entry_point, vec!["0x01", "0x02"], vec!["0x02"])]),
+                call_info(vec![
+                    (entry_point, vec!["0x01"], vec![]),
+                    (paymaster, vec![], vec!["0x01"]),
+                ]),
+            ],
+            ..Default::default()
+        };
+
+        // the slots accessed several times (or by several entities) are cold only once
+        assert_eq!(StorageGas::count_cold_slots(&js_trace), 3.into());
+        assert_eq!(storage_gas().calculate_storage_gas(3.into()), 6930.into());
+
+        assert!(storage_gas().check_trace(&user_operation(6930.into()), &js_trace).is_ok());
+        assert!(matches!(
+            storage_gas().check_trace(&user_operation(6929.into()), &js_trace),
+            Err(SimulationError::VerificationGasLimitTooLowForStorage {
+                storage_gas,
+                cold_slots,
+                ..
+            }) if storage_gas == 6930.into() && cold_slots == 3.into()
+        ));
+    }
+
+    #[test]
+    fn storage_gas_without_storage_access() {
+        let js_trace = JsTracerFrame::default();
+
+        assert_eq!(StorageGas::count_cold_slots(&js_trace), U256::zero());
+        assert!(storage_gas().check_trace(&user_operation(U256::zero()), &js_trace).is_ok());
+    }
+}
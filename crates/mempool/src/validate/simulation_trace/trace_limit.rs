@@ -0,0 +1,160 @@
+use crate::{
+    mempool::Mempool,
+    validate::{SimulationTraceCheck, SimulationTraceHelper},
+    Reputation, SimulationError,
+};
+use ethers::{providers::Middleware, types::GethTrace};
+use silius_primitives::{
+    constants::validation::simulation_trace::{MAX_TRACE_DEPTH, MAX_TRACE_STEPS},
+    simulation::{RETURN_OPCODE, REVERT_OPCODE},
+    UserOperation,
+};
+
+/// Limits of the size of the validation trace.
+#[derive(Clone, Copy, Debug)]
+pub struct TraceLimit {
+    /// Maximum number of the call frame entries (enters and exits) in the trace
+    pub max_steps: usize,
+    /// Maximum depth of the nested calls in the trace
+    pub max_depth: usize,
+}
+
+impl Default for TraceLimit {
+    fn default() -> Self {
+        Self { max_steps: MAX_TRACE_STEPS, max_depth: MAX_TRACE_DEPTH }
+    }
+}
+
+impl TraceLimit {
+    /// Checks the number of the steps and the call depth of the trace.
+    ///
+    /// # Arguments
+    /// `steps` - The number of the call frame entries in the trace.
+    /// `types` - The types of the call frame entries (`RETURN` and `REVERT` exit a call, any
+    /// other type enters a call).
+    ///
+    /// # Returns
+    /// None if the trace is within the limits, otherwise a [SimulationError] naming the limit.
+    fn check_calls<'a>(
+        &self,
+        steps: usize,
+        types: impl Iterator<Item = &'a str>,
+    ) -> Result<(), SimulationError> {
+        if steps > self.max_steps {
+            return Err(SimulationError::TraceLimit {
+                limit: "steps".into(),
+                value: steps,
+                max: self.max_steps,
+            });
+        }
+
+        let mut depth: usize = 0;
+        for typ in types {
+            if typ == *RETURN_OPCODE || typ == *REVERT_OPCODE {
+                // the top-level return is recorded without the matching enter
+                depth = depth.saturating_sub(1);
+            } else {
+                depth += 1;
+                if depth > self.max_depth {
+                    return Err(SimulationError::TraceLimit {
+                        limit: "depth".into(),
+                        value: depth,
+                        max: self.max_depth,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the raw trace returned by the execution client before it is parsed into a
+    /// [JsTracerFrame](silius_contracts::tracer::JsTracerFrame), so oversized traces are rejected
+    /// without being deserialized.
+    ///
+    /// # Arguments
+    /// `trace` - The raw [GethTrace] of the simulation.
+    ///
+    /// # Returns
+    /// None if the trace is within the limits, otherwise a [SimulationError] naming the limit.
+    pub fn check_geth_trace(&self, trace: &GethTrace) -> Result<(), SimulationError> {
+        let calls = match trace {
+            GethTrace::Unknown(val) => match val.get("calls").and_then(|calls| calls.as_array()) {
+                Some(calls) => calls,
+                None => return Ok(()),
+            },
+            // rejected when parsed
+            GethTrace::Known(_) => return Ok(()),
+        };
+
+        self.check_calls(
+            calls.len(),
+            calls.iter().map(|call| call.get("type").and_then(|typ| typ.as_str()).unwrap_or("")),
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware> SimulationTraceCheck<M> for TraceLimit {
+    /// The method implementation that checks that the trace doesn't exceed the maximum number of
+    /// steps and the maximum call depth.
+    ///
+    /// # Arguments
+    /// `helper` - The [SimulationTraceHelper]
+    ///
+    /// # Returns
+    /// None if the check passes, otherwise a [SimulationError] error.
+    async fn check_user_operation(
+        &self,
+        _uo: &UserOperation,
+        _mempool: &Mempool,
+        _reputation: &Reputation,
+        helper: &mut SimulationTraceHelper<M>,
+    ) -> Result<(), SimulationError> {
+        let calls = &helper.js_trace.calls;
+        self.check_calls(calls.len(), calls.iter().map(|call| call.typ.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn trace(depth: usize, siblings: usize) -> GethTrace {
+        let enter = json!({ "type": "CALL", "gas": 100_000 });
+        let exit = json!({ "type": "RETURN", "gasUsed": 0, "data": "0x" });
+
+        let mut calls = vec![];
+        for _ in 0..siblings {
+            calls.extend(std::iter::repeat(enter.clone()).take(depth));
+            calls.extend(std::iter::repeat(exit.clone()).take(depth));
+        }
+
+        GethTrace::Unknown(json!({
+            "callsFromEntryPoint": [],
+            "keccak": [],
+            "logs": [],
+            "calls": calls,
+            "debug": [],
+        }))
+    }
+
+    #[test]
+    fn trace_limit_oversized_trace() {
+        let limit = TraceLimit { max_steps: 1_000, max_depth: 10 };
+
+        assert!(limit.check_geth_trace(&trace(10, 50)).is_ok());
+        assert!(matches!(
+            limit.check_geth_trace(&trace(11, 1)),
+            Err(SimulationError::TraceLimit { limit, value: 11, max: 10 }) if limit == "depth"
+        ));
+        assert!(matches!(
+            limit.check_geth_trace(&trace(1, 501)),
+            Err(SimulationError::TraceLimit { limit, value: 1_002, max: 1_000 }) if limit == "steps"
+        ));
+
+        // the default limits are generous
+        assert!(TraceLimit::default().check_geth_trace(&trace(64, 10)).is_ok());
+    }
+}
@@ -1,9 +1,17 @@
+use crate::SimulationError;
 use ethers::types::{Address, U256};
 use silius_contracts::{entry_point::SimulateValidationResult, tracer::JsTracerFrame};
 use silius_primitives::{
     constants::validation::entities::NUMBER_OF_LEVELS, get_address, reputation::StakeInfo,
     simulation::StorageMap, UserOperation,
 };
+use std::{
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Storage slots written during the validation of a user operation (per contract)
+pub type StorageWrites = HashMap<Address, HashSet<String>>;
 
 /// Helper function to extract the gas limit for verification from the simulation result
 ///
@@ -51,6 +59,35 @@ pub fn extract_timestamps(sim_res: &SimulateValidationResult) -> (U256, U256) {
     }
 }
 
+/// Helper function to extract the aggregator from the simulation result
+///
+/// # Arguments
+/// `sim_res` - The [simulation result](SimulateValidationResult) from the simulation
+///
+/// # Returns
+/// The address of the aggregator if the user operation uses signature aggregation
+pub fn extract_aggregator(sim_res: &SimulateValidationResult) -> Option<Address> {
+    match sim_res {
+        SimulateValidationResult::ValidationResult(_) => None,
+        SimulateValidationResult::ValidationResultWithAggregation(res) => {
+            Some(res.aggregator_info.0)
+        }
+    }
+}
+
+/// Helper function to get the current unix timestamp (in seconds)
+///
+/// # Returns
+/// The current unix timestamp
+pub fn current_timestamp() -> Result<U256, SimulationError> {
+    Ok(U256::from(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| SimulationError::Other { inner: err.to_string() })?
+            .as_secs(),
+    ))
+}
+
 /// Helper function to extract the stake info from the simulation result
 ///
 /// # Arguments
@@ -109,6 +146,28 @@ pub fn extract_storage_map(js_trace: &JsTracerFrame) -> StorageMap {
     storage_map
 }
 
+/// Helper function to extract the storage slots written during the validation from the simulation
+/// result
+///
+/// # Arguments
+/// `js_trace` - The [js tracer frame](JsTracerFrame) to extract the written slots from
+///
+/// # Returns
+/// The [storage writes](StorageWrites)
+pub fn extract_storage_writes(js_trace: &JsTracerFrame) -> StorageWrites {
+    let mut writes = StorageWrites::new();
+
+    for l in js_trace.calls_from_entry_point.iter() {
+        for (addr, acc) in l.access.iter() {
+            if !acc.writes.is_empty() {
+                writes.entry(*addr).or_default().extend(acc.writes.keys().cloned());
+            }
+        }
+    }
+
+    writes
+}
+
 /// Helper function to merge multiple storage maps into one.
 ///
 /// # Arguments
@@ -143,3 +202,155 @@ pub fn merge_storage_maps(storage_maps: Vec<StorageMap>) -> StorageMap {
 
     merged_map
 }
+
+/// Helper function to find the storage slots written during the validation of one user operation
+/// and accessed (read or written) during the validation of the other. Including both user
+/// operations in the same bundle may invalidate the second one. Slots only read by both user
+/// operations don't conflict. A root hash covers the whole storage of the account, so every slot
+/// of that account written by the other user operation conflicts (including when both user
+/// operations depend on the root hash).
+///
+/// # Arguments
+/// `storage_map` - The [StorageMap](StorageMap) of the first user operation
+/// `writes` - The [StorageWrites](StorageWrites) of the first user operation
+/// `other` - The [StorageMap](StorageMap) of the second user operation
+/// `other_writes` - The [StorageWrites](StorageWrites) of the second user operation
+///
+/// # Returns
+/// The set of conflicting (address, slot) pairs
+pub fn storage_map_conflicts(
+    storage_map: &StorageMap,
+    writes: &StorageWrites,
+    other: &StorageMap,
+    other_writes: &StorageWrites,
+) -> HashSet<(Address, String)> {
+    let mut conflicts = HashSet::new();
+    write_conflicts(writes, other, other_writes, &mut conflicts);
+    write_conflicts(other_writes, storage_map, writes, &mut conflicts);
+    conflicts
+}
+
+/// Collects the slots in `writes` accessed by the other user operation.
+fn write_conflicts(
+    writes: &StorageWrites,
+    other: &StorageMap,
+    other_writes: &StorageWrites,
+    conflicts: &mut HashSet<(Address, String)>,
+) {
+    for (addr, slots) in writes.iter() {
+        let root_hash = other.root_hashes.contains_key(addr);
+        let reads = other.slots.get(addr);
+        let writes = other_writes.get(addr);
+        conflicts.extend(
+            slots
+                .iter()
+                .filter(|slot| {
+                    root_hash ||
+                        reads.is_some_and(|reads| reads.contains_key(*slot)) ||
+                        writes.is_some_and(|writes| writes.contains(*slot))
+                })
+                .map(|slot| (*addr, slot.clone())),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::H256;
+    use silius_contracts::entry_point::{ValidationResult, ValidationResultWithAggregation};
+
+    fn storage_map(slots: Vec<(Address, Vec<&str>)>) -> StorageMap {
+        StorageMap {
+            root_hashes: HashMap::new(),
+            slots: slots
+                .into_iter()
+                .map(|(addr, slots)| {
+                    (addr, slots.into_iter().map(|slot| (slot.into(), "0x01".into())).collect())
+                })
+                .collect(),
+        }
+    }
+
+    fn storage_writes(slots: Vec<(Address, Vec<&str>)>) -> StorageWrites {
+        slots
+            .into_iter()
+            .map(|(addr, slots)| (addr, slots.into_iter().map(Into::into).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn storage_map_conflicts_overlapping() {
+        let addr = Address::random();
+        let addr_other = Address::random();
+        let (a, a_writes) = (
+            storage_map(vec![(addr, vec!["0x01"]), (addr_other, vec!["0x03"])]),
+            storage_writes(vec![(addr, vec!["0x02"])]),
+        );
+
+        // write-read
+        let b = storage_map(vec![(addr, vec!["0x02", "0x04"])]);
+        let expected = HashSet::from([(addr, "0x02".to_string())]);
+        assert_eq!(storage_map_conflicts(&a, &a_writes, &b, &StorageWrites::new()), expected);
+        assert_eq!(storage_map_conflicts(&b, &StorageWrites::new(), &a, &a_writes), expected);
+
+        // write-write
+        let b_writes = storage_writes(vec![(addr, vec!["0x02"])]);
+        assert_eq!(
+            storage_map_conflicts(&a, &a_writes, &StorageMap::default(), &b_writes),
+            expected
+        );
+
+        // write to an account whose root hash the other user operation depends on
+        let mut c = StorageMap::default();
+        c.root_hashes.insert(addr, H256::random());
+        assert_eq!(storage_map_conflicts(&a, &a_writes, &c, &StorageWrites::new()), expected);
+        assert_eq!(storage_map_conflicts(&c, &StorageWrites::new(), &a, &a_writes), expected);
+
+        // both depend on the root hash and one writes to the account
+        let mut d = StorageMap::default();
+        d.root_hashes.insert(addr, H256::random());
+        let d_writes = storage_writes(vec![(addr, vec!["0x05"])]);
+        assert_eq!(
+            storage_map_conflicts(&c, &StorageWrites::new(), &d, &d_writes),
+            HashSet::from([(addr, "0x05".to_string())])
+        );
+    }
+
+    #[test]
+    fn storage_map_conflicts_disjoint() {
+        let addr = Address::random();
+        let (a, a_writes) =
+            (storage_map(vec![(addr, vec!["0x01"])]), storage_writes(vec![(addr, vec!["0x02"])]));
+        let (b, b_writes) = (
+            storage_map(vec![(addr, vec!["0x01", "0x03"]), (Address::random(), vec!["0x02"])]),
+            storage_writes(vec![(addr, vec!["0x04"])]),
+        );
+
+        // the slot 0x01 is only read by both
+        assert!(storage_map_conflicts(&a, &a_writes, &b, &b_writes).is_empty());
+        assert!(storage_map_conflicts(&b, &b_writes, &a, &a_writes).is_empty());
+
+        // root hashes without any writes
+        let mut c = StorageMap::default();
+        c.root_hashes.insert(addr, H256::random());
+        assert!(
+            storage_map_conflicts(&c, &StorageWrites::new(), &c, &StorageWrites::new()).is_empty()
+        );
+    }
+
+    #[test]
+    fn extract_aggregator_from_simulation_result() {
+        let sim_res = SimulateValidationResult::ValidationResult(ValidationResult::default());
+        assert_eq!(extract_aggregator(&sim_res), None);
+
+        let aggregator = Address::random();
+        let sim_res = SimulateValidationResult::ValidationResultWithAggregation(
+            ValidationResultWithAggregation {
+                aggregator_info: (aggregator, Default::default()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(extract_aggregator(&sim_res), Some(aggregator));
+    }
+}
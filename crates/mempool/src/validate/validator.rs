@@ -1,51 +1,212 @@
 use super::{
+    deposit_cache::DepositInfoCache,
+    metrics::{on_failure, ValidationPhase, ValidationTimer},
     sanity::{
-        call_gas::CallGas, entities::Entities, max_fee::MaxFee, paymaster::Paymaster,
+        bundle_gas::BundleGas, call_gas::CallGas, denylist::Denylist,
+        dupe_init_code::DupeByInitCode, entities::Entities, max_fee::MaxFee, paymaster::Paymaster,
         sender::Sender, unstaked_entities::UnstakedEntities, verification_gas::VerificationGas,
     },
     simulation::{
-        signature::Signature, timestamp::Timestamp, verification_extra_gas::VerificationExtraGas,
+        aggregator::Aggregator, signature::Signature, timestamp::Timestamp,
+        valid_until_window::ValidUntilWindow, verification_extra_gas::VerificationExtraGas,
     },
     simulation_trace::{
-        call_stack::CallStack, code_hashes::CodeHashes, external_contracts::ExternalContracts,
-        gas::Gas, opcodes::Opcodes, storage_access::StorageAccess,
+        call_stack::CallStack, code_hashes::CodeHashes, deployment_storage::DeploymentStorage,
+        external_contracts::ExternalContracts, gas::Gas, opcodes::Opcodes,
+        storage_access::StorageAccess, storage_gas::StorageGas, trace_limit::TraceLimit,
     },
-    utils::{extract_pre_fund, extract_storage_map, extract_verification_gas_limit},
-    SanityCheck, SanityHelper, SimulationCheck, SimulationHelper, SimulationTraceCheck,
-    SimulationTraceHelper, UserOperationValidationOutcome, UserOperationValidator,
-    UserOperationValidatorMode,
+    utils::{
+        extract_aggregator, extract_pre_fund, extract_stake_info, extract_storage_map,
+        extract_storage_writes, extract_verification_gas_limit,
+    },
+    SanityCheck, SanityChecks, SanityHelper, SimulationCheck, SimulationChecks, SimulationHelper,
+    SimulationTraceCheck, SimulationTraceChecks, SimulationTraceHelper,
+    UserOperationValidationOutcome, UserOperationValidator, UserOperationValidatorMode,
+    VerifiedBlock,
 };
 use crate::{
     mempool::Mempool, InvalidMempoolUserOperationError, Reputation, SanityError, SimulationError,
 };
-use alloy_chains::Chain;
+use alloy_chains::{Chain, NamedChain};
 use enumset::EnumSet;
 use ethers::{
     providers::Middleware,
-    types::{BlockNumber, GethTrace, U256},
+    types::{Address, Block, BlockId, BlockNumber, GethTrace, H256, U256},
 };
+use futures::future::join_all;
+use parking_lot::RwLock;
 use silius_contracts::{
     entry_point::{EntryPointError, SimulateValidationResult},
     tracer::JsTracerFrame,
     EntryPoint,
 };
-use silius_primitives::{simulation::ValidationConfig, UserOperation};
-use tracing::debug;
+use silius_primitives::{
+    constants::{
+        bundler::MAX_BUNDLE_GAS,
+        mempool::GAS_INCREASE_PERC,
+        validation::{
+            dupe_init_code::{MAX_UOS_PER_WINDOW, WINDOW as DUPE_INIT_CODE_WINDOW},
+            reputation::{BAN_SLACK, MIN_INCLUSION_RATE_DENOMINATOR, THROTTLING_SLACK},
+            BLOCK_CACHE_TTL, BLOCK_FETCH_TIMEOUT, DEPOSIT_INFO_CACHE_TTL, MAX_VERIFICATION_GAS,
+            SIMULATION_MAX_RETRIES, SIMULATION_RETRY_BASE_DELAY, SIMULATION_TIMEOUT,
+            SIMULATION_TRACE_TIMEOUT,
+        },
+    },
+    simulation::ValidationConfig,
+    UserOperation,
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
+
+/// Hook which normalizes (e.g. clamps the gas fields) a copy of the user operation before the
+/// simulation.
+pub type UserOperationPreprocessor = Arc<dyn Fn(&mut UserOperation) + Send + Sync>;
 
 pub type StandardValidator<M> = StandardUserOperationValidator<
     M,
-    (Sender, VerificationGas, CallGas, MaxFee, Paymaster, Entities, UnstakedEntities),
-    (Signature, Timestamp, VerificationExtraGas),
-    (Gas, Opcodes, ExternalContracts, StorageAccess, CallStack, CodeHashes),
+    (
+        Denylist,
+        Sender,
+        VerificationGas,
+        BundleGas,
+        CallGas,
+        MaxFee,
+        Paymaster,
+        Entities,
+        UnstakedEntities,
+        DupeByInitCode,
+    ),
+    (Signature, Timestamp, VerificationExtraGas, Aggregator, Option<ValidUntilWindow>),
+    (
+        Gas,
+        Opcodes,
+        ExternalContracts,
+        StorageAccess,
+        CallStack,
+        CodeHashes,
+        Option<DeploymentStorage>,
+    ),
 >;
 
+pub type StorageGasValidator<M> = StandardUserOperationValidator<
+    M,
+    (
+        Denylist,
+        Sender,
+        VerificationGas,
+        BundleGas,
+        CallGas,
+        MaxFee,
+        Paymaster,
+        Entities,
+        UnstakedEntities,
+        DupeByInitCode,
+    ),
+    (Signature, Timestamp, VerificationExtraGas, Aggregator, Option<ValidUntilWindow>),
+    (
+        Gas,
+        Opcodes,
+        ExternalContracts,
+        StorageAccess,
+        StorageGas,
+        CallStack,
+        CodeHashes,
+        Option<DeploymentStorage>,
+    ),
+>;
+
+/// Validator with the checks composed at runtime (see
+/// [builder](StandardUserOperationValidator::builder)).
+pub type DynamicValidator<M> =
+    StandardUserOperationValidator<M, SanityChecks<M>, SimulationChecks, SimulationTraceChecks<M>>;
+
 type UnsafeValidator<M> = StandardUserOperationValidator<
     M,
-    (Sender, VerificationGas, CallGas, MaxFee, Paymaster, Entities, UnstakedEntities),
-    (Signature, Timestamp, VerificationExtraGas),
+    (
+        Denylist,
+        Sender,
+        VerificationGas,
+        BundleGas,
+        CallGas,
+        MaxFee,
+        Paymaster,
+        Entities,
+        UnstakedEntities,
+        DupeByInitCode,
+    ),
+    (Signature, Timestamp, VerificationExtraGas, Aggregator, Option<ValidUntilWindow>),
     (),
 >;
 
+/// Calls the function until it succeeds or fails with an error that is not
+/// [transient](EntryPointError::is_transient), at most `max_retries + 1` times. The delay between
+/// the calls starts at `base_delay` and doubles on every retry.
+///
+/// # Arguments
+/// `max_retries` - The maximum number of retries.
+/// `base_delay` - The delay before the first retry.
+/// `f` - The function to call.
+///
+/// # Returns
+/// The result of the last call.
+async fn retry_transient<T, F, Fut>(
+    max_retries: u32,
+    base_delay: Duration,
+    mut f: F,
+) -> Result<T, EntryPointError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, EntryPointError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Err(err) if err.is_transient() && attempt < max_retries => {
+                let delay = base_delay.saturating_mul(2_u32.saturating_pow(attempt));
+                debug!("Retrying simulation in {delay:?} after transient error: {err}");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// The latest block which the user operations are verified on.
+#[derive(Debug, Clone, Copy)]
+struct CachedBlock {
+    /// Hash and number of the block
+    block: VerifiedBlock,
+    /// Time when the block was fetched
+    fetched_at: Instant,
+}
+
+/// Timeouts of the external calls of the validation stages.
+#[derive(Debug, Clone, Copy)]
+pub struct StageTimeouts {
+    /// Timeout of the simulation of the validation (including the retries)
+    pub simulation: Duration,
+    /// Timeout of the simulation of the validation with the trace
+    pub trace: Duration,
+    /// Timeout of fetching the latest block
+    pub block: Duration,
+}
+
+impl Default for StageTimeouts {
+    fn default() -> Self {
+        Self {
+            simulation: Duration::from_secs(SIMULATION_TIMEOUT),
+            trace: Duration::from_secs(SIMULATION_TRACE_TIMEOUT),
+            block: Duration::from_secs(BLOCK_FETCH_TIMEOUT),
+        }
+    }
+}
+
 /// Standard implementation of [UserOperationValidator].
 pub struct StandardUserOperationValidator<M: Middleware + 'static, SanCk, SimCk, SimTrCk>
 where
@@ -53,8 +214,8 @@ where
     SimCk: SimulationCheck,
     SimTrCk: SimulationTraceCheck<M>,
 {
-    /// The [EntryPoint](EntryPoint) object.
-    entry_point: EntryPoint<M>,
+    /// The [EntryPoint](EntryPoint) objects keyed by the entry point address.
+    entry_points: HashMap<Address, EntryPoint<M>>,
     /// A [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID.
     chain: Chain,
     /// An array of [SanityChecks](SanityCheck).
@@ -63,6 +224,27 @@ where
     simulation_checks: SimCk,
     /// An array of [SimulationTraceChecks](SimulationTraceCheck).
     simulation_trace_checks: SimTrCk,
+    /// For how long the latest block is reused between validations (`None` disables caching).
+    block_cache_ttl: Option<Duration>,
+    /// The cached latest block (shared between clones).
+    block_cache: Arc<RwLock<Option<CachedBlock>>>,
+    /// The cached deposit info of the entities (shared between clones).
+    deposit_cache: DepositInfoCache,
+    /// How many times the simulation failed due to a transient provider error is retried.
+    simulation_max_retries: u32,
+    /// Delay before the first retry of the simulation (doubled on every retry).
+    simulation_retry_base_delay: Duration,
+    /// Hook applied to a copy of the user operation after the sanity checks and before the
+    /// simulation (`None` simulates the user operation as is).
+    preprocess: Option<UserOperationPreprocessor>,
+    /// Timeouts of the external calls of the validation stages.
+    timeouts: StageTimeouts,
+    /// Limits of the validation trace, checked before the trace is parsed.
+    trace_limit: TraceLimit,
+    /// Whether the provider is an archive node (required to validate on historical blocks).
+    archive: bool,
+    /// The historical block the user operations are simulated on (`None` for the latest block).
+    historical_block: Option<u64>,
 }
 
 impl<M: Middleware + Clone + 'static, SanCk, SimCk, SimTrCk> Clone
@@ -74,11 +256,141 @@ where
 {
     fn clone(&self) -> Self {
         Self {
-            entry_point: self.entry_point.clone(),
+            entry_points: self.entry_points.clone(),
             chain: self.chain,
             sanity_checks: self.sanity_checks.clone(),
             simulation_checks: self.simulation_checks.clone(),
             simulation_trace_checks: self.simulation_trace_checks.clone(),
+            block_cache_ttl: self.block_cache_ttl,
+            block_cache: self.block_cache.clone(),
+            deposit_cache: self.deposit_cache.clone(),
+            simulation_max_retries: self.simulation_max_retries,
+            simulation_retry_base_delay: self.simulation_retry_base_delay,
+            preprocess: self.preprocess.clone(),
+            timeouts: self.timeouts,
+            trace_limit: self.trace_limit,
+            archive: self.archive,
+            historical_block: self.historical_block,
+        }
+    }
+}
+
+impl<M: Middleware + 'static> DynamicValidator<M> {
+    /// Creates a new [StandardUserOperationValidator] without any checks. The checks are added
+    /// with [add_sanity_check](Self::add_sanity_check),
+    /// [add_simulation_check](Self::add_simulation_check) and
+    /// [add_trace_check](Self::add_trace_check) and run in the order they were added.
+    ///
+    /// # Arguments
+    /// `entry_point` - [EntryPoint] object.
+    /// `chain` - A [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID.
+    ///
+    /// # Returns
+    /// A new [DynamicValidator].
+    pub fn builder(entry_point: EntryPoint<M>, chain: Chain) -> Self {
+        Self::new(
+            entry_point,
+            chain,
+            vec![],
+            vec![],
+            vec![],
+            Some(Duration::from_secs(BLOCK_CACHE_TTL)),
+        )
+    }
+
+    /// Adds a sanity check.
+    ///
+    /// # Arguments
+    /// `check` - The [SanityCheck].
+    ///
+    /// # Returns
+    /// The [DynamicValidator] with the sanity check.
+    pub fn add_sanity_check(mut self, check: Box<dyn SanityCheck<M>>) -> Self {
+        self.sanity_checks.push(Arc::from(check));
+        self
+    }
+
+    /// Adds a simulation check.
+    ///
+    /// # Arguments
+    /// `check` - The [SimulationCheck].
+    ///
+    /// # Returns
+    /// The [DynamicValidator] with the simulation check.
+    pub fn add_simulation_check(mut self, check: Box<dyn SimulationCheck>) -> Self {
+        self.simulation_checks.push(Arc::from(check));
+        self
+    }
+
+    /// Adds a simulation trace check.
+    ///
+    /// # Arguments
+    /// `check` - The [SimulationTraceCheck].
+    ///
+    /// # Returns
+    /// The [DynamicValidator] with the simulation trace check.
+    pub fn add_trace_check(mut self, check: Box<dyn SimulationTraceCheck<M>>) -> Self {
+        self.simulation_trace_checks.push(Arc::from(check));
+        self
+    }
+}
+
+/// Limits and options of the canonical validators (see [new_canonical]).
+#[derive(Clone, Debug)]
+pub struct CanonicalConfig {
+    /// Max verification gas that bundler would accept for one user operation
+    pub max_verification_gas: U256,
+    /// Max aggregate gas of the user operations in one bundle
+    pub max_bundle_gas: U256,
+    /// Min priority fee per gas that bundler would accept for one user operation
+    pub min_priority_fee_per_gas: U256,
+    /// Gas increase percentage that bundler would accept for overwriting one user operation
+    pub gas_increase_perc: U256,
+    /// Denied senders, factories and paymasters (a clone shares the lists, so they can be updated
+    /// at runtime)
+    pub denylist: Denylist,
+    /// Signature aggregators whose user operations are accepted (`None` accepts any aggregator)
+    pub aggregator_allowlist: Option<HashSet<Address>>,
+    /// Max number of seconds a user operation may be valid for (`None` accepts any `validUntil`)
+    pub max_valid_until_window: Option<U256>,
+    /// Flag the user operations reading TIMESTAMP or NUMBER in the outcome instead of rejecting
+    /// them
+    pub flag_time_dependent: bool,
+    /// Restrict the storage writes of the factory while deploying the account (see
+    /// [DeploymentStorage])
+    pub restrict_deployment_storage: bool,
+}
+
+impl Default for CanonicalConfig {
+    fn default() -> Self {
+        Self {
+            max_verification_gas: U256::from(MAX_VERIFICATION_GAS),
+            max_bundle_gas: U256::from(MAX_BUNDLE_GAS),
+            min_priority_fee_per_gas: U256::zero(),
+            gas_increase_perc: U256::from(GAS_INCREASE_PERC),
+            denylist: Denylist::default(),
+            aggregator_allowlist: None,
+            max_valid_until_window: None,
+            flag_time_dependent: false,
+            restrict_deployment_storage: false,
+        }
+    }
+}
+
+impl CanonicalConfig {
+    /// Creates a [CanonicalConfig] with the [defaults](ChainDefaults) of the chain.
+    ///
+    /// # Arguments
+    /// `chain` - The [Chain](Chain) the user operations are validated for
+    ///
+    /// # Returns
+    /// The [CanonicalConfig](CanonicalConfig) for the chain
+    pub fn for_chain(chain: &Chain) -> Self {
+        let defaults = ChainDefaults::from_chain(chain);
+        Self {
+            max_verification_gas: defaults.max_verification_gas,
+            min_priority_fee_per_gas: defaults.min_priority_fee_per_gas,
+            ..Default::default()
         }
     }
 }
@@ -89,57 +401,229 @@ where
 /// # Arguments
 /// `entry_point` - [EntryPoint] object.
 /// `chain` - A [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID.
-/// `max_verification_gas` - max verification gas that bundler would accept for one user operation
-/// `min_priority_fee_per_gas` - min priority fee per gas that bundler would accept for one user
-/// operation `max_uos_per_sender` - max user operations that bundler would accept from one sender
-/// `gas_increase_perc` - gas increase percentage that bundler would accept for overwriting one user
-/// operation
+/// `config` - The [CanonicalConfig] with the limits of the checks
 ///
 /// # Returns
 /// A new [StandardUserOperationValidator].
 pub fn new_canonical<M: Middleware + 'static>(
     entry_point: EntryPoint<M>,
     chain: Chain,
-    max_verification_gas: U256,
-    min_priority_fee_per_gas: U256,
+    config: CanonicalConfig,
+) -> StandardValidator<M> {
+    StandardUserOperationValidator::new(
+        entry_point,
+        chain,
+        (
+            config.denylist,
+            Sender { gas_increase_perc: config.gas_increase_perc },
+            VerificationGas::new(config.max_verification_gas),
+            BundleGas { max_bundle_gas: config.max_bundle_gas },
+            CallGas,
+            MaxFee { min_priority_fee_per_gas: config.min_priority_fee_per_gas },
+            Paymaster,
+            Entities,
+            UnstakedEntities {
+                min_inclusion_rate_denominator: MIN_INCLUSION_RATE_DENOMINATOR,
+                throttling_slack: THROTTLING_SLACK,
+                ban_slack: BAN_SLACK,
+                stake_thresholds: Default::default(),
+            },
+            DupeByInitCode::new(MAX_UOS_PER_WINDOW, Duration::from_secs(DUPE_INIT_CODE_WINDOW)),
+        ),
+        (
+            Signature,
+            Timestamp,
+            VerificationExtraGas,
+            Aggregator { allowlist: config.aggregator_allowlist },
+            config
+                .max_valid_until_window
+                .map(|max_valid_until_window| ValidUntilWindow { max_valid_until_window }),
+        ),
+        (
+            Gas,
+            Opcodes { flag_time_dependent: config.flag_time_dependent },
+            ExternalContracts,
+            StorageAccess::default(),
+            CallStack,
+            CodeHashes,
+            config.restrict_deployment_storage.then_some(DeploymentStorage),
+        ),
+        Some(Duration::from_secs(BLOCK_CACHE_TTL)),
+    )
+}
+
+/// Default validation limits of the canonical validator for a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainDefaults {
+    /// Max verification gas that bundler would accept for one user operation
+    pub max_verification_gas: U256,
+    /// Min priority fee per gas that bundler would accept for one user operation
+    pub min_priority_fee_per_gas: U256,
+}
+
+impl ChainDefaults {
+    /// Selects the defaults for the given chain. Polygon enforces a minimum priority fee of 30
+    /// gwei, the OP stack L2s have tiny priority fees and Arbitrum ignores the priority fee (but
+    /// its gas units are cheaper, so more verification gas is accepted).
+    ///
+    /// # Arguments
+    /// `chain` - The [Chain](Chain) the user operations are validated for
+    ///
+    /// # Returns
+    /// The [ChainDefaults](ChainDefaults) for the chain (no minimum priority fee for unknown
+    /// chains)
+    pub fn from_chain(chain: &Chain) -> Self {
+        let (max_verification_gas, min_priority_fee_per_gas) = match chain.named() {
+            Some(NamedChain::Mainnet | NamedChain::Sepolia | NamedChain::Holesky) => {
+                (MAX_VERIFICATION_GAS, 100_000_000)
+            }
+            Some(NamedChain::Polygon | NamedChain::PolygonMumbai | NamedChain::PolygonAmoy) => {
+                (MAX_VERIFICATION_GAS, 30_000_000_000)
+            }
+            Some(
+                NamedChain::Optimism |
+                NamedChain::OptimismSepolia |
+                NamedChain::Base |
+                NamedChain::BaseSepolia,
+            ) => (MAX_VERIFICATION_GAS, 1_000_000),
+            Some(NamedChain::Arbitrum | NamedChain::ArbitrumNova | NamedChain::ArbitrumSepolia) => {
+                (2 * MAX_VERIFICATION_GAS, 0)
+            }
+            _ => (MAX_VERIFICATION_GAS, 0),
+        };
+
+        Self {
+            max_verification_gas: U256::from(max_verification_gas),
+            min_priority_fee_per_gas: U256::from(min_priority_fee_per_gas),
+        }
+    }
+}
+
+/// Creates a new [StandardUserOperationValidator] with the default checks for canonical mempool
+/// and the [defaults](ChainDefaults) of the chain (see [new_canonical] to set the limits
+/// explicitly).
+///
+/// # Arguments
+/// `entry_point` - [EntryPoint] object.
+/// `chain` - A [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID.
+///
+/// # Returns
+/// A new [StandardUserOperationValidator].
+pub fn new_canonical_for_chain<M: Middleware + 'static>(
+    entry_point: EntryPoint<M>,
+    chain: Chain,
 ) -> StandardValidator<M> {
+    new_canonical(entry_point, chain, CanonicalConfig::for_chain(&chain))
+}
+
+/// Creates a new [StandardUserOperationValidator] with the default checks for canonical mempool
+/// and an additional [StorageGas] simulation trace check, which rejects user operations whose
+/// verification gas limit cannot cover the cold storage accesses (EIP-2929) of the validation.
+///
+/// # Arguments
+/// `entry_point` - [EntryPoint] object.
+/// `chain` - A [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID.
+/// `config` - The [CanonicalConfig] with the limits of the checks
+/// `storage_gas` - The [StorageGas] check with the cold slot cost and the safety margin
+///
+/// # Returns
+/// A new [StandardUserOperationValidator].
+pub fn new_canonical_with_storage_gas<M: Middleware + 'static>(
+    entry_point: EntryPoint<M>,
+    chain: Chain,
+    config: CanonicalConfig,
+    storage_gas: StorageGas,
+) -> StorageGasValidator<M> {
     StandardUserOperationValidator::new(
         entry_point,
         chain,
         (
-            Sender,
-            VerificationGas { max_verification_gas },
+            config.denylist,
+            Sender { gas_increase_perc: config.gas_increase_perc },
+            VerificationGas::new(config.max_verification_gas),
+            BundleGas { max_bundle_gas: config.max_bundle_gas },
             CallGas,
-            MaxFee { min_priority_fee_per_gas },
+            MaxFee { min_priority_fee_per_gas: config.min_priority_fee_per_gas },
             Paymaster,
             Entities,
-            UnstakedEntities,
+            UnstakedEntities {
+                min_inclusion_rate_denominator: MIN_INCLUSION_RATE_DENOMINATOR,
+                throttling_slack: THROTTLING_SLACK,
+                ban_slack: BAN_SLACK,
+                stake_thresholds: Default::default(),
+            },
+            DupeByInitCode::new(MAX_UOS_PER_WINDOW, Duration::from_secs(DUPE_INIT_CODE_WINDOW)),
         ),
-        (Signature, Timestamp, VerificationExtraGas),
-        (Gas, Opcodes, ExternalContracts, StorageAccess, CallStack, CodeHashes),
+        (
+            Signature,
+            Timestamp,
+            VerificationExtraGas,
+            Aggregator { allowlist: config.aggregator_allowlist },
+            config
+                .max_valid_until_window
+                .map(|max_valid_until_window| ValidUntilWindow { max_valid_until_window }),
+        ),
+        (
+            Gas,
+            Opcodes { flag_time_dependent: config.flag_time_dependent },
+            ExternalContracts,
+            StorageAccess::default(),
+            storage_gas,
+            CallStack,
+            CodeHashes,
+            config.restrict_deployment_storage.then_some(DeploymentStorage),
+        ),
+        Some(Duration::from_secs(BLOCK_CACHE_TTL)),
     )
 }
 
+/// Creates a new [StandardUserOperationValidator] with the sanity and simulation checks for
+/// canonical mempool, but without the simulation trace checks (`flag_time_dependent` and
+/// `restrict_deployment_storage` of the config are ignored).
+///
+/// # Arguments
+/// `entry_point` - [EntryPoint] object.
+/// `chain` - A [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID.
+/// `config` - The [CanonicalConfig] with the limits of the checks
+///
+/// # Returns
+/// A new [StandardUserOperationValidator].
 pub fn new_canonical_unsafe<M: Middleware + Clone + 'static>(
     entry_point: EntryPoint<M>,
     chain: Chain,
-    max_verification_gas: U256,
-    min_priority_fee_per_gas: U256,
+    config: CanonicalConfig,
 ) -> UnsafeValidator<M> {
     StandardUserOperationValidator::new(
         entry_point.clone(),
         chain,
         (
-            Sender,
-            VerificationGas { max_verification_gas },
+            config.denylist,
+            Sender { gas_increase_perc: config.gas_increase_perc },
+            VerificationGas::new(config.max_verification_gas),
+            BundleGas { max_bundle_gas: config.max_bundle_gas },
             CallGas,
-            MaxFee { min_priority_fee_per_gas },
+            MaxFee { min_priority_fee_per_gas: config.min_priority_fee_per_gas },
             Paymaster,
             Entities,
-            UnstakedEntities,
+            UnstakedEntities {
+                min_inclusion_rate_denominator: MIN_INCLUSION_RATE_DENOMINATOR,
+                throttling_slack: THROTTLING_SLACK,
+                ban_slack: BAN_SLACK,
+                stake_thresholds: Default::default(),
+            },
+            DupeByInitCode::new(MAX_UOS_PER_WINDOW, Duration::from_secs(DUPE_INIT_CODE_WINDOW)),
+        ),
+        (
+            Signature,
+            Timestamp,
+            VerificationExtraGas,
+            Aggregator { allowlist: config.aggregator_allowlist },
+            config
+                .max_valid_until_window
+                .map(|max_valid_until_window| ValidUntilWindow { max_valid_until_window }),
         ),
-        (Signature, Timestamp, VerificationExtraGas),
         (),
+        Some(Duration::from_secs(BLOCK_CACHE_TTL)),
     )
 }
 
@@ -150,14 +634,360 @@ where
     SimCk: SimulationCheck,
     SimTrCk: SimulationTraceCheck<M>,
 {
+    /// Creates a new [StandardUserOperationValidator] for a single entry point.
+    ///
+    /// # Arguments
+    /// `entry_point` - [EntryPoint] object.
+    /// `chain` - A [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID.
+    /// `sanity_checks` - The [SanityChecks](SanityCheck).
+    /// `simulation_checks` - The [SimulationChecks](SimulationCheck).
+    /// `simulation_trace_checks` - The [SimulationTraceChecks](SimulationTraceCheck).
+    /// `block_cache_ttl` - For how long the latest block is reused between validations (`None`
+    /// disables caching).
+    ///
+    /// # Returns
+    /// A new [StandardUserOperationValidator].
     pub fn new(
         entry_point: EntryPoint<M>,
         chain: Chain,
         sanity_checks: SanCk,
         simulation_checks: SimCk,
         simulation_trace_checks: SimTrCk,
+        block_cache_ttl: Option<Duration>,
     ) -> Self {
-        Self { entry_point, chain, sanity_checks, simulation_checks, simulation_trace_checks }
+        Self::from_entry_points(
+            HashMap::from([(entry_point.address(), entry_point)]),
+            chain,
+            sanity_checks,
+            simulation_checks,
+            simulation_trace_checks,
+            block_cache_ttl,
+        )
+    }
+
+    /// Creates a new [StandardUserOperationValidator] which validates user operations of
+    /// multiple entry points. The entry point is selected per user operation.
+    ///
+    /// # Arguments
+    /// `entry_points` - [EntryPoint] objects keyed by the entry point address.
+    /// `chain` - A [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID.
+    /// `sanity_checks` - The [SanityChecks](SanityCheck).
+    /// `simulation_checks` - The [SimulationChecks](SimulationCheck).
+    /// `simulation_trace_checks` - The [SimulationTraceChecks](SimulationTraceCheck).
+    /// `block_cache_ttl` - For how long the latest block is reused between validations (`None`
+    /// disables caching).
+    ///
+    /// # Returns
+    /// A new [StandardUserOperationValidator], or an error if no entry point is given.
+    pub fn new_with_entry_points(
+        entry_points: HashMap<Address, EntryPoint<M>>,
+        chain: Chain,
+        sanity_checks: SanCk,
+        simulation_checks: SimCk,
+        simulation_trace_checks: SimTrCk,
+        block_cache_ttl: Option<Duration>,
+    ) -> eyre::Result<Self> {
+        if entry_points.is_empty() {
+            return Err(eyre::format_err!("At least one entry point is required"));
+        }
+
+        Ok(Self::from_entry_points(
+            entry_points,
+            chain,
+            sanity_checks,
+            simulation_checks,
+            simulation_trace_checks,
+            block_cache_ttl,
+        ))
+    }
+
+    fn from_entry_points(
+        entry_points: HashMap<Address, EntryPoint<M>>,
+        chain: Chain,
+        sanity_checks: SanCk,
+        simulation_checks: SimCk,
+        simulation_trace_checks: SimTrCk,
+        block_cache_ttl: Option<Duration>,
+    ) -> Self {
+        Self {
+            entry_points,
+            chain,
+            sanity_checks,
+            simulation_checks,
+            simulation_trace_checks,
+            block_cache_ttl,
+            block_cache: Arc::new(RwLock::new(None)),
+            deposit_cache: DepositInfoCache::new(Duration::from_secs(DEPOSIT_INFO_CACHE_TTL)),
+            simulation_max_retries: SIMULATION_MAX_RETRIES,
+            simulation_retry_base_delay: Duration::from_millis(SIMULATION_RETRY_BASE_DELAY),
+            preprocess: None,
+            timeouts: StageTimeouts::default(),
+            trace_limit: TraceLimit::default(),
+            archive: false,
+            historical_block: None,
+        }
+    }
+
+    /// Sets how the simulation failed due to a transient provider error (e.g. timeout or
+    /// connection reset) is retried.
+    ///
+    /// # Arguments
+    /// `max_retries` - The maximum number of retries (0 disables retrying).
+    /// `base_delay` - The delay before the first retry, doubled on every retry.
+    ///
+    /// # Returns
+    /// The [StandardUserOperationValidator] with the retry settings.
+    pub fn with_simulation_retries(mut self, max_retries: u32, base_delay: Duration) -> Self {
+        self.simulation_max_retries = max_retries;
+        self.simulation_retry_base_delay = base_delay;
+        self
+    }
+
+    /// Sets the maximum number of (entry point, entity) pairs whose deposit info is cached.
+    ///
+    /// # Arguments
+    /// `max_entries` - The capacity of the deposit info cache.
+    ///
+    /// # Returns
+    /// The [StandardUserOperationValidator] with the deposit info cache capacity.
+    pub fn with_deposit_cache_max_entries(mut self, max_entries: usize) -> Self {
+        self.deposit_cache = self.deposit_cache.with_max_entries(max_entries);
+        self
+    }
+
+    /// Sets the chain the user operations are validated for (e.g. after the chain ID changed).
+    ///
+    /// # Arguments
+    /// `chain` - A [EIP-155](https://eips.ethereum.org/EIPS/eip-155) chain ID.
+    ///
+    /// # Returns
+    /// The [StandardUserOperationValidator] with the chain.
+    pub fn with_chain(mut self, chain: Chain) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Sets the timeouts of the external calls of the validation stages (simulation, simulation
+    /// trace, block fetch).
+    ///
+    /// # Arguments
+    /// `timeouts` - The [StageTimeouts](StageTimeouts).
+    ///
+    /// # Returns
+    /// The [StandardUserOperationValidator] with the timeouts.
+    pub fn with_stage_timeouts(mut self, timeouts: StageTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Sets the limits of the validation trace (number of steps and call depth). Traces exceeding
+    /// the limits are rejected before they are parsed.
+    ///
+    /// # Arguments
+    /// `trace_limit` - The [TraceLimit](TraceLimit).
+    ///
+    /// # Returns
+    /// The [StandardUserOperationValidator] with the trace limits.
+    pub fn with_trace_limit(mut self, trace_limit: TraceLimit) -> Self {
+        self.trace_limit = trace_limit;
+        self
+    }
+
+    /// Sets whether the provider is an archive node, which enables
+    /// [validate_at_block](Self::validate_at_block).
+    ///
+    /// # Arguments
+    /// `archive` - Whether the provider serves the state of historical blocks.
+    ///
+    /// # Returns
+    /// The [StandardUserOperationValidator] with the archive capability.
+    pub fn with_archive(mut self, archive: bool) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Sets the hook which normalizes the user operations before the simulation. The hook runs
+    /// after the sanity checks on a copy of the user operation, so the user operation passed by
+    /// the caller (and added to the mempool) is never mutated. The simulation, the simulation
+    /// checks and the simulation trace checks see the preprocessed copy.
+    ///
+    /// # Arguments
+    /// `preprocess` - The [UserOperationPreprocessor](UserOperationPreprocessor) hook.
+    ///
+    /// # Returns
+    /// The [StandardUserOperationValidator] with the preprocessing hook.
+    pub fn with_preprocess(mut self, preprocess: UserOperationPreprocessor) -> Self {
+        self.preprocess = Some(preprocess);
+        self
+    }
+
+    /// Applies the preprocessing hook (if any) to a copy of the user operation.
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to preprocess.
+    ///
+    /// # Returns
+    /// The preprocessed copy, or the user operation itself if no hook is set.
+    fn preprocessed<'a>(&self, uo: &'a UserOperation) -> Cow<'a, UserOperation> {
+        match self.preprocess.as_ref() {
+            Some(preprocess) => {
+                let mut uo = uo.clone();
+                preprocess(&mut uo);
+                Cow::Owned(uo)
+            }
+            None => Cow::Borrowed(uo),
+        }
+    }
+
+    /// Gets the funds the sender can cover the prefund of the user operation with: its deposit in
+    /// the entry point (served from the [DepositInfoCache] if possible) plus its balance (the
+    /// account pays the missing funds during validation).
+    ///
+    /// # Arguments
+    /// `ep` - The [EntryPoint] object.
+    /// `uo` - The [UserOperation] to get the funds for.
+    ///
+    /// # Returns
+    /// The funds of the sender, None if the user operation uses a paymaster.
+    async fn sender_funds(
+        &self,
+        ep: &EntryPoint<M>,
+        uo: &UserOperation,
+    ) -> Result<Option<U256>, SimulationError> {
+        if uo.get_entities().2.is_some() {
+            return Ok(None);
+        }
+
+        let deposit = self.deposit_cache.get_deposit_info(ep, &uo.sender).await?.deposit;
+        let balance = ep
+            .eth_client()
+            .get_balance(uo.sender, None)
+            .await
+            .map_err(|err| SimulationError::Provider { inner: err.to_string() })?;
+
+        Ok(Some(balance.saturating_add(deposit.into())))
+    }
+
+    /// Preloads the deposit info of the given entities (e.g. senders and paymasters) from all
+    /// entry points, so the first validation doesn't need a cold RPC call. Failed queries are
+    /// skipped and retried on validation.
+    ///
+    /// # Arguments
+    /// `addresses` - The addresses of the entities.
+    pub async fn warm_up(&self, addresses: &[Address]) {
+        let queries = self.entry_points.values().flat_map(|ep| {
+            addresses.iter().map(move |addr| async move {
+                (addr, self.deposit_cache.get_deposit_info(ep, addr).await)
+            })
+        });
+
+        for (addr, res) in join_all(queries).await {
+            if let Err(err) = res {
+                warn!("Failed to preload deposit info of {addr:?}: {err:?}");
+            }
+        }
+    }
+
+    /// Invalidates the cached deposit info of the entity (e.g. after a deposit-changing
+    /// transaction).
+    ///
+    /// # Arguments
+    /// `addr` - The address of the entity.
+    pub fn invalidate_deposit_info(&self, addr: &Address) {
+        self.deposit_cache.invalidate(addr);
+    }
+
+    /// Creates a copy of the validator whose caches (latest block, deposit info) start from the
+    /// current state but are not shared with this validator, so validations with the copy (e.g.
+    /// dry runs) leave the caches untouched.
+    ///
+    /// # Returns
+    /// A detached [StandardUserOperationValidator].
+    pub fn detached(&self) -> Self
+    where
+        Self: Clone,
+    {
+        let mut validator = self.clone();
+        validator.block_cache = Arc::new(RwLock::new(*self.block_cache.read()));
+        validator.deposit_cache = self.deposit_cache.snapshot();
+        validator
+    }
+
+    /// Validates the user operation on the state of a historical block (e.g. to debug why the
+    /// user operation wasn't included). The simulations run on the state of the block, while the
+    /// sanity checks and the funds of the sender use the latest state. Requires an archive node
+    /// (see [with_archive](Self::with_archive)).
+    ///
+    /// # Arguments
+    /// `uo` - The [UserOperation](UserOperation) to validate.
+    /// `entry_point` - The address of the entry point.
+    /// `mempool` - The [Mempool](Mempool).
+    /// `reputation` - The [Reputation](Reputation).
+    /// `mode` - The [validation modes](UserOperationValidatorMode).
+    /// `block_number` - The number of the block to validate on.
+    ///
+    /// # Returns
+    /// The [UserOperationValidationOutcome] verified on the block, otherwise a
+    /// [InvalidMempoolUserOperationError] ([SimulationError::HistoricalState] if the state of
+    /// the block is not available).
+    pub async fn validate_at_block(
+        &self,
+        uo: &UserOperation,
+        entry_point: &Address,
+        mempool: &Mempool,
+        reputation: &Reputation,
+        mode: EnumSet<UserOperationValidatorMode>,
+        block_number: u64,
+    ) -> Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError>
+    where
+        Self: Clone,
+    {
+        if !self.archive {
+            return Err(SimulationError::HistoricalState {
+                block: block_number,
+                inner: "the provider is not an archive node".into(),
+            }
+            .into());
+        }
+
+        let block: Block<H256> = self
+            .entry_point(entry_point)?
+            .eth_client()
+            .get_block(BlockNumber::Number(block_number.into()))
+            .await
+            .map_err(|e| SanityError::Provider { inner: e.to_string() })?
+            .ok_or(SimulationError::HistoricalState {
+                block: block_number,
+                inner: "block not found".into(),
+            })?;
+
+        let mut validator = self.detached();
+        validator.historical_block = Some(block_number);
+        validator
+            .validate_user_operation_with_block(
+                uo,
+                entry_point,
+                mempool,
+                reputation,
+                None,
+                mode,
+                block,
+            )
+            .await
+    }
+
+    /// Gets the [EntryPoint] object for the given entry point address.
+    ///
+    /// # Arguments
+    /// `entry_point` - The address of the entry point.
+    ///
+    /// # Returns
+    /// The [EntryPoint] object if the entry point is supported, otherwise a
+    /// [SanityError](SanityError).
+    fn entry_point(&self, entry_point: &Address) -> Result<&EntryPoint<M>, SanityError> {
+        self.entry_points
+            .get(entry_point)
+            .ok_or(SanityError::UnsupportedEntryPoint { entry_point: *entry_point })
     }
 
     /// Simulates validation of a [UserOperation](UserOperation) via the
@@ -165,22 +995,55 @@ where
     /// [entry_point](crate::entry_point::EntryPoint).
     ///
     /// # Arguments
+    /// `entry_point` - [EntryPoint] object to simulate validation with.
     /// `uo` - [UserOperation](UserOperation) to simulate validation on.
     ///
+    /// Transient provider errors are retried with exponential backoff (within the simulation
+    /// timeout).
+    ///
     /// # Returns
     /// A [SimulateValidationResult](crate::entry_point::SimulateValidationResult) if the simulation
     /// was successful, otherwise a [SimulationError](crate::error::SimulationError).
     async fn simulate_validation(
         &self,
+        entry_point: &EntryPoint<M>,
         uo: &UserOperation,
     ) -> Result<SimulateValidationResult, SimulationError> {
-        match self.entry_point.simulate_validation(uo.user_operation.clone()).await {
-            Ok(res) => Ok(res),
-            Err(err) => Err(match err {
-                EntryPointError::FailedOp(op) => SimulationError::Validation { inner: op.reason },
-                EntryPointError::Provider { inner } => SimulationError::Provider { inner },
-                _ => SimulationError::Other { inner: err.to_string() },
+        let res = tokio::time::timeout(
+            self.timeouts.simulation,
+            retry_transient(self.simulation_max_retries, self.simulation_retry_base_delay, || {
+                entry_point.simulate_validation_at(uo.user_operation.clone(), self.block_id())
             }),
+        )
+        .await
+        .map_err(|_| SimulationError::Timeout {
+            stage: "simulation".into(),
+            timeout_ms: self.timeouts.simulation.as_millis() as u64,
+        })?;
+
+        res.map_err(|err| self.simulation_error(err))
+    }
+
+    /// Simulates the execution phase of a [UserOperation](UserOperation) via the
+    /// [simulate_execution](crate::entry_point::EntryPoint::simulate_execution) method of the
+    /// [entry_point](crate::entry_point::EntryPoint).
+    ///
+    /// # Arguments
+    /// `entry_point` - [EntryPoint] object to simulate the execution with.
+    /// `uo` - [UserOperation](UserOperation) to simulate the execution of.
+    ///
+    /// # Returns
+    /// `None` if the execution succeeds, the revert reason (empty if not available) if it reverts,
+    /// otherwise a [SimulationError](crate::error::SimulationError).
+    async fn simulate_execution(
+        entry_point: &EntryPoint<M>,
+        uo: &UserOperation,
+    ) -> Result<Option<String>, SimulationError> {
+        match entry_point.simulate_execution(uo.user_operation.clone()).await {
+            Ok(_) => Ok(None),
+            Err(EntryPointError::ExecutionReverted(reason)) => Ok(Some(reason)),
+            Err(EntryPointError::Provider { inner }) => Err(SimulationError::Provider { inner }),
+            Err(err) => Err(SimulationError::Other { inner: err.to_string() }),
         }
     }
 
@@ -189,6 +1052,7 @@ where
     /// method of the [entry_point](crate::entry_point::EntryPoint)
     ///
     /// # Arguments
+    /// `entry_point` - [EntryPoint] object to simulate validation with.
     /// `uo` - [UserOperation](UserOperation) to simulate validation on.
     ///
     /// # Returns
@@ -196,15 +1060,36 @@ where
     /// [SimulationError](crate::error::SimulationError).
     async fn simulate_validation_trace(
         &self,
+        entry_point: &EntryPoint<M>,
         uo: &UserOperation,
     ) -> Result<GethTrace, SimulationError> {
-        match self.entry_point.simulate_validation_trace(uo.user_operation.clone()).await {
-            Ok(trace) => Ok(trace),
-            Err(err) => Err(match err {
-                EntryPointError::FailedOp(op) => SimulationError::Validation { inner: op.reason },
-                EntryPointError::Provider { inner } => SimulationError::Provider { inner },
-                _ => SimulationError::Other { inner: err.to_string() },
-            }),
+        let res = tokio::time::timeout(
+            self.timeouts.trace,
+            entry_point.simulate_validation_trace_at(uo.user_operation.clone(), self.block_id()),
+        )
+        .await
+        .map_err(|_| SimulationError::Timeout {
+            stage: "simulation trace".into(),
+            timeout_ms: self.timeouts.trace.as_millis() as u64,
+        })?;
+
+        res.map_err(|err| self.simulation_error(err))
+    }
+
+    /// The block the user operations are simulated on (`None` for the latest block).
+    fn block_id(&self) -> Option<BlockId> {
+        self.historical_block.map(|block| BlockNumber::Number(block.into()).into())
+    }
+
+    /// Converts the error of the simulation (with or without the trace) to a [SimulationError].
+    fn simulation_error(&self, err: EntryPointError) -> SimulationError {
+        match (err, self.historical_block) {
+            (err, Some(block)) if err.is_missing_state() => {
+                SimulationError::HistoricalState { block, inner: err.to_string() }
+            }
+            (EntryPointError::FailedOp(op), _) => SimulationError::Validation { inner: op.reason },
+            (EntryPointError::Provider { inner }, _) => SimulationError::Provider { inner },
+            (err, _) => SimulationError::Other { inner: err.to_string() },
         }
     }
 }
@@ -217,31 +1102,95 @@ where
     SimCk: SimulationCheck,
     SimTrCk: SimulationTraceCheck<M>,
 {
+    /// Gets the latest block (its hash as [U256] and its number) which the user operations are
+    /// verified on. The block is reused for the validations within the block cache TTL.
+    ///
+    /// # Returns
+    /// The [VerifiedBlock] if the latest block was fetched successfully, otherwise a
+    /// [InvalidMempoolUserOperationError](InvalidMempoolUserOperationError).
+    async fn get_verified_block(&self) -> Result<VerifiedBlock, InvalidMempoolUserOperationError> {
+        if let Some(ttl) = self.block_cache_ttl {
+            if let Some(cached) = *self.block_cache.read() {
+                if cached.fetched_at.elapsed() < ttl {
+                    debug!(
+                        "Using cached block {:?} ({:?})",
+                        cached.block.number, cached.block.hash
+                    );
+                    return Ok(cached.block);
+                }
+            }
+        }
+
+        let eth_client = self
+            .entry_points
+            .values()
+            .next()
+            .ok_or(SanityError::Provider { inner: "no entry point configured".into() })?
+            .eth_client();
+        let block =
+            tokio::time::timeout(self.timeouts.block, eth_client.get_block(BlockNumber::Latest))
+                .await
+                .map_err(|_| SanityError::Timeout {
+                    stage: "block fetch".into(),
+                    timeout_ms: self.timeouts.block.as_millis() as u64,
+                })?
+                .map_err(|e| SanityError::Provider { inner: e.to_string() })?
+                .ok_or(SanityError::Provider { inner: "latest block not found".into() })?;
+        // pending blocks (returned by some providers) have no hash
+        let verified_block = VerifiedBlock {
+            hash: U256::from(
+                block
+                    .hash
+                    .ok_or(SanityError::Provider { inner: "latest block has no hash".into() })?
+                    .0,
+            ),
+            number: block.number.unwrap_or_default().as_u64(),
+        };
+
+        if self.block_cache_ttl.is_some() {
+            *self.block_cache.write() =
+                Some(CachedBlock { block: verified_block, fetched_at: Instant::now() });
+        }
+
+        Ok(verified_block)
+    }
+
     /// Validates a [UserOperation](UserOperation) via the
     /// [simulate_validation](silius_contracts::entry_point::EntryPoint::simulate_validation) method
     /// of the entry point. The function also optionally performs
     /// sanity checks and simulation checks if the
     /// [UserOperationValidatorMode](UserOperationValidatorMode) contains the respective flags.
+    /// The preprocessing hook (if set) runs after the sanity checks and before the simulation.
+    /// In the execution mode, the user operations whose execution phase reverts are flagged in the
+    /// outcome (the validation doesn't fail).
     ///
     /// # Arguments
     /// `uo` - [UserOperation](UserOperation) to validate.
+    /// `entry_point` - The address of the entry point the user operation is sent to.
     /// `mempool` - [Mempool](Mempool) object.
     /// `reputation` - [Reputation](Reputation) object.
     /// `val_config` - Optional [ValidationConfig](ValidationConfig) object.
     /// `mode` - [UserOperationValidatorMode](UserOperationValidatorMode) flag.
+    /// `verified_block` - The [VerifiedBlock] which the user operation is verified on.
     ///
     /// # Returns
     /// A [UserOperationValidationOutcome](UserOperationValidationOutcome) if the validation was
     /// successful, otherwise a
     /// [InvalidMempoolUserOperationError](InvalidMempoolUserOperationError).
-    async fn validate_user_operation(
+    async fn validate_user_operation_on_block(
         &self,
         uo: &UserOperation,
+        entry_point: &Address,
         mempool: &Mempool,
         reputation: &Reputation,
         val_config: Option<ValidationConfig>,
         mode: EnumSet<UserOperationValidatorMode>,
+        verified_block: VerifiedBlock,
     ) -> Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError> {
+        let ep = self.entry_point(entry_point)?;
+        let entry_point = ep.address();
+        let _timer = ValidationTimer::new(entry_point);
+
         let mut out: UserOperationValidationOutcome = Default::default();
 
         if let Some(val_config) = val_config.clone() {
@@ -257,71 +1206,786 @@ where
 
         if mode.contains(UserOperationValidatorMode::Sanity) {
             let sanity_helper = SanityHelper {
-                entry_point: &self.entry_point,
+                entry_point: ep,
                 chain: self.chain,
                 val_config: val_config.clone().unwrap_or_default(),
+                deposit_cache: &self.deposit_cache,
             };
 
             self.sanity_checks
                 .check_user_operation(uo, mempool, reputation, &sanity_helper)
-                .await?;
+                .await
+                .map_err(on_failure(entry_point, ValidationPhase::Sanity))?;
         }
 
         if let Some(uo) = mempool.get_prev_by_sender(uo) {
             out.prev_hash = Some(uo.hash);
         }
 
+        let uo = self.preprocessed(uo);
+        let uo = uo.as_ref();
+
         debug!("Simulate user operation from {:?}", uo.sender);
-        let sim_res = self.simulate_validation(uo).await?;
+        let sim_res = self
+            .simulate_validation(ep, uo)
+            .await
+            .map_err(on_failure(entry_point, ValidationPhase::Simulation))?;
 
         if mode.contains(UserOperationValidatorMode::Simulation) {
+            let sender_funds = if self.simulation_checks.needs_sender_funds() {
+                self.sender_funds(ep, uo)
+                    .await
+                    .map_err(on_failure(entry_point, ValidationPhase::Simulation))?
+            } else {
+                None
+            };
+
             let mut sim_helper = SimulationHelper {
                 simulate_validation_result: &sim_res,
                 val_config: val_config.clone().unwrap_or_default(),
                 valid_after: None,
+                sender_funds,
             };
 
-            self.simulation_checks.check_user_operation(uo, &mut sim_helper)?;
+            self.simulation_checks
+                .check_user_operation(uo, &mut sim_helper)
+                .map_err(on_failure(entry_point, ValidationPhase::Simulation))?;
 
             out.valid_after = sim_helper.valid_after;
         }
 
         out.pre_fund = extract_pre_fund(&sim_res);
         out.verification_gas_limit = extract_verification_gas_limit(&sim_res);
+        out.staked = extract_stake_info(uo, &sim_res).iter().any(|info| info.is_staked());
 
-        let block_number = self
-            .entry_point
-            .eth_client()
-            .get_block(BlockNumber::Latest)
-            .await
-            .map_err(|e| SanityError::Provider { inner: e.to_string() })?
-            .expect("block should exist");
-        out.verified_block = U256::from(block_number.hash.expect("block hash should exist").0);
+        if let Some(aggregator) = extract_aggregator(&sim_res) {
+            let sig = ep
+                .validate_user_op_signature(&aggregator, uo.user_operation.clone())
+                .await
+                .map_err(|e| match e {
+                    EntryPointError::Provider { inner } => SimulationError::Provider { inner },
+                    _ => SimulationError::Signature,
+                })
+                .map_err(on_failure(entry_point, ValidationPhase::Simulation))?;
+            out.aggregator = Some(aggregator);
+            out.aggregator_signature = Some(sig);
+        }
+
+        out.verified_block = verified_block.hash;
+        out.verified_block_number = verified_block.number;
 
         if mode.contains(UserOperationValidatorMode::SimulationTrace) {
             debug!("Simulate user operation with trace from {:?}", uo.sender);
-            let geth_trace = self.simulate_validation_trace(uo).await?;
+            let geth_trace = self
+                .simulate_validation_trace(ep, uo)
+                .await
+                .map_err(on_failure(entry_point, ValidationPhase::SimulationTrace))?;
+            self.trace_limit
+                .check_geth_trace(&geth_trace)
+                .map_err(on_failure(entry_point, ValidationPhase::SimulationTrace))?;
             let js_trace: JsTracerFrame = JsTracerFrame::try_from(geth_trace)
-                .map_err(|error| SimulationError::Validation { inner: error.to_string() })?;
+                .map_err(SimulationError::from)
+                .map_err(on_failure(entry_point, ValidationPhase::SimulationTrace))?;
 
             let mut sim_helper = SimulationTraceHelper {
-                entry_point: &self.entry_point,
+                entry_point: ep,
                 chain: self.chain,
                 simulate_validation_result: &sim_res,
                 js_trace: &js_trace,
                 val_config: val_config.unwrap_or_default(),
                 stake_info: None,
                 code_hashes: None,
+                time_dependent: false,
             };
 
             self.simulation_trace_checks
                 .check_user_operation(uo, mempool, reputation, &mut sim_helper)
-                .await?;
+                .await
+                .map_err(on_failure(entry_point, ValidationPhase::SimulationTrace))?;
 
             out.code_hashes = sim_helper.code_hashes;
+            out.time_dependent = sim_helper.time_dependent;
             out.storage_map = extract_storage_map(&js_trace);
+            out.storage_writes = extract_storage_writes(&js_trace);
+        }
+
+        if mode.contains(UserOperationValidatorMode::Execution) {
+            debug!("Simulate execution of user operation from {:?}", uo.sender);
+            if let Some(reason) = Self::simulate_execution(ep, uo)
+                .await
+                .map_err(on_failure(entry_point, ValidationPhase::Execution))?
+            {
+                debug!("User operation from {:?} would revert: {reason:?}", uo.sender);
+                out.would_revert = true;
+                out.revert_reason = (!reason.is_empty()).then_some(reason);
+            }
         }
 
         Ok(out)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{memory_mempool, memory_reputation},
+        validate::simulation::prefund::Prefund,
+    };
+    use ethers::{
+        providers::{JsonRpcError, MockProvider, MockResponse, Provider, ProviderError},
+        types::{Address, Block, BlockId, H256},
+    };
+    use silius_contracts::{
+        entry_point::{ValidationResult, ValidationResultWithAggregation},
+        FailedOp,
+    };
+    use silius_primitives::UserOperationSigned;
+
+    fn validator(
+        block_cache_ttl: Option<Duration>,
+    ) -> (StandardUserOperationValidator<Provider<MockProvider>, (), (Signature,), ()>, MockProvider)
+    {
+        let (provider, mock) = Provider::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        (
+            StandardUserOperationValidator::new(
+                entry_point,
+                Chain::dev(),
+                (),
+                (Signature,),
+                (),
+                block_cache_ttl,
+            ),
+            mock,
+        )
+    }
+
+    fn block() -> Block<H256> {
+        Block { hash: Some(H256::random()), number: Some(1.into()), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn verified_block_cached() {
+        let (validator, mock) = validator(Some(Duration::from_secs(BLOCK_CACHE_TTL)));
+        let block = block();
+        mock.push(block.clone()).unwrap();
+
+        // only one block is available, so the second call must be served from the cache
+        let first = validator.get_verified_block().await.unwrap();
+        let second = validator.clone().get_verified_block().await.unwrap();
+
+        assert_eq!(first, VerifiedBlock { hash: U256::from(block.hash.unwrap().0), number: 1 });
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn validate_batch_without_block() {
+        let (validator, mock) = validator(Some(Duration::from_secs(BLOCK_CACHE_TTL)));
+        mock.push(serde_json::Value::Null).unwrap();
+
+        let uo = UserOperation::from_user_operation_signed(Default::default(), Default::default());
+        // the error fetching the block is returned once for the whole batch
+        assert!(matches!(
+            validator
+                .validate_user_operation_batch(
+                    &[&uo, &uo],
+                    &Address::random(),
+                    &memory_mempool(),
+                    &memory_reputation(),
+                    None,
+                    UserOperationValidatorMode::Sanity.into(),
+                )
+                .await,
+            Err(InvalidMempoolUserOperationError::Sanity(SanityError::Provider { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn verified_block_without_hash() {
+        let (validator, mock) = validator(Some(Duration::from_secs(BLOCK_CACHE_TTL)));
+        mock.push(Block::<H256> { hash: None, ..block() }).unwrap();
+
+        assert!(matches!(
+            validator.get_verified_block().await,
+            Err(InvalidMempoolUserOperationError::Sanity(SanityError::Provider { .. }))
+        ));
+        // the failed fetch is not cached
+        assert!(validator.block_cache.read().is_none());
+
+        mock.push(serde_json::Value::Null).unwrap();
+        assert!(matches!(
+            validator.get_verified_block().await,
+            Err(InvalidMempoolUserOperationError::Sanity(SanityError::Provider { .. }))
+        ));
+    }
+
+    /// Provider which delays fetching the blocks, the calls and the traces of the calls
+    #[derive(Debug, Clone)]
+    struct DelayedProvider {
+        inner: Provider<MockProvider>,
+        delay: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl Middleware for DelayedProvider {
+        type Error = ProviderError;
+        type Provider = MockProvider;
+        type Inner = Provider<MockProvider>;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+
+        async fn get_block<T: Into<BlockId> + Send + Sync>(
+            &self,
+            block_hash_or_number: T,
+        ) -> Result<Option<Block<H256>>, Self::Error> {
+            tokio::time::sleep(self.delay).await;
+            self.inner.get_block(block_hash_or_number).await
+        }
+
+        async fn call(
+            &self,
+            tx: &ethers::types::transaction::eip2718::TypedTransaction,
+            block: Option<BlockId>,
+        ) -> Result<ethers::types::Bytes, Self::Error> {
+            tokio::time::sleep(self.delay).await;
+            self.inner.call(tx, block).await
+        }
+
+        async fn debug_trace_call<
+            T: Into<ethers::types::transaction::eip2718::TypedTransaction> + Send + Sync,
+        >(
+            &self,
+            req: T,
+            block: Option<BlockId>,
+            trace_options: ethers::types::GethDebugTracingCallOptions,
+        ) -> Result<GethTrace, Self::Error> {
+            tokio::time::sleep(self.delay).await;
+            self.inner.debug_trace_call(req, block, trace_options).await
+        }
+    }
+
+    fn delayed_validator(
+        timeouts: StageTimeouts,
+    ) -> StandardUserOperationValidator<DelayedProvider, (), (Signature,), ()> {
+        let (provider, _) = Provider::mocked();
+        let eth_client = DelayedProvider { inner: provider, delay: Duration::from_millis(500) };
+        StandardUserOperationValidator::new(
+            EntryPoint::new(Arc::new(eth_client), Address::random()),
+            Chain::dev(),
+            (),
+            (Signature,),
+            (),
+            None,
+        )
+        .with_stage_timeouts(timeouts)
+    }
+
+    #[tokio::test]
+    async fn simulation_timeout() {
+        let validator = delayed_validator(StageTimeouts {
+            simulation: Duration::from_millis(10),
+            ..Default::default()
+        });
+        let ep = validator.entry_points.values().next().unwrap();
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(Address::random()),
+        );
+
+        match validator.simulate_validation(ep, &uo).await {
+            Err(SimulationError::Timeout { stage, timeout_ms }) => {
+                assert_eq!(stage, "simulation");
+                assert_eq!(timeout_ms, 10);
+            }
+            res => panic!("unexpected result {res:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn simulation_trace_timeout() {
+        let validator = delayed_validator(StageTimeouts {
+            trace: Duration::from_millis(10),
+            ..Default::default()
+        });
+        let ep = validator.entry_points.values().next().unwrap();
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(Address::random()),
+        );
+
+        match validator.simulate_validation_trace(ep, &uo).await {
+            Err(SimulationError::Timeout { stage, timeout_ms }) => {
+                assert_eq!(stage, "simulation trace");
+                assert_eq!(timeout_ms, 10);
+            }
+            res => panic!("unexpected result {res:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn verified_block_timeout() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(block()).unwrap();
+        let eth_client = DelayedProvider { inner: provider, delay: Duration::from_millis(500) };
+        let validator = StandardUserOperationValidator::<_, (), (Signature,), ()>::new(
+            EntryPoint::new(Arc::new(eth_client), Address::random()),
+            Chain::dev(),
+            (),
+            (Signature,),
+            (),
+            None,
+        )
+        .with_stage_timeouts(StageTimeouts {
+            block: Duration::from_millis(10),
+            ..Default::default()
+        });
+
+        assert!(matches!(
+            validator.get_verified_block().await,
+            Err(InvalidMempoolUserOperationError::Sanity(SanityError::Timeout {
+                timeout_ms: 10,
+                ..
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn verified_block_not_cached() {
+        let (validator, mock) = validator(None);
+        mock.push(block()).unwrap();
+
+        assert!(validator.get_verified_block().await.is_ok());
+        assert!(validator.get_verified_block().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn retry_transient_errors() {
+        let timeout = || EntryPointError::Provider { inner: "request timed out".into() };
+
+        // fails twice with a transient error and then succeeds
+        let mut calls = 0;
+        let res = retry_transient(2, Duration::from_millis(1), || {
+            calls += 1;
+            let res = if calls <= 2 { Err(timeout()) } else { Ok(calls) };
+            async move { res }
+        })
+        .await;
+        assert_eq!(res.unwrap(), 3);
+
+        // gives up once the retries are exhausted
+        let mut calls = 0;
+        let res: Result<(), _> = retry_transient(2, Duration::from_millis(1), || {
+            calls += 1;
+            let err = timeout();
+            async move { Err(err) }
+        })
+        .await;
+        assert!(res.unwrap_err().is_transient());
+        assert_eq!(calls, 3);
+
+        // fails fast on a failed user operation
+        let mut calls = 0;
+        let res: Result<(), _> = retry_transient(2, Duration::from_millis(1), || {
+            calls += 1;
+            async {
+                Err(EntryPointError::FailedOp(FailedOp {
+                    op_index: U256::zero(),
+                    reason: "AA23 reverted".into(),
+                }))
+            }
+        })
+        .await;
+        assert!(matches!(res, Err(EntryPointError::FailedOp(_))));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn entry_point_selection() {
+        let (provider, _) = Provider::mocked();
+        let provider = Arc::new(provider);
+        let entry_points = [Address::random(), Address::random()];
+        let validator: StandardUserOperationValidator<_, (), (Signature,), ()> =
+            StandardUserOperationValidator::new_with_entry_points(
+                entry_points
+                    .iter()
+                    .map(|ep| (*ep, EntryPoint::new(provider.clone(), *ep)))
+                    .collect(),
+                Chain::dev(),
+                (),
+                (Signature,),
+                (),
+                None,
+            )
+            .unwrap();
+
+        for ep in entry_points.iter() {
+            assert_eq!(validator.entry_point(ep).unwrap().address(), *ep);
+        }
+        assert!(matches!(
+            validator.entry_point(&Address::random()),
+            Err(SanityError::UnsupportedEntryPoint { .. })
+        ));
+
+        let validator: eyre::Result<StandardUserOperationValidator<_, (), (Signature,), ()>> =
+            StandardUserOperationValidator::new_with_entry_points(
+                HashMap::<Address, EntryPoint<Provider<MockProvider>>>::new(),
+                Chain::dev(),
+                (),
+                (Signature,),
+                (),
+                None,
+            );
+        assert!(validator.is_err());
+    }
+
+    #[test]
+    fn preprocess_copy_only() {
+        let (validator, _) = validator(None);
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default()
+                .verification_gas_limit(10_000_000.into())
+                .signature(vec![1, 2, 0, 0].into()),
+        );
+        assert!(matches!(validator.preprocessed(&uo), Cow::Borrowed(_)));
+
+        let validator = validator.with_preprocess(Arc::new(|uo: &mut UserOperation| {
+            uo.user_operation.verification_gas_limit =
+                uo.verification_gas_limit.min(1_000_000.into());
+            while uo.signature.last() == Some(&0) {
+                uo.user_operation.signature =
+                    uo.signature[..uo.signature.len() - 1].to_vec().into();
+            }
+        }));
+
+        let simulated = validator.preprocessed(&uo);
+        assert_eq!(simulated.verification_gas_limit, 1_000_000.into());
+        assert_eq!(simulated.signature, vec![1, 2].into());
+        assert_eq!(simulated.hash, uo.hash);
+
+        // the user operation of the caller is untouched
+        assert_eq!(uo.verification_gas_limit, 10_000_000.into());
+        assert_eq!(uo.signature, vec![1, 2, 0, 0].into());
+    }
+
+    #[tokio::test]
+    async fn simulate_execution_revert() {
+        let (validator, mock) = validator(None);
+        let ep = validator.entry_points.values().next().unwrap();
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(Address::random()).call_data(vec![1].into()),
+        );
+
+        let mut data = vec![0x08, 0xc3, 0x79, 0xa0];
+        data.extend(ethers::abi::encode(&[ethers::abi::Token::String("not allowed".into())]));
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: 3,
+            message: "execution reverted: not allowed".into(),
+            data: Some(serde_json::json!(ethers::types::Bytes::from(data))),
+        }));
+        assert_eq!(
+            StandardUserOperationValidator::<_, (), (Signature,), ()>::simulate_execution(ep, &uo)
+                .await
+                .unwrap(),
+            Some("not allowed".to_string())
+        );
+
+        mock.push(ethers::types::Bytes::default()).unwrap();
+        assert_eq!(
+            StandardUserOperationValidator::<_, (), (Signature,), ()>::simulate_execution(ep, &uo)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_with_aggregator() {
+        let (validator, mock) = validator(None);
+        let ep = *validator.entry_points.keys().next().unwrap();
+        let mempool = memory_mempool();
+        let reputation = memory_reputation();
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(Address::random()),
+        );
+        let aggregator = Address::random();
+        let signature = ethers::types::Bytes::from(vec![0xab, 0xcd]);
+
+        // the mocked responses are returned in reverse order
+        mock.push(ethers::types::Bytes::from(ethers::abi::encode(&[ethers::abi::Token::Bytes(
+            signature.to_vec(),
+        )])))
+        .unwrap();
+        let sim_res = ValidationResultWithAggregation {
+            aggregator_info: (aggregator, Default::default()),
+            ..Default::default()
+        };
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: 3,
+            message: "execution reverted".into(),
+            data: Some(serde_json::json!(ethers::types::Bytes::from(
+                ethers::abi::AbiEncode::encode(sim_res)
+            ))),
+        }));
+
+        let out = validator
+            .validate_user_operation_on_block(
+                &uo,
+                &ep,
+                &mempool,
+                &reputation,
+                None,
+                UserOperationValidatorMode::Sanity | UserOperationValidatorMode::Simulation,
+                VerifiedBlock::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.aggregator, Some(aggregator));
+        assert_eq!(out.aggregator_signature, Some(signature));
+    }
+
+    #[tokio::test]
+    async fn validate_at_block_requires_archive() {
+        let (validator, mock) = validator(None);
+        let ep = *validator.entry_points.keys().next().unwrap();
+        let (mempool, reputation) = (memory_mempool(), memory_reputation());
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(Address::random()),
+        );
+        let historical_state = |res| {
+            matches!(
+                res,
+                Err(InvalidMempoolUserOperationError::Simulation(
+                    SimulationError::HistoricalState { block: 100, .. }
+                ))
+            )
+        };
+
+        assert!(historical_state(
+            validator.validate_at_block(&uo, &ep, &mempool, &reputation, EnumSet::all(), 100).await
+        ));
+
+        // the block is not known to the provider
+        let validator = validator.with_archive(true);
+        mock.push(serde_json::Value::Null).unwrap();
+        assert!(historical_state(
+            validator.validate_at_block(&uo, &ep, &mempool, &reputation, EnumSet::all(), 100).await
+        ));
+
+        // pruned state is reported only when validating on a historical block
+        let missing_state =
+            || EntryPointError::Provider { inner: "missing trie node 1f2c (path )".into() };
+        assert!(matches!(
+            validator.simulation_error(missing_state()),
+            SimulationError::Provider { .. }
+        ));
+        let mut validator = validator.detached();
+        validator.historical_block = Some(100);
+        assert_eq!(validator.block_id(), Some(BlockNumber::Number(100.into()).into()));
+        assert!(matches!(
+            validator.simulation_error(missing_state()),
+            SimulationError::HistoricalState { block: 100, .. }
+        ));
+    }
+
+    /// Validator which only records the block it validates on (it never fetches the block)
+    struct BlockRecordingValidator;
+
+    #[async_trait::async_trait]
+    impl UserOperationValidator for BlockRecordingValidator {
+        async fn get_verified_block(
+            &self,
+        ) -> Result<VerifiedBlock, InvalidMempoolUserOperationError> {
+            panic!("block must not be fetched")
+        }
+
+        async fn validate_user_operation_on_block(
+            &self,
+            _uo: &UserOperation,
+            _entry_point: &Address,
+            _mempool: &Mempool,
+            _reputation: &Reputation,
+            _val_config: Option<ValidationConfig>,
+            _mode: EnumSet<UserOperationValidatorMode>,
+            verified_block: VerifiedBlock,
+        ) -> Result<UserOperationValidationOutcome, InvalidMempoolUserOperationError> {
+            Ok(UserOperationValidationOutcome {
+                verified_block: verified_block.hash,
+                verified_block_number: verified_block.number,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_with_injected_block() {
+        let mempool = memory_mempool();
+        let reputation = memory_reputation();
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(Address::random()),
+        );
+        let validate = |block: Block<H256>| {
+            BlockRecordingValidator.validate_user_operation_with_block(
+                &uo,
+                &Address::random(),
+                &mempool,
+                &reputation,
+                None,
+                EnumSet::empty(),
+                block,
+            )
+        };
+
+        let block = block();
+        let out = validate(block.clone()).await.unwrap();
+        assert_eq!(out.verified_block, U256::from(block.hash.unwrap().0));
+        assert_eq!(out.verified_block_number, 1);
+
+        // pending blocks have no hash
+        assert!(matches!(
+            validate(Block::default()).await,
+            Err(InvalidMempoolUserOperationError::Sanity(SanityError::Provider { .. }))
+        ));
+    }
+
+    #[test]
+    fn chain_defaults_differ() {
+        let mainnet = ChainDefaults::from_chain(&Chain::mainnet());
+        let polygon = ChainDefaults::from_chain(&Chain::from(NamedChain::Polygon));
+        let optimism = ChainDefaults::from_chain(&Chain::from(NamedChain::Optimism));
+        let arbitrum = ChainDefaults::from_chain(&Chain::from(NamedChain::Arbitrum));
+
+        assert_eq!(mainnet.max_verification_gas, U256::from(MAX_VERIFICATION_GAS));
+        assert!(polygon.min_priority_fee_per_gas > mainnet.min_priority_fee_per_gas);
+        assert!(optimism.min_priority_fee_per_gas < mainnet.min_priority_fee_per_gas);
+        assert!(arbitrum.max_verification_gas > mainnet.max_verification_gas);
+        // unknown chains have no minimum priority fee
+        assert_eq!(
+            ChainDefaults::from_chain(&Chain::from_id(123_456)).min_priority_fee_per_gas,
+            0.into()
+        );
+
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let validator = new_canonical_for_chain(entry_point, Chain::from(NamedChain::Polygon));
+        assert_eq!(validator.chain, Chain::from(NamedChain::Polygon));
+    }
+
+    #[derive(Clone)]
+    struct RejectSender(Address);
+
+    #[async_trait::async_trait]
+    impl<M: Middleware> SanityCheck<M> for RejectSender {
+        async fn check_user_operation(
+            &self,
+            uo: &UserOperation,
+            _mempool: &Mempool,
+            _reputation: &Reputation,
+            _helper: &SanityHelper<M>,
+        ) -> Result<(), SanityError> {
+            if uo.sender == self.0 {
+                return Err(SanityError::Sender { inner: "sender rejected".into() });
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn builder_composes_checks() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let sender = Address::random();
+        let validator = DynamicValidator::builder(entry_point.clone(), Chain::dev())
+            .add_sanity_check(Box::new(RejectSender(Address::random())))
+            .add_sanity_check(Box::new(RejectSender(sender)))
+            .add_simulation_check(Box::new(Timestamp))
+            .add_simulation_check(Box::new(Prefund))
+            .add_trace_check(Box::new(TraceLimit::default()));
+
+        assert_eq!(validator.sanity_checks.len(), 2);
+        assert!(validator.simulation_checks.needs_sender_funds());
+        assert_eq!(validator.simulation_trace_checks.len(), 1);
+
+        let mempool = memory_mempool();
+        let reputation = memory_reputation();
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(sender),
+        );
+
+        // the second sanity check rejects the sender before the simulation
+        assert!(matches!(
+            validator
+                .clone()
+                .validate_user_operation_on_block(
+                    &uo,
+                    &entry_point.address(),
+                    &mempool,
+                    &reputation,
+                    None,
+                    UserOperationValidatorMode::Sanity.into(),
+                    VerifiedBlock::default(),
+                )
+                .await,
+            Err(InvalidMempoolUserOperationError::Sanity(SanityError::Sender { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn boxed_checks_vec() {
+        let (provider, _) = Provider::<MockProvider>::mocked();
+        let entry_point = EntryPoint::new(Arc::new(provider), Address::random());
+        let deposit_cache = DepositInfoCache::new(Duration::from_secs(DEPOSIT_INFO_CACHE_TTL));
+        let sender = Address::random();
+        let uo = UserOperation::from_user_operation_signed(
+            Default::default(),
+            UserOperationSigned::default().sender(sender),
+        );
+
+        let sanity_checks: Vec<Box<dyn SanityCheck<Provider<MockProvider>>>> = vec![
+            Box::new(()),
+            Box::new((RejectSender(Address::random()),)),
+            Box::new(RejectSender(sender)),
+        ];
+        let helper = SanityHelper {
+            entry_point: &entry_point,
+            chain: Chain::dev(),
+            val_config: Default::default(),
+            deposit_cache: &deposit_cache,
+        };
+        let mempool = memory_mempool();
+        let reputation = memory_reputation();
+        assert!(matches!(
+            sanity_checks.check_user_operation(&uo, &mempool, &reputation, &helper).await,
+            Err(SanityError::Sender { .. })
+        ));
+        assert_eq!(sanity_checks.check_all(&uo, &mempool, &reputation, &helper).await.len(), 1);
+
+        let simulation_checks: Vec<Box<dyn SimulationCheck>> =
+            vec![Box::new(Timestamp), Box::new(Prefund)];
+        let mut res = ValidationResult::default();
+        res.return_info.1 = 1_000.into();
+        res.return_info.4 = u64::MAX.into();
+        let sim_res = SimulateValidationResult::ValidationResult(res);
+        let mut helper = SimulationHelper {
+            simulate_validation_result: &sim_res,
+            val_config: Default::default(),
+            valid_after: None,
+            sender_funds: Some(999.into()),
+        };
+        assert!(simulation_checks.needs_sender_funds());
+        assert!(matches!(
+            simulation_checks.check_user_operation(&uo, &mut helper),
+            Err(SimulationError::Prefund { .. })
+        ));
+        helper.sender_funds = Some(1_000.into());
+        assert!(simulation_checks.check_user_operation(&uo, &mut helper).is_ok());
+    }
+}
@@ -4,6 +4,7 @@ use crate::{
 use label::LabelValue;
 use metrics_exporter_prometheus::PrometheusBuilder;
 use metrics_util::MetricKindMask;
+use silius_mempool::validate::metrics::describe_validation_metrics;
 use std::{net::SocketAddr, time::Duration};
 use tracing::info;
 
@@ -33,4 +34,5 @@ pub fn launch_metrics_exporter(listen_addr: SocketAddr, label_value_opt: Option<
     describe_json_rpc_metrics();
     describe_mempool_metrics();
     describe_grpc_metrics();
+    describe_validation_metrics();
 }
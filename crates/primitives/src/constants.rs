@@ -12,6 +12,8 @@ pub mod entry_point {
 pub mod bundler {
     /// Default time interval for auto bundling mode (in seconds)
     pub const BUNDLE_INTERVAL: u64 = 10;
+    /// Default maximum aggregate gas of the user operations in one bundle
+    pub const MAX_BUNDLE_GAS: u64 = 15_000_000;
 }
 
 /// User operation mempool
@@ -20,10 +22,39 @@ pub mod mempool {
     pub const GAS_INCREASE_PERC: u64 = 10;
     /// Depth scan when searching for previous user operations
     pub const LATEST_SCAN_DEPTH: u64 = 1000;
+    /// Maximum number of simulations when binary searching the call gas limit during the gas
+    /// estimation
+    pub const ESTIMATION_MAX_ITERATIONS: u64 = 20;
+    /// Maximum number of blocks queried in a single `eth_getLogs` call
+    pub const MAX_LOG_RANGE: u64 = 2000;
+    /// Maximum number of pending user operations re-validated at once
+    pub const REVALIDATION_BATCH_SIZE: usize = 64;
 }
 
 /// User operation validation
 pub mod validation {
+    /// Time (in seconds) for which the latest block used for the validation is cached
+    pub const BLOCK_CACHE_TTL: u64 = 2;
+    /// Percentage by which the pre-verification gas of a user operation may be lower than the
+    /// pre-verification gas calculated by the bundler
+    pub const PRE_VERIFICATION_GAS_TOLERANCE_PERC: u64 = 10;
+    /// Time (in seconds) for which the deposit info of the entities is cached
+    pub const DEPOSIT_INFO_CACHE_TTL: u64 = 12;
+    /// Maximum number of (entry point, entity) pairs whose deposit info is cached
+    pub const DEPOSIT_INFO_CACHE_MAX_ENTRIES: usize = 10_000;
+    /// Number of retries of the simulation failed due to a transient provider error
+    pub const SIMULATION_MAX_RETRIES: u32 = 2;
+    /// Delay (in milliseconds) before the first retry of the simulation (doubled on every retry)
+    pub const SIMULATION_RETRY_BASE_DELAY: u64 = 100;
+    /// Timeout (in seconds) of the simulation of the validation
+    pub const SIMULATION_TIMEOUT: u64 = 10;
+    /// Timeout (in seconds) of the simulation of the validation with the trace
+    pub const SIMULATION_TRACE_TIMEOUT: u64 = 20;
+    /// Timeout (in seconds) of fetching the latest block the user operations are verified on
+    pub const BLOCK_FETCH_TIMEOUT: u64 = 5;
+    /// Maximum verification gas limit of a user operation
+    pub const MAX_VERIFICATION_GAS: u64 = 5_000_000;
+
     /// Entities (factory, sender/account, paymaster, aggregator)
     pub mod entities {
         // 0 - factory, 1 - sender/account, 2 - paymaster
@@ -55,12 +86,79 @@ pub mod validation {
         pub const INCLUSION_RATE_FACTOR: u64 = 10;
         pub const THROTTLING_SLACK: u64 = 10;
         pub const BAN_SLACK: u64 = 50;
+        /// Interval (in seconds) of the reputation decay (uo_seen and uo_included multiplied by
+        /// 23/24)
+        pub const DECAY_INTERVAL: u64 = 60 * 60;
     }
 
     /// Simulation
     pub mod simulation {
         pub const MIN_EXTRA_GAS: u64 = 2000;
     }
+
+    /// Simulation trace
+    pub mod simulation_trace {
+        /// Maximum number of call frame entries (enters and exits) in the validation trace
+        pub const MAX_TRACE_STEPS: usize = 20_000;
+        /// Maximum call depth in the validation trace (the EVM call depth limit)
+        pub const MAX_TRACE_DEPTH: usize = 1024;
+    }
+
+    /// Storage gas (EIP-2929)
+    pub mod storage_gas {
+        /// Gas charged for the first access of a storage slot in a transaction
+        pub const COLD_SLOAD_COST: u64 = 2100;
+        /// Default safety margin (in percentage) on top of the estimated storage gas
+        pub const SAFETY_MARGIN_PERC: u64 = 10;
+    }
+
+    /// Account deployments through unstaked factories
+    pub mod dupe_init_code {
+        /// Default maximum number of user operations in the mempool deploying through the same
+        /// unstaked factory (without any included user operations) within one window
+        pub const MAX_UOS_PER_WINDOW: u64 = 10;
+        /// Default length (in seconds) of the window
+        pub const WINDOW: u64 = 60;
+    }
+
+    /// Nonce
+    pub mod nonce {
+        /// Default maximum gap between the on-chain nonce (sequence) of the sender and the nonce of
+        /// the user operation
+        pub const MAX_NONCE_GAP: u64 = 10;
+    }
+
+    /// Size limits (in bytes) of the dynamic fields of the user operation
+    pub mod calldata_size {
+        /// Default maximum size of the call data
+        pub const MAX_CALL_DATA_SIZE: usize = 32 * 1024;
+        /// Default maximum size of the init code (factory address and factory call data)
+        pub const MAX_INIT_CODE_SIZE: usize = 16 * 1024;
+        /// Default maximum size of the paymaster and data
+        pub const MAX_PAYMASTER_AND_DATA_SIZE: usize = 4 * 1024;
+        /// Default maximum size of the signature
+        pub const MAX_SIGNATURE_SIZE: usize = 4 * 1024;
+    }
+
+    /// Signature length
+    pub mod signature {
+        /// Default minimum length (in bytes) of the signature (ECDSA signature `r`, `s`, `v`)
+        pub const MIN_SIGNATURE_LENGTH: usize = 65;
+        /// Default maximum length (in bytes) of the signature (ECDSA signature `r`, `s`, `v`)
+        pub const MAX_SIGNATURE_LENGTH: usize = 65;
+    }
+}
+
+/// L1 data fee on OP-stack chains
+pub mod l1_data_fee {
+    /// Address of the `GasPriceOracle` predeploy
+    pub const GAS_PRICE_ORACLE: &str = "0x420000000000000000000000000000000000000F";
+}
+
+/// L1 gas on Arbitrum chains
+pub mod l1_gas {
+    /// Address of the `NodeInterface` (virtual contract served by the Arbitrum nodes)
+    pub const NODE_INTERFACE: &str = "0x00000000000000000000000000000000000000C8";
 }
 
 /// Flashbots relay endpoints
@@ -120,6 +218,15 @@ pub mod grpc {
     pub const MEMPOOL_PORT: u16 = 3002;
     /// The default port for bundler
     pub const BUNDLER_PORT: u16 = 3003;
+    /// Number of added user operations buffered for every subscriber (the oldest are dropped for
+    /// slow subscribers)
+    pub const SUBSCRIPTION_CAPACITY: usize = 1024;
+    /// Default maximum number of user operation validations (simulations) run concurrently by the
+    /// mempool service (the other requests wait for a free slot)
+    pub const MAX_CONCURRENT_VALIDATIONS: usize = 32;
+    /// Time (in seconds) the result of the health check is cached for (the provider is queried at
+    /// most once per period)
+    pub const HEALTH_CHECK_CACHE_TTL: u64 = 2;
 }
 
 /// Storage
@@ -18,8 +18,9 @@ pub use bundler::Mode as BundlerMode;
 pub use mempool::Mode as UoPoolMode;
 pub use p2p::{MempoolConfig, VerifiedUserOperation};
 pub use user_operation::{
-    UserOperation, UserOperationByHash, UserOperationGasEstimation, UserOperationHash,
-    UserOperationReceipt, UserOperationRequest, UserOperationSigned,
+    user_operation_hasher, PackedUserOperation, UserOperation, UserOperationByHash,
+    UserOperationGasEstimation, UserOperationHash, UserOperationHasher, UserOperationHasherV06,
+    UserOperationHasherV07, UserOperationReceipt, UserOperationRequest, UserOperationSigned,
 };
 pub use utils::get_address;
 pub use wallet::Wallet;
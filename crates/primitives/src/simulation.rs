@@ -1,6 +1,7 @@
 //! Simulation (validation) primitives
 
 use ethers::{
+    abi::{AbiType, InvalidOutputType, ParamType, Token, Tokenizable},
     prelude::{EthAbiCodec, EthAbiType},
     types::{Address, H256, U256},
 };
@@ -16,6 +17,7 @@ lazy_static! {
     pub static ref RETURN_OPCODE: String = "RETURN".into();
     pub static ref REVERT_OPCODE: String = "REVERT".into();
     pub static ref CREATE_OPCODE: String = "CREATE".into();
+    pub static ref SELFDESTRUCT_OPCODE: String = "SELFDESTRUCT".into();
     pub static ref VALIDATE_PAYMASTER_USER_OP_FUNCTION: String = "validatePaymasterUserOp".into();
     pub static ref FORBIDDEN_OPCODES: HashSet<String> = {
         let mut set = HashSet::new();
@@ -37,10 +39,25 @@ lazy_static! {
         set.insert("PREVRANDAO".into());
         set
     };
+    /// Forbidden opcodes which staked entities may use (OP-080)
+    pub static ref STAKED_ALLOWED_OPCODES: HashSet<String> = {
+        let mut set = HashSet::new();
+        set.insert("BALANCE".into());
+        set.insert("SELFBALANCE".into());
+        set
+    };
+    /// Opcodes which make the validation depend on the block it is included in
+    pub static ref TIME_DEPENDENT_OPCODES: HashSet<String> = {
+        let mut set = HashSet::new();
+        set.insert("TIMESTAMP".into());
+        set.insert("NUMBER".into());
+        set
+    };
 }
 
 /// Validaton config (you can override some validation values).
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ValidationConfig {
     pub min_stake: Option<U256>,
     pub min_unstake_delay: Option<U256>,
@@ -68,8 +85,42 @@ pub struct CodeHash {
 }
 
 /// Storage map
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct StorageMap {
     pub root_hashes: HashMap<Address, H256>,
     pub slots: HashMap<Address, HashMap<String, String>>,
 }
+
+/// ABI representation of the [StorageMap](StorageMap) (maps are encoded as arrays of entries)
+type StorageMapAbi = (Vec<(Address, H256)>, Vec<(Address, Vec<(String, String)>)>);
+
+impl AbiType for StorageMap {
+    fn param_type() -> ParamType {
+        StorageMapAbi::param_type()
+    }
+}
+
+impl Tokenizable for StorageMap {
+    fn from_token(token: Token) -> Result<Self, InvalidOutputType> {
+        let (root_hashes, slots) = StorageMapAbi::from_token(token)?;
+        Ok(Self {
+            root_hashes: root_hashes.into_iter().collect(),
+            slots: slots
+                .into_iter()
+                .map(|(addr, slots)| (addr, slots.into_iter().collect()))
+                .collect(),
+        })
+    }
+
+    fn into_token(self) -> Token {
+        let abi: StorageMapAbi = (
+            self.root_hashes.into_iter().collect(),
+            self.slots
+                .into_iter()
+                .map(|(addr, slots)| (addr, slots.into_iter().collect()))
+                .collect(),
+        );
+        abi.into_token()
+    }
+}
@@ -0,0 +1,110 @@
+//! Hashing schemes of the user operations (the hash depends on the version of the entry point)
+
+use super::{PackedUserOperation, UserOperationHash, UserOperationSigned};
+use ethers::types::Address;
+use std::{fmt::Debug, sync::Arc};
+
+/// Scheme of calculating the [UserOperationHash](UserOperationHash) of a user operation
+pub trait UserOperationHasher: Debug + Send + Sync {
+    /// Calculates the hash of the user operation
+    ///
+    /// # Arguments
+    /// * `uo` - The [UserOperationSigned](UserOperationSigned) to hash
+    /// * `entry_point` - The address of the entry point
+    /// * `chain_id` - The chain ID
+    ///
+    /// # Returns
+    /// * `eyre::Result<UserOperationHash>` - The hash of the user operation, fails if the user
+    ///   operation can't be represented in the format of the entry point
+    fn hash(
+        &self,
+        uo: &UserOperationSigned,
+        entry_point: &Address,
+        chain_id: u64,
+    ) -> eyre::Result<UserOperationHash>;
+}
+
+/// Hashing scheme of entry point v0.6
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UserOperationHasherV06;
+
+impl UserOperationHasher for UserOperationHasherV06 {
+    fn hash(
+        &self,
+        uo: &UserOperationSigned,
+        entry_point: &Address,
+        chain_id: u64,
+    ) -> eyre::Result<UserOperationHash> {
+        Ok(uo.hash(entry_point, chain_id))
+    }
+}
+
+/// Hashing scheme of entry point v0.7 (the hash of the [PackedUserOperation](PackedUserOperation))
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UserOperationHasherV07;
+
+impl UserOperationHasher for UserOperationHasherV07 {
+    fn hash(
+        &self,
+        uo: &UserOperationSigned,
+        entry_point: &Address,
+        chain_id: u64,
+    ) -> eyre::Result<UserOperationHash> {
+        Ok(PackedUserOperation::try_from(uo.clone())?.hash(entry_point, chain_id))
+    }
+}
+
+/// Selects the hashing scheme matching the version of the entry point (e.g. 0.7.0). Unknown
+/// versions use the scheme of entry point v0.6.
+///
+/// # Arguments
+/// * `version` - The version of the entry point
+///
+/// # Returns
+/// * `Arc<dyn UserOperationHasher>` - The hashing scheme
+pub fn user_operation_hasher(version: &str) -> Arc<dyn UserOperationHasher> {
+    if version.starts_with("0.7") {
+        Arc::new(UserOperationHasherV07)
+    } else {
+        Arc::new(UserOperationHasherV06)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+
+    #[test]
+    fn user_operation_hasher_versions() {
+        let uo = UserOperationSigned {
+            sender: "0x9c5754De1443984659E1b3a8d1931D83475ba29C".parse().unwrap(),
+            nonce: 1.into(),
+            call_data: "0x1234".parse().unwrap(),
+            call_gas_limit: 200_000.into(),
+            verification_gas_limit: 100_000.into(),
+            pre_verification_gas: 21_000.into(),
+            max_fee_per_gas: 3_000_000_000_u64.into(),
+            max_priority_fee_per_gas: 1_000_000_000.into(),
+            signature: "0xabcd".parse().unwrap(),
+            ..Default::default()
+        };
+        let ep: Address = "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789".parse().unwrap();
+
+        let hash_v06 = user_operation_hasher("0.6.0").hash(&uo, &ep, 5).unwrap();
+        let hash_v07 = user_operation_hasher("0.7.0").hash(&uo, &ep, 5).unwrap();
+        assert_eq!(hash_v06, uo.hash(&ep, 5));
+        assert_eq!(hash_v07, PackedUserOperation::try_from(uo.clone()).unwrap().hash(&ep, 5));
+        assert_ne!(hash_v06, hash_v07);
+
+        // the signature isn't hashed
+        let uo_resigned = uo.clone().signature("0x1234".parse().unwrap());
+        assert_eq!(UserOperationHasherV07.hash(&uo_resigned, &ep, 5).unwrap(), hash_v07);
+        assert_ne!(UserOperationHasherV07.hash(&uo, &ep, 1).unwrap(), hash_v07);
+
+        // the gas limits and fees of v0.7 user operations are limited to 128 bits
+        let uo_overflow = uo.clone().call_gas_limit(U256::MAX);
+        assert!(UserOperationHasherV06.hash(&uo_overflow, &ep, 5).is_ok());
+        assert!(UserOperationHasherV07.hash(&uo_overflow, &ep, 5).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 //! Basic transaction type for account abstraction (ERC-4337)
 
 mod hash;
+mod hasher;
+mod packed;
 mod request;
 
 use crate::{get_address, utils::as_checksum_addr};
@@ -12,6 +14,10 @@ use ethers::{
     utils::keccak256,
 };
 pub use hash::UserOperationHash;
+pub use hasher::{
+    user_operation_hasher, UserOperationHasher, UserOperationHasherV06, UserOperationHasherV07,
+};
+pub use packed::PackedUserOperation;
 pub use request::UserOperationRequest;
 use serde::{Deserialize, Serialize};
 use ssz_rs::List;
@@ -233,6 +239,12 @@ impl UserOperationSigned {
         (sender, factory, paymaster)
     }
 
+    /// Gets the gas price the entry point charges for the user operation at the given base fee,
+    /// i.e. `min(max_fee_per_gas, base_fee + max_priority_fee_per_gas)`
+    pub fn effective_gas_price(&self, base_fee: U256) -> U256 {
+        self.max_fee_per_gas.min(base_fee.saturating_add(self.max_priority_fee_per_gas))
+    }
+
     /// Creates random user operation (for testing purposes)
     #[cfg(feature = "test-utils")]
     pub fn random() -> Self {
@@ -440,16 +452,17 @@ pub struct UserOperationReceipt {
     pub tx_receipt: TransactionReceipt,
 }
 
-/// Struct that is returned from the RPC endpoint eth_getUserOperationByHash
+/// Struct that is returned from the RPC endpoint eth_getUserOperationByHash (the transaction and
+/// the block are null for the pending user operations)
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserOperationByHash {
     pub user_operation: UserOperationSigned,
     #[serde(serialize_with = "as_checksum_addr")]
     pub entry_point: Address,
-    pub transaction_hash: H256,
-    pub block_hash: H256,
-    pub block_number: U64,
+    pub transaction_hash: Option<H256>,
+    pub block_hash: Option<H256>,
+    pub block_number: Option<U64>,
 }
 
 /// Gas estimations for user operation (returned from the RPC endpoint eth_estimateUserOperationGas)
@@ -459,6 +472,15 @@ pub struct UserOperationGasEstimation {
     pub pre_verification_gas: U256,
     pub verification_gas_limit: U256,
     pub call_gas_limit: U256,
+    /// Fee (in wei) for posting the user operation to L1 (only on OP-stack chains)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l1_data_fee: Option<U256>,
+    /// Suggested max fee per gas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    /// Suggested max priority fee per gas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
 }
 
 #[cfg(test)]
@@ -476,6 +498,23 @@ mod tests {
         assert_eq!(uos[1].pack(), "0x0000000000000000000000009c5754de1443984659e1b3a8d1931d83475ba29c0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000016000000000000000000000000000000000000000000000000000000000000001800000000000000000000000000000000000000000000000000000000000030d4000000000000000000000000000000000000000000000000000000000000186a0000000000000000000000000000000000000000000000000000000000000520800000000000000000000000000000000000000000000000000000000b2d05e00000000000000000000000000000000000000000000000000000000003b9aca0000000000000000000000000000000000000000000000000000000000000001a000000000000000000000000000000000000000000000000000000000000001c000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000417cb39607585dee8e297d0d7a669ad8c5e43975220b6773c10a138deadbc8ec864981de4b9b3c735288a217115fb33f8326a61ddabc60a534e3b5536515c70f931c00000000000000000000000000000000000000000000000000000000000000".parse::<Bytes>().unwrap());
     }
 
+    #[test]
+    fn user_operation_effective_gas_price() {
+        let uo = UserOperationSigned::default()
+            .max_fee_per_gas(100.into())
+            .max_priority_fee_per_gas(10.into());
+
+        // base fee plus priority fee below the max fee
+        assert_eq!(uo.effective_gas_price(0.into()), 10.into());
+        assert_eq!(uo.effective_gas_price(89.into()), 99.into());
+        // exactly at the max fee
+        assert_eq!(uo.effective_gas_price(90.into()), 100.into());
+        // clamped to the max fee
+        assert_eq!(uo.effective_gas_price(91.into()), 100.into());
+        assert_eq!(uo.effective_gas_price(200.into()), 100.into());
+        assert_eq!(uo.effective_gas_price(U256::MAX), 100.into());
+    }
+
     #[test]
     fn user_operation_signed_pack_without_signature() {
         let uos =  vec![
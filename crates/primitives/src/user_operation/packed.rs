@@ -0,0 +1,234 @@
+//! Packed user operation (entry point v0.7)
+//!
+//! Only the representation is provided (the conversion from and to the unpacked
+//! [UserOperationSigned] and the hash). The validator simulates the user operations against entry
+//! point v0.6 only, there is no validation of v0.7 user operations yet.
+
+use super::{UserOperationHash, UserOperationSigned};
+use crate::utils::as_checksum_addr;
+use ethers::{
+    abi::AbiEncode,
+    contract::{EthAbiCodec, EthAbiType},
+    types::{Address, Bytes, H256, U256},
+    utils::keccak256,
+};
+use eyre::format_err;
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+
+/// User operation in the packed form used by entry point v0.7
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackedUserOperation {
+    /// Sender of the user operation
+    #[serde(serialize_with = "as_checksum_addr")]
+    pub sender: Address,
+
+    /// Nonce (anti replay protection)
+    pub nonce: U256,
+
+    /// Init code for the account (needed if account not yet deployed and needs to be created)
+    pub init_code: Bytes,
+
+    /// The data that is passed to the sender during the main execution call
+    pub call_data: Bytes,
+
+    /// Verification gas limit (upper 128 bits) and call gas limit (lower 128 bits)
+    pub account_gas_limits: H256,
+
+    /// The amount of gas to pay bundler to compensate for the pre-verification execution and
+    /// calldata
+    pub pre_verification_gas: U256,
+
+    /// Max priority fee per gas (upper 128 bits) and max fee per gas (lower 128 bits)
+    pub gas_fees: H256,
+
+    /// Address of paymaster sponsoring the user operation, followed by extra data to send to the
+    /// paymaster (can be empty)
+    pub paymaster_and_data: Bytes,
+
+    /// Data passed to the account along with the nonce during the verification step
+    pub signature: Bytes,
+}
+
+/// Packed user operation without signature (helper for calculating the hash)
+#[derive(EthAbiCodec, EthAbiType)]
+struct PackedUserOperationNoSignature {
+    pub sender: Address,
+    pub nonce: U256,
+    pub init_code: H256,
+    pub call_data: H256,
+    pub account_gas_limits: H256,
+    pub pre_verification_gas: U256,
+    pub gas_fees: H256,
+    pub paymaster_and_data: H256,
+}
+
+impl From<PackedUserOperation> for PackedUserOperationNoSignature {
+    fn from(value: PackedUserOperation) -> Self {
+        Self {
+            sender: value.sender,
+            nonce: value.nonce,
+            init_code: keccak256(value.init_code.deref()).into(),
+            call_data: keccak256(value.call_data.deref()).into(),
+            account_gas_limits: value.account_gas_limits,
+            pre_verification_gas: value.pre_verification_gas,
+            gas_fees: value.gas_fees,
+            paymaster_and_data: keccak256(value.paymaster_and_data.deref()).into(),
+        }
+    }
+}
+
+/// Packs two 128-bit values into a single 32-byte word, fails if a value doesn't fit into 128 bits
+fn pack_uints(high: (&str, U256), low: (&str, U256)) -> eyre::Result<H256> {
+    let to_u128 = |(name, value): (&str, U256)| {
+        u128::try_from(value).map_err(|_| format_err!("{name} {value} doesn't fit into 128 bits"))
+    };
+
+    let mut buf = [0u8; 32];
+    buf[..16].copy_from_slice(&to_u128(high)?.to_be_bytes());
+    buf[16..].copy_from_slice(&to_u128(low)?.to_be_bytes());
+    Ok(H256::from(buf))
+}
+
+/// Unpacks a 32-byte word into two 128-bit values (high, low)
+fn unpack_uints(packed: &H256) -> (U256, U256) {
+    (U256::from_big_endian(&packed.0[..16]), U256::from_big_endian(&packed.0[16..]))
+}
+
+impl PackedUserOperation {
+    /// Packs the user operation without signature to bytes (used for calculating the hash)
+    pub fn pack_without_signature(&self) -> Bytes {
+        PackedUserOperationNoSignature::from(self.clone()).encode().into()
+    }
+
+    /// Calculates the hash of the user operation (as in entry point v0.7)
+    pub fn hash(&self, entry_point: &Address, chain_id: u64) -> UserOperationHash {
+        H256::from_slice(
+            keccak256(
+                [
+                    keccak256(self.pack_without_signature().deref()).to_vec(),
+                    entry_point.encode(),
+                    U256::from(chain_id).encode(),
+                ]
+                .concat(),
+            )
+            .as_slice(),
+        )
+        .into()
+    }
+
+    /// Gets the verification gas limit of the user operation
+    pub fn verification_gas_limit(&self) -> U256 {
+        unpack_uints(&self.account_gas_limits).0
+    }
+
+    /// Gets the call gas limit of the user operation
+    pub fn call_gas_limit(&self) -> U256 {
+        unpack_uints(&self.account_gas_limits).1
+    }
+
+    /// Gets the max priority fee per gas of the user operation
+    pub fn max_priority_fee_per_gas(&self) -> U256 {
+        unpack_uints(&self.gas_fees).0
+    }
+
+    /// Gets the max fee per gas of the user operation
+    pub fn max_fee_per_gas(&self) -> U256 {
+        unpack_uints(&self.gas_fees).1
+    }
+}
+
+impl From<PackedUserOperation> for UserOperationSigned {
+    fn from(value: PackedUserOperation) -> Self {
+        let (verification_gas_limit, call_gas_limit) = unpack_uints(&value.account_gas_limits);
+        let (max_priority_fee_per_gas, max_fee_per_gas) = unpack_uints(&value.gas_fees);
+
+        Self {
+            sender: value.sender,
+            nonce: value.nonce,
+            init_code: value.init_code,
+            call_data: value.call_data,
+            call_gas_limit,
+            verification_gas_limit,
+            pre_verification_gas: value.pre_verification_gas,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            paymaster_and_data: value.paymaster_and_data,
+            signature: value.signature,
+        }
+    }
+}
+
+impl TryFrom<UserOperationSigned> for PackedUserOperation {
+    type Error = eyre::Report;
+
+    /// Fails if a gas limit or a gas fee doesn't fit into 128 bits (the size of the packed fields
+    /// in entry point v0.7).
+    fn try_from(value: UserOperationSigned) -> eyre::Result<Self> {
+        Ok(Self {
+            sender: value.sender,
+            nonce: value.nonce,
+            init_code: value.init_code,
+            call_data: value.call_data,
+            account_gas_limits: pack_uints(
+                ("verification gas limit", value.verification_gas_limit),
+                ("call gas limit", value.call_gas_limit),
+            )?,
+            pre_verification_gas: value.pre_verification_gas,
+            gas_fees: pack_uints(
+                ("max priority fee per gas", value.max_priority_fee_per_gas),
+                ("max fee per gas", value.max_fee_per_gas),
+            )?,
+            paymaster_and_data: value.paymaster_and_data,
+            signature: value.signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_user_operation_unpack() {
+        let packed = PackedUserOperation {
+            sender: "0x9c5754De1443984659E1b3a8d1931D83475ba29C".parse().unwrap(),
+            nonce: 1.into(),
+            account_gas_limits:
+                "0x000000000000000000000000000186a000000000000000000000000000030d40"
+                    .parse()
+                    .unwrap(),
+            pre_verification_gas: 21_000.into(),
+            gas_fees: "0x0000000000000000000000003b9aca00000000000000000000000000b2d05e00"
+                .parse()
+                .unwrap(),
+            ..Default::default()
+        };
+
+        let uo: UserOperationSigned = packed.clone().into();
+        assert_eq!(uo.verification_gas_limit, 100_000.into());
+        assert_eq!(uo.call_gas_limit, 200_000.into());
+        assert_eq!(uo.max_priority_fee_per_gas, 1_000_000_000.into());
+        assert_eq!(uo.max_fee_per_gas, 3_000_000_000_u64.into());
+        assert_eq!(uo.pre_verification_gas, 21_000.into());
+
+        assert_eq!(PackedUserOperation::try_from(uo).unwrap(), packed);
+    }
+
+    #[test]
+    fn packed_user_operation_overflow() {
+        let uo = UserOperationSigned {
+            call_gas_limit: U256::from(u128::MAX),
+            max_fee_per_gas: U256::from(u128::MAX),
+            ..Default::default()
+        };
+        assert!(PackedUserOperation::try_from(uo.clone()).is_ok());
+
+        // the values which don't fit into 128 bits are rejected instead of truncated
+        let uo_overflow = uo.clone().call_gas_limit(U256::from(u128::MAX) + 1);
+        assert!(PackedUserOperation::try_from(uo_overflow).is_err());
+        let uo_overflow = uo.max_fee_per_gas(U256::from(u128::MAX) + 1);
+        assert!(PackedUserOperation::try_from(uo_overflow).is_err());
+    }
+}
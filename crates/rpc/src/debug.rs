@@ -1,17 +1,18 @@
 use crate::{
-    debug_api::{DebugApiServer, ResponseSuccess},
+    debug_api::{DebugApiServer, DenylistEntity, RehashedUserOperation, ResponseSuccess},
     error::JsonRpcError,
 };
 use async_trait::async_trait;
-use ethers::types::{Address, H256};
+use ethers::types::{Address, H256, U64};
 use jsonrpsee::{
     core::RpcResult,
     types::{error::INTERNAL_ERROR_CODE, ErrorObjectOwned},
 };
 use silius_grpc::{
     bundler_client::BundlerClient, uo_pool_client::UoPoolClient, AddMempoolRequest,
-    GetAllReputationRequest, GetAllRequest, GetStakeInfoRequest, Mode as GrpcMode, SetModeRequest,
-    SetReputationRequest, SetReputationResult,
+    DenylistEntity as GrpcDenylistEntity, DumpMempoolRequest, GetAllReputationRequest,
+    GetStakeInfoRequest, Mode as GrpcMode, RekeyMempoolsRequest, SendBundleNowResult,
+    SetModeRequest, SetReputationRequest, SetReputationResult, UpdateDenylistRequest,
 };
 use silius_primitives::{
     constants::bundler::BUNDLE_INTERVAL,
@@ -122,8 +123,9 @@ impl DebugApiServer for DebugApiServerImpl {
         Ok(ResponseSuccess::Ok)
     }
 
-    /// Sending an [GetAllRequest](GetAllRequest) to the UoPool gRPC server
-    /// to get all of the [UserOperation](UserOperationRequest) in the mempool.
+    /// Sending an [DumpMempoolRequest](DumpMempoolRequest) to the UoPool gRPC server
+    /// to get all of the [UserOperation](UserOperationRequest) in the mempool (in the same order
+    /// as they are bundled).
     ///
     /// # Arguments
     /// * `entry_point: Address` - The address of the entry point.
@@ -133,16 +135,13 @@ impl DebugApiServer for DebugApiServerImpl {
     async fn dump_mempool(&self, ep: Address) -> RpcResult<Vec<UserOperationRequest>> {
         let mut uopool_grpc_client = self.uopool_grpc_client.clone();
 
-        let req = Request::new(GetAllRequest { ep: Some(ep.into()) });
+        let req = Request::new(DumpMempoolRequest { ep: Some(ep.into()) });
 
-        let res = uopool_grpc_client.get_all(req).await.map_err(JsonRpcError::from)?.into_inner();
+        let res =
+            uopool_grpc_client.dump_mempool(req).await.map_err(JsonRpcError::from)?.into_inner();
 
-        let mut uos: Vec<UserOperationRequest> = res
-            .uos
-            .iter()
-            .map(|uo| UserOperation::from(uo.clone()).user_operation.into())
-            .collect();
-        uos.sort_by(|a, b| a.nonce.cmp(&b.nonce));
+        let uos = serde_json::from_str::<Vec<UserOperationRequest>>(&res.data)
+            .map_err(JsonRpcError::from)?;
         Ok(uos)
     }
 
@@ -237,10 +236,22 @@ impl DebugApiServer for DebugApiServerImpl {
 
         let req = Request::new(());
 
-        match bundler_grpc_client.send_bundle_now(req).await {
-            Ok(res) => Ok(res.into_inner().res.expect("Must return send bundle tx data").into()),
-            Err(s) => Err(JsonRpcError::from(s).into()),
+        let res = bundler_grpc_client
+            .send_bundle_now(req)
+            .await
+            .map_err(JsonRpcError::from)?
+            .into_inner();
+
+        if res.result() == SendBundleNowResult::AutoMode {
+            return Err(ErrorObjectOwned::owned(
+                INTERNAL_ERROR_CODE,
+                "Bundler is in auto bundling mode, set the manual mode to send bundles on demand"
+                    .to_string(),
+                None::<bool>,
+            ));
         }
+
+        Ok(res.res.expect("Must return send bundle tx data").into())
     }
 
     /// Returns the stake info of the given address.
@@ -268,4 +279,72 @@ impl DebugApiServer for DebugApiServerImpl {
             Err(s) => Err(JsonRpcError::from(s).into()),
         }
     }
+
+    /// Adds the addresses to and removes the addresses from the denylist of the entity role via
+    /// the [UpdateDenylistRequest](UpdateDenylistRequest).
+    ///
+    /// # Arguments
+    /// * `entity: DenylistEntity` - The [entity role](DenylistEntity) of the addresses.
+    /// * `add: Vec<Address>` - The addresses to deny.
+    /// * `remove: Vec<Address>` - The addresses to allow again.
+    ///
+    /// # Returns
+    /// * `RpcResult<ResponseSuccess>` - Ok
+    async fn update_denylist(
+        &self,
+        entity: DenylistEntity,
+        add: Vec<Address>,
+        remove: Vec<Address>,
+    ) -> RpcResult<ResponseSuccess> {
+        let mut uopool_grpc_client = self.uopool_grpc_client.clone();
+
+        let entity = match entity {
+            DenylistEntity::Sender => GrpcDenylistEntity::DenylistSender,
+            DenylistEntity::Paymaster => GrpcDenylistEntity::DenylistPaymaster,
+            DenylistEntity::Factory => GrpcDenylistEntity::DenylistFactory,
+        };
+        let req = Request::new(UpdateDenylistRequest {
+            entity: entity as i32,
+            add: add.into_iter().map(Into::into).collect(),
+            remove: remove.into_iter().map(Into::into).collect(),
+        });
+
+        match uopool_grpc_client.update_denylist(req).await {
+            Ok(_) => Ok(ResponseSuccess::Ok),
+            Err(s) => Err(JsonRpcError::from(s).into()),
+        }
+    }
+
+    /// Switches the mempools to a new chain ID via the
+    /// [RekeyMempoolsRequest](RekeyMempoolsRequest).
+    ///
+    /// # Arguments
+    /// * `chain_id: U64` - The new chain ID.
+    /// * `migrate: bool` - Whether the user operations and the reputation are kept.
+    ///
+    /// # Returns
+    /// * `RpcResult<Vec<RehashedUserOperation>>` - The previous and the new hashes of the migrated
+    ///   user operations.
+    async fn rekey_mempools(
+        &self,
+        chain_id: U64,
+        migrate: bool,
+    ) -> RpcResult<Vec<RehashedUserOperation>> {
+        let mut uopool_grpc_client = self.uopool_grpc_client.clone();
+
+        let req = Request::new(RekeyMempoolsRequest { chain_id: chain_id.as_u64(), migrate });
+
+        match uopool_grpc_client.rekey_mempools(req).await {
+            Ok(res) => Ok(res
+                .into_inner()
+                .rehashed
+                .into_iter()
+                .map(|rehashed| RehashedUserOperation {
+                    prev_hash: rehashed.prev_hash.unwrap_or_default().into(),
+                    hash: rehashed.hash.map(|hash| hash.into()),
+                })
+                .collect()),
+            Err(s) => Err(JsonRpcError::from(s).into()),
+        }
+    }
 }
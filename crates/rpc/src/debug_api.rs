@@ -1,5 +1,5 @@
 pub use crate::debug::DebugApiServerImpl;
-use ethers::types::{Address, H256};
+use ethers::types::{Address, H256, U64};
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 use serde::{Deserialize, Serialize};
 use silius_primitives::{
@@ -13,6 +13,24 @@ pub enum ResponseSuccess {
     Ok,
 }
 
+/// The entity role of the denied addresses
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DenylistEntity {
+    Sender,
+    Paymaster,
+    Factory,
+}
+
+/// The previous and the new hash of a user operation migrated to another chain (the new hash is
+/// null if the user operation is no longer valid on the new chain and was dropped)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RehashedUserOperation {
+    pub prev_hash: H256,
+    pub hash: Option<H256>,
+}
+
 /// The ERC-4337 `debug` namespace RPC methods trait
 #[rpc(server, namespace = "debug_bundler")]
 pub trait DebugApi {
@@ -127,4 +145,40 @@ pub trait DebugApi {
         address: Address,
         entry_point: Address,
     ) -> RpcResult<StakeInfoResponse>;
+
+    /// Adds the addresses to and removes the addresses from the denylist of the entity role. The
+    /// user operations of the denied addresses are rejected regardless of their reputation.
+    ///
+    /// # Arguments
+    /// * `entity: DenylistEntity` - The [entity role](DenylistEntity) of the addresses.
+    /// * `add: Vec<Address>` - The addresses to deny.
+    /// * `remove: Vec<Address>` - The addresses to allow again.
+    ///
+    /// # Returns
+    /// * `RpcResult<ResponseSuccess>` - Ok
+    #[method(name = "updateDenylist")]
+    async fn update_denylist(
+        &self,
+        entity: DenylistEntity,
+        add: Vec<Address>,
+        remove: Vec<Address>,
+    ) -> RpcResult<ResponseSuccess>;
+
+    /// Switches the mempools to a new chain ID (e.g. after a fork). The user operations and the
+    /// reputation are either migrated (the user operations are rehashed and validated again on the
+    /// new chain, the invalid ones are dropped) or cleared.
+    ///
+    /// # Arguments
+    /// * `chain_id: U64` - The new chain ID.
+    /// * `migrate: bool` - Whether the user operations and the reputation are kept.
+    ///
+    /// # Returns
+    /// * `RpcResult<Vec<RehashedUserOperation>>` - The previous and the new hashes of the migrated
+    ///   user operations (no new hash for the dropped ones).
+    #[method(name = "rekeyMempools")]
+    async fn rekey_mempools(
+        &self,
+        chain_id: U64,
+        migrate: bool,
+    ) -> RpcResult<Vec<RehashedUserOperation>>;
 }
@@ -1,6 +1,6 @@
 use crate::codes::{
-    BANNED_OR_THROTTLED_ENTITY, EXECUTION, OPCODE, SANITY, SIGNATURE, STAKE_TOO_LOW, TIMESTAMP,
-    VALIDATION,
+    BANNED_OR_THROTTLED_ENTITY, EXECUTION, OPCODE, SANITY, SIGNATURE, SIGNATURE_AGGREGATOR,
+    STAKE_TOO_LOW, TIMESTAMP, VALIDATION,
 };
 use jsonrpsee::types::{
     error::{ErrorCode, INTERNAL_ERROR_CODE},
@@ -8,7 +8,7 @@ use jsonrpsee::types::{
 };
 use silius_mempool::{
     InvalidMempoolUserOperationError, MempoolError, MempoolErrorKind, ReputationError, SanityError,
-    SimulationError,
+    SimulationError, TraceError,
 };
 
 /// A wrapper for the [ErrorObjectOwned](ErrorObjectOwned) type.
@@ -49,7 +49,7 @@ impl From<ReputationError> for JsonRpcError {
             ReputationError::BannedEntity { entity: _, address: _ } => {
                 ErrorObject::owned(BANNED_OR_THROTTLED_ENTITY, err.to_string(), None::<bool>)
             }
-            ReputationError::ThrottledEntity { entity: _, address: _ } => {
+            ReputationError::ThrottledEntity { entity: _, address: _, reason: _ } => {
                 ErrorObject::owned(BANNED_OR_THROTTLED_ENTITY, err.to_string(), None::<bool>)
             }
             ReputationError::StakeTooLow { entity: _, address: _, stake: _, min_stake: _ } => {
@@ -80,13 +80,22 @@ impl From<SanityError> for JsonRpcError {
             SanityError::PreVerificationGasTooLow {
                 pre_verification_gas: _,
                 pre_verification_gas_expected: _,
+                pre_verification_gas_calculated: _,
             } => ErrorObject::owned(SANITY, err.to_string(), None::<bool>),
-            SanityError::CallGasLimitTooLow { call_gas_limit: _, call_gas_limit_expected: _ } => {
+            SanityError::GasLimitTooHighForBundle { gas_limit: _, max_bundle_gas: _ } => {
+                ErrorObject::owned(SANITY, err.to_string(), None::<bool>)
+            }
+            SanityError::FieldTooLarge { field: _, size: _, max_size: _ } => {
                 ErrorObject::owned(SANITY, err.to_string(), None::<bool>)
             }
-            SanityError::MaxFeePerGasTooLow { max_fee_per_gas: _, base_fee_per_gas: _ } => {
+            SanityError::CallGasLimitTooLow { call_gas_limit: _, call_gas_limit_expected: _ } => {
                 ErrorObject::owned(SANITY, err.to_string(), None::<bool>)
             }
+            SanityError::MaxFeePerGasTooLow {
+                max_fee_per_gas: _,
+                base_fee_per_gas: _,
+                min_priority_fee_per_gas: _,
+            } => ErrorObject::owned(SANITY, err.to_string(), None::<bool>),
             SanityError::MaxPriorityFeePerGasTooHigh {
                 max_priority_fee_per_gas: _,
                 max_fee_per_gas: _,
@@ -95,12 +104,25 @@ impl From<SanityError> for JsonRpcError {
                 max_priority_fee_per_gas: _,
                 max_priority_fee_per_gas_expected: _,
             } => ErrorObject::owned(SANITY, err.to_string(), None::<bool>),
+            SanityError::MempoolFull {
+                max_priority_fee_per_gas: _,
+                max_priority_fee_per_gas_expected: _,
+            } => ErrorObject::owned(SANITY, err.to_string(), None::<bool>),
+            SanityError::NonceGap { nonce: _, nonce_expected: _ } => {
+                ErrorObject::owned(SANITY, err.to_string(), None::<bool>)
+            }
+            SanityError::UnsupportedEntryPoint { entry_point: _ } => {
+                ErrorObject::owned(SANITY, err.to_string(), None::<bool>)
+            }
             SanityError::Paymaster { inner: _ } => {
                 ErrorObject::owned(SANITY, err.to_string(), None::<bool>)
             }
             SanityError::Sender { inner: _ } => {
                 ErrorObject::owned(SANITY, err.to_string(), None::<bool>)
             }
+            SanityError::Denied { entity: _, address: _ } => {
+                ErrorObject::owned(BANNED_OR_THROTTLED_ENTITY, err.to_string(), None::<bool>)
+            }
             SanityError::EntityRoles { entity: _, address: _, entity_other: _ } => {
                 ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
             }
@@ -114,9 +136,15 @@ impl From<SimulationError> for JsonRpcError {
     /// Convert a [SimulationError](SimulationError) to a [JsonRpcError](JsonRpcError).
     fn from(err: SimulationError) -> Self {
         JsonRpcError(match err {
-            SimulationError::Signature => {
+            SimulationError::Signature | SimulationError::SignatureFailed => {
+                ErrorObject::owned(SIGNATURE, err.to_string(), None::<bool>)
+            }
+            SimulationError::SignatureLength { length: _, min: _, max: _ } => {
                 ErrorObject::owned(SIGNATURE, err.to_string(), None::<bool>)
             }
+            SimulationError::Aggregator { aggregator: _ } => {
+                ErrorObject::owned(SIGNATURE_AGGREGATOR, err.to_string(), None::<bool>)
+            }
             SimulationError::Timestamp { inner: _ } => {
                 ErrorObject::owned(TIMESTAMP, err.to_string(), None::<bool>)
             }
@@ -126,9 +154,18 @@ impl From<SimulationError> for JsonRpcError {
             SimulationError::Execution { inner: _ } => {
                 ErrorObject::owned(EXECUTION, err.to_string(), None::<bool>)
             }
+            SimulationError::Prefund { pre_fund: _, funds: _ } => {
+                ErrorObject::owned(VALIDATION, err.to_string(), None::<bool>)
+            }
             SimulationError::Opcode { entity: _, opcode: _ } => {
                 ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
             }
+            SimulationError::GasOpcode { entity: _, pc: _ } => {
+                ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
+            }
+            SimulationError::ContractCreation { entity: _, inner: _ } => {
+                ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
+            }
             SimulationError::StorageAccess { slot: _ } => {
                 ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
             }
@@ -138,12 +175,27 @@ impl From<SimulationError> for JsonRpcError {
             SimulationError::CallStack { inner: _ } => {
                 ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
             }
+            SimulationError::Precompile { address: _ } => {
+                ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
+            }
             SimulationError::CodeHashes {} => {
                 ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
             }
             SimulationError::OutOfGas {} => {
                 ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
             }
+            SimulationError::VerificationGasLimitTooLowForStorage {
+                verification_gas_limit: _,
+                storage_gas: _,
+                cold_slots: _,
+            } => ErrorObject::owned(SANITY, err.to_string(), None::<bool>),
+            SimulationError::TraceLimit { limit: _, value: _, max: _ } => {
+                ErrorObject::owned(OPCODE, err.to_string(), None::<bool>)
+            }
+            // an unexpected trace (misconfigured tracer) is an internal error
+            SimulationError::Trace(TraceError::Decode { inner: _ }) => {
+                ErrorObject::owned(VALIDATION, err.to_string(), None::<bool>)
+            }
             SimulationError::Reputation(err) => JsonRpcError::from(err).0,
             _ => ErrorObject::owned(INTERNAL_ERROR_CODE, err.to_string(), None::<bool>),
         })
@@ -171,3 +223,230 @@ impl From<serde_json::Error> for JsonRpcError {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, U256};
+
+    fn code(err: impl Into<JsonRpcError>) -> i32 {
+        err.into().0.code()
+    }
+
+    #[test]
+    fn sanity_error_codes() {
+        let cases: Vec<(SanityError, i32)> = vec![
+            (
+                SanityError::VerificationGasLimitTooHigh {
+                    verification_gas_limit: U256::zero(),
+                    verification_gas_limit_expected: U256::zero(),
+                },
+                SANITY,
+            ),
+            (
+                SanityError::PreVerificationGasTooLow {
+                    pre_verification_gas: U256::zero(),
+                    pre_verification_gas_expected: U256::zero(),
+                    pre_verification_gas_calculated: U256::zero(),
+                },
+                SANITY,
+            ),
+            (
+                SanityError::GasLimitTooHighForBundle {
+                    gas_limit: U256::zero(),
+                    max_bundle_gas: U256::zero(),
+                },
+                SANITY,
+            ),
+            (SanityError::FieldTooLarge { field: "callData".into(), size: 0, max_size: 0 }, SANITY),
+            (
+                SanityError::CallGasLimitTooLow {
+                    call_gas_limit: U256::zero(),
+                    call_gas_limit_expected: U256::zero(),
+                },
+                SANITY,
+            ),
+            (
+                SanityError::MaxFeePerGasTooLow {
+                    max_fee_per_gas: U256::zero(),
+                    base_fee_per_gas: U256::zero(),
+                    min_priority_fee_per_gas: U256::zero(),
+                },
+                SANITY,
+            ),
+            (
+                SanityError::MaxPriorityFeePerGasTooHigh {
+                    max_priority_fee_per_gas: U256::zero(),
+                    max_fee_per_gas: U256::zero(),
+                },
+                SANITY,
+            ),
+            (
+                SanityError::MaxPriorityFeePerGasTooLow {
+                    max_priority_fee_per_gas: U256::zero(),
+                    max_priority_fee_per_gas_expected: U256::zero(),
+                },
+                SANITY,
+            ),
+            (
+                SanityError::MempoolFull {
+                    max_priority_fee_per_gas: U256::zero(),
+                    max_priority_fee_per_gas_expected: U256::zero(),
+                },
+                SANITY,
+            ),
+            (SanityError::NonceGap { nonce: U256::zero(), nonce_expected: U256::zero() }, SANITY),
+            (SanityError::UnsupportedEntryPoint { entry_point: Address::zero() }, SANITY),
+            (SanityError::Paymaster { inner: String::new() }, SANITY),
+            (SanityError::Sender { inner: String::new() }, SANITY),
+            (
+                SanityError::Denied { entity: String::new(), address: Address::zero() },
+                BANNED_OR_THROTTLED_ENTITY,
+            ),
+            (
+                SanityError::EntityRoles {
+                    entity: String::new(),
+                    address: Address::zero(),
+                    entity_other: String::new(),
+                },
+                OPCODE,
+            ),
+            (
+                SanityError::Reputation(ReputationError::BannedEntity {
+                    entity: String::new(),
+                    address: Address::zero(),
+                }),
+                BANNED_OR_THROTTLED_ENTITY,
+            ),
+            (SanityError::Timeout { stage: String::new(), timeout_ms: 0 }, INTERNAL_ERROR_CODE),
+            (SanityError::Provider { inner: String::new() }, INTERNAL_ERROR_CODE),
+            (SanityError::Other { inner: String::new() }, INTERNAL_ERROR_CODE),
+        ];
+
+        for (err, expected) in cases {
+            let msg = err.to_string();
+            assert_eq!(code(err), expected, "{msg}");
+        }
+    }
+
+    #[test]
+    fn simulation_error_codes() {
+        let cases: Vec<(SimulationError, i32)> = vec![
+            (SimulationError::Signature, SIGNATURE),
+            (SimulationError::SignatureFailed, SIGNATURE),
+            (SimulationError::SignatureLength { length: 64, min: 65, max: 65 }, SIGNATURE),
+            (SimulationError::Aggregator { aggregator: Address::zero() }, SIGNATURE_AGGREGATOR),
+            (SimulationError::Timestamp { inner: String::new() }, TIMESTAMP),
+            (SimulationError::Validation { inner: String::new() }, VALIDATION),
+            (SimulationError::Execution { inner: String::new() }, EXECUTION),
+            (SimulationError::Prefund { pre_fund: U256::zero(), funds: U256::zero() }, VALIDATION),
+            (SimulationError::Opcode { entity: String::new(), opcode: String::new() }, OPCODE),
+            (SimulationError::GasOpcode { entity: String::new(), pc: 0 }, OPCODE),
+            (
+                SimulationError::ContractCreation { entity: String::new(), inner: String::new() },
+                OPCODE,
+            ),
+            (SimulationError::StorageAccess { slot: String::new() }, OPCODE),
+            (
+                SimulationError::Unstaked {
+                    entity: String::new(),
+                    address: Address::zero(),
+                    inner: String::new(),
+                },
+                OPCODE,
+            ),
+            (SimulationError::CallStack { inner: String::new() }, OPCODE),
+            (SimulationError::Precompile { address: Address::zero() }, OPCODE),
+            (SimulationError::CodeHashes, OPCODE),
+            (SimulationError::OutOfGas, OPCODE),
+            (
+                SimulationError::VerificationGasLimitTooLowForStorage {
+                    verification_gas_limit: U256::zero(),
+                    storage_gas: U256::zero(),
+                    cold_slots: U256::zero(),
+                },
+                SANITY,
+            ),
+            (SimulationError::TraceLimit { limit: String::new(), value: 0, max: 0 }, OPCODE),
+            (SimulationError::Trace(TraceError::Decode { inner: String::new() }), VALIDATION),
+            (
+                SimulationError::Trace(TraceError::Unexpected { inner: String::new() }),
+                INTERNAL_ERROR_CODE,
+            ),
+            (
+                SimulationError::Reputation(ReputationError::UnstakedEntity {
+                    entity: String::new(),
+                    address: Address::zero(),
+                }),
+                STAKE_TOO_LOW,
+            ),
+            (SimulationError::Timeout { stage: String::new(), timeout_ms: 0 }, INTERNAL_ERROR_CODE),
+            (
+                SimulationError::HistoricalState { block: 0, inner: String::new() },
+                INTERNAL_ERROR_CODE,
+            ),
+            (SimulationError::Provider { inner: String::new() }, INTERNAL_ERROR_CODE),
+            (SimulationError::Other { inner: String::new() }, INTERNAL_ERROR_CODE),
+        ];
+
+        for (err, expected) in cases {
+            let msg = err.to_string();
+            assert_eq!(code(err), expected, "{msg}");
+        }
+    }
+
+    #[test]
+    fn reputation_error_codes() {
+        let cases: Vec<(ReputationError, i32)> = vec![
+            (
+                ReputationError::BannedEntity { entity: String::new(), address: Address::zero() },
+                BANNED_OR_THROTTLED_ENTITY,
+            ),
+            (
+                ReputationError::ThrottledEntity {
+                    entity: String::new(),
+                    address: Address::zero(),
+                    reason: String::new(),
+                },
+                BANNED_OR_THROTTLED_ENTITY,
+            ),
+            (
+                ReputationError::StakeTooLow {
+                    entity: String::new(),
+                    address: Address::zero(),
+                    stake: U256::zero(),
+                    min_stake: U256::zero(),
+                },
+                STAKE_TOO_LOW,
+            ),
+            (
+                ReputationError::UnstakeDelayTooLow {
+                    entity: String::new(),
+                    address: Address::zero(),
+                    unstake_delay: U256::zero(),
+                    min_unstake_delay: U256::zero(),
+                },
+                STAKE_TOO_LOW,
+            ),
+            (
+                ReputationError::UnstakedEntity { entity: String::new(), address: Address::zero() },
+                STAKE_TOO_LOW,
+            ),
+        ];
+
+        for (err, expected) in cases {
+            let msg = err.to_string();
+            assert_eq!(code(err), expected, "{msg}");
+        }
+
+        // the codes survive the round trip through the gRPC `add` response data
+        let err = MempoolError {
+            hash: Default::default(),
+            kind: MempoolErrorKind::InvalidUserOperation(InvalidMempoolUserOperationError::Sanity(
+                SanityError::NonceGap { nonce: U256::zero(), nonce_expected: U256::zero() },
+            )),
+        };
+        let data = serde_json::to_string(&err).unwrap();
+        assert_eq!(code(serde_json::from_str::<MempoolError>(&data).unwrap()), SANITY);
+    }
+}
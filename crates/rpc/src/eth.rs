@@ -1,13 +1,13 @@
 use crate::{codes::USER_OPERATION_HASH, error::JsonRpcError, eth_api::EthApiServer};
 use async_trait::async_trait;
 use ethers::{
-    types::{Address, U64},
+    types::{spoof, Address, U64},
     utils::to_checksum,
 };
 use jsonrpsee::{core::RpcResult, types::ErrorObjectOwned};
 use silius_grpc::{
     uo_pool_client::UoPoolClient, AddRequest, AddResult, EstimateUserOperationGasRequest,
-    EstimateUserOperationGasResult, UserOperationHashRequest,
+    EstimateUserOperationGasResult, GetUserOperationByHashResult, UserOperationHashRequest,
 };
 use silius_mempool::MempoolError;
 use silius_primitives::{
@@ -91,7 +91,8 @@ impl EthApiServer for EthApiServerImpl {
 
         let res = uopool_grpc_client.add(req).await.map_err(JsonRpcError::from)?.into_inner();
 
-        if res.res == AddResult::Added as i32 {
+        // resubmitting an identical user operation returns its hash
+        if res.res == AddResult::Added as i32 || res.res == AddResult::AlreadyKnown as i32 {
             let uo_hash =
                 serde_json::from_str::<UserOperationHash>(&res.data).map_err(JsonRpcError::from)?;
             return Ok(uo_hash);
@@ -111,6 +112,7 @@ impl EthApiServer for EthApiServerImpl {
     /// * `user_operation: [UserOperation](UserOperationRequest)` - User operation for which to
     ///   estimate the gas.
     /// * `entry_point: Address` - The address of the entry point.
+    /// * `state_override: Option<spoof::State>` - The state overrides applied to the simulations.
     ///
     /// # Returns
     /// * `RpcResult<UserOperationGasEstimation>` - The
@@ -119,6 +121,7 @@ impl EthApiServer for EthApiServerImpl {
         &self,
         uo: UserOperationRequest,
         ep: Address,
+        state_override: Option<spoof::State>,
     ) -> RpcResult<UserOperationGasEstimation> {
         let mut uopool_grpc_client = self.uopool_grpc_client.clone();
 
@@ -140,6 +143,12 @@ impl EthApiServer for EthApiServerImpl {
                     .into(),
                 ),
                 ep: Some(ep.into()),
+                state_override: match state_override {
+                    Some(state_override) => {
+                        serde_json::to_string(&state_override).map_err(JsonRpcError::from)?
+                    }
+                    None => String::new(),
+                },
             });
 
         let res = uopool_grpc_client
@@ -189,7 +198,7 @@ impl EthApiServer for EthApiServerImpl {
                                 actual_gas_cost: res.actual_gas_cost?.into(),
                                 actual_gas_used: res.actual_gas_used?.into(),
                                 success: res.success,
-                                reason: String::new(),
+                                reason: res.reason,
                                 logs: res.logs.into_iter().map(|l| l.into()).collect(),
                                 tx_receipt: res.tx_receipt?.into(),
                             })
@@ -223,7 +232,8 @@ impl EthApiServer for EthApiServerImpl {
     ///
     /// # Returns
     /// * `RpcResult<Option<UserOperationByHash>>` - The [UserOperation](UserOperation) associated
-    ///   with the hash, or None if it does not exist.
+    ///   with the hash (without the transaction and the block while it's pending), or None if it
+    ///   does not exist.
     async fn get_user_operation_by_hash(
         &self,
         uo_hash: String,
@@ -235,29 +245,37 @@ impl EthApiServer for EthApiServerImpl {
                 match self.uopool_grpc_client.clone().get_user_operation_by_hash(req).await {
                     Ok(res) => {
                         let res = res.into_inner();
+                        if res.res != GetUserOperationByHashResult::Found as i32 {
+                            return Ok(None);
+                        }
 
                         let uo: Option<UserOperationByHash> = res.user_operation.and_then(|uo| {
                             let entry_point = res.entry_point?.into();
-                            let block_hash = res.block_hash?.into();
-                            let transaction_hash = res.transaction_hash?.into();
+                            // pending user operations are not included in a block yet
+                            let (transaction_hash, block_hash, block_number) = if res.pending {
+                                (None, None, None)
+                            } else {
+                                (
+                                    Some(res.transaction_hash?.into()),
+                                    Some(res.block_hash?.into()),
+                                    Some(res.block_number.into()),
+                                )
+                            };
                             Some(UserOperationByHash {
                                 user_operation: uo.into(),
                                 entry_point,
-                                block_number: res.block_number.into(),
+                                block_number,
                                 block_hash,
                                 transaction_hash,
                             })
                         });
                         Ok(uo)
                     }
-                    Err(s) => match s.code() {
-                        tonic::Code::NotFound => Ok(None),
-                        _ => Err(ErrorObjectOwned::owned(
-                            USER_OPERATION_HASH,
-                            "Missing/invalid userOpHash".to_string(),
-                            None::<bool>,
-                        )),
-                    },
+                    Err(_) => Err(ErrorObjectOwned::owned(
+                        USER_OPERATION_HASH,
+                        "Missing/invalid userOpHash".to_string(),
+                        None::<bool>,
+                    )),
                 }
             }
             Err(_) => Err(ErrorObjectOwned::owned(
@@ -3,8 +3,10 @@ use ethers::types::{Address, U256};
 use parking_lot::RwLock;
 use silius_contracts::EntryPoint;
 use silius_mempool::{
-    init_env, validate::validator::new_canonical, CodeHashes, DatabaseTable, Mempool, Reputation,
-    UoPoolBuilder, UserOperations, UserOperationsByEntity, UserOperationsBySender, WriteMap,
+    init_env,
+    validate::validator::{new_canonical, CanonicalConfig},
+    CodeHashes, DatabaseTable, Mempool, Reputation, UoPoolBuilder, UserOperations,
+    UserOperationsByEntity, UserOperationsBySender, WriteMap,
 };
 use silius_primitives::{
     constants::{
@@ -65,7 +67,15 @@ async fn main() -> eyre::Result<()> {
             U256::from(5000000),
             mempool,
             reputation,
-            new_canonical(entry_point, chain, U256::from(5000000), U256::from(1)),
+            new_canonical(
+                entry_point,
+                chain,
+                CanonicalConfig {
+                    max_verification_gas: U256::from(5000000),
+                    min_priority_fee_per_gas: U256::from(1),
+                    ..Default::default()
+                },
+            ),
             None,
         );
 
@@ -2,7 +2,10 @@ use alloy_chains::Chain;
 use ethers::types::{Address, U256};
 use parking_lot::RwLock;
 use silius_contracts::EntryPoint;
-use silius_mempool::{validate::validator::new_canonical, Mempool, Reputation, UoPoolBuilder};
+use silius_mempool::{
+    validate::validator::{new_canonical, CanonicalConfig},
+    Mempool, Reputation, UoPoolBuilder,
+};
 use silius_primitives::{
     constants::{
         entry_point::ADDRESS,
@@ -62,7 +65,15 @@ async fn main() -> eyre::Result<()> {
             U256::from(5000000),
             mempool,
             reputation,
-            new_canonical(entry_point, chain, U256::from(5000000), U256::from(1)),
+            new_canonical(
+                entry_point,
+                chain,
+                CanonicalConfig {
+                    max_verification_gas: U256::from(5000000),
+                    min_priority_fee_per_gas: U256::from(1),
+                    ..Default::default()
+                },
+            ),
             None,
         );
 
@@ -0,0 +1,46 @@
+//! Fuzzes the offline sanity checks of the user operation (they must never panic).
+//!
+//! Run with `cargo +nightly fuzz run quick_sanity` from the `fuzz` directory.
+#![no_main]
+
+use ethers::types::{Address, Bytes, U256};
+use libfuzzer_sys::fuzz_target;
+use silius_mempool::validate::sanity::quick_sanity;
+use silius_primitives::{UserOperation, UserOperationSigned};
+
+/// Takes the next `n` bytes of the input (zero-padded if the input is too short).
+fn take(data: &mut &[u8], n: usize) -> Vec<u8> {
+    let n_taken = n.min(data.len());
+    let mut buf = data[..n_taken].to_vec();
+    buf.resize(n, 0);
+    *data = &data[n_taken..];
+    buf
+}
+
+fn take_u256(data: &mut &[u8]) -> U256 {
+    U256::from_big_endian(&take(data, 32))
+}
+
+fn take_bytes(data: &mut &[u8]) -> Bytes {
+    let len = u16::from_be_bytes([take(data, 1)[0], take(data, 1)[0]]) as usize;
+    Bytes::from(take(data, len.min(data.len())))
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut data = data;
+    let uo = UserOperationSigned {
+        sender: Address::from_slice(&take(&mut data, 20)),
+        nonce: take_u256(&mut data),
+        call_gas_limit: take_u256(&mut data),
+        verification_gas_limit: take_u256(&mut data),
+        pre_verification_gas: take_u256(&mut data),
+        max_fee_per_gas: take_u256(&mut data),
+        max_priority_fee_per_gas: take_u256(&mut data),
+        init_code: take_bytes(&mut data),
+        call_data: take_bytes(&mut data),
+        paymaster_and_data: take_bytes(&mut data),
+        signature: take_bytes(&mut data),
+    };
+
+    let _ = quick_sanity(&UserOperation::from_user_operation_signed(Default::default(), uo));
+});
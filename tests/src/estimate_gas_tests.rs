@@ -11,8 +11,14 @@ use ethers::{
     utils::GethInstance,
 };
 use silius_contracts::EntryPoint;
-use silius_mempool::{validate::validator::new_canonical, UoPool};
-use silius_primitives::{UoPoolMode, UserOperationSigned, Wallet as UoWallet};
+use silius_mempool::{
+    validate::{validator::new_canonical, UserOperationValidatorMode},
+    UoPool,
+};
+use silius_primitives::{
+    constants::{bundler::MAX_BUNDLE_GAS, mempool::GAS_INCREASE_PERC},
+    UoPoolMode, UserOperationSigned, Wallet as UoWallet,
+};
 use std::sync::Arc;
 
 async fn setup_basic() -> eyre::Result<(
@@ -40,7 +46,19 @@ async fn estimate_with_zero() -> eyre::Result<()> {
     let entry = EntryPoint::new(client.clone(), entry_point.address);
     let entry_for_uopool = EntryPoint::new(client.clone(), entry_point.address);
     let min_priority_fee_per_gas = 0.into();
-    let validator = new_canonical(entry, chain, max_verification_gas, min_priority_fee_per_gas);
+    let validator = new_canonical(
+        entry,
+        chain,
+        max_verification_gas,
+        MAX_BUNDLE_GAS.into(),
+        min_priority_fee_per_gas,
+        GAS_INCREASE_PERC.into(),
+        Default::default(),
+        Default::default(),
+        None,
+        false,
+        false,
+    );
     let mut uopool = UoPool::new(
         UoPoolMode::Standard,
         entry_for_uopool,
@@ -106,6 +124,15 @@ async fn estimate_with_zero() -> eyre::Result<()> {
         ..user_op.user_operation
     };
     let user_op = uo_wallet.sign_user_operation(&user_op, &entry_point.address, chain_id).await?;
-    uopool.add_user_operations(vec![user_op], None).await.expect("handle done");
+    uopool
+        .add_user_operations(
+            vec![user_op],
+            None,
+            UserOperationValidatorMode::Sanity |
+                UserOperationValidatorMode::Simulation |
+                UserOperationValidatorMode::SimulationTrace,
+        )
+        .await
+        .expect("handle done");
     Ok(())
 }
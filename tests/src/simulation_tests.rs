@@ -27,7 +27,11 @@ use silius_mempool::{
     InvalidMempoolUserOperationError, Mempool, Reputation, SimulationError,
 };
 use silius_primitives::{
-    constants::validation::entities::{FACTORY, PAYMASTER, SENDER},
+    constants::{
+        bundler::MAX_BUNDLE_GAS,
+        mempool::GAS_INCREASE_PERC,
+        validation::entities::{FACTORY, PAYMASTER, SENDER},
+    },
     UserOperation, UserOperationSigned,
 };
 use std::{ops::Deref, sync::Arc};
@@ -115,8 +119,19 @@ async fn setup_database() -> eyre::Result<TestContext<ClientType>> {
     let entry_point = EntryPoint::new(client.clone(), ep.address);
     let c = Chain::from(chain_id);
 
-    let validator =
-        new_canonical(entry_point, c.clone(), U256::from(3000000_u64), U256::from(1u64));
+    let validator = new_canonical(
+        entry_point,
+        c.clone(),
+        U256::from(3000000_u64),
+        U256::from(MAX_BUNDLE_GAS),
+        U256::from(1u64),
+        U256::from(GAS_INCREASE_PERC),
+        Default::default(),
+        Default::default(),
+        None,
+        false,
+        false,
+    );
 
     Ok(TestContext {
         client: client.clone(),
@@ -140,8 +155,19 @@ async fn setup_memory() -> eyre::Result<TestContext<ClientType>> {
     let entry_point = EntryPoint::new(client.clone(), ep.address);
     let c = Chain::from(chain_id);
 
-    let validator =
-        new_canonical(entry_point, c.clone(), U256::from(3000000_u64), U256::from(1u64));
+    let validator = new_canonical(
+        entry_point,
+        c.clone(),
+        U256::from(3000000_u64),
+        U256::from(MAX_BUNDLE_GAS),
+        U256::from(1u64),
+        U256::from(GAS_INCREASE_PERC),
+        Default::default(),
+        Default::default(),
+        None,
+        false,
+        false,
+    );
     Ok(TestContext {
         client: client.clone(),
         _geth,
@@ -280,6 +306,7 @@ where
                 uo.hash(&context.entry_point.address, context.chain_id),
                 uo.clone(),
             ),
+            &context.entry_point.address,
             &context.mempool,
             &context.reputation,
             None,
@@ -741,3 +768,35 @@ macro_rules! fail_with_inner_oog_revert {
 
 fail_with_inner_oog_revert!(setup_database().await?, fail_with_inner_oog_revert_database);
 fail_with_inner_oog_revert!(setup_memory().await?, fail_with_inner_oog_revert_memory);
+
+#[cfg(feature = "archive")]
+#[tokio::test]
+async fn validate_at_pinned_block() -> eyre::Result<()> {
+    let c = setup_memory().await?;
+    let pinned = c.client.get_block_number().await?.as_u64();
+    let uo = existing_storage_account_user_operation(&c, "".into(), "".into());
+    let uo =
+        UserOperation::from_user_operation_signed(uo.hash(&c.entry_point.address, c.chain_id), uo);
+    let validator = c.validator.clone().with_archive(true);
+    let validate_at = |block| {
+        validator.validate_at_block(
+            &uo,
+            &c.entry_point.address,
+            &c.mempool,
+            &c.reputation,
+            UserOperationValidatorMode::Simulation | UserOperationValidatorMode::SimulationTrace,
+            block,
+        )
+    };
+
+    let out = validate_at(pinned).await.expect("succeed");
+    assert_eq!(
+        out.verified_block,
+        U256::from(c.client.get_block(pinned).await?.unwrap().hash.unwrap().0)
+    );
+
+    // the entry point is not deployed yet
+    assert!(validate_at(1).await.is_err());
+
+    Ok(())
+}